@@ -0,0 +1,66 @@
+//! Canonical stress scenes shared by the benchmarks.
+//!
+//! Each scene is built the same way every run so timings can be compared between commits.
+
+use newton_2d::math::Vec2;
+use newton_2d::physics::{RevoluteJoint, RigidBody, World};
+
+/// Static box centered at (x, y)
+fn wall(world: &mut World, x: f64, y: f64, w: f64, h: f64) {
+    let (w, h) = (w / 2.0, h / 2.0);
+    let mut body = RigidBody::with_polygon(0.0, &[-w, -h, w, -h, w, h, -w, h]);
+    body.set_position(Vec2::new(x, y));
+    world.create_body(body);
+}
+
+/// count balls dropped into a walled pit
+pub fn ball_pit(count: usize) -> World {
+    let mut world = World::new();
+    let width = 40.0;
+    wall(&mut world, 0.0, -0.5, width + 2.0, 1.0);
+    wall(&mut world, -width / 2.0 - 0.5, 30.0, 1.0, 60.0);
+    wall(&mut world, width / 2.0 + 0.5, 30.0, 1.0, 60.0);
+
+    let radius = 0.25;
+    let columns = (width / (2.2 * radius)) as usize;
+    for i in 0..count {
+        let mut ball = RigidBody::with_circle(1.0, radius);
+        // Offset every other row so the balls don't stack in columns
+        let row = i / columns;
+        let x = -width / 2.0 + (i % columns) as f64 * 2.2 * radius + radius * (1.0 + (row % 2) as f64 * 0.5);
+        ball.set_position(Vec2::new(x, radius + row as f64 * 2.2 * radius));
+        world.create_body(ball);
+    }
+    world
+}
+
+/// A single column of count unit boxes resting on the ground
+pub fn box_stack(count: usize) -> World {
+    let mut world = World::new();
+    wall(&mut world, 0.0, -0.5, 20.0, 1.0);
+    for i in 0..count {
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        block.set_position(Vec2::new(0.0, 0.5 + i as f64 * 1.01));
+        world.create_body(block);
+    }
+    world
+}
+
+/// count links hanging from a static anchor by revolute joints, starting out horizontal
+pub fn joint_chain(count: usize) -> World {
+    let mut world = World::new();
+    let mut anchor = RigidBody::with_circle(0.0, 0.1);
+    anchor.set_position(Vec2::new(0.0, 0.0));
+    let mut previous = world.create_body(anchor);
+
+    let length = 0.5;
+    for i in 0..count {
+        let mut link = RigidBody::with_circle(1.0, 0.2);
+        link.set_position(Vec2::new((i as f64 + 0.5) * length, 0.0));
+        let link = world.create_body(link);
+        let anchor_a = if i == 0 { Vec2::new(0.0, 0.0) } else { Vec2::new(length / 2.0, 0.0) };
+        world.add_revolute_joint(RevoluteJoint::new(previous, link, anchor_a, Vec2::new(-length / 2.0, 0.0))).unwrap();
+        previous = link;
+    }
+    world
+}
@@ -0,0 +1,49 @@
+//! Step timings of the canonical scenes.
+//!
+//! Run with `cargo bench --bench solver`. Each scene is settled into contact, then one 60 Hz step
+//! of a copy of it is timed per iteration. `-- --save-baseline <name>` stores the results and
+//! `-- --baseline <name>` compares against them, so a collision or solver change can be checked
+//! against the commit before it. Timings depend on the machine, so compare baselines saved on
+//! the same one.
+
+mod scenes;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use newton_2d::physics::World;
+
+/// Steps taken before timing so the scene has settled into contact
+const WARMUP_STEPS: usize = 60;
+
+/// Scene name and how to build it
+type Scene = (&'static str, fn() -> World);
+
+fn settled(build: fn() -> World) -> World {
+    let mut world = build();
+    for _ in 0..WARMUP_STEPS {
+        world.step(1.0 / 60.0);
+    }
+    world
+}
+
+fn step(c: &mut Criterion) {
+    let scenes: [Scene; 3] = [
+        ("ball_pit_1000", || scenes::ball_pit(1000)),
+        ("box_stack_50", || scenes::box_stack(50)),
+        ("joint_chain_200", || scenes::joint_chain(200)),
+    ];
+
+    let mut group = c.benchmark_group("step");
+    // The ball pit takes milliseconds a step, so fewer samples keep a run short
+    group.sample_size(20);
+    for (name, build) in scenes.iter() {
+        let world = settled(*build);
+        group.bench_function(*name, |b| {
+            b.iter_batched_ref(|| world.clone(), |world| world.step(1.0 / 60.0), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+//! A chain of links hanging from revolute joints, swinging down onto a ledge.
+//!
+//! Run with `cargo run --example chain`, adding `--features window` to watch it in a window.
+
+mod common;
+
+use newton_2d::geometry::Aabb;
+use newton_2d::math::Vec2;
+use newton_2d::physics::{RevoluteJoint, RigidBody, World};
+
+fn main() {
+    let mut world = World::new();
+    let mut ledge = RigidBody::with_polygon(0.0, &[-3.0, -0.5, 3.0, -0.5, 3.0, 0.5, -3.0, 0.5]);
+    ledge.set_position(Vec2::new(3.0, -2.5));
+    world.create_body(ledge);
+
+    let mut anchor = RigidBody::with_circle(0.0, 0.1);
+    anchor.set_position(Vec2::new(-6.0, 6.0));
+    let mut previous = world.create_body(anchor);
+
+    // Links start out horizontal to the right of the anchor
+    let length = 1.0;
+    for i in 0..10 {
+        let mut link = RigidBody::with_circle(1.0, 0.3);
+        link.set_position(Vec2::new(-6.0 + (i as f64 + 0.5) * length, 6.0));
+        let link = world.create_body(link);
+        let anchor_a = if i == 0 { Vec2::new(0.0, 0.0) } else { Vec2::new(length / 2.0, 0.0) };
+        world.add_revolute_joint(RevoluteJoint::new(previous, link, anchor_a, Vec2::new(-length / 2.0, 0.0))).unwrap();
+        previous = link;
+    }
+
+    let region = Aabb::new(Vec2::new(-12.0, -6.0), Vec2::new(12.0, 8.0));
+    common::run(&mut world, &region, 400);
+}
@@ -0,0 +1,135 @@
+//! Rendering shared by the examples.
+//!
+//! Scenes are drawn from the flat command list of World::debug_draw recorded into a
+//! CommandBuffer, the same export a wasm front end draws from. Outlines are rasterized into
+//! character cells in the terminal, or into the pixels of a minifb window when built with
+//! `--features window`.
+
+use newton_2d::geometry::Aabb;
+use newton_2d::math::Vec2;
+use newton_2d::physics::debug_draw::{DRAW_CIRCLE, DRAW_POINT, DRAW_POLYGON, DRAW_SEGMENT, DRAW_TRANSFORM};
+use newton_2d::physics::{CommandBuffer, World};
+
+#[cfg(not(feature = "window"))]
+mod terminal;
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(not(feature = "window"))]
+pub use terminal::run;
+#[cfg(feature = "window")]
+pub use window::run;
+
+/// Grid of cells covering region, filled by segments drawn in world coordinates. Cells are
+/// characters in the terminal and pixels in the window.
+struct Canvas<'a, T> {
+    region: &'a Aabb,
+    columns: usize,
+    rows: usize,
+    cells: Vec<T>,
+}
+
+impl<'a, T: Copy> Canvas<'a, T> {
+    fn new(region: &'a Aabb, columns: usize, rows: usize, blank: T) -> Canvas<'a, T> {
+        Canvas {
+            region,
+            columns,
+            rows,
+            cells: vec![blank; columns * rows],
+        }
+    }
+
+    fn cell(&self, p: Vec2) -> Vec2 {
+        let q = p - self.region.min;
+        Vec2::new(
+            q.x / self.region.width() * self.columns as f64,
+            q.y / self.region.height() * self.rows as f64,
+        )
+    }
+
+    fn plot(&mut self, p: Vec2, glyph: T) {
+        let (column, row) = (p.x.floor(), p.y.floor());
+        if column >= 0.0 && row >= 0.0 && (column as usize) < self.columns && (row as usize) < self.rows {
+            // Rows are stored top down
+            self.cells[(self.rows - 1 - row as usize) * self.columns + column as usize] = glyph;
+        }
+    }
+
+    fn segment(&mut self, a: Vec2, b: Vec2, glyph: T) {
+        let (a, b) = (self.cell(a), self.cell(b));
+        let steps = (b - a).x.abs().max((b - a).y.abs()).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            self.plot(a + (b - a) * (i as f64 / steps as f64), glyph);
+        }
+    }
+
+    fn polygon(&mut self, vertices: &[Vec2], glyph: T) {
+        for (i, &a) in vertices.iter().enumerate() {
+            self.segment(a, vertices[(i + 1) % vertices.len()], glyph);
+        }
+    }
+
+    /// Draw the commands of a CommandBuffer, styling each primitive by its color code
+    fn draw(&mut self, commands: &[f64], style: impl Fn(f64) -> Option<T>) {
+        let point = |i: usize| Vec2::new(commands[i], commands[i + 1]);
+        let mut i = 0;
+        while i < commands.len() {
+            let code = commands[i];
+            if code == DRAW_TRANSFORM {
+                i += 4;
+                continue;
+            }
+            let glyph = style(commands[i + 1]);
+            if code == DRAW_SEGMENT {
+                if let Some(g) = glyph {
+                    self.segment(point(i + 2), point(i + 4), g);
+                }
+                i += 6;
+            } else if code == DRAW_POLYGON {
+                let n = commands[i + 2] as usize;
+                if let Some(g) = glyph {
+                    let vertices: Vec<Vec2> = (0..n).map(|k| point(i + 3 + 2 * k)).collect();
+                    self.polygon(&vertices, g);
+                }
+                i += 3 + 2 * n;
+            } else if code == DRAW_CIRCLE {
+                if let Some(g) = glyph {
+                    let (center, radius) = (point(i + 2), commands[i + 4]);
+                    let vertices: Vec<Vec2> = (0..24)
+                        .map(|k| {
+                            let angle = k as f64 / 24.0 * std::f64::consts::TAU;
+                            center + Vec2::new(angle.cos(), angle.sin()) * radius
+                        })
+                        .collect();
+                    self.polygon(&vertices, g);
+                }
+                i += 5;
+            } else if code == DRAW_POINT {
+                if let Some(g) = glyph {
+                    let p = self.cell(point(i + 2));
+                    self.plot(p, g);
+                }
+                i += 4;
+            } else {
+                panic!("Unknown draw command {}", code);
+            }
+        }
+    }
+}
+
+/// Canvas of columns by rows cells showing the debug outlines of world inside region, with each
+/// primitive styled by its color code
+fn draw_world<'a, T: Copy>(
+    world: &World,
+    region: &'a Aabb,
+    columns: usize,
+    rows: usize,
+    blank: T,
+    style: impl Fn(f64) -> Option<T>,
+) -> Canvas<'a, T> {
+    let mut buffer = CommandBuffer::new();
+    world.debug_draw(&mut buffer);
+    let mut canvas = Canvas::new(region, columns, rows, blank);
+    canvas.draw(buffer.commands(), style);
+    canvas
+}
@@ -0,0 +1,55 @@
+//! Drawing into the terminal, the default.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use newton_2d::geometry::Aabb;
+use newton_2d::physics::{DebugColor, World};
+
+use super::Canvas;
+
+const COLUMNS: usize = 80;
+const ROWS: usize = 30;
+
+/// Character a primitive is drawn with, or None for ones left out to keep the picture readable
+fn glyph(color: f64) -> Option<char> {
+    let glyphs = [
+        (DebugColor::StaticBody, '#'),
+        (DebugColor::DynamicBody, 'o'),
+        (DebugColor::Contact, '*'),
+        (DebugColor::Joint, '+'),
+        (DebugColor::Spring, '~'),
+    ];
+    glyphs.iter().find(|(c, _)| *c as u8 as f64 == color).map(|(_, g)| *g)
+}
+
+impl<'a> Canvas<'a, char> {
+    fn into_string(self) -> String {
+        let mut out = String::with_capacity((self.columns + 1) * self.rows);
+        for row in self.cells.chunks(self.columns) {
+            out.extend(row);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Draw the debug outlines of world inside region: static bodies as '#', dynamic ones as 'o',
+/// contacts as '*', joints as '+' and springs as '~'
+fn render(world: &World, region: &Aabb) -> String {
+    super::draw_world(world, region, COLUMNS, ROWS, ' ', glyph).into_string()
+}
+
+/// Step world for the given number of 60 Hz frames, redrawing the terminal after each
+pub fn run(world: &mut World, region: &Aabb, frames: usize) {
+    let stdout = io::stdout();
+    for frame in 0..frames {
+        world.step(1.0 / 60.0);
+        let mut out = stdout.lock();
+        // Clear the screen and move the cursor home
+        writeln!(out, "\x1b[2J\x1b[H{}frame {}/{}", render(world, region), frame + 1, frames).unwrap();
+        out.flush().unwrap();
+        thread::sleep(Duration::from_millis(16));
+    }
+}
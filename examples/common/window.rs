@@ -0,0 +1,41 @@
+//! Drawing into a minifb window, with `--features window`.
+
+use minifb::{Key, Window, WindowOptions};
+
+use newton_2d::geometry::Aabb;
+use newton_2d::physics::{DebugColor, World};
+
+/// Size of the window in pixels
+const WINDOW_SIZE: (usize, usize) = (960, 600);
+
+/// RGB pixel color a primitive is drawn with, or None for ones left out
+fn pixel(color: f64) -> Option<u32> {
+    let pixels = [
+        (DebugColor::StaticBody, 0x8a8a8a),
+        (DebugColor::DynamicBody, 0x4fa3e0),
+        (DebugColor::Contact, 0xe05a4f),
+        (DebugColor::Joint, 0x6fd06f),
+        (DebugColor::Spring, 0xe0c04f),
+    ];
+    pixels.iter().find(|(c, _)| *c as u8 as f64 == color).map(|(_, p)| *p)
+}
+
+/// Step world at 60 Hz in a window showing region until it is closed or Escape is pressed,
+/// stopping the simulation after the given number of frames
+pub fn run(world: &mut World, region: &Aabb, frames: usize) {
+    let (width, height) = WINDOW_SIZE;
+    let mut window = Window::new("newton-2d", width, height, WindowOptions::default())
+        .unwrap_or_else(|e| panic!("Can't open a window: {}", e));
+    window.set_target_fps(60);
+    let mut frame = 0;
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        if frame < frames {
+            world.step(1.0 / 60.0);
+            frame += 1;
+        }
+        let canvas = super::draw_world(world, region, width, height, 0x000000, pixel);
+        window
+            .update_with_buffer(&canvas.cells, width, height)
+            .unwrap_or_else(|e| panic!("Can't draw to the window: {}", e));
+    }
+}
@@ -0,0 +1,31 @@
+//! A pyramid of boxes settling on the ground.
+//!
+//! Run with `cargo run --example pyramid`, adding `--features window` to watch it in a window.
+
+mod common;
+
+use newton_2d::geometry::Aabb;
+use newton_2d::math::Vec2;
+use newton_2d::physics::{RigidBody, World};
+
+fn main() {
+    let mut world = World::new();
+    let mut ground = RigidBody::with_polygon(0.0, &[-20.0, -1.0, 20.0, -1.0, 20.0, 0.0, -20.0, 0.0]);
+    ground.set_position(Vec2::new(0.0, 0.0));
+    world.create_body(ground);
+
+    let size = 1.0;
+    let base = 6;
+    for row in 0..base {
+        for i in 0..(base - row) {
+            let h = size / 2.0;
+            let mut block = RigidBody::with_polygon(1.0, &[-h, -h, h, -h, h, h, -h, h]);
+            let x = (i as f64 - (base - row - 1) as f64 / 2.0) * size * 1.05;
+            block.set_position(Vec2::new(x, h + row as f64 * size * 1.02));
+            world.create_body(block);
+        }
+    }
+
+    let region = Aabb::new(Vec2::new(-8.0, -1.0), Vec2::new(8.0, 9.0));
+    common::run(&mut world, &region, 300);
+}
@@ -0,0 +1,107 @@
+//! Single precision boxes for the broadphases.
+//!
+//! Broadphase boxes are only used to find candidate pairs, so they don't need the precision of
+//! the narrowphase. Storing them as f32 halves their size, which keeps more of the tree in cache
+//! for large worlds. Converting from f64 rounds outward, so a stored box always contains the box
+//! it was made from and no overlapping pair is ever missed.
+
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Aabb32 {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+/// Largest f32 no greater than x
+fn round_down(x: f64) -> f32 {
+    let f = x as f32;
+    if f as f64 > x {
+        f.next_down()
+    } else {
+        f
+    }
+}
+
+/// Smallest f32 no less than x
+fn round_up(x: f64) -> f32 {
+    let f = x as f32;
+    if (f as f64) < x {
+        f.next_up()
+    } else {
+        f
+    }
+}
+
+impl Aabb32 {
+    /// Smallest f32 box containing aabb
+    pub fn enclosing(aabb: &Aabb) -> Aabb32 {
+        Aabb32 {
+            min: [round_down(aabb.min.x), round_down(aabb.min.y)],
+            max: [round_up(aabb.max.x), round_up(aabb.max.y)],
+        }
+    }
+
+    /// The same box in double precision, which is exact
+    pub fn to_aabb(&self) -> Aabb {
+        Aabb::new(
+            Vec2::new(self.min[0] as f64, self.min[1] as f64),
+            Vec2::new(self.max[0] as f64, self.max[1] as f64),
+        )
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        2.0 * ((self.max[0] - self.min[0]) + (self.max[1] - self.min[1]))
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb32) -> Aabb32 {
+        Aabb32 {
+            min: [self.min[0].min(other.min[0]), self.min[1].min(other.min[1])],
+            max: [self.max[0].max(other.max[0]), self.max[1].max(other.max[1])],
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb32) -> bool {
+        self.min[0] <= other.max[0]
+            && self.max[0] >= other.min[0]
+            && self.min[1] <= other.max[1]
+            && self.max[1] >= other.min[1]
+    }
+
+    /// True if other lies entirely inside this box
+    pub fn contains(&self, other: &Aabb32) -> bool {
+        self.min[0] <= other.min[0] && self.min[1] <= other.min[1] && self.max[0] >= other.max[0] && self.max[1] >= other.max[1]
+    }
+
+    /// Distance from p to the closest point of the box, never more than to the box it encloses
+    pub fn distance_to_point(&self, p: &Vec2) -> f64 {
+        self.to_aabb().distance_to_point(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_outward() {
+        let aabb = Aabb::new(Vec2::new(0.1, -0.3), Vec2::new(1e8 + 1.0, 1.0 / 3.0));
+        let rounded = Aabb32::enclosing(&aabb);
+
+        assert!(rounded.to_aabb().contains(&aabb));
+        assert!(rounded.to_aabb().width() - aabb.width() <= 16.0);
+        // Representable values stay exact
+        let exact = Aabb::new(Vec2::new(-1.5, 0.0), Vec2::new(2.0, 0.25));
+        assert_eq!(Aabb32::enclosing(&exact).to_aabb(), exact);
+    }
+
+    #[test]
+    fn test_touching_boxes_overlap() {
+        let a = Aabb32::enclosing(&Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(0.1, 0.1)));
+        let b = Aabb32::enclosing(&Aabb::new(Vec2::new(0.1, 0.0), Vec2::new(0.2, 0.1)));
+        assert!(a.overlaps(&b));
+        assert!(a.union(&b).contains(&a));
+    }
+}
@@ -0,0 +1,666 @@
+//! Broadphases finding bodies whose boxes overlap.
+//!
+//! The default is a dynamic bounding volume hierarchy, which suits bodies of any size. Worlds of
+//! many similar small bodies can use the SpatialHash grid instead, and worlds whose bodies move
+//! smoothly the SweepAndPrune list, all through the Broadphase trait, which other structures can
+//! implement too.
+//! Both store their boxes in single precision, see Aabb32.
+//!
+//! Leaves store a fattened AABB and a user value, usually a body index. Moving a leaf only
+//! reinserts it once its tight box leaves the fat one, so slowly moving bodies rarely touch the
+//! tree. New leaves are placed next to the sibling that grows the tree's total perimeter least,
+//! and tree rotations keep the heights of siblings within one of each other.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use super::aabb32::Aabb32;
+use super::spatial_hash::SpatialHash;
+use super::sweep_and_prune::SweepAndPrune;
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+
+/// Margin leaves are fattened by so small movements don't need reinserting
+pub const FAT_MARGIN: f64 = 0.1;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadphaseKind {
+    /// Bounding volume hierarchy, good for bodies of any size
+    Bvh,
+    /// Uniform grid, faster for many bodies about the size of a cell
+    SpatialHash,
+    /// Boxes sorted along x, fast when bodies move a little each step
+    SweepAndPrune,
+}
+
+/// Which broadphase a World uses
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BroadphaseConfig {
+    pub kind: BroadphaseKind,
+    /// Cell size of the spatial hash, about the size of a typical body
+    pub cell_size: f64,
+}
+
+impl Default for BroadphaseConfig {
+    fn default() -> BroadphaseConfig {
+        BroadphaseConfig::new()
+    }
+}
+
+#[wasm_bindgen]
+impl BroadphaseConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BroadphaseConfig {
+        BroadphaseConfig {
+            kind: BroadphaseKind::Bvh,
+            cell_size: 1.0,
+        }
+    }
+}
+
+impl BroadphaseConfig {
+    /// An empty broadphase of the configured kind
+    pub fn build(&self) -> Box<dyn Broadphase> {
+        match self.kind {
+            BroadphaseKind::Bvh => Box::new(Bvh::new(FAT_MARGIN)),
+            BroadphaseKind::SpatialHash => Box::new(SpatialHash::new(self.cell_size, FAT_MARGIN)),
+            BroadphaseKind::SweepAndPrune => Box::new(SweepAndPrune::new(FAT_MARGIN)),
+        }
+    }
+}
+
+/// A structure storing boxes with a user value each, finding the ones that overlap.
+/// World keeps one proxy per body, with the body's index as its user value.
+/// Implement this to give a World a structure suited to its scene, see World::set_broadphase.
+pub trait Broadphase: BroadphaseClone + fmt::Debug {
+    /// Number of boxes
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self);
+
+    /// Add a box, returning a proxy id used to update or remove it
+    fn insert(&mut self, aabb: Aabb, data: usize) -> usize;
+
+    fn remove(&mut self, proxy: usize);
+
+    /// Move a box, returning true if the structure had to change
+    fn update(&mut self, proxy: usize, aabb: Aabb) -> bool;
+
+    /// User value stored with a box
+    fn data(&self, proxy: usize) -> usize;
+
+    fn set_data(&mut self, proxy: usize, data: usize);
+
+    /// User values of all boxes overlapping aabb. Extra values are allowed, they are filtered later.
+    fn query(&self, aabb: &Aabb) -> Vec<usize>;
+
+    /// Pairs of user values whose boxes overlap, smaller value first, in sorted order
+    fn pairs(&self) -> Vec<(usize, usize)>;
+
+    /// The k boxes nearest to point, closest first. See Bvh::nearest and nearest_by_bounds.
+    fn nearest(&self, point: &Vec2, k: usize, distance: &mut dyn FnMut(usize) -> Option<f64>) -> Vec<(usize, f64)>;
+}
+
+/// Cloning boxed broadphases, implemented for every Broadphase that is Clone
+pub trait BroadphaseClone {
+    fn clone_box(&self) -> Box<dyn Broadphase>;
+}
+
+impl<T: Broadphase + Clone + 'static> BroadphaseClone for T {
+    fn clone_box(&self) -> Box<dyn Broadphase> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Broadphase> {
+    fn clone(&self) -> Box<dyn Broadphase> {
+        self.clone_box()
+    }
+}
+
+impl Default for Box<dyn Broadphase> {
+    fn default() -> Box<dyn Broadphase> {
+        BroadphaseConfig::new().build()
+    }
+}
+
+macro_rules! impl_broadphase {
+    ($t:ty) => {
+        impl Broadphase for $t {
+            fn len(&self) -> usize {
+                <$t>::len(self)
+            }
+
+            fn clear(&mut self) {
+                <$t>::clear(self)
+            }
+
+            fn insert(&mut self, aabb: Aabb, data: usize) -> usize {
+                <$t>::insert(self, aabb, data)
+            }
+
+            fn remove(&mut self, proxy: usize) {
+                <$t>::remove(self, proxy)
+            }
+
+            fn update(&mut self, proxy: usize, aabb: Aabb) -> bool {
+                <$t>::update(self, proxy, aabb)
+            }
+
+            fn data(&self, proxy: usize) -> usize {
+                <$t>::data(self, proxy)
+            }
+
+            fn set_data(&mut self, proxy: usize, data: usize) {
+                <$t>::set_data(self, proxy, data)
+            }
+
+            fn query(&self, aabb: &Aabb) -> Vec<usize> {
+                <$t>::query(self, aabb)
+            }
+
+            fn pairs(&self) -> Vec<(usize, usize)> {
+                <$t>::pairs(self)
+            }
+
+            fn nearest(
+                &self,
+                point: &Vec2,
+                k: usize,
+                distance: &mut dyn FnMut(usize) -> Option<f64>,
+            ) -> Vec<(usize, f64)> {
+                <$t>::nearest(self, point, k, distance)
+            }
+        }
+    };
+}
+
+impl_broadphase!(Bvh);
+impl_broadphase!(SpatialHash);
+impl_broadphase!(SweepAndPrune);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    aabb: Aabb32,
+    parent: Option<usize>,
+    children: Option<[usize; 2]>,
+    data: usize,
+    height: usize,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.children.is_none()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    root: Option<usize>,
+    free: Vec<usize>,
+    leaves: usize,
+    margin: f64,
+}
+
+impl Default for Bvh {
+    fn default() -> Bvh {
+        Bvh::new(FAT_MARGIN)
+    }
+}
+
+impl Bvh {
+    pub fn new(margin: f64) -> Bvh {
+        Bvh {
+            nodes: Vec::new(),
+            root: None,
+            free: Vec::new(),
+            leaves: 0,
+            margin,
+        }
+    }
+
+    /// Number of leaves
+    pub fn len(&self) -> usize {
+        self.leaves
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = None;
+        self.leaves = 0;
+    }
+
+    /// Height of the tree, 0 for a single leaf
+    pub fn height(&self) -> usize {
+        self.root.map_or(0, |r| self.nodes[r].height)
+    }
+
+    /// Add a leaf, returning a proxy id used to update or remove it
+    pub fn insert(&mut self, aabb: Aabb, data: usize) -> usize {
+        let leaf = self.allocate(Node {
+            aabb: Aabb32::enclosing(&aabb.expand(self.margin)),
+            parent: None,
+            children: None,
+            data,
+            height: 0,
+        });
+        self.insert_leaf(leaf);
+        self.leaves += 1;
+        leaf
+    }
+
+    pub fn remove(&mut self, proxy: usize) {
+        debug_assert!(self.nodes[proxy].is_leaf());
+        self.remove_leaf(proxy);
+        self.free.push(proxy);
+        self.leaves -= 1;
+    }
+
+    /// Move a leaf to a new box, returning true if it had to be reinserted
+    pub fn update(&mut self, proxy: usize, aabb: Aabb) -> bool {
+        if self.nodes[proxy].aabb.contains(&Aabb32::enclosing(&aabb)) {
+            return false;
+        }
+        self.remove_leaf(proxy);
+        self.nodes[proxy].aabb = Aabb32::enclosing(&aabb.expand(self.margin));
+        self.insert_leaf(proxy);
+        true
+    }
+
+    /// User value stored with a leaf
+    pub fn data(&self, proxy: usize) -> usize {
+        self.nodes[proxy].data
+    }
+
+    pub fn set_data(&mut self, proxy: usize, data: usize) {
+        self.nodes[proxy].data = data;
+    }
+
+    /// Fattened box stored for a leaf
+    pub fn fat_aabb(&self, proxy: usize) -> Aabb {
+        self.nodes[proxy].aabb.to_aabb()
+    }
+
+    /// User values of all leaves whose fat box overlaps aabb
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let aabb = &Aabb32::enclosing(aabb);
+        let mut out = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !node.aabb.overlaps(aabb) {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend_from_slice(&children),
+                None => out.push(node.data),
+            }
+        }
+        out
+    }
+
+    /// Pairs of user values whose fat boxes overlap, smaller value first, in sorted order
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            match node.children {
+                Some(children) => stack.extend_from_slice(&children),
+                None => out.extend(
+                    self.query(&node.aabb.to_aabb())
+                        .into_iter()
+                        .filter(|&other| other > node.data)
+                        .map(|other| (node.data, other)),
+                ),
+            }
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// The k leaves nearest to point, closest first.
+    /// distance gives the exact distance for a leaf's user value, or None to skip the leaf.
+    /// It must never be less than the distance to the leaf's box.
+    pub fn nearest<F: FnMut(usize) -> Option<f64>>(&self, point: &Vec2, k: usize, mut distance: F) -> Vec<(usize, f64)> {
+        let mut out = Vec::with_capacity(k);
+        let mut heap = BinaryHeap::new();
+        if let Some(root) = self.root {
+            heap.push(Candidate::bound(root, self.nodes[root].aabb.distance_to_point(point)));
+        }
+
+        while out.len() < k {
+            let candidate = match heap.pop() {
+                Some(c) => c,
+                None => break,
+            };
+            let node = &self.nodes[candidate.node];
+            if candidate.exact {
+                out.push((node.data, candidate.distance));
+                continue;
+            }
+            match node.children {
+                Some(children) => {
+                    for c in &children {
+                        heap.push(Candidate::bound(*c, self.nodes[*c].aabb.distance_to_point(point)));
+                    }
+                }
+                None => {
+                    if let Some(d) = distance(node.data) {
+                        heap.push(Candidate {
+                            distance: d,
+                            node: candidate.node,
+                            exact: true,
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(leaf);
+                self.nodes[leaf].parent = None;
+                return;
+            }
+        };
+
+        // Descend towards the cheapest sibling
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = root;
+        while let Some([c1, c2]) = self.nodes[index].children {
+            let area = self.nodes[index].aabb.perimeter();
+            let combined = self.nodes[index].aabb.union(&leaf_aabb).perimeter();
+            let cost = 2.0 * combined;
+            let inheritance = 2.0 * (combined - area);
+
+            let child_cost = |c: usize| {
+                let node = &self.nodes[c];
+                let grown = node.aabb.union(&leaf_aabb).perimeter();
+                if node.is_leaf() {
+                    grown + inheritance
+                } else {
+                    grown - node.aabb.perimeter() + inheritance
+                }
+            };
+            let (cost1, cost2) = (child_cost(c1), child_cost(c2));
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+            index = if cost1 < cost2 { c1 } else { c2 };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(Node {
+            aabb: self.nodes[sibling].aabb.union(&leaf_aabb),
+            parent: old_parent,
+            children: Some([sibling, leaf]),
+            data: 0,
+            height: self.nodes[sibling].height + 1,
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+        match old_parent {
+            Some(p) => self.replace_child(p, sibling, new_parent),
+            None => self.root = Some(new_parent),
+        }
+
+        self.refit(old_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if self.root == Some(leaf) {
+            self.root = None;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent.expect("non-root leaf has a parent");
+        let [c1, c2] = self.nodes[parent].children.expect("parent has children");
+        let sibling = if c1 == leaf { c2 } else { c1 };
+        let grandparent = self.nodes[parent].parent;
+
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(g) => self.replace_child(g, parent, sibling),
+            None => self.root = Some(sibling),
+        }
+        self.free.push(parent);
+        self.refit(grandparent);
+    }
+
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if let Some(children) = &mut self.nodes[parent].children {
+            for c in children.iter_mut() {
+                if *c == old {
+                    *c = new;
+                }
+            }
+        }
+    }
+
+    /// Rebalance, then recompute boxes and heights from index up to the root
+    fn refit(&mut self, mut index: Option<usize>) {
+        while let Some(i) = index {
+            let i = self.balance(i);
+            self.recompute(i);
+            index = self.nodes[i].parent;
+        }
+    }
+
+    fn recompute(&mut self, i: usize) {
+        let [c1, c2] = self.nodes[i].children.expect("internal node has children");
+        self.nodes[i].aabb = self.nodes[c1].aabb.union(&self.nodes[c2].aabb);
+        self.nodes[i].height = 1 + self.nodes[c1].height.max(self.nodes[c2].height);
+    }
+
+    /// Rotate the taller child of a up if the children's heights differ by more than one.
+    /// Returns the node now in a's place.
+    fn balance(&mut self, a: usize) -> usize {
+        let [b, c] = match self.nodes[a].children {
+            Some(children) => children,
+            None => return a,
+        };
+        let (hb, hc) = (self.nodes[b].height, self.nodes[c].height);
+        let (slot, tall) = if hc > hb + 1 {
+            (1, c)
+        } else if hb > hc + 1 {
+            (0, b)
+        } else {
+            return a;
+        };
+
+        let [f, g] = self.nodes[tall].children.expect("tall child has children");
+        let (keep, give) = if self.nodes[f].height > self.nodes[g].height { (f, g) } else { (g, f) };
+
+        // tall takes a's place, with a and its taller grandchild as children
+        let parent = self.nodes[a].parent;
+        self.nodes[tall].parent = parent;
+        match parent {
+            Some(p) => self.replace_child(p, a, tall),
+            None => self.root = Some(tall),
+        }
+        self.nodes[tall].children = Some([a, keep]);
+        self.nodes[a].parent = Some(tall);
+
+        // a adopts the shorter grandchild in place of tall
+        if let Some(children) = &mut self.nodes[a].children {
+            children[slot] = give;
+        }
+        self.nodes[give].parent = Some(a);
+
+        self.recompute(a);
+        self.recompute(tall);
+        tall
+    }
+}
+
+/// The k boxes nearest to point by checking every box, closest first. See Bvh::nearest.
+pub fn nearest_by_bounds<I, F>(boxes: I, point: &Vec2, k: usize, mut distance: F) -> Vec<(usize, f64)>
+where
+    I: Iterator<Item = (Aabb32, usize)>,
+    F: FnMut(usize) -> Option<f64>,
+{
+    let mut bounds: Vec<(f64, usize)> = boxes.map(|(aabb, data)| (aabb.distance_to_point(point), data)).collect();
+    bounds.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Visit boxes by distance until the next box is further than the k-th result
+    let mut out: Vec<(usize, f64)> = Vec::with_capacity(k + 1);
+    for (bound, data) in bounds {
+        if out.len() == k && out.last().is_some_and(|&(_, d)| bound > d) {
+            break;
+        }
+        if let Some(d) = distance(data) {
+            let at = out.partition_point(|&(_, other)| other <= d);
+            out.insert(at, (data, d));
+            out.truncate(k);
+        }
+    }
+    out
+}
+
+/// Entry in the nearest neighbour search, ordered so the closest pops first
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Candidate {
+    distance: f64,
+    node: usize,
+    /// distance is exact rather than a lower bound
+    exact: bool,
+}
+
+impl Candidate {
+    fn bound(node: usize, distance: f64) -> Candidate {
+        Candidate {
+            distance,
+            node,
+            exact: false,
+        }
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        // Exact entries win ties so results aren't delayed behind equal bounds
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then(self.exact.cmp(&other.exact))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::test_util::unit_box;
+
+    fn grid() -> Bvh {
+        let mut bvh = Bvh::new(0.0);
+        for i in 0..10 {
+            for j in 0..10 {
+                bvh.insert(unit_box(2.0 * i as f64, 2.0 * j as f64), i * 10 + j);
+            }
+        }
+        bvh
+    }
+
+    #[test]
+    fn test_query() {
+        let bvh = grid();
+        let mut hits = bvh.query(&Aabb::new(Vec2::new(1.5, 1.5), Vec2::new(2.5, 2.5)));
+        hits.sort_unstable();
+
+        assert_eq!(bvh.len(), 100);
+        assert_eq!(hits, vec![11]);
+        assert!(bvh.height() <= 10);
+        for d in 0..100 {
+            let b = unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64);
+            assert_eq!(bvh.query(&b), vec![d]);
+        }
+    }
+
+    #[test]
+    fn test_remove_and_update() {
+        let mut bvh = Bvh::default();
+        let a = bvh.insert(unit_box(0.0, 0.0), 0);
+        let b = bvh.insert(unit_box(5.0, 0.0), 1);
+
+        assert!(!bvh.update(a, unit_box(0.05, 0.0)));
+        assert!(bvh.update(a, unit_box(5.0, 0.0)));
+        let mut hits = bvh.query(&unit_box(5.0, 0.0));
+        hits.sort_unstable();
+        assert_eq!(hits, vec![0, 1]);
+
+        bvh.remove(b);
+        assert_eq!(bvh.query(&unit_box(5.0, 0.0)), vec![0]);
+        assert_eq!(bvh.len(), 1);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let bvh = grid();
+        let point = Vec2::new(4.5, 4.5);
+        let boxes: Vec<Aabb> = (0..100).map(|d| unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64)).collect();
+
+        let nearest = bvh.nearest(&point, 5, |d| Some(boxes[d].distance_to_point(&point)));
+        assert_eq!(nearest.len(), 5);
+        assert_eq!(nearest[0], (22, 0.0));
+        assert!(nearest[1..].iter().all(|(_, d)| *d == 1.5));
+    }
+
+    #[test]
+    fn test_nearest_filter() {
+        let bvh = grid();
+        let point = Vec2::new(0.5, 0.5);
+        // Skip the first column
+        let nearest = bvh.nearest(&point, 2, |d| {
+            if d < 10 {
+                None
+            } else {
+                Some(unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64).distance_to_point(&point))
+            }
+        });
+
+        let ids: Vec<usize> = nearest.iter().map(|(d, _)| *d).collect();
+        assert_eq!(ids, vec![10, 11]);
+    }
+}
@@ -1,10 +1,10 @@
 use std::f64;
 
+use wasm_bindgen::prelude::*;
+
 use crate::geometry::Shape;
 use crate::math::Vec2;
 
-use wasm_bindgen::prelude::*;
-
 /// gjk return types
 #[derive(PartialEq, Debug)]
 pub enum EvolveResult {
@@ -13,6 +13,7 @@ pub enum EvolveResult {
     NoIntersection,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 enum Winding {
     CCW,
     CW,
@@ -34,31 +35,120 @@ impl Edge {
     }
 }
 
+/// How far two shapes overlap, as found by EPA
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Penetration {
+    /// Unit direction from the first shape towards the second
+    pub normal: Vec2,
+    /// Distance along normal the shapes must move apart to stop overlapping
+    pub depth: f64,
+    /// Point of the first shape deepest inside the second
+    pub point_a: Vec2,
+    /// Point of the second shape deepest inside the first
+    pub point_b: Vec2,
+}
+
+#[wasm_bindgen]
+impl Penetration {
+    /// Penetration vector normal * depth, equal to point_a - point_b
+    pub fn vector(&self) -> Vec2 {
+        self.normal * self.depth
+    }
+}
+
+/// Closest points of two separate shapes
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClosestPoints {
+    pub distance: f64,
+    /// Unit direction from point_a towards point_b
+    pub normal: Vec2,
+    /// Point of the first shape closest to the second
+    pub point_a: Vec2,
+    /// Point of the second shape closest to the first
+    pub point_b: Vec2,
+}
+
+/// Accuracy settings for penetration depth computation
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GjkConfig {
+    /// EPA stops once the polytope grows less than this towards the closest edge
+    pub epa_tolerance: f64,
+    /// Cap on EPA expansions, trading accuracy for speed on curved shapes
+    pub epa_max_iterations: usize,
+    /// Supports closer than this to the origin count as touching, not overlapping
+    pub tolerance: f64,
+    /// Cap on GJK simplex updates, after which the shapes count as separate
+    pub max_iterations: usize,
+}
+
+impl Default for GjkConfig {
+    fn default() -> GjkConfig {
+        GjkConfig::new()
+    }
+}
+
+#[wasm_bindgen]
+impl GjkConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GjkConfig {
+        GjkConfig {
+            epa_tolerance: 0.0001,
+            epa_max_iterations: 16,
+            tolerance: 1e-9,
+            max_iterations: 32,
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct GJK {
     vertices: Vec<Vec2>,
+    /// Support points on each shape that each vertex was made from
+    witnesses: Vec<(Vec2, Vec2)>,
     direction: Vec2,
+    config: GjkConfig,
+}
+
+impl Default for GJK {
+    fn default() -> GJK {
+        GJK::new()
+    }
 }
 
 impl GJK {
     pub fn new() -> GJK {
+        GJK::with_config(GjkConfig::new())
+    }
+
+    pub fn with_config(config: GjkConfig) -> GJK {
         GJK {
             vertices: Vec::with_capacity(3),
+            witnesses: Vec::with_capacity(3),
             direction: Vec2::new(0.0, 0.0),
+            config,
         }
     }
 
     pub fn test<T: Shape, U: Shape>(&mut self, a: &T, b: &U) -> bool {
         self.vertices = Vec::with_capacity(3);
+        self.witnesses = Vec::with_capacity(3);
 
-        let mut result = EvolveResult::StillEvolving;
-        while result == EvolveResult::StillEvolving {
-            result = self.evolve_simplex(a, b);
+        for _ in 0..self.config.max_iterations {
+            match self.evolve_simplex(a, b) {
+                EvolveResult::StillEvolving => continue,
+                result => return result == EvolveResult::Intersection,
+            }
         }
-        result == EvolveResult::Intersection
+
+        log::warn!("GJK did not converge, treating shapes as separate");
+        false
     }
 
     // https://blog.hamaluik.ca/posts/building-a-collision-engine-part-2-2d-penetration-vectors/
-    pub fn intersect<T: Shape, U: Shape>(&mut self, a: &T, b: &U) -> Option<Vec2> {
+    pub fn intersect<T: Shape, U: Shape>(&mut self, a: &T, b: &U) -> Option<Penetration> {
         if !self.test(a, b) {
             return None;
         }
@@ -72,43 +162,200 @@ impl GJK {
             false => Winding::CCW,
         };
 
-        let mut intersection = Vec2::new(0.0, 0.0);
+        let mut penetration = None;
 
-        for _ in 0..16 {
+        for _ in 0..self.config.epa_max_iterations {
             let edge = self.find_closest_edge(&winding);
-            let support = a.support(&edge.normal) - b.support(&-edge.normal);
+            let witness = (a.support(&edge.normal), b.support(&-edge.normal));
+            let support = witness.0 - witness.1;
             let distance = Vec2::dot(&support, &edge.normal);
 
-            intersection = edge.normal * distance;
+            let (point_a, point_b) = self.edge_witnesses(&edge);
+            penetration = Some(Penetration {
+                normal: edge.normal,
+                depth: distance,
+                point_a,
+                point_b,
+            });
 
-            if (distance - edge.distance).abs() <= 0.0001 {
-                return Some(intersection);
+            if (distance - edge.distance).abs() <= self.config.epa_tolerance {
+                return penetration;
             } else {
                 self.vertices.insert(edge.index, support);
+                self.witnesses.insert(edge.index, witness);
             }
         }
 
-        Some(intersection)
+        log::warn!("EPA did not converge, penetration vector may be inaccurate");
+        penetration
+    }
+
+    /// Distance between two convex shapes with their closest points, None if they touch or overlap
+    pub fn distance<T: Shape, U: Shape>(&mut self, a: &T, b: &U) -> Option<ClosestPoints> {
+        let mut direction = b.center() - a.center();
+        if direction.len() <= self.config.tolerance {
+            direction = Vec2::new(1.0, 0.0);
+        }
+        let witness = (a.support(&direction), b.support(&-direction));
+        self.vertices = vec![witness.0 - witness.1];
+        self.witnesses = vec![witness];
+        let (mut closest, mut point_a, mut point_b) = (self.vertices[0], witness.0, witness.1);
+
+        for _ in 0..self.config.max_iterations {
+            if closest.len() <= self.config.tolerance {
+                return None;
+            }
+            let witness = (a.support(&-closest), b.support(&closest));
+            let vertex = witness.0 - witness.1;
+            // Stop once the new support gets no closer to the origin
+            if closest.len_sq() - Vec2::dot(&closest, &vertex) <= self.config.tolerance * closest.len() {
+                break;
+            }
+            self.vertices.push(vertex);
+            self.witnesses.push(witness);
+            let (c, pa, pb) = self.closest_on_simplex()?;
+            closest = c;
+            point_a = pa;
+            point_b = pb;
+        }
+
+        let distance = closest.len();
+        if distance <= self.config.tolerance {
+            return None;
+        }
+        Some(ClosestPoints {
+            distance,
+            normal: -closest / distance,
+            point_a,
+            point_b,
+        })
+    }
+
+    /// Point of the simplex closest to the origin with the points on each shape making it up.
+    /// The simplex is reduced to the vertices needed for that point. None if it holds the origin.
+    fn closest_on_simplex(&mut self) -> Option<(Vec2, Vec2, Vec2)> {
+        let edges: &[(usize, usize)] = match self.vertices.len() {
+            2 => &[(0, 1)],
+            _ => {
+                let (a, b, c) = (self.vertices[0], self.vertices[1], self.vertices[2]);
+                let area = Vec2::cross(&(b - a), &(c - a));
+                let sides = [
+                    Vec2::cross(&(b - a), &-a),
+                    Vec2::cross(&(c - b), &-b),
+                    Vec2::cross(&(a - c), &-c),
+                ];
+                if area.abs() > self.config.tolerance && sides.iter().all(|side| side * area >= 0.0) {
+                    return None;
+                }
+                &[(0, 1), (1, 2), (0, 2)]
+            }
+        };
+
+        let (i, j, t) = edges
+            .iter()
+            .map(|&(i, j)| (i, j, self.closest_on_edge(i, j)))
+            .min_by(|x, y| {
+                let dx = self.vertices[x.0] + (self.vertices[x.1] - self.vertices[x.0]) * x.2;
+                let dy = self.vertices[y.0] + (self.vertices[y.1] - self.vertices[y.0]) * y.2;
+                dx.len_sq().total_cmp(&dy.len_sq())
+            })
+            .unwrap();
+
+        let (p, q) = (self.vertices[i], self.vertices[j]);
+        let ((ai, bi), (aj, bj)) = (self.witnesses[i], self.witnesses[j]);
+        let closest = (p + (q - p) * t, ai + (aj - ai) * t, bi + (bj - bi) * t);
+        let keep: Vec<usize> = match t {
+            t if t <= 0.0 => vec![i],
+            t if t >= 1.0 => vec![j],
+            _ => vec![i, j],
+        };
+        self.vertices = keep.iter().map(|&k| self.vertices[k]).collect();
+        self.witnesses = keep.iter().map(|&k| self.witnesses[k]).collect();
+        Some(closest)
+    }
+
+    /// Fraction along the simplex edge from vertex i to j of its point closest to the origin
+    fn closest_on_edge(&self, i: usize, j: usize) -> f64 {
+        let p = self.vertices[i];
+        let along = self.vertices[j] - p;
+        if along.len_sq() > 0.0 {
+            (-Vec2::dot(&p, &along) / along.len_sq()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Points on each shape making up the point of edge closest to the origin
+    fn edge_witnesses(&self, edge: &Edge) -> (Vec2, Vec2) {
+        let j = edge.index;
+        let i = if j == 0 { self.vertices.len() - 1 } else { j - 1 };
+        let t = self.closest_on_edge(i, j);
+        let (ai, bi) = self.witnesses[i];
+        let (aj, bj) = self.witnesses[j];
+        (ai + (aj - ai) * t, bi + (bj - bi) * t)
+    }
+
+    fn remove_vertex(&mut self, index: usize) {
+        self.vertices.remove(index);
+        self.witnesses.remove(index);
     }
 
     fn add_support<T: Shape, U: Shape>(&mut self, a: &T, b: &U) -> bool {
-        let new_vertex = a.support(&self.direction) - b.support(&-self.direction);
+        let witness = (a.support(&self.direction), b.support(&-self.direction));
+        let new_vertex = witness.0 - witness.1;
         self.vertices.push(new_vertex);
-        Vec2::dot(&self.direction, &new_vertex) > 0.0
+        self.witnesses.push(witness);
+        Vec2::dot(&self.direction, &new_vertex) > self.config.tolerance * self.direction.len()
+    }
+
+    /// Direction from the edge cb towards the origin, or either normal if the origin is on its line
+    fn edge_direction(&self, b: Vec2, c: Vec2) -> Vec2 {
+        let cb = b - c;
+        let direction = Vec2::triple_product(&cb, &-c, &cb);
+        if direction.len() > self.config.tolerance {
+            direction
+        } else {
+            cb.perp()
+        }
+    }
+
+    /// Whether the origin, inside the triangle simplex, is within tolerance of the outline of the
+    /// Minkowski difference. Only triangle edges that close to the origin need checking, by
+    /// looking for a support further out past them.
+    fn touching<T: Shape, U: Shape>(&self, a: &T, b: &U) -> bool {
+        (0..3).any(|i| {
+            let (p, q, opposite) = (self.vertices[i], self.vertices[(i + 1) % 3], self.vertices[(i + 2) % 3]);
+            let mut normal = (q - p).perp().normalize();
+            if Vec2::dot(&normal, &(opposite - p)) > 0.0 {
+                normal = -normal;
+            }
+            if Vec2::dot(&normal, &p) > self.config.tolerance {
+                return false;
+            }
+            let support = a.support(&normal) - b.support(&-normal);
+            Vec2::dot(&normal, &support) <= self.config.tolerance
+        })
     }
 
     pub fn evolve_simplex<T: Shape, U: Shape>(&mut self, shape_a: &T, shape_b: &U) -> EvolveResult {
         match self.vertices.len() {
-            0 => self.direction = shape_b.center() - shape_a.center(),
+            0 => {
+                self.direction = shape_b.center() - shape_a.center();
+                if self.direction.len() <= self.config.tolerance {
+                    // coincident centers, any direction will do
+                    self.direction = Vec2::new(1.0, 0.0);
+                }
+            }
             1 => self.direction = -self.direction,
             2 => {
                 let b = self.vertices[1];
                 let c = self.vertices[0];
+                if (b - c).len() <= self.config.tolerance {
+                    // both supports are the same point, which isn't the origin
+                    return EvolveResult::NoIntersection;
+                }
 
-                let cb= b - c;
-                let c0 = -c;
-
-                self.direction = Vec2::triple_product(&cb, &c0, &cb);
+                self.direction = self.edge_direction(b, c);
             }
             3 => {
                 let a = self.vertices[2];
@@ -122,23 +369,35 @@ impl GJK {
                 let ab_perp = Vec2::triple_product(&ac, &ab, &ab);
                 let ac_perp = Vec2::triple_product(&ab, &ac, &ac);
 
-                if Vec2::dot(&ab_perp, &a0) > 0.0 {
+                if Vec2::cross(&ab, &ac).abs() <= self.config.tolerance {
+                    // a flat triangle can't enclose the origin, keep the newest edge
+                    self.remove_vertex(0);
+                    self.direction = self.edge_direction(a, b);
+                } else if Vec2::dot(&ab_perp, &a0) > 0.0 {
                     // the origin is outside line ab
                     // get rid of c and add a new support in the direction of abPerp
-                    self.vertices.remove(0);
+                    self.remove_vertex(0);
                     self.direction = ab_perp;
                 } else if Vec2::dot(&ac_perp, &a0) > 0.0 {
                     // the origin is outside line ac
                     // get rid of b and add a new support in the direction of acPerp
-                    self.vertices.remove(1);
+                    self.remove_vertex(1);
                     self.direction = ac_perp;
+                } else if self.touching(shape_a, shape_b) {
+                    // the origin is on the outline of the difference, so the shapes only touch
+                    return EvolveResult::NoIntersection;
                 } else {
                     // the origin is inside both ab and ac,
                     // so it must be inside the triangle!
                     return EvolveResult::Intersection;
                 }
             }
-            _ => panic!("Can't have simplex with _ verts!"),
+            _ => {
+                // left over from a previous query, start again
+                self.vertices.clear();
+                self.witnesses.clear();
+                return EvolveResult::StillEvolving;
+            }
         };
 
         match self.add_support(shape_a, shape_b) {
@@ -148,7 +407,7 @@ impl GJK {
     }
 
     fn find_closest_edge(&self, winding: &Winding) -> Edge {
-        let mut closest_distance = std::f64::MAX;
+        let mut closest_distance = f64::MAX;
         let mut closest_normal = Vec2::new(0.0, 0.0);
         let mut closest_index = 0;
     
@@ -161,8 +420,8 @@ impl GJK {
             let edge = self.vertices[j] - self.vertices[i];
     
             let mut norm = match winding {
-                Winding::CW => Vec2::new(-edge.y, edge.x),
-                Winding::CCW => Vec2::new(edge.y, -edge.x),
+                Winding::CW => edge.perp(),
+                Winding::CCW => -edge.perp(),
             };
             norm = norm.normalize();
     
@@ -179,6 +438,7 @@ impl GJK {
 
 /// gjk unit tests
 #[cfg(test)]
+#[allow(clippy::useless_vec, clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use crate::geometry::{Circle, Polygon};
@@ -268,7 +528,7 @@ mod tests {
             Vec2::new(10.0, 15.0),
         ]);
 
-        assert_eq!(gjk.intersect(&a, &b).unwrap(), Vec2::new(0.0, 1.0));
+        assert_eq!(gjk.intersect(&a, &b).unwrap().vector(), Vec2::new(0.0, 1.0));
         assert_eq!(gjk.intersect(&a, &c), None);
     }
 
@@ -291,7 +551,7 @@ mod tests {
         let c = Circle::new(Vec2::new(6.0, 6.0), 1.5);
 
         assert_eq!(
-            gjk.intersect(&a, &c).unwrap(),
+            gjk.intersect(&a, &c).unwrap().vector(),
             Vec2::new(0.06139496752482719, 0.060041963125428935)
         );
         assert_eq!(gjk.intersect(&b, &c), None);
@@ -306,9 +566,242 @@ mod tests {
         let c = Circle::new(Vec2::new(6.0, 6.0), 1.0);
 
         assert_eq!(
-            gjk.intersect(&a, &b).unwrap(),
+            gjk.intersect(&a, &b).unwrap().vector(),
             Vec2::new(0.5000282363673584, 0.0030681733371634417)
         );
         assert_eq!(gjk.intersect(&a, &c), None);
     }
+
+    #[test]
+    fn test_intersect_witness_points() {
+        let mut gjk = GJK::new();
+
+        let a = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+        ]);
+        let b = Polygon::new(&[
+            Vec2::new(3.0, 4.0),
+            Vec2::new(8.0, 4.0),
+            Vec2::new(8.0, 9.0),
+            Vec2::new(3.0, 9.0),
+        ]);
+        let p = gjk.intersect(&a, &b).unwrap();
+        assert_eq!((p.normal, p.depth), (Vec2::new(0.0, 1.0), 1.0));
+        assert_eq!((p.point_a.y, p.point_b.y), (5.0, 4.0));
+        assert!(p.point_a.x >= 3.0 && p.point_a.x <= 5.0);
+        assert!((p.point_a - p.point_b - p.vector()).len() < 1e-12);
+
+        let c = Circle::new(Vec2::new(1.0, 1.0), 1.0);
+        let d = Circle::new(Vec2::new(3.0, 1.0), 1.5);
+        let p = gjk.intersect(&c, &d).unwrap();
+        assert!((p.point_a - Vec2::new(2.0, 1.0)).len() < 0.01);
+        assert!((p.point_b - Vec2::new(1.5, 1.0)).len() < 0.01);
+        assert!((p.normal.len() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_distance() {
+        let mut gjk = GJK::new();
+
+        let a = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+        ]);
+        let b = Polygon::new(&[
+            Vec2::new(7.0, 1.0),
+            Vec2::new(9.0, 1.0),
+            Vec2::new(9.0, 3.0),
+            Vec2::new(7.0, 3.0),
+        ]);
+        let d = gjk.distance(&a, &b).unwrap();
+        assert!((d.distance - 2.0).abs() < 1e-12);
+        assert!((d.normal - Vec2::new(1.0, 0.0)).len() < 1e-12);
+        assert_eq!((d.point_a.x, d.point_b.x), (5.0, 7.0));
+
+        let c = Circle::new(Vec2::new(8.0, 8.0), 1.0);
+        let d = gjk.distance(&a, &c).unwrap();
+        assert!((d.distance - (18f64.sqrt() - 1.0)).abs() < 1e-6);
+        assert!((d.point_a - Vec2::new(5.0, 5.0)).len() < 1e-6);
+
+        let overlapping = Circle::new(Vec2::new(5.0, 2.0), 1.0);
+        assert_eq!(gjk.distance(&a, &overlapping), None);
+        assert_eq!(gjk.distance(&a, &a), None);
+    }
+
+    #[test]
+    fn test_gjk_touching() {
+        let mut gjk = GJK::new();
+
+        let a = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+        ]);
+        let b = Polygon::new(&[
+            Vec2::new(5.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 5.0),
+            Vec2::new(5.0, 5.0),
+        ]);
+        let corner = Polygon::new(&[
+            Vec2::new(5.0, 5.0),
+            Vec2::new(10.0, 5.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(5.0, 10.0),
+        ]);
+        let c = Circle::new(Vec2::new(1.0, 1.0), 1.0);
+        let d = Circle::new(Vec2::new(3.0, 1.0), 1.0);
+
+        assert!(!gjk.test(&a, &b));
+        assert!(!gjk.test(&b, &a));
+        assert!(!gjk.test(&a, &corner));
+        assert!(!gjk.test(&c, &d));
+        assert_eq!(gjk.intersect(&a, &b), None);
+
+        // A small box resting on part of a wider one
+        let ground = Polygon::new(&[
+            Vec2::new(-2.5, -0.5),
+            Vec2::new(2.5, -0.5),
+            Vec2::new(2.5, 0.5),
+            Vec2::new(-2.5, 0.5),
+        ]);
+        for x in [-2.0, -0.3, 0.0, 1.0, 1.5, 2.5] {
+            let block = Polygon::new(&[
+                Vec2::new(x - 0.5, 0.5),
+                Vec2::new(x + 0.5, 0.5),
+                Vec2::new(x + 0.5, 1.5),
+                Vec2::new(x - 0.5, 1.5),
+            ]);
+            assert!(!gjk.test(&ground, &block), "box at {} touching", x);
+            assert!(!gjk.test(&block, &ground), "box at {} touching", x);
+            assert_eq!(gjk.intersect(&ground, &block), None);
+        }
+
+        // A vertex resting on an edge, and on the end of an edge
+        for x in [0.0, 1.2, 2.5] {
+            let diamond = Polygon::new(&[
+                Vec2::new(x, 0.5),
+                Vec2::new(x + 0.5, 1.0),
+                Vec2::new(x, 1.5),
+                Vec2::new(x - 0.5, 1.0),
+            ]);
+            assert!(!gjk.test(&ground, &diamond), "diamond at {} touching", x);
+            assert!(!gjk.test(&diamond, &ground), "diamond at {} touching", x);
+        }
+        let sunk = Polygon::new(&[
+            Vec2::new(1.2, 0.49),
+            Vec2::new(1.7, 0.99),
+            Vec2::new(1.2, 1.49),
+            Vec2::new(0.7, 0.99),
+        ]);
+        assert!(gjk.test(&ground, &sunk));
+    }
+
+    #[test]
+    fn test_gjk_small_scale() {
+        let mut gjk = GJK::new();
+
+        let square = |x: f64, y: f64, size: f64| {
+            Polygon::new(&[
+                Vec2::new(x, y),
+                Vec2::new(x + size, y),
+                Vec2::new(x + size, y + size),
+                Vec2::new(x, y + size),
+            ])
+        };
+        for size in [0.1, 0.01] {
+            let a = square(0.0, 0.0, size);
+            for i in 0..40 {
+                for j in 0..40 {
+                    let x = (i as f64 / 39.0 - 0.5) * 6.0 * size;
+                    let y = (j as f64 / 39.0 - 0.5) * 6.0 * size;
+                    let gap = (x.abs() - size).max(y.abs() - size);
+                    if gap.abs() < 0.05 * size {
+                        continue;
+                    }
+                    let b = square(x, y, size);
+                    assert_eq!(gjk.test(&a, &b), gap < 0.0, "size {} at ({}, {})", size, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gjk_coincident_centers() {
+        let mut gjk = GJK::new();
+
+        let a = Circle::new(Vec2::new(1.0, 1.0), 1.0);
+        let b = Circle::new(Vec2::new(1.0, 1.0), 0.5);
+        let square = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ]);
+
+        assert!(gjk.test(&a, &b));
+        assert!(gjk.test(&square, &square));
+        assert!(gjk.test(&square, &a));
+        assert!(gjk.intersect(&square, &square).is_some());
+    }
+
+    #[test]
+    fn test_gjk_reused_after_intersect() {
+        let mut gjk = GJK::new();
+
+        let a = Circle::new(Vec2::new(1.0, 1.0), 1.0);
+        let b = Circle::new(Vec2::new(2.0, 1.0), 1.0);
+        assert!(gjk.intersect(&a, &b).is_some());
+
+        // The EPA polytope left behind has more than three vertices
+        let mut result = EvolveResult::StillEvolving;
+        for _ in 0..GjkConfig::new().max_iterations {
+            result = gjk.evolve_simplex(&a, &b);
+            if result != EvolveResult::StillEvolving {
+                break;
+            }
+        }
+        assert_eq!(result, EvolveResult::Intersection);
+    }
+
+    #[test]
+    fn test_gjk_iteration_cap() {
+        let mut config = GjkConfig::new();
+        config.max_iterations = 1;
+        let a = Circle::new(Vec2::new(1.0, 1.0), 1.0);
+        let b = Circle::new(Vec2::new(2.0, 1.0), 1.0);
+
+        assert!(!GJK::with_config(config).test(&a, &b));
+        assert!(GJK::new().test(&a, &b));
+    }
+
+    #[test]
+    fn test_intersect_config() {
+        let a = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+        ]);
+        let c = Circle::new(Vec2::new(6.0, 6.0), 1.5);
+        // The circle overlaps the corner at (5, 5)
+        let expected = Vec2::new(1.0, 1.0).normalize() * (1.5 - 2f64.sqrt());
+
+        let mut precise = GjkConfig::new();
+        precise.epa_tolerance = 1e-9;
+        precise.epa_max_iterations = 64;
+        let mut loose = GjkConfig::new();
+        loose.epa_tolerance = 0.1;
+
+        let precise_error = (GJK::with_config(precise).intersect(&a, &c).unwrap().vector() - expected).len();
+        let loose_error = (GJK::with_config(loose).intersect(&a, &c).unwrap().vector() - expected).len();
+        assert!(precise_error < 1e-5);
+        assert!(loose_error > precise_error);
+    }
 }
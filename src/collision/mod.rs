@@ -1 +1,10 @@
+pub mod aabb32;
+pub mod broadphase;
+pub mod cast;
 pub mod gjk;
+pub mod narrowphase;
+pub mod spatial_hash;
+pub mod sweep_and_prune;
+#[cfg(test)]
+mod test_util;
+pub mod toi;
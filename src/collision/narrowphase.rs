@@ -0,0 +1,553 @@
+//! Shape pair collision dispatch.
+//!
+//! Convex shapes are tested directly with GJK/EPA. Compound shapes are split into their
+//! children, with each child pair culled by bounding box before running GJK, so a concave
+//! compound only reports contacts where a child actually overlaps. Circle pairs are solved
+//! in closed form. EPA accuracy is configured separately for pairs with a curved side, where
+//! the polytope converges slowly, and for pairs of flat sided shapes.
+
+use wasm_bindgen::prelude::*;
+
+use super::gjk::{GjkConfig, GJK};
+use crate::geometry::sweep::outline_points;
+use crate::geometry::{Aabb, Shape, Shapes, Transformed};
+use crate::math::{Transform2D, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+/// Overlap between a child of shape a and a child of shape b
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeContact {
+    /// Top level compound child of a, or 0 if a is not a compound
+    pub child_a: usize,
+    /// Top level compound child of b, or 0 if b is not a compound
+    pub child_b: usize,
+    /// Penetration vector pointing from a towards b
+    pub penetration: Vec2,
+    /// World space point midway between the overlapping surfaces
+    pub point: Vec2,
+    /// Features the point was found between
+    pub feature: ContactFeature,
+}
+
+/// Edge and vertex a contact point was clipped from, which tell apart the points between the
+/// same two shapes from step to step. Points not found by clipping have the default feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContactFeature {
+    /// Edge of the reference shape the point was clipped against
+    pub reference_edge: usize,
+    /// Vertex of the incident shape the point came from
+    pub incident_vertex: usize,
+    /// True if the reference edge is on shape b
+    pub flipped: bool,
+}
+
+impl Binary for ContactFeature {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.reference_edge);
+        w.put(&self.incident_vertex);
+        w.put(&self.flipped);
+    }
+
+    fn decode(r: &mut Reader) -> Result<ContactFeature, SerializeError> {
+        Ok(ContactFeature {
+            reference_edge: r.get()?,
+            incident_vertex: r.get()?,
+            flipped: r.get()?,
+        })
+    }
+}
+
+/// GJK/EPA settings for each kind of convex pair
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct NarrowphaseConfig {
+    /// Pairs where one shape is a circle
+    pub curved: GjkConfig,
+    /// Pairs of polygons and segments
+    pub polygon: GjkConfig,
+}
+
+#[wasm_bindgen]
+impl NarrowphaseConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NarrowphaseConfig {
+        NarrowphaseConfig::default()
+    }
+}
+
+impl NarrowphaseConfig {
+    /// Settings used for a pair of convex shapes
+    pub fn for_pair(&self, a: &Shapes, b: &Shapes) -> GjkConfig {
+        match (a, b) {
+            (Shapes::Circle(_), _) | (_, Shapes::Circle(_)) => self.curved,
+            _ => self.polygon,
+        }
+    }
+}
+
+/// Points whose projections onto the normal are this close count as one contact feature
+const FEATURE_TOLERANCE: f64 = 1e-3;
+
+/// All overlapping child pairs of two shapes placed in world space
+pub fn collide(a: &Shapes, ta: &Transform2D, b: &Shapes, tb: &Transform2D) -> Vec<ShapeContact> {
+    collide_with(&NarrowphaseConfig::default(), a, ta, b, tb)
+}
+
+/// Like collide with the given GJK/EPA settings
+pub fn collide_with(
+    config: &NarrowphaseConfig,
+    a: &Shapes,
+    ta: &Transform2D,
+    b: &Shapes,
+    tb: &Transform2D,
+) -> Vec<ShapeContact> {
+    let mut contacts = Vec::new();
+    let parts_b = parts(b, tb);
+    for (child_a, (sa, xa)) in parts(a, ta).into_iter().enumerate() {
+        let box_a = bounds(sa, &xa);
+        for (child_b, (sb, xb)) in parts_b.iter().enumerate() {
+            if !box_a.overlaps(&bounds(sb, xb)) {
+                continue;
+            }
+            let mut penetrations = Vec::new();
+            convex_penetrations(config, sa, &xa, sb, xb, &mut penetrations);
+            contacts.extend(penetrations.into_iter().map(|(penetration, point, feature)| ShapeContact {
+                child_a,
+                child_b,
+                penetration,
+                point,
+                feature,
+            }));
+        }
+    }
+    contacts
+}
+
+/// True if any part of the two shapes overlaps
+pub fn overlaps(a: &Shapes, ta: &Transform2D, b: &Shapes, tb: &Transform2D) -> bool {
+    !collide(a, ta, b, tb).is_empty()
+}
+
+/// World space bounding box of a shape
+pub fn bounds(shape: &Shapes, transform: &Transform2D) -> Aabb {
+    Transformed::new(shape, *transform).aabb()
+}
+
+/// Distance from a point to a shape placed in world space, 0 if the point is inside
+pub fn point_distance(shape: &Shapes, transform: &Transform2D, point: &Vec2) -> f64 {
+    signed_distance(shape, transform, point).max(0.0)
+}
+
+/// Distance from a point to the surface of a shape placed in world space, negative inside.
+/// Segments have no inside so their distance is never negative.
+pub fn signed_distance(shape: &Shapes, transform: &Transform2D, point: &Vec2) -> f64 {
+    let local = transform.inverse_apply(point);
+    match shape {
+        Shapes::Circle(c) => (local - c.center()).len() - c.radius(),
+        Shapes::Polygon(p) => polygon_signed_distance(p.vertices(), &local),
+        Shapes::Segment(s) => s.distance_to_point(&local),
+        Shapes::Compound(c) => c
+            .children()
+            .iter()
+            .map(|(t, child)| signed_distance(child, t, &local))
+            .fold(f64::INFINITY, f64::min),
+    }
+}
+
+fn polygon_signed_distance(vertices: &[Vec2], p: &Vec2) -> f64 {
+    let n = vertices.len();
+    let edges = (0..n).map(|i| (vertices[i], vertices[(i + 1) % n]));
+
+    let edge_distance = edges
+        .clone()
+        .map(|(a, b)| {
+            let ab = b - a;
+            let t = (Vec2::dot(&(p - a), &ab) / ab.len_sq()).clamp(0.0, 1.0);
+            (p - (a + ab * t)).len()
+        })
+        .fold(f64::INFINITY, f64::min);
+
+    // Inside if p is on the same side of every edge, whichever the winding
+    let sides: Vec<f64> = edges.map(|(a, b)| Vec2::cross(&(b - a), &(p - a))).collect();
+    if sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0) {
+        -edge_distance
+    } else {
+        edge_distance
+    }
+}
+
+/// False if shape is a one-sided segment and other is behind it or would be pushed through it.
+/// push is the penetration vector from shape towards other.
+fn facing(shape: &Shapes, transform: &Transform2D, other_center: &Vec2, push: &Vec2) -> bool {
+    match shape {
+        Shapes::Segment(s) if s.one_sided => {
+            let normal = transform.apply_vector(&s.normal());
+            let offset = other_center - transform.apply(&s.a());
+            Vec2::dot(push, &normal) > 0.0 && Vec2::dot(&offset, &normal) > 0.0
+        }
+        _ => true,
+    }
+}
+
+/// Children of a compound with their world transforms, or the shape itself
+fn parts<'a>(shape: &'a Shapes, transform: &Transform2D) -> Vec<(&'a Shapes, Transform2D)> {
+    match shape {
+        Shapes::Compound(c) => c
+            .children()
+            .iter()
+            .map(|(local, child)| (child, transform.mul(local)))
+            .collect(),
+        _ => vec![(shape, *transform)],
+    }
+}
+
+/// Penetrations and contact points between convex pieces, descending into nested compounds
+fn convex_penetrations(
+    config: &NarrowphaseConfig,
+    a: &Shapes,
+    ta: &Transform2D,
+    b: &Shapes,
+    tb: &Transform2D,
+    out: &mut Vec<(Vec2, Vec2, ContactFeature)>,
+) {
+    match (a, b) {
+        (Shapes::Compound(_), _) => {
+            for (child, transform) in parts(a, ta) {
+                convex_penetrations(config, child, &transform, b, tb, out);
+            }
+        }
+        (_, Shapes::Compound(_)) => {
+            for (child, transform) in parts(b, tb) {
+                convex_penetrations(config, a, ta, child, &transform, out);
+            }
+        }
+        (Shapes::Circle(ca), Shapes::Circle(cb)) => {
+            let pa = ta.apply(&ca.center());
+            let pb = tb.apply(&cb.center());
+            let d = pb - pa;
+            let dist = d.len();
+            let depth = ca.radius() + cb.radius() - dist;
+            // Coincident centers have no separating direction
+            if depth > 0.0 && dist > 0.0 {
+                let n = d / dist;
+                out.push((n * depth, pa + n * (ca.radius() - depth * 0.5), ContactFeature::default()));
+            }
+        }
+        _ => {
+            let wa = Transformed::new(a, *ta);
+            let wb = Transformed::new(b, *tb);
+            if let Some(penetration) = GJK::with_config(config.for_pair(a, b)).intersect(&wa, &wb) {
+                // Shapes that only touch have no direction to push apart along
+                if !(penetration.depth > 0.0 && penetration.depth.is_finite()) {
+                    return;
+                }
+                let p = penetration.vector();
+                if facing(a, ta, &wb.center(), &p) && facing(b, tb, &wa.center(), &-p) {
+                    let clipped = match (flat_outline(a, ta), flat_outline(b, tb)) {
+                        (Some(va), Some(vb)) => clipped_contacts(&va, &vb, &penetration.normal),
+                        _ => Vec::new(),
+                    };
+                    if clipped.is_empty() {
+                        out.push((p, contact_point(a, ta, b, tb, &p), ContactFeature::default()));
+                    } else {
+                        out.extend(clipped);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// World space vertices of a polygon in counter-clockwise order or the ends of a segment, None
+/// for curved shapes
+fn flat_outline(shape: &Shapes, transform: &Transform2D) -> Option<Vec<Vec2>> {
+    match shape {
+        Shapes::Polygon(p) => Some(p.vertices().iter().map(|v| transform.apply(v)).collect()),
+        Shapes::Segment(s) => Some(vec![transform.apply(&s.a()), transform.apply(&s.b())]),
+        _ => None,
+    }
+}
+
+/// Outward normal of the edge from v[i] to the next vertex. A segment's two edges are its two sides.
+fn face_normal(v: &[Vec2], i: usize) -> Vec2 {
+    let edge = v[(i + 1) % v.len()] - v[i];
+    Vec2::new(edge.y, -edge.x).normalize()
+}
+
+/// Edge whose outward normal points furthest along dir, and how far
+fn best_face(v: &[Vec2], dir: &Vec2) -> (usize, f64) {
+    (0..v.len())
+        .map(|i| (i, Vec2::dot(&face_normal(v, i), dir)))
+        .fold((0, f64::MIN), |best, face| if face.1 > best.1 { face } else { best })
+}
+
+/// Up to two contacts between flat sided outlines overlapping along normal n, pointing from a to
+/// b. The face of one outline most facing the other is the reference face; the face of the other
+/// most against it is clipped to the reference face's extent, and its points behind the
+/// reference face each become a contact. Boxes resting on each other get a point at each corner,
+/// which keeps them from rocking on a single point in the middle.
+fn clipped_contacts(va: &[Vec2], vb: &[Vec2], n: &Vec2) -> Vec<(Vec2, Vec2, ContactFeature)> {
+    let (face_a, along_a) = best_face(va, n);
+    let (face_b, along_b) = best_face(vb, &-*n);
+    // Prefer a so nearly equal faces don't switch from step to step
+    let flip = along_b > along_a + FEATURE_TOLERANCE;
+    let (reference, face, incident) = if flip { (vb, face_b, va) } else { (va, face_a, vb) };
+
+    let normal = face_normal(reference, face);
+    let (r0, r1) = (reference[face], reference[(face + 1) % reference.len()]);
+    let tangent = (r1 - r0).normalize();
+    let (incident_face, _) = best_face(incident, &-normal);
+    let next = (incident_face + 1) % incident.len();
+    let mut points = vec![(incident[incident_face], incident_face), (incident[next], next)];
+    points = clip(&points, &tangent, Vec2::dot(&r0, &tangent));
+    points = clip(&points, &-tangent, -Vec2::dot(&r1, &tangent));
+
+    let direction = if flip { -normal } else { normal };
+    points
+        .iter()
+        .filter_map(|&(p, vertex)| {
+            let depth = -Vec2::dot(&(p - r0), &normal);
+            let feature = ContactFeature {
+                reference_edge: face,
+                incident_vertex: vertex,
+                flipped: flip,
+            };
+            // Midway between the incident point and the reference face
+            (depth > 0.0).then(|| (direction * depth, p + normal * (depth * 0.5), feature))
+        })
+        .collect()
+}
+
+/// The part of the segment between two points where dot(p, dir) >= offset. A point cut off the
+/// end keeps the vertex it replaced.
+fn clip(points: &[(Vec2, usize)], dir: &Vec2, offset: f64) -> Vec<(Vec2, usize)> {
+    if points.len() < 2 {
+        return points.iter().filter(|(p, _)| Vec2::dot(p, dir) >= offset).copied().collect();
+    }
+    let ((p0, v0), (p1, v1)) = (points[0], points[1]);
+    let (d0, d1) = (Vec2::dot(&p0, dir) - offset, Vec2::dot(&p1, dir) - offset);
+    let mut out = Vec::with_capacity(2);
+    if d0 >= 0.0 {
+        out.push((p0, v0));
+    }
+    if d1 >= 0.0 {
+        out.push((p1, v1));
+    }
+    if d0 * d1 < 0.0 {
+        out.push((p0 + (p1 - p0) * (d0 / (d0 - d1)), if d0 < 0.0 { v0 } else { v1 }));
+    }
+    out
+}
+
+/// Middle of the region where the deepest features of two convex shapes overlap
+fn contact_point(a: &Shapes, ta: &Transform2D, b: &Shapes, tb: &Transform2D, penetration: &Vec2) -> Vec2 {
+    let n = penetration.normalize();
+    let tangent = n.perp();
+    let (a_lo, a_hi, a_depth) = feature(a, ta, &n, &tangent);
+    let (b_lo, b_hi, b_depth) = feature(b, tb, &-n, &tangent);
+
+    let lo = a_lo.max(b_lo);
+    let hi = a_hi.min(b_hi);
+    let along = if lo <= hi {
+        (lo + hi) * 0.5
+    } else {
+        (a_lo + a_hi + b_lo + b_hi) * 0.25
+    };
+    tangent * along + n * ((a_depth - b_depth) * 0.5)
+}
+
+/// Extent along tangent of the points of a shape furthest along dir, and how far along dir they are
+fn feature(shape: &Shapes, transform: &Transform2D, dir: &Vec2, tangent: &Vec2) -> (f64, f64, f64) {
+    let points: Vec<Vec2> = match shape {
+        Shapes::Circle(_) => vec![Transformed::new(shape, *transform).support(dir)],
+        _ => outline_points(shape, 0).iter().map(|p| transform.apply(p)).collect(),
+    };
+
+    let furthest = points.iter().map(|p| Vec2::dot(p, dir)).fold(f64::MIN, f64::max);
+    let (mut lo, mut hi) = (f64::MAX, f64::MIN);
+    for p in points.iter().filter(|p| Vec2::dot(p, dir) >= furthest - FEATURE_TOLERANCE) {
+        let t = Vec2::dot(p, tangent);
+        lo = lo.min(t);
+        hi = hi.max(t);
+    }
+    (lo, hi, furthest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Compound, Polygon, Segment};
+
+    fn rect(w: f64, h: f64) -> Shapes {
+        Shapes::Polygon(Polygon::new(&[
+            Vec2::new(-w / 2.0, -h / 2.0),
+            Vec2::new(w / 2.0, -h / 2.0),
+            Vec2::new(w / 2.0, h / 2.0),
+            Vec2::new(-w / 2.0, h / 2.0),
+        ]))
+    }
+
+    /// Handle along x with the head at the right end
+    fn hammer() -> Shapes {
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::identity(), rect(4.0, 0.5));
+        compound.add_child(Transform2D::new(Vec2::new(2.0, 0.0), 0.0), rect(1.0, 2.0));
+        Shapes::Compound(compound)
+    }
+
+    fn ball() -> Shapes {
+        Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5))
+    }
+
+    #[test]
+    fn test_convex_pair() {
+        let contacts = collide(
+            &rect(2.0, 2.0),
+            &Transform2D::identity(),
+            &rect(2.0, 2.0),
+            &Transform2D::new(Vec2::new(0.0, 1.5), 0.0),
+        );
+
+        // Both corners of the overlapping faces
+        assert_eq!(contacts.len(), 2);
+        for (c, x) in contacts.iter().zip(&[-1.0, 1.0]) {
+            assert_eq!(c.child_a, 0);
+            assert!((c.penetration - Vec2::new(0.0, 0.5)).len() < 1e-6);
+            assert!((c.point - Vec2::new(*x, 0.75)).len() < 1e-6, "{:?}", c.point);
+        }
+    }
+
+    #[test]
+    fn test_circle_pair() {
+        let contacts = collide(
+            &ball(),
+            &Transform2D::identity(),
+            &ball(),
+            &Transform2D::new(Vec2::new(0.8, 0.0), 0.0),
+        );
+
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].penetration - Vec2::new(0.2, 0.0)).len() < 1e-12);
+        assert!((contacts[0].point - Vec2::new(0.4, 0.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn test_contact_point_on_overlap() {
+        // Small box resting off center on a wide box
+        let contacts = collide(
+            &rect(4.0, 1.0),
+            &Transform2D::identity(),
+            &rect(1.0, 1.0),
+            &Transform2D::new(Vec2::new(1.0, 0.9), 0.0),
+        );
+
+        assert_eq!(contacts.len(), 2);
+        assert!((contacts[0].point - Vec2::new(0.5, 0.45)).len() < 1e-6, "{:?}", contacts[0].point);
+        assert!((contacts[1].point - Vec2::new(1.5, 0.45)).len() < 1e-6, "{:?}", contacts[1].point);
+    }
+
+    #[test]
+    fn test_compound_reports_child() {
+        let head_hit = collide(
+            &hammer(),
+            &Transform2D::identity(),
+            &ball(),
+            &Transform2D::new(Vec2::new(2.0, 1.2), 0.0),
+        );
+        assert_eq!(head_hit.len(), 1);
+        assert_eq!(head_hit[0].child_a, 1);
+        assert_eq!(head_hit[0].child_b, 0);
+    }
+
+    #[test]
+    fn test_compound_concave_gap() {
+        // Inside the hull of the hammer but above the handle
+        let t = Transform2D::new(Vec2::new(0.0, 0.9), 0.0);
+        assert!(GJK::new().test(
+            &Transformed::new(&hammer(), Transform2D::identity()),
+            &Transformed::new(&ball(), t)
+        ));
+        assert!(!overlaps(&hammer(), &Transform2D::identity(), &ball(), &t));
+    }
+
+    #[test]
+    fn test_segment() {
+        let floor = Shapes::Segment(Segment::new(Vec2::new(2.0, 0.0), Vec2::new(-2.0, 0.0)));
+        let t = Transform2D::new(Vec2::new(0.0, 0.25), 0.0);
+
+        let contacts = collide(&floor, &Transform2D::identity(), &rect(1.0, 1.0), &t);
+        assert_eq!(contacts.len(), 2);
+        for (c, x) in contacts.iter().zip(&[-0.5, 0.5]) {
+            assert!((c.penetration - Vec2::new(0.0, 0.25)).len() < 1e-6);
+            assert!((c.point - Vec2::new(*x, -0.125)).len() < 1e-6, "{:?}", c.point);
+        }
+    }
+
+    #[test]
+    fn test_one_sided_segment() {
+        let floor = Shapes::Segment(Segment::one_sided(Vec2::new(2.0, 0.0), Vec2::new(-2.0, 0.0)));
+        let above = Transform2D::new(Vec2::new(0.0, 0.25), 0.0);
+        let below = Transform2D::new(Vec2::new(0.0, -0.25), 0.0);
+
+        assert!(overlaps(&floor, &Transform2D::identity(), &ball(), &above));
+        assert!(overlaps(&ball(), &above, &floor, &Transform2D::identity()));
+        assert!(!overlaps(&floor, &Transform2D::identity(), &ball(), &below));
+        assert!(!overlaps(&ball(), &below, &floor, &Transform2D::identity()));
+    }
+
+    #[test]
+    fn test_point_distance() {
+        let t = Transform2D::new(Vec2::new(1.0, 0.0), 0.0);
+
+        assert_eq!(point_distance(&ball(), &t, &Vec2::new(1.0, 2.0)), 1.5);
+        assert_eq!(point_distance(&rect(2.0, 2.0), &t, &Vec2::new(1.5, 0.5)), 0.0);
+        assert_eq!(point_distance(&rect(2.0, 2.0), &t, &Vec2::new(4.0, 0.0)), 2.0);
+        // Closest to the head, which reaches x = 2.5
+        assert_eq!(point_distance(&hammer(), &Transform2D::identity(), &Vec2::new(3.0, 0.9)), 0.5);
+    }
+
+    #[test]
+    fn test_signed_distance() {
+        let t = Transform2D::new(Vec2::new(1.0, 0.0), 0.0);
+
+        assert_eq!(signed_distance(&ball(), &t, &Vec2::new(1.0, 0.25)), -0.25);
+        assert_eq!(signed_distance(&rect(2.0, 2.0), &t, &Vec2::new(1.5, 0.5)), -0.5);
+        assert_eq!(signed_distance(&rect(2.0, 2.0), &t, &Vec2::new(4.0, 0.0)), 2.0);
+        // Deepest inside the head, which spans x from 1.5 to 2.5
+        assert_eq!(signed_distance(&hammer(), &Transform2D::identity(), &Vec2::new(2.0, 0.5)), -0.5);
+    }
+
+    #[test]
+    fn test_compound_transform() {
+        // Rotated a quarter turn the head sits at (0, 2)
+        let ta = Transform2D::new(Vec2::new(0.0, 0.0), std::f64::consts::FRAC_PI_2);
+        let tb = Transform2D::new(Vec2::new(0.0, 2.5), 0.0);
+
+        let contacts = collide(&hammer(), &ta, &ball(), &tb);
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].child_a, 1);
+        assert_eq!(collide(&ball(), &tb, &hammer(), &ta)[0].child_b, 1);
+    }
+
+    #[test]
+    fn test_config_per_pair() {
+        let mut config = NarrowphaseConfig::new();
+        config.curved.epa_max_iterations = 1;
+        assert_eq!(config.for_pair(&rect(1.0, 1.0), &ball()).epa_max_iterations, 1);
+        assert_eq!(config.for_pair(&rect(1.0, 1.0), &rect(1.0, 1.0)), GjkConfig::new());
+
+        // A single expansion leaves the circle depth rough but boxes are unaffected
+        let t = Transform2D::new(Vec2::new(0.0, 1.3), 0.0);
+        let exact = collide(&rect(2.0, 2.0), &Transform2D::identity(), &ball(), &t)[0].penetration;
+        let rough = collide_with(&config, &rect(2.0, 2.0), &Transform2D::identity(), &ball(), &t)[0].penetration;
+        assert!((exact - Vec2::new(0.0, 0.2)).len() < 1e-3);
+        assert!((rough - exact).len() > 1e-3);
+
+        let boxes = Transform2D::new(Vec2::new(0.0, 1.5), 0.0);
+        assert_eq!(
+            collide_with(&config, &rect(2.0, 2.0), &Transform2D::identity(), &rect(2.0, 2.0), &boxes),
+            collide(&rect(2.0, 2.0), &Transform2D::identity(), &rect(2.0, 2.0), &boxes)
+        );
+    }
+}
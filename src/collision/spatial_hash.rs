@@ -0,0 +1,305 @@
+//! Uniform grid broadphase.
+//!
+//! Each proxy is listed in every grid cell its fattened box touches, so a query only looks at
+//! the cells under the query box. This beats the BVH when bodies are many and of similar size,
+//! like bullets, as long as the cell size is about the size of a body. Boxes covering more than
+//! MAX_PROXY_CELLS cells, like level geometry, are kept in a separate list checked by every query.
+//! Boxes are stored in single precision like those of the BVH.
+
+use std::collections::HashMap;
+
+use super::aabb32::Aabb32;
+use super::broadphase;
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+
+/// Cells a proxy may cover before it goes in the oversized list
+pub const MAX_PROXY_CELLS: i64 = 64;
+
+type Cell = (i64, i64);
+
+/// Number of cells from lo to hi inclusive
+fn cell_count(lo: Cell, hi: Cell) -> i64 {
+    hi.0.saturating_sub(lo.0).saturating_add(1).saturating_mul(hi.1.saturating_sub(lo.1).saturating_add(1))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Proxy {
+    aabb: Aabb32,
+    data: usize,
+    /// First and last cell covered, None for oversized proxies
+    cells: Option<(Cell, Cell)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpatialHash {
+    cell_size: f64,
+    margin: f64,
+    proxies: Vec<Option<Proxy>>,
+    free: Vec<usize>,
+    cells: HashMap<Cell, Vec<usize>>,
+    oversized: Vec<usize>,
+    len: usize,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f64, margin: f64) -> SpatialHash {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+        SpatialHash {
+            cell_size,
+            margin,
+            proxies: Vec::new(),
+            free: Vec::new(),
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Number of proxies
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.proxies.clear();
+        self.free.clear();
+        self.cells.clear();
+        self.oversized.clear();
+        self.len = 0;
+    }
+
+    /// Add a proxy, returning an id used to update or remove it
+    pub fn insert(&mut self, aabb: Aabb, data: usize) -> usize {
+        let proxy = Proxy {
+            aabb: Aabb32::enclosing(&aabb.expand(self.margin)),
+            data,
+            cells: None,
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.proxies[id] = Some(proxy);
+                id
+            }
+            None => {
+                self.proxies.push(Some(proxy));
+                self.proxies.len() - 1
+            }
+        };
+        self.link(id);
+        self.len += 1;
+        id
+    }
+
+    pub fn remove(&mut self, proxy: usize) {
+        self.unlink(proxy);
+        self.proxies[proxy] = None;
+        self.free.push(proxy);
+        self.len -= 1;
+    }
+
+    /// Move a proxy to a new box, returning true if its cells had to be updated
+    pub fn update(&mut self, proxy: usize, aabb: Aabb) -> bool {
+        if self.proxy(proxy).aabb.contains(&Aabb32::enclosing(&aabb)) {
+            return false;
+        }
+        self.unlink(proxy);
+        self.proxy_mut(proxy).aabb = Aabb32::enclosing(&aabb.expand(self.margin));
+        self.link(proxy);
+        true
+    }
+
+    /// User value stored with a proxy
+    pub fn data(&self, proxy: usize) -> usize {
+        self.proxy(proxy).data
+    }
+
+    pub fn set_data(&mut self, proxy: usize, data: usize) {
+        self.proxy_mut(proxy).data = data;
+    }
+
+    /// Fattened box stored for a proxy
+    pub fn fat_aabb(&self, proxy: usize) -> Aabb {
+        self.proxy(proxy).aabb.to_aabb()
+    }
+
+    /// User values of all proxies whose fat box overlaps aabb
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let aabb = &Aabb32::enclosing(aabb);
+        let mut ids = self.oversized.clone();
+        let (lo, hi) = self.cell_range(aabb);
+        // A huge query box visits the proxies instead of the cells
+        if cell_count(lo, hi) > self.len as i64 {
+            ids = (0..self.proxies.len()).filter(|&id| self.proxies[id].is_some()).collect();
+        } else {
+            for x in lo.0..=hi.0 {
+                for y in lo.1..=hi.1 {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        ids.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter()
+            .map(|id| self.proxy(id))
+            .filter(|p| p.aabb.overlaps(aabb))
+            .map(|p| p.data)
+            .collect()
+    }
+
+    /// Pairs of user values whose fat boxes overlap, smaller value first, in sorted order
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        for p in self.proxies.iter().flatten() {
+            let others = self.query(&p.aabb.to_aabb());
+            out.extend(others.into_iter().filter(|&other| other > p.data).map(|other| (p.data, other)));
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// The k proxies nearest to point, closest first. See Bvh::nearest.
+    pub fn nearest<F: FnMut(usize) -> Option<f64>>(&self, point: &Vec2, k: usize, distance: F) -> Vec<(usize, f64)> {
+        let boxes = self.proxies.iter().flatten().map(|p| (p.aabb, p.data));
+        broadphase::nearest_by_bounds(boxes, point, k, distance)
+    }
+
+    fn proxy(&self, proxy: usize) -> &Proxy {
+        self.proxies[proxy].as_ref().expect("proxy was removed")
+    }
+
+    fn proxy_mut(&mut self, proxy: usize) -> &mut Proxy {
+        self.proxies[proxy].as_mut().expect("proxy was removed")
+    }
+
+    fn cell_range(&self, aabb: &Aabb32) -> (Cell, Cell) {
+        let cell = |p: [f32; 2]| {
+            (
+                (p[0] as f64 / self.cell_size).floor() as i64,
+                (p[1] as f64 / self.cell_size).floor() as i64,
+            )
+        };
+        (cell(aabb.min), cell(aabb.max))
+    }
+
+    /// Add a proxy to the cells under its box
+    fn link(&mut self, proxy: usize) {
+        let (lo, hi) = self.cell_range(&self.proxy(proxy).aabb);
+        if cell_count(lo, hi) > MAX_PROXY_CELLS {
+            self.oversized.push(proxy);
+            self.proxy_mut(proxy).cells = None;
+            return;
+        }
+        for x in lo.0..=hi.0 {
+            for y in lo.1..=hi.1 {
+                self.cells.entry((x, y)).or_default().push(proxy);
+            }
+        }
+        self.proxy_mut(proxy).cells = Some((lo, hi));
+    }
+
+    fn unlink(&mut self, proxy: usize) {
+        let (lo, hi) = match self.proxy(proxy).cells {
+            Some(range) => range,
+            None => {
+                self.oversized.retain(|&p| p != proxy);
+                return;
+            }
+        };
+        for x in lo.0..=hi.0 {
+            for y in lo.1..=hi.1 {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|&p| p != proxy);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::test_util::unit_box;
+
+    fn grid() -> SpatialHash {
+        let mut hash = SpatialHash::new(1.0, 0.0);
+        for i in 0..10 {
+            for j in 0..10 {
+                hash.insert(unit_box(2.0 * i as f64, 2.0 * j as f64), i * 10 + j);
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn test_query() {
+        let hash = grid();
+        let hits = hash.query(&Aabb::new(Vec2::new(1.5, 1.5), Vec2::new(2.5, 2.5)));
+
+        assert_eq!(hash.len(), 100);
+        assert_eq!(hits, vec![11]);
+        for d in 0..100 {
+            let b = unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64);
+            assert_eq!(hash.query(&b), vec![d]);
+        }
+        assert_eq!(hash.query(&Aabb::new(Vec2::new(-100.0, -100.0), Vec2::new(100.0, 100.0))).len(), 100);
+    }
+
+    #[test]
+    fn test_remove_and_update() {
+        let mut hash = SpatialHash::new(1.0, 0.1);
+        let a = hash.insert(unit_box(0.0, 0.0), 0);
+        let b = hash.insert(unit_box(5.0, 0.0), 1);
+
+        assert!(!hash.update(a, unit_box(0.05, 0.0)));
+        assert!(hash.update(a, unit_box(5.0, 0.0)));
+        assert_eq!(hash.query(&unit_box(5.0, 0.0)), vec![0, 1]);
+        assert!(hash.query(&unit_box(0.0, 0.0)).is_empty());
+
+        hash.remove(b);
+        assert_eq!(hash.query(&unit_box(5.0, 0.0)), vec![0]);
+        assert_eq!(hash.len(), 1);
+        // Removed ids are reused
+        assert_eq!(hash.insert(unit_box(9.0, 9.0), 2), b);
+    }
+
+    #[test]
+    fn test_oversized() {
+        let mut hash = SpatialHash::new(1.0, 0.0);
+        let floor = hash.insert(Aabb::new(Vec2::new(-500.0, -1.0), Vec2::new(500.0, 0.0)), 0);
+        hash.insert(unit_box(3.0, 0.0), 1);
+
+        assert_eq!(hash.query(&unit_box(2.5, -0.5)), vec![0, 1]);
+        assert_eq!(hash.query(&unit_box(-200.0, -0.5)), vec![0]);
+        hash.update(floor, Aabb::new(Vec2::new(-500.0, 10.0), Vec2::new(500.0, 11.0)));
+        assert_eq!(hash.query(&unit_box(-200.0, -0.5)), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_nearest() {
+        let hash = grid();
+        let point = Vec2::new(4.5, 4.5);
+        let boxes: Vec<Aabb> = (0..100).map(|d| unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64)).collect();
+
+        let nearest = hash.nearest(&point, 5, |d| Some(boxes[d].distance_to_point(&point)));
+        assert_eq!(nearest.len(), 5);
+        assert_eq!(nearest[0], (22, 0.0));
+        assert!(nearest[1..].iter().all(|(_, d)| *d == 1.5));
+
+        let skipped = hash.nearest(&point, 1, |d| if d == 22 { None } else { Some(boxes[d].distance_to_point(&point)) });
+        assert_eq!(skipped[0].1, 1.5);
+    }
+}
@@ -0,0 +1,308 @@
+//! Sort based broadphase.
+//!
+//! The start and end of every box along x are kept in one sorted list. Bodies move little from
+//! step to step, so an insertion sort brings the list back in order after an update in close to
+//! linear time. Sweeping the list while tracking which boxes are open gives every overlapping
+//! pair. Ties are broken by proxy id, which keeps the order and the pairs deterministic.
+
+use super::aabb32::Aabb32;
+use super::broadphase;
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Endpoint {
+    value: f32,
+    /// End of the box rather than its start
+    is_max: bool,
+    proxy: usize,
+}
+
+impl Endpoint {
+    /// Starts sort before ends at the same value so touching boxes overlap
+    fn before(&self, other: &Endpoint) -> bool {
+        (self.value, self.is_max, self.proxy) < (other.value, other.is_max, other.proxy)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Proxy {
+    aabb: Aabb32,
+    data: usize,
+    /// Positions of the start and end in the endpoint list
+    ends: [usize; 2],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepAndPrune {
+    margin: f64,
+    proxies: Vec<Option<Proxy>>,
+    free: Vec<usize>,
+    endpoints: Vec<Endpoint>,
+}
+
+impl SweepAndPrune {
+    pub fn new(margin: f64) -> SweepAndPrune {
+        SweepAndPrune {
+            margin,
+            proxies: Vec::new(),
+            free: Vec::new(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Number of proxies
+    pub fn len(&self) -> usize {
+        self.endpoints.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.proxies.clear();
+        self.free.clear();
+        self.endpoints.clear();
+    }
+
+    /// Add a proxy, returning an id used to update or remove it
+    pub fn insert(&mut self, aabb: Aabb, data: usize) -> usize {
+        let aabb = Aabb32::enclosing(&aabb.expand(self.margin));
+        let n = self.endpoints.len();
+        let proxy = Proxy {
+            aabb,
+            data,
+            ends: [n, n + 1],
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.proxies[id] = Some(proxy);
+                id
+            }
+            None => {
+                self.proxies.push(Some(proxy));
+                self.proxies.len() - 1
+            }
+        };
+        self.endpoints.push(Endpoint {
+            value: aabb.min[0],
+            is_max: false,
+            proxy: id,
+        });
+        self.endpoints.push(Endpoint {
+            value: aabb.max[0],
+            is_max: true,
+            proxy: id,
+        });
+        self.sort_from(n);
+        self.sort_from(n + 1);
+        id
+    }
+
+    pub fn remove(&mut self, proxy: usize) {
+        let ends = self.proxy(proxy).ends;
+        self.endpoints.remove(ends[1]);
+        self.endpoints.remove(ends[0]);
+        self.proxies[proxy] = None;
+        self.free.push(proxy);
+        self.reindex(ends[0]);
+    }
+
+    /// Move a proxy to a new box, returning true if its endpoints had to move
+    pub fn update(&mut self, proxy: usize, aabb: Aabb) -> bool {
+        if self.proxy(proxy).aabb.contains(&Aabb32::enclosing(&aabb)) {
+            return false;
+        }
+        let aabb = Aabb32::enclosing(&aabb.expand(self.margin));
+        let p = self.proxy_mut(proxy);
+        let moved_right = aabb.min[0] > p.aabb.min[0];
+        p.aabb = aabb;
+        let ends = p.ends;
+        self.endpoints[ends[0]].value = aabb.min[0];
+        self.endpoints[ends[1]].value = aabb.max[0];
+        // Sort the endpoint leading the move first so the other one can't get stuck behind it
+        let order = if moved_right { [1, 0] } else { [0, 1] };
+        for end in order {
+            self.sort_from(self.proxy(proxy).ends[end]);
+        }
+        true
+    }
+
+    /// User value stored with a proxy
+    pub fn data(&self, proxy: usize) -> usize {
+        self.proxy(proxy).data
+    }
+
+    pub fn set_data(&mut self, proxy: usize, data: usize) {
+        self.proxy_mut(proxy).data = data;
+    }
+
+    /// Fattened box stored for a proxy
+    pub fn fat_aabb(&self, proxy: usize) -> Aabb {
+        self.proxy(proxy).aabb.to_aabb()
+    }
+
+    /// User values of all proxies whose fat box overlaps aabb
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let aabb = Aabb32::enclosing(aabb);
+        // Boxes starting after the query box ends can't overlap it
+        let end = self.endpoints.partition_point(|e| e.value <= aabb.max[0]);
+        let mut out: Vec<usize> = self.endpoints[..end]
+            .iter()
+            .filter(|e| !e.is_max)
+            .map(|e| self.proxy(e.proxy))
+            .filter(|p| p.aabb.overlaps(&aabb))
+            .map(|p| p.data)
+            .collect();
+        out.sort_unstable();
+        out
+    }
+
+    /// Pairs of user values whose fat boxes overlap, smaller value first, in sorted order
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        for e in &self.endpoints {
+            if e.is_max {
+                open.retain(|&p| p != e.proxy);
+                continue;
+            }
+            let p = self.proxy(e.proxy);
+            for &other in &open {
+                let q = self.proxy(other);
+                if p.aabb.overlaps(&q.aabb) {
+                    out.push((p.data.min(q.data), p.data.max(q.data)));
+                }
+            }
+            open.push(e.proxy);
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// The k proxies nearest to point, closest first. See Bvh::nearest.
+    pub fn nearest<F: FnMut(usize) -> Option<f64>>(&self, point: &Vec2, k: usize, distance: F) -> Vec<(usize, f64)> {
+        let boxes = self.proxies.iter().flatten().map(|p| (p.aabb, p.data));
+        broadphase::nearest_by_bounds(boxes, point, k, distance)
+    }
+
+    fn proxy(&self, proxy: usize) -> &Proxy {
+        self.proxies[proxy].as_ref().expect("proxy was removed")
+    }
+
+    fn proxy_mut(&mut self, proxy: usize) -> &mut Proxy {
+        self.proxies[proxy].as_mut().expect("proxy was removed")
+    }
+
+    /// Move the endpoint at index to its sorted place, keeping the proxies' positions current
+    fn sort_from(&mut self, mut index: usize) {
+        while index > 0 && self.endpoints[index].before(&self.endpoints[index - 1]) {
+            self.swap(index, index - 1);
+            index -= 1;
+        }
+        while index + 1 < self.endpoints.len() && self.endpoints[index + 1].before(&self.endpoints[index]) {
+            self.swap(index, index + 1);
+            index += 1;
+        }
+        self.set_position(index);
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.endpoints.swap(i, j);
+        self.set_position(i);
+        self.set_position(j);
+    }
+
+    fn set_position(&mut self, index: usize) {
+        let e = self.endpoints[index];
+        self.proxy_mut(e.proxy).ends[e.is_max as usize] = index;
+    }
+
+    /// Update the positions of every endpoint from index on
+    fn reindex(&mut self, from: usize) {
+        for index in from..self.endpoints.len() {
+            self.set_position(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::test_util::unit_box;
+
+    fn grid() -> SweepAndPrune {
+        let mut sap = SweepAndPrune::new(0.0);
+        for i in 0..10 {
+            for j in 0..10 {
+                sap.insert(unit_box(2.0 * i as f64, 2.0 * j as f64), i * 10 + j);
+            }
+        }
+        sap
+    }
+
+    fn is_sorted(sap: &SweepAndPrune) -> bool {
+        sap.endpoints.windows(2).all(|w| w[0].before(&w[1]))
+            && sap.proxies.iter().flatten().all(|p| {
+                sap.endpoints[p.ends[0]].value == p.aabb.min[0] && sap.endpoints[p.ends[1]].value == p.aabb.max[0]
+            })
+    }
+
+    #[test]
+    fn test_query() {
+        let sap = grid();
+        assert_eq!(sap.len(), 100);
+        assert!(is_sorted(&sap));
+        assert_eq!(sap.query(&Aabb::new(Vec2::new(1.5, 1.5), Vec2::new(2.5, 2.5))), vec![11]);
+        for d in 0..100 {
+            let b = unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64);
+            assert_eq!(sap.query(&b), vec![d]);
+        }
+    }
+
+    #[test]
+    fn test_pairs() {
+        let mut sap = SweepAndPrune::new(0.0);
+        sap.insert(unit_box(0.0, 0.0), 0);
+        sap.insert(unit_box(0.5, 0.5), 1);
+        sap.insert(unit_box(1.0, 0.0), 2);
+        sap.insert(unit_box(0.0, 5.0), 3);
+        assert_eq!(sap.pairs(), vec![(0, 1), (0, 2), (1, 2)]);
+        assert!(grid().pairs().is_empty());
+    }
+
+    #[test]
+    fn test_remove_and_update() {
+        let mut sap = SweepAndPrune::new(0.1);
+        let a = sap.insert(unit_box(0.0, 0.0), 0);
+        let b = sap.insert(unit_box(5.0, 0.0), 1);
+        let c = sap.insert(unit_box(-5.0, 0.0), 2);
+
+        assert!(!sap.update(a, unit_box(0.05, 0.0)));
+        assert!(sap.update(a, unit_box(5.0, 0.0)));
+        assert!(is_sorted(&sap));
+        assert_eq!(sap.query(&unit_box(5.0, 0.0)), vec![0, 1]);
+        assert_eq!(sap.pairs(), vec![(0, 1)]);
+        assert!(sap.update(a, unit_box(-5.5, 0.0)));
+        assert!(is_sorted(&sap));
+        assert_eq!(sap.pairs(), vec![(0, 2)]);
+
+        sap.remove(c);
+        assert!(is_sorted(&sap));
+        assert!(sap.pairs().is_empty());
+        assert_eq!(sap.len(), 2);
+        assert_eq!(sap.insert(unit_box(5.5, 0.5), 3), c);
+        assert_eq!(sap.pairs(), vec![(1, 3)]);
+        sap.remove(b);
+        assert!(is_sorted(&sap));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let sap = grid();
+        let point = Vec2::new(4.5, 4.5);
+        let nearest = sap.nearest(&point, 1, |d| Some(unit_box(2.0 * (d / 10) as f64, 2.0 * (d % 10) as f64).distance_to_point(&point)));
+        assert_eq!(nearest, vec![(22, 0.0)]);
+    }
+}
@@ -0,0 +1,9 @@
+//! Helpers shared by the broadphase tests.
+
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+
+/// 1x1 box with its lower left corner at (x, y)
+pub(crate) fn unit_box(x: f64, y: f64) -> Aabb {
+    Aabb::new(Vec2::new(x, y), Vec2::new(x + 1.0, y + 1.0))
+}
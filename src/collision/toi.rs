@@ -0,0 +1,153 @@
+//! Time of impact between two shapes that both move and turn.
+//!
+//! Each shape follows a Sweep from a start to an end transform, with position and rotation
+//! changing at a constant rate. Like rotating shape casts, this uses conservative advancement:
+//! the gap between the shapes divided by the fastest the two can close it is a time they surely
+//! can't touch before, so both are advanced by that much until the gap is within CAST_TOLERANCE.
+//! Compounds are split into their convex pieces, and the earliest touch of any pair is taken.
+
+use wasm_bindgen::prelude::*;
+
+use super::cast::{self, CAST_TOLERANCE};
+use super::gjk::GJK;
+use crate::geometry::{Shapes, Transformed};
+use crate::math::{Transform2D, Vec2};
+
+/// Cap on advancement steps, after which the shapes count as touching where they got to
+const MAX_TOI_STEPS: usize = 64;
+
+/// Motion of a shape from start to end, moving and turning at a constant rate
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sweep {
+    pub start: Transform2D,
+    pub end: Transform2D,
+}
+
+#[wasm_bindgen]
+impl Sweep {
+    #[wasm_bindgen(constructor)]
+    pub fn new(start: Transform2D, end: Transform2D) -> Sweep {
+        Sweep { start, end }
+    }
+
+    /// A shape staying at transform
+    pub fn stationary(transform: Transform2D) -> Sweep {
+        Sweep::new(transform, transform)
+    }
+
+    /// Transform at fraction t of the motion
+    pub fn at(&self, t: f64) -> Transform2D {
+        Transform2D::new(
+            self.start.position + self.translation() * t,
+            self.start.rotation + self.rotation() * t,
+        )
+    }
+
+    pub fn translation(&self) -> Vec2 {
+        self.end.position - self.start.position
+    }
+
+    /// Radians turned over the motion
+    pub fn rotation(&self) -> f64 {
+        self.end.rotation - self.start.rotation
+    }
+}
+
+/// Fraction of the motion at which shape_a following sweep_a first touches shape_b following
+/// sweep_b, 0 if they overlap at the start. None if they don't touch during the motion.
+pub fn time_of_impact(shape_a: &Shapes, sweep_a: &Sweep, shape_b: &Shapes, sweep_b: &Sweep) -> Option<f64> {
+    let parts_b = cast::convex_parts(shape_b, &Transform2D::identity());
+    cast::convex_parts(shape_a, &Transform2D::identity())
+        .iter()
+        .flat_map(|(piece_a, local_a)| {
+            parts_b.iter().filter_map(move |(piece_b, local_b)| {
+                toi_convex((piece_a, local_a, sweep_a), (piece_b, local_b, sweep_b))
+            })
+        })
+        .min_by(f64::total_cmp)
+}
+
+/// Conservative advancement of two convex pieces, each placed by a local transform in its shape
+fn toi_convex(a: (&Shapes, &Transform2D, &Sweep), b: (&Shapes, &Transform2D, &Sweep)) -> Option<f64> {
+    let (piece_a, local_a, sweep_a) = a;
+    let (piece_b, local_b, sweep_b) = b;
+    let turning = sweep_a.rotation().abs() * cast::max_radius(piece_a, local_a)
+        + sweep_b.rotation().abs() * cast::max_radius(piece_b, local_b);
+    let relative = sweep_a.translation() - sweep_b.translation();
+    let mut gjk = GJK::new();
+    let mut t = 0.0;
+
+    for _ in 0..MAX_TOI_STEPS {
+        let placed_a = Transformed::new(piece_a, sweep_a.at(t).mul(local_a));
+        let placed_b = Transformed::new(piece_b, sweep_b.at(t).mul(local_b));
+        let closest = match gjk.distance(&placed_a, &placed_b) {
+            Some(closest) => closest,
+            None => return Some(t),
+        };
+        if closest.distance <= CAST_TOLERANCE {
+            return Some(t);
+        }
+
+        // Fastest any points of the pieces can approach each other
+        let closing_speed = Vec2::dot(&relative, &closest.normal) + turning;
+        if closing_speed <= 0.0 {
+            return None;
+        }
+        t += (closest.distance - CAST_TOLERANCE * 0.5) / closing_speed;
+        if t > 1.0 {
+            return None;
+        }
+    }
+    Some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::narrowphase;
+    use crate::geometry::{Circle, Polygon};
+    use std::f64::consts::FRAC_PI_2;
+
+    fn ball() -> Shapes {
+        Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5))
+    }
+
+    fn at(x: f64, y: f64) -> Transform2D {
+        Transform2D::new(Vec2::new(x, y), 0.0)
+    }
+
+    #[test]
+    fn test_head_on() {
+        let a = Sweep::new(at(0.0, 0.0), at(10.0, 0.0));
+        let b = Sweep::new(at(10.0, 0.0), at(0.0, 0.0));
+        // The gap of 9 closes at 20 per step
+        let t = time_of_impact(&ball(), &a, &ball(), &b).unwrap();
+        assert!((t - 0.45).abs() < 1e-4);
+
+        // Moving side by side they never meet
+        let beside = Sweep::new(at(0.0, 2.0), at(10.0, 2.0));
+        assert!(time_of_impact(&ball(), &a, &ball(), &beside).is_none());
+        assert_eq!(time_of_impact(&ball(), &Sweep::stationary(at(0.0, 0.0)), &ball(), &a), Some(0.0));
+    }
+
+    #[test]
+    fn test_rotating_bar() {
+        // A bar swinging a quarter turn about its middle sweeps into a ball above it
+        let bar = Shapes::Polygon(Polygon::rect(4.0, 0.2));
+        let swing = Sweep::new(at(0.0, 0.0), Transform2D::new(Vec2::new(0.0, 0.0), -FRAC_PI_2));
+        let target = Sweep::stationary(at(-1.0, 1.5));
+        let t = time_of_impact(&bar, &swing, &ball(), &target).unwrap();
+        assert!(t > 0.0 && t < 1.0);
+
+        let gap = |t: f64| {
+            let (a, b) = (swing.at(t), target.at(t));
+            !narrowphase::overlaps(&bar, &a, &ball(), &b)
+        };
+        assert!(gap(t - 0.01));
+        assert!(!gap(t + 0.01));
+        // Swinging the other way misses it
+        let back = Sweep::new(at(0.0, 0.0), Transform2D::new(Vec2::new(0.0, 0.0), 0.5));
+        assert!(time_of_impact(&bar, &back, &ball(), &target).is_none());
+    }
+}
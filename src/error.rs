@@ -0,0 +1,97 @@
+//! Crate wide error type.
+//!
+//! Each module reports its own error type, and Error wraps any of them so an application can
+//! handle every failure with one `?`. All of them implement std::error::Error, so they also
+//! convert to wasm_bindgen::JsError through its blanket From impl, which every wasm wrapper
+//! throws with. A From<Error> for JsError of our own would conflict with that impl.
+
+use std::fmt;
+
+use crate::geometry::GeometryError;
+use crate::math::MathError;
+use crate::physics::WorldError;
+use crate::serialize::SerializeError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Geometry(GeometryError),
+    Math(MathError),
+    World(WorldError),
+    Serialization(SerializeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Geometry(e) => e.fmt(f),
+            Error::Math(e) => e.fmt(f),
+            Error::World(e) => e.fmt(f),
+            Error::Serialization(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Geometry(e) => Some(e),
+            Error::Math(e) => Some(e),
+            Error::World(e) => Some(e),
+            Error::Serialization(e) => Some(e),
+        }
+    }
+}
+
+impl From<GeometryError> for Error {
+    fn from(e: GeometryError) -> Error {
+        Error::Geometry(e)
+    }
+}
+
+impl From<MathError> for Error {
+    fn from(e: MathError) -> Error {
+        Error::Math(e)
+    }
+}
+
+impl From<WorldError> for Error {
+    fn from(e: WorldError) -> Error {
+        Error::World(e)
+    }
+}
+
+impl From<SerializeError> for Error {
+    fn from(e: SerializeError) -> Error {
+        Error::Serialization(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Polygon;
+    use crate::math::{Matrix, Vec2, Vector};
+    use crate::physics::World;
+    use std::error::Error as _;
+
+    fn build() -> Result<World, Error> {
+        let mut world = World::from_json(&World::new().to_json())?;
+        let a = Matrix::try_from(2, 2, &[2.0, 0.0, 0.0, 4.0])?;
+        let x = a.try_solve(&Vector::from(&[2.0, 4.0]))?;
+        let _ = Polygon::try_new(&[Vec2::new(0.0, 0.0), Vec2::new(x.get(0), 0.0), Vec2::new(0.0, x.get(1))])?;
+        Polygon::try_from_flat(&[0.0, 0.0, 1.0])?;
+        world.step(0.1);
+        Ok(world)
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        let err = build().unwrap_err();
+        assert_eq!(err, Error::Geometry(GeometryError::OddCoordinateCount(3)));
+        assert!(err.source().is_some());
+        assert_eq!(err.to_string(), "Expected x, y pairs but got 3 values");
+
+        let err: Error = Matrix::new(2, 3).try_lu().unwrap_err().into();
+        assert_eq!(err, Error::Math(MathError::NotSquare { rows: 2, cols: 3 }));
+    }
+}
@@ -0,0 +1,135 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::Vec2;
+
+/// An axis aligned bounding box
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+#[wasm_bindgen]
+impl Aabb {
+    #[wasm_bindgen(constructor)]
+    pub fn new(min: Vec2, max: Vec2) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Box around a center with the given half extents
+    pub fn from_center(center: Vec2, half_extents: Vec2) -> Aabb {
+        Aabb::new(center - half_extents, center + half_extents)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec2 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        2.0 * (self.width() + self.height())
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vec2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            Vec2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        )
+    }
+
+    /// Grow the box by margin on every side
+    pub fn expand(&self, margin: f64) -> Aabb {
+        Aabb::new(self.min - Vec2::splat(margin), self.max + Vec2::splat(margin))
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x && self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+
+    pub fn contains_point(&self, p: &Vec2) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    /// Distance from p to the closest point of the box, 0 if p is inside
+    pub fn distance_to_point(&self, p: &Vec2) -> f64 {
+        let dx = (self.min.x - p.x).max(p.x - self.max.x).max(0.0);
+        let dy = (self.min.y - p.y).max(p.y - self.max.y).max(0.0);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// True if other lies entirely inside this box
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+}
+
+impl Aabb {
+    /// Smallest box containing all points
+    pub fn from_points(points: &[Vec2]) -> Aabb {
+        let mut min = Vec2::splat(f64::INFINITY);
+        let mut max = Vec2::splat(f64::NEG_INFINITY);
+        for p in points {
+            min = Vec2::new(min.x.min(p.x), min.y.min(p.y));
+            max = Vec2::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        Aabb::new(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aabb_union() {
+        let a = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+        let b = Aabb::new(Vec2::new(2.0, -1.0), Vec2::new(3.0, 0.5));
+
+        assert_eq!(a.union(&b), Aabb::new(Vec2::new(0.0, -1.0), Vec2::new(3.0, 1.0)));
+    }
+
+    #[test]
+    fn test_aabb_overlaps() {
+        let a = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+        let b = Aabb::new(Vec2::new(0.5, 0.5), Vec2::new(2.0, 2.0));
+        let c = Aabb::new(Vec2::new(1.5, 1.5), Vec2::new(2.0, 2.0));
+
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+        assert!(b.contains(&c));
+    }
+
+    #[test]
+    fn test_aabb_distance_to_point() {
+        let a = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+
+        assert_eq!(a.distance_to_point(&Vec2::new(0.5, 0.5)), 0.0);
+        assert_eq!(a.distance_to_point(&Vec2::new(0.5, 3.0)), 2.0);
+        assert_eq!(a.distance_to_point(&Vec2::new(4.0, 5.0)), 5.0);
+    }
+
+    #[test]
+    fn test_aabb_from_points() {
+        let a = Aabb::from_points(&[Vec2::new(1.0, 2.0), Vec2::new(-1.0, 3.0), Vec2::new(0.0, 0.0)]);
+
+        assert_eq!(a, Aabb::new(Vec2::new(-1.0, 0.0), Vec2::new(1.0, 3.0)));
+        assert_eq!(a.center(), Vec2::new(0.0, 1.5));
+    }
+}
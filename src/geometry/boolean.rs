@@ -0,0 +1,402 @@
+//! Union, intersection and difference of simple polygons, e.g. for destructible terrain.
+//!
+//! Uses Greiner-Hormann clipping: the crossings of the two outlines are inserted into both
+//! vertex lists, and the results are traced by walking one outline and switching to the other
+//! at each crossing. Outlines that only touch, where a vertex lies on the other polygon's edge,
+//! are handled by nudging the second polygon by a tiny fraction of the polygons' size. It is
+//! nudged towards the first for unions, so touching polygons merge, and away from it for
+//! differences, so a cut along an edge goes cleanly through it.
+//!
+//! Polygons can't have holes, so a union enclosing empty space fills it in, and a difference
+//! cutting a hole out of the middle of a polygon splits the rest into two pieces around it.
+
+use super::aabb::Aabb;
+use super::polygon::Polygon;
+use super::shape::Shape;
+use crate::math::Vec2;
+
+/// Fraction of the polygons' size within which a vertex counts as touching an edge
+const TOUCH_TOLERANCE: f64 = 1e-10;
+/// Fraction of the polygons' size the second polygon is moved by when the outlines touch
+const NUDGE: f64 = 1e-8;
+/// Pieces with less area than this fraction of the polygons' size squared are dropped
+const SLIVER_AREA: f64 = 1e-12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Area covered by a or b
+pub fn union(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    boolean(a, b, Operation::Union)
+}
+
+/// Area covered by both a and b
+pub fn intersection(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    boolean(a, b, Operation::Intersection)
+}
+
+/// Area covered by a but not b
+pub fn difference(a: &Polygon, b: &Polygon) -> Vec<Polygon> {
+    boolean(a, b, Operation::Difference)
+}
+
+/// A vertex of one outline, or a crossing with the other outline
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    point: Vec2,
+    crossing: bool,
+    /// Crossings only: index of the same crossing in the other list
+    neighbor: usize,
+    /// Crossings only: whether the result continues forward along this outline from here
+    forward: bool,
+    visited: bool,
+}
+
+impl Node {
+    fn vertex(point: Vec2) -> Node {
+        Node {
+            point,
+            crossing: false,
+            neighbor: 0,
+            forward: false,
+            visited: false,
+        }
+    }
+}
+
+fn boolean(a: &Polygon, b: &Polygon, op: Operation) -> Vec<Polygon> {
+    let bounds = a.aabb().union(&b.aabb());
+    let scale = (bounds.max - bounds.min).len();
+    let (va, mut vb) = (a.vertices().to_vec(), b.vertices().to_vec());
+    let towards = a.center() - b.center();
+    // Tilted off the line between the centres so edges parallel to it stop lining up
+    let mut direction = if towards.len_sq() > 0.0 { towards.normalize().rotate(0.5) } else { Vec2::new(0.6, 0.8) };
+    if op == Operation::Difference {
+        direction = -direction;
+    }
+    for attempt in 1..=8 {
+        if !touching(&va, &vb, TOUCH_TOLERANCE * scale) {
+            break;
+        }
+        let nudge = direction * (NUDGE * scale * attempt as f64);
+        vb = b.vertices().iter().map(|v| v + nudge).collect();
+    }
+
+    let (mut list_a, mut list_b) = crossing_lists(&va, &vb);
+    if !list_a.iter().any(|n| n.crossing) {
+        return disjoint(a, &va, b, &vb, op, &bounds);
+    }
+
+    // Crossings alternate between entering and leaving the other polygon
+    for (list, own, other, inside_forward) in [
+        (&mut list_a, &va, &vb, op == Operation::Intersection),
+        (&mut list_b, &vb, &va, op != Operation::Union),
+    ] {
+        let mut inside = contains(other, &own[0]);
+        for node in list.iter_mut().filter(|n| n.crossing) {
+            // Forward from a crossing into the other polygon runs inside it
+            node.forward = inside != inside_forward;
+            inside = !inside;
+        }
+    }
+
+    let mut pieces = Vec::new();
+    for start in 0..list_a.len() {
+        if !list_a[start].crossing || list_a[start].visited {
+            continue;
+        }
+        // Trace so the first stretch runs forward along a, keeping results counter-clockwise
+        let reversed = !list_a[start].forward;
+        let mut outline = Vec::new();
+        let (mut on_a, mut i) = (true, start);
+        loop {
+            let (list, other) = if on_a { (&mut list_a, &mut list_b) } else { (&mut list_b, &mut list_a) };
+            list[i].visited = true;
+            other[list[i].neighbor].visited = true;
+            outline.push(list[i].point);
+            let forward = list[i].forward;
+            let n = list.len();
+            loop {
+                i = if forward { (i + 1) % n } else { (i + n - 1) % n };
+                if list[i].crossing {
+                    break;
+                }
+                outline.push(list[i].point);
+            }
+            if list[i].visited {
+                break;
+            }
+            i = list[i].neighbor;
+            on_a = !on_a;
+        }
+        if reversed {
+            outline.reverse();
+        }
+        pieces.push(outline);
+    }
+
+    pieces
+        .into_iter()
+        // Clockwise outlines are holes, which only a union can make
+        .filter(|outline| signed_area(outline) > SLIVER_AREA * scale * scale)
+        .filter_map(|outline| Polygon::try_new(&outline).ok())
+        .collect()
+}
+
+/// Results when the outlines don't cross, so each polygon is inside the other or apart
+fn disjoint(a: &Polygon, va: &[Vec2], b: &Polygon, vb: &[Vec2], op: Operation, bounds: &Aabb) -> Vec<Polygon> {
+    let a_in_b = contains(vb, &va[0]);
+    let b_in_a = contains(va, &vb[0]);
+    match op {
+        Operation::Union if a_in_b => vec![b.clone()],
+        Operation::Union if b_in_a => vec![a.clone()],
+        Operation::Union => vec![a.clone(), b.clone()],
+        Operation::Intersection if a_in_b => vec![a.clone()],
+        Operation::Intersection if b_in_a => vec![b.clone()],
+        Operation::Intersection => Vec::new(),
+        Operation::Difference if a_in_b => Vec::new(),
+        Operation::Difference if b_in_a => {
+            // Cut a in two through b, so b crosses the outline of each half
+            let x = b.center().x;
+            let (lo, hi) = (bounds.min - Vec2::splat(1.0), bounds.max + Vec2::splat(1.0));
+            let halves = [
+                Polygon::rect_at(Vec2::new((lo.x + x) / 2.0, (lo.y + hi.y) / 2.0), x - lo.x, hi.y - lo.y),
+                Polygon::rect_at(Vec2::new((x + hi.x) / 2.0, (lo.y + hi.y) / 2.0), hi.x - x, hi.y - lo.y),
+            ];
+            halves
+                .iter()
+                .flat_map(|half| intersection(a, half))
+                .flat_map(|piece| difference(&piece, b))
+                .collect()
+        }
+        Operation::Difference => vec![a.clone()],
+    }
+}
+
+/// Where an edge of a crosses an edge of b, with the fraction along each edge
+struct Crossing {
+    edge_a: usize,
+    t: f64,
+    edge_b: usize,
+    u: f64,
+    point: Vec2,
+}
+
+/// Vertex lists of both outlines with their crossings inserted in order along each edge
+fn crossing_lists(va: &[Vec2], vb: &[Vec2]) -> (Vec<Node>, Vec<Node>) {
+    let mut crossings = Vec::new();
+    for i in 0..va.len() {
+        let (a0, a1) = (va[i], va[(i + 1) % va.len()]);
+        for j in 0..vb.len() {
+            let (b0, b1) = (vb[j], vb[(j + 1) % vb.len()]);
+            let (da, db) = (a1 - a0, b1 - b0);
+            let denom = Vec2::cross(&da, &db);
+            if denom == 0.0 {
+                continue;
+            }
+            let t = Vec2::cross(&(b0 - a0), &db) / denom;
+            let u = Vec2::cross(&(b0 - a0), &da) / denom;
+            if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+                crossings.push(Crossing {
+                    edge_a: i,
+                    t,
+                    edge_b: j,
+                    u,
+                    point: a0 + da * t,
+                });
+            }
+        }
+    }
+
+    let build = |vertices: &[Vec2], along: &dyn Fn(&Crossing) -> (usize, f64)| {
+        let mut list = Vec::with_capacity(vertices.len() + crossings.len());
+        let mut position = vec![0; crossings.len()];
+        for (edge, v) in vertices.iter().enumerate() {
+            list.push(Node::vertex(*v));
+            let mut on_edge: Vec<(f64, usize)> = crossings
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| along(c).0 == edge)
+                .map(|(k, c)| (along(c).1, k))
+                .collect();
+            on_edge.sort_by(|x, y| x.0.total_cmp(&y.0));
+            for (_, k) in on_edge {
+                position[k] = list.len();
+                list.push(Node {
+                    crossing: true,
+                    ..Node::vertex(crossings[k].point)
+                });
+            }
+        }
+        (list, position)
+    };
+    let (mut list_a, in_a) = build(va, &|c| (c.edge_a, c.t));
+    let (mut list_b, in_b) = build(vb, &|c| (c.edge_b, c.u));
+    for k in 0..crossings.len() {
+        list_a[in_a[k]].neighbor = in_b[k];
+        list_b[in_b[k]].neighbor = in_a[k];
+    }
+    (list_a, list_b)
+}
+
+/// True if a vertex of either outline is within tolerance of an edge of the other
+fn touching(va: &[Vec2], vb: &[Vec2], tolerance: f64) -> bool {
+    let near = |points: &[Vec2], outline: &[Vec2]| {
+        points.iter().any(|p| {
+            (0..outline.len()).any(|i| {
+                let (e0, e1) = (outline[i], outline[(i + 1) % outline.len()]);
+                let edge = e1 - e0;
+                let t = (Vec2::dot(&(p - e0), &edge) / edge.len_sq()).clamp(0.0, 1.0);
+                (p - (e0 + edge * t)).len() <= tolerance
+            })
+        })
+    };
+    near(va, vb) || near(vb, va)
+}
+
+/// Even-odd test of point against an outline that may be concave
+fn contains(outline: &[Vec2], point: &Vec2) -> bool {
+    let mut inside = false;
+    let mut j = outline.len() - 1;
+    for i in 0..outline.len() {
+        let (a, b) = (outline[i], outline[j]);
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn signed_area(outline: &[Vec2]) -> f64 {
+    let n = outline.len();
+    (0..n).map(|i| Vec2::cross(&outline[i], &outline[(i + 1) % n])).sum::<f64>() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    fn area(pieces: &[Polygon]) -> f64 {
+        pieces.iter().map(|p| p.area()).sum()
+    }
+
+    #[test]
+    fn test_overlapping_squares() {
+        let a = Polygon::rect_at(Vec2::new(0.0, 0.0), 2.0, 2.0);
+        let b = Polygon::rect_at(Vec2::new(1.0, 1.0), 2.0, 2.0);
+
+        let both = intersection(&a, &b);
+        assert_eq!(both.len(), 1);
+        assert!((area(&both) - 1.0).abs() < 1e-12);
+        let either = union(&a, &b);
+        assert_eq!(either.len(), 1);
+        assert_eq!(either[0].vertices().len(), 8);
+        assert!((area(&either) - 7.0).abs() < 1e-12);
+        let rest = difference(&a, &b);
+        assert_eq!(rest.len(), 1);
+        assert!((area(&rest) - 3.0).abs() < 1e-12);
+        assert!(rest[0].area() > 0.0);
+    }
+
+    #[test]
+    fn test_cut_in_two() {
+        // A bar through the middle of a square leaves two pieces
+        let square = Polygon::rect(4.0, 4.0);
+        let bar = Polygon::rect(1.0, 6.0);
+        let pieces = difference(&square, &bar);
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|p| (p.area() - 6.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_nested_and_apart() {
+        let big = Polygon::rect(4.0, 4.0);
+        let small = Polygon::regular(6, 1.0);
+        let far = Polygon::rect_at(Vec2::new(10.0, 0.0), 1.0, 1.0);
+
+        assert_eq!(union(&big, &small), vec![big.clone()]);
+        assert_eq!(intersection(&big, &small), vec![small.clone()]);
+        assert!(difference(&small, &big).is_empty());
+        assert_eq!(union(&big, &far).len(), 2);
+        assert!(intersection(&big, &far).is_empty());
+        assert_eq!(difference(&big, &far), vec![big.clone()]);
+
+        // A hole can't be represented, so the rest comes in two pieces around it
+        let holed = difference(&big, &small);
+        assert_eq!(holed.len(), 2);
+        assert!((area(&holed) - (16.0 - small.area())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_touching_outlines() {
+        // Squares sharing an edge merge into one rectangle
+        let a = Polygon::rect_at(Vec2::new(0.0, 0.0), 2.0, 2.0);
+        let b = Polygon::rect_at(Vec2::new(2.0, 0.0), 2.0, 2.0);
+        let merged = union(&a, &b);
+        assert_eq!(merged.len(), 1);
+        assert!((area(&merged) - 8.0).abs() < 1e-6);
+        assert!(intersection(&a, &b).iter().all(|p| p.area() < 1e-6));
+
+        // Cutting out a corner that shares two edges with the polygon
+        let corner = Polygon::rect_at(Vec2::new(0.5, 0.5), 1.0, 1.0);
+        let rest = difference(&a, &corner);
+        assert_eq!(rest.len(), 1);
+        assert!((area(&rest) - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_random_convex_pairs() {
+        // Area identities that hold for any pair: the union and intersection together cover both
+        // polygons, and the difference is a less the part shared with b
+        let mut rng = Rng::new(7);
+        let convex = |rng: &mut Rng| {
+            let center = rng.in_disk(&Vec2::new(0.0, 0.0), 2.0);
+            let points: Vec<Vec2> = (0..3 + rng.next_u64() % 6).map(|_| rng.in_disk(&center, 2.0)).collect();
+            Polygon::try_convex_hull(&points).ok()
+        };
+        let mut checked = 0;
+        while checked < 2000 {
+            let (a, b) = match (convex(&mut rng), convex(&mut rng)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            checked += 1;
+            let both = area(&intersection(&a, &b));
+            let tolerance = 1e-9 * (a.area() + b.area());
+            let context = format!("a {:?} b {:?}", a.vertices(), b.vertices());
+            assert!((area(&union(&a, &b)) + both - a.area() - b.area()).abs() < tolerance, "{}", context);
+            assert!((area(&difference(&a, &b)) - (a.area() - both)).abs() < tolerance, "{}", context);
+            assert!((area(&difference(&b, &a)) - (b.area() - both)).abs() < tolerance, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_concave() {
+        // A U shape with a bar across its mouth
+        let u = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 3.0),
+            Vec2::new(2.0, 3.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ]);
+        let bar = Polygon::rect_at(Vec2::new(1.5, 2.5), 4.0, 0.5);
+
+        let arms = intersection(&u, &bar);
+        assert_eq!(arms.len(), 2);
+        assert!((area(&arms) - 1.0).abs() < 1e-12);
+        // The union encloses the notch below the bar, which gets filled in
+        let closed = union(&u, &bar);
+        assert_eq!(closed.len(), 1);
+        assert!((area(&closed) - 9.25).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,10 @@
 use wasm_bindgen::prelude::*;
 
-use super::shape::Shape;
+use super::aabb::Aabb;
+use super::mass;
+use super::shape::{ContentHasher, Shape};
+use super::ShapeKind;
+use crate::math::trig;
 use crate::math::vec2::Vec2;
 
 #[wasm_bindgen]
@@ -21,9 +25,31 @@ impl Circle {
         self.center
     }
 
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
     pub fn area(&self) -> f64 {
         self.radius * self.radius * std::f64::consts::PI
     }
+
+    pub fn inertia(&self, mass: f64) -> f64 {
+        mass::inertia_circle(mass, self.radius)
+    }
+}
+
+impl Circle {
+    /// Corners of a regular polygon with segments sides, at least 3, drawn around the circle.
+    /// Its edges touch the circle, so it encloses the circle and reaches at most
+    /// radius / cos(π / segments) from the center.
+    pub fn enclosing_points(&self, segments: usize) -> Vec<Vec2> {
+        let segments = segments.max(3);
+        let step = 2.0 * std::f64::consts::PI / segments as f64;
+        let radius = self.radius / trig::cos(step / 2.0);
+        (0..segments)
+            .map(|i| self.center + Vec2::new(1.0, 0.0).rotate(step * (i as f64 + 0.5)) * radius)
+            .collect()
+    }
 }
 
 impl Shape for Circle {
@@ -38,6 +64,18 @@ impl Shape for Circle {
     fn area(&self) -> f64 {
         self.area()
     }
+
+    fn inertia(&self, mass: f64) -> f64 {
+        self.inertia(mass)
+    }
+
+    fn content_hash(&self) -> u64 {
+        ContentHasher::new(ShapeKind::Circle).vec2(&self.center).f64(self.radius).finish()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_center(self.center, Vec2::splat(self.radius))
+    }
 }
 
 #[cfg(test)]
@@ -65,4 +103,25 @@ mod tests {
 
         assert_eq!(a.area(), std::f64::consts::PI * 1.0 * 1.0);
     }
+
+    #[test]
+    fn test_circle_inertia() {
+        let a = Circle::new(Vec2::new(1.0, 1.0), 2.0);
+
+        assert_eq!(a.inertia(3.0), 6.0);
+    }
+
+    #[test]
+    fn test_circle_enclosing_points() {
+        let a = Circle::new(Vec2::new(1.0, 1.0), 2.0);
+        let points = a.enclosing_points(8);
+
+        // Edge midpoints lie on the circle, corners outside it
+        assert_eq!(points.len(), 8);
+        for (p, q) in points.iter().zip(points.iter().cycle().skip(1)) {
+            assert!((((p + q) / 2.0 - a.center()).len() - 2.0).abs() < 1e-12);
+            assert!(((p - a.center()).len() - 2.0 / (std::f64::consts::PI / 8.0).cos()).abs() < 1e-12);
+        }
+        assert_eq!(a.enclosing_points(1).len(), 3);
+    }
 }
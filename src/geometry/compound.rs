@@ -0,0 +1,266 @@
+use super::aabb::Aabb;
+use super::decompose;
+use super::mass;
+use super::polygon::Polygon;
+use super::shape::{ContentHasher, Shape};
+use super::transformed::Transformed;
+use super::{ShapeKind, Shapes};
+use crate::math::{Transform2D, Vec2};
+
+/// A shape assembled from child shapes placed by local transforms
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Compound {
+    children: Vec<(Transform2D, Shapes)>,
+}
+
+/// Mass contribution of a single compound child
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChildMass {
+    pub index: usize,
+    pub mass: f64,
+    /// Child centroid in compound space
+    pub centroid: Vec2,
+    /// Inertia of the child about its own centroid
+    pub inertia: f64,
+    /// Inertia of the child about the compound center of mass
+    pub inertia_about_center: f64,
+}
+
+/// Per-child and combined mass properties of a compound
+#[derive(Clone, Debug, PartialEq)]
+pub struct MassBreakdown {
+    pub children: Vec<ChildMass>,
+    pub mass: f64,
+    pub center: Vec2,
+    /// Inertia about the compound center of mass
+    pub inertia: f64,
+}
+
+impl Compound {
+    pub fn new() -> Compound {
+        Compound { children: Vec::new() }
+    }
+
+    /// Add a child shape placed by transform, returning its index
+    pub fn add_child(&mut self, transform: Transform2D, shape: Shapes) -> usize {
+        self.children.push((transform, shape));
+        self.children.len() - 1
+    }
+
+    pub fn children(&self) -> &[(Transform2D, Shapes)] {
+        &self.children
+    }
+
+    /// Mirror image across the line through the origin along axis
+    pub fn mirrored(&self, axis: &Vec2) -> Compound {
+        let children = self
+            .children
+            .iter()
+            .map(|(t, shape)| (Transform2D::new(t.position.mirror(axis), -t.rotation), shape.mirrored(axis)))
+            .collect();
+        Compound { children }
+    }
+
+    /// The same compound moved by offset
+    pub fn translated(&self, offset: &Vec2) -> Compound {
+        let children = self
+            .children
+            .iter()
+            .map(|(t, shape)| (Transform2D::new(t.position + *offset, t.rotation), shape.clone()))
+            .collect();
+        Compound { children }
+    }
+
+    /// The same outline with polygon children sharing edges merged into fewer convex polygons,
+    /// removing the internal edges between them. See decompose::merge_convex.
+    /// Merged polygons come first, placed without a transform, then the other children.
+    pub fn merged(&self) -> Compound {
+        let mut polygons = Vec::new();
+        let mut rest = Vec::new();
+        for (t, shape) in &self.children {
+            match shape {
+                Shapes::Polygon(p) => {
+                    let points: Vec<Vec2> = p.vertices().iter().map(|v| t.apply(v)).collect();
+                    polygons.push(Polygon::new(&points));
+                }
+                _ => rest.push((*t, shape.clone())),
+            }
+        }
+        let mut children: Vec<(Transform2D, Shapes)> = decompose::merge_convex(&polygons)
+            .into_iter()
+            .map(|p| (Transform2D::identity(), Shapes::Polygon(p)))
+            .collect();
+        children.extend(rest);
+        Compound { children }
+    }
+
+    /// Mass, centroid and inertia contributed by each child for a uniform density
+    pub fn mass_breakdown(&self, density: f64) -> MassBreakdown {
+        self.mass_breakdown_with(&vec![density; self.children.len()])
+    }
+
+    /// Like mass_breakdown with a density for each child, in child order
+    pub fn mass_breakdown_with(&self, densities: &[f64]) -> MassBreakdown {
+        assert_eq!(densities.len(), self.children.len(), "One density per compound child");
+        let mut children: Vec<ChildMass> = self
+            .children
+            .iter()
+            .zip(densities)
+            .enumerate()
+            .map(|(index, ((transform, shape), density))| {
+                let mass = density * shape.area().abs();
+                ChildMass {
+                    index,
+                    mass,
+                    centroid: transform.apply(&shape.center()),
+                    inertia: shape.inertia(mass),
+                    inertia_about_center: 0.0,
+                }
+            })
+            .collect();
+
+        let mass: f64 = children.iter().map(|c| c.mass).sum();
+        let mut center = Vec2::new(0.0, 0.0);
+        if mass > 0.0 {
+            for c in &children {
+                center = center + c.centroid * c.mass;
+            }
+            center = center / mass;
+        }
+
+        let mut inertia = 0.0;
+        for c in &mut children {
+            c.inertia_about_center = mass::parallel_axis(c.inertia, c.mass, &(c.centroid - center));
+            inertia += c.inertia_about_center;
+        }
+
+        MassBreakdown {
+            children,
+            mass,
+            center,
+            inertia,
+        }
+    }
+}
+
+impl Shape for Compound {
+    /// Support point of the convex hull of all children
+    fn support(&self, dir: &Vec2) -> Vec2 {
+        let mut max_dist = f64::MIN;
+        let mut max_point = Vec2::new(0.0, 0.0);
+        for (transform, shape) in &self.children {
+            let p = Transformed::new(shape, *transform).support(dir);
+            let dist = Vec2::dot(&p, dir);
+            if dist > max_dist {
+                max_dist = dist;
+                max_point = p;
+            }
+        }
+        max_point
+    }
+
+    /// Area weighted centroid of the children
+    fn center(&self) -> Vec2 {
+        self.mass_breakdown(1.0).center
+    }
+
+    fn area(&self) -> f64 {
+        self.children.iter().map(|(_, s)| s.area().abs()).sum()
+    }
+
+    fn inertia(&self, mass: f64) -> f64 {
+        let area = self.area();
+        if area <= 0.0 {
+            return 0.0;
+        }
+        self.mass_breakdown(mass / area).inertia
+    }
+
+    fn content_hash(&self) -> u64 {
+        let hasher = ContentHasher::new(ShapeKind::Compound).u64(self.children.len() as u64);
+        self.children
+            .iter()
+            .fold(hasher, |h, (t, s)| h.vec2(&t.position).f64(t.rotation).u64(s.content_hash()))
+            .finish()
+    }
+
+    fn aabb(&self) -> Aabb {
+        self.children
+            .iter()
+            .map(|(transform, shape)| Transformed::new(shape, *transform).aabb())
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Circle;
+
+    fn unit_square() -> Shapes {
+        Shapes::Polygon(Polygon::new(&[
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(0.5, -0.5),
+            Vec2::new(0.5, 0.5),
+            Vec2::new(-0.5, 0.5),
+        ]))
+    }
+
+    #[test]
+    fn test_mass_breakdown_matches_single_rectangle() {
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::new(Vec2::new(-0.5, 0.0), 0.0), unit_square());
+        compound.add_child(Transform2D::new(Vec2::new(0.5, 0.0), 0.0), unit_square());
+
+        let breakdown = compound.mass_breakdown(1.0);
+        assert_eq!(breakdown.mass, 2.0);
+        assert_eq!(breakdown.center, Vec2::new(0.0, 0.0));
+        assert!((breakdown.inertia - 10.0 / 12.0).abs() < 1e-12);
+        assert_eq!(breakdown.children[1].centroid, Vec2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn test_compound_shape() {
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::new(Vec2::new(-0.5, 0.0), 0.0), unit_square());
+        compound.add_child(Transform2D::new(Vec2::new(1.5, 0.0), 0.0), unit_square());
+
+        assert_eq!(compound.area(), 2.0);
+        assert_eq!(compound.center(), Vec2::new(0.5, 0.0));
+        assert_eq!(compound.aabb(), Aabb::new(Vec2::new(-1.0, -0.5), Vec2::new(2.0, 0.5)));
+        assert_eq!(compound.support(&Vec2::new(1.0, 1.0)), Vec2::new(2.0, 0.5));
+    }
+
+    #[test]
+    fn test_mass_breakdown_weighted_center() {
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::identity(), unit_square());
+        compound.add_child(
+            Transform2D::new(Vec2::new(3.0, 0.0), 0.0),
+            Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 1.0)),
+        );
+
+        let breakdown = compound.mass_breakdown(2.0);
+        let pi = std::f64::consts::PI;
+        assert_eq!(breakdown.children[0].mass, 2.0);
+        assert_eq!(breakdown.children[1].mass, 2.0 * pi);
+        assert!((breakdown.center.x - 3.0 * pi / (1.0 + pi)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_merged() {
+        let mut compound = Compound::new();
+        for x in 0..3 {
+            compound.add_child(Transform2D::new(Vec2::new(x as f64, 0.0), 0.0), unit_square());
+        }
+        let circle = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5));
+        compound.add_child(Transform2D::new(Vec2::new(0.0, 2.0), 0.0), circle.clone());
+
+        let merged = compound.merged();
+        assert_eq!(merged.children().len(), 2);
+        assert_eq!(merged.area(), compound.area());
+        assert_eq!(merged.aabb(), compound.aabb());
+        assert_eq!(merged.children()[1], (Transform2D::new(Vec2::new(0.0, 2.0), 0.0), circle));
+    }
+}
@@ -0,0 +1,322 @@
+//! Convex decomposition of simple polygons.
+//!
+//! The polygon is triangulated by ear clipping, then neighbouring pieces are merged across
+//! shared diagonals while the result stays convex (Hertel-Mehlhorn). This produces at most
+//! four times the optimal number of pieces.
+
+use super::compound::Compound;
+use super::polygon::Polygon;
+use super::shape::Shape;
+use super::Shapes;
+use crate::math::{Transform2D, Vec2};
+
+const EPSILON: f64 = 1e-9;
+
+/// True if the counter-clockwise vertex loop has no reflex corners
+pub fn is_convex(vertices: &[Vec2]) -> bool {
+    let n = vertices.len();
+    (0..n).all(|i| {
+        let a = vertices[(i + n - 1) % n];
+        let b = vertices[i];
+        let c = vertices[(i + 1) % n];
+        Vec2::cross(&(b - a), &(c - b)) >= -EPSILON
+    })
+}
+
+/// Split a simple (non self-intersecting) polygon into convex polygons
+pub fn decompose(polygon: &Polygon) -> Vec<Polygon> {
+    let mut vertices = polygon.vertices().to_vec();
+    if polygon.area() < 0.0 {
+        vertices.reverse();
+    }
+    if is_convex(&vertices) {
+        return vec![Polygon::new(&vertices)];
+    }
+
+    let pieces = merge_pieces(&vertices, triangulate(&vertices));
+    pieces
+        .iter()
+        .map(|piece| {
+            let points: Vec<Vec2> = piece.iter().map(|i| vertices[*i]).collect();
+            Polygon::new(&remove_collinear(&points))
+        })
+        .collect()
+}
+
+/// Decompose a simple polygon and hold the convex pieces in a Compound
+pub fn decompose_to_compound(polygon: &Polygon) -> Compound {
+    let mut compound = Compound::new();
+    for piece in decompose(polygon) {
+        compound.add_child(Transform2D::identity(), Shapes::Polygon(piece));
+    }
+    compound
+}
+
+/// Merge convex polygons across shared edges while they stay convex, such as tiles baked side
+/// by side or pieces from separate decompositions. Edges are shared where they overlap, even
+/// if one polygon's corner lies in the middle of the other's edge.
+pub fn merge_convex(pieces: &[Polygon]) -> Vec<Polygon> {
+    // Corners in the same place become the same vertex
+    let mut vertices: Vec<Vec2> = Vec::new();
+    let loops: Vec<Vec<usize>> = pieces
+        .iter()
+        .map(|p| p.vertices().iter().map(|v| weld(&mut vertices, v)).collect())
+        .collect();
+    let loops = loops.iter().map(|l| split_edges(&vertices, l)).collect();
+
+    merge_pieces(&vertices, loops)
+        .iter()
+        .map(|piece| {
+            let points: Vec<Vec2> = piece.iter().map(|i| vertices[*i]).collect();
+            Polygon::new(&remove_collinear(&points))
+        })
+        .collect()
+}
+
+/// Index of the vertex at v, adding it if there is none
+fn weld(vertices: &mut Vec<Vec2>, v: &Vec2) -> usize {
+    match vertices.iter().position(|w| (w - v).len_sq() <= EPSILON * EPSILON) {
+        Some(i) => i,
+        None => {
+            vertices.push(*v);
+            vertices.len() - 1
+        }
+    }
+}
+
+/// The loop with every other vertex lying on one of its edges inserted into that edge
+fn split_edges(vertices: &[Vec2], indices: &[usize]) -> Vec<usize> {
+    let n = indices.len();
+    let mut out = Vec::with_capacity(n);
+    for k in 0..n {
+        let (ia, ib) = (indices[k], indices[(k + 1) % n]);
+        let (a, b) = (vertices[ia], vertices[ib]);
+        let edge = b - a;
+        let mut inside: Vec<(f64, usize)> = vertices
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| i != ia && i != ib && Vec2::cross(&edge, &(p - a)).abs() <= EPSILON * edge.len())
+            .map(|(i, p)| (Vec2::dot(&(p - a), &edge) / edge.len_sq(), i))
+            .filter(|&(t, _)| t > 0.0 && t < 1.0)
+            .collect();
+        inside.sort_by(|x, y| x.0.total_cmp(&y.0));
+        out.push(ia);
+        out.extend(inside.into_iter().map(|(_, i)| i));
+    }
+    out
+}
+
+/// Ear clipping triangulation of a counter-clockwise polygon, as vertex index triples
+fn triangulate(vertices: &[Vec2]) -> Vec<Vec<usize>> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    let mut triangles = Vec::with_capacity(vertices.len().saturating_sub(2));
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| is_ear(vertices, &remaining, i));
+        // Degenerate input without a valid ear: clip the first convex corner to make progress
+        let i = ear
+            .or_else(|| (0..n).find(|&i| corner(vertices, &remaining, i) > 0.0))
+            .unwrap_or(0);
+
+        triangles.push(vec![remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]]);
+        remaining.remove(i);
+    }
+    triangles.push(remaining);
+    triangles
+}
+
+/// Cross product at a corner of the remaining polygon, positive when convex
+fn corner(vertices: &[Vec2], remaining: &[usize], i: usize) -> f64 {
+    let n = remaining.len();
+    let a = vertices[remaining[(i + n - 1) % n]];
+    let b = vertices[remaining[i]];
+    let c = vertices[remaining[(i + 1) % n]];
+    Vec2::cross(&(b - a), &(c - b))
+}
+
+fn is_ear(vertices: &[Vec2], remaining: &[usize], i: usize) -> bool {
+    if corner(vertices, remaining, i) <= EPSILON {
+        return false;
+    }
+    let n = remaining.len();
+    let (ia, ib, ic) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+    let (a, b, c) = (vertices[ia], vertices[ib], vertices[ic]);
+
+    remaining
+        .iter()
+        .filter(|&&j| j != ia && j != ib && j != ic)
+        .all(|&j| !in_triangle(&vertices[j], &a, &b, &c))
+}
+
+/// Point in or on a counter-clockwise triangle
+fn in_triangle(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> bool {
+    Vec2::cross(&(b - a), &(p - a)) >= -EPSILON
+        && Vec2::cross(&(c - b), &(p - b)) >= -EPSILON
+        && Vec2::cross(&(a - c), &(p - c)) >= -EPSILON
+}
+
+/// Merge pieces across shared edges while they stay convex
+fn merge_pieces(vertices: &[Vec2], mut pieces: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for a in 0..pieces.len() {
+            for b in (a + 1)..pieces.len() {
+                if let Some(candidate) = merge_pair(&pieces[a], &pieces[b]) {
+                    let points: Vec<Vec2> = candidate.iter().map(|i| vertices[*i]).collect();
+                    if is_convex(&points) {
+                        pieces[a] = candidate;
+                        pieces.remove(b);
+                        merged = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+    pieces
+}
+
+/// Join two counter-clockwise index loops along an edge u->v in a and v->u in b
+pub(crate) fn merge_pair(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (na, nb) = (a.len(), b.len());
+    for k in 0..na {
+        let (u, v) = (a[k], a[(k + 1) % na]);
+        let m = match (0..nb).find(|&m| b[m] == v && b[(m + 1) % nb] == u) {
+            Some(m) => m,
+            None => continue,
+        };
+
+        let mut out = Vec::with_capacity(na + nb - 2);
+        // a from v around to u
+        for i in 0..na {
+            out.push(a[(k + 1 + i) % na]);
+        }
+        // b after u until before v
+        for i in 2..nb {
+            out.push(b[(m + i) % nb]);
+        }
+        return Some(out);
+    }
+    None
+}
+
+/// Drop vertices lying on the line between their neighbours
+pub(crate) fn remove_collinear(points: &[Vec2]) -> Vec<Vec2> {
+    let n = points.len();
+    let out: Vec<Vec2> = (0..n)
+        .filter(|&i| {
+            let a = points[(i + n - 1) % n];
+            let b = points[i];
+            let c = points[(i + 1) % n];
+            Vec2::cross(&(b - a), &(c - b)).abs() > EPSILON
+        })
+        .map(|i| points[i])
+        .collect();
+    if out.len() < 3 {
+        points.to_vec()
+    } else {
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn l_shape() -> Polygon {
+        Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn test_decompose_convex_is_unchanged() {
+        let square = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+
+        assert_eq!(decompose(&square), vec![square]);
+    }
+
+    #[test]
+    fn test_decompose_l_shape() {
+        let pieces = decompose(&l_shape());
+
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|p| is_convex(p.vertices())));
+        let area: f64 = pieces.iter().map(|p| p.area()).sum();
+        assert!((area - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_decompose_clockwise() {
+        let mut vertices = l_shape().vertices().to_vec();
+        vertices.reverse();
+        let pieces = decompose(&Polygon::new(&vertices));
+
+        assert!(pieces.iter().all(|p| p.area() > 0.0 && is_convex(p.vertices())));
+    }
+
+    #[test]
+    fn test_decompose_to_compound() {
+        let compound = decompose_to_compound(&l_shape());
+        let breakdown = compound.mass_breakdown(1.0);
+
+        assert!((breakdown.mass - 3.0).abs() < 1e-12);
+        assert!((breakdown.center - Vec2::new(5.0 / 6.0, 5.0 / 6.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn test_merge_convex() {
+        let tile = |x: f64, y: f64| Polygon::rect_at(Vec2::new(x + 0.5, y + 0.5), 1.0, 1.0);
+
+        // A row of tiles becomes one rectangle
+        let row = merge_convex(&[tile(0.0, 0.0), tile(1.0, 0.0), tile(2.0, 0.0)]);
+        assert_eq!(row.len(), 1);
+        assert_eq!(row[0].vertices().len(), 4);
+        assert!((row[0].area() - 3.0).abs() < 1e-12);
+
+        // Two tiles on a wide slab meet it in the middle of its top edge
+        let slab = Polygon::rect_at(Vec2::new(1.0, -0.5), 2.0, 1.0);
+        let block = merge_convex(&[tile(0.0, 0.0), slab, tile(1.0, 0.0)]);
+        assert_eq!(block.len(), 1);
+        assert!((block[0].area() - 4.0).abs() < 1e-12);
+
+        // An L can't be convex, and tiles that only share a corner stay apart
+        assert_eq!(merge_convex(&[tile(0.0, 0.0), tile(1.0, 0.0), tile(0.0, 1.0)]).len(), 2);
+        assert_eq!(merge_convex(&[tile(0.0, 0.0), tile(1.0, 1.0)]).len(), 2);
+    }
+
+    #[test]
+    fn test_decompose_comb() {
+        let comb = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 3.0),
+            Vec2::new(4.0, 3.0),
+            Vec2::new(4.0, 1.0),
+            Vec2::new(3.0, 1.0),
+            Vec2::new(3.0, 3.0),
+            Vec2::new(2.0, 3.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ]);
+        let pieces = decompose(&comb);
+
+        assert!(pieces.iter().all(|p| is_convex(p.vertices())));
+        let area: f64 = pieces.iter().map(|p| p.area()).sum();
+        assert!((area - 11.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,128 @@
+use wasm_bindgen::prelude::*;
+
+use super::aabb::Aabb;
+use crate::math::Vec2;
+
+/// Signed distances sampled at the cell centers of a grid over a region
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceField {
+    region: Aabb,
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    /// Row major, starting at the minimum corner of the region
+    values: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl DistanceField {
+    pub fn region(&self) -> Aabb {
+        self.region
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// All samples as a flat row major array, for heatmap rendering
+    pub fn values(&self) -> Vec<f64> {
+        self.values.clone()
+    }
+
+    pub fn value(&self, column: usize, row: usize) -> f64 {
+        self.values[row * self.columns + column]
+    }
+
+    pub fn cell_center(&self, column: usize, row: usize) -> Vec2 {
+        self.region.min + Vec2::new(column as f64 + 0.5, row as f64 + 0.5) * self.cell_size
+    }
+
+    /// Bilinear interpolation between the nearest samples, clamped to the grid
+    pub fn sample(&self, point: &Vec2) -> f64 {
+        let g = (point - self.region.min) / self.cell_size - Vec2::new(0.5, 0.5);
+        let gx = g.x.clamp(0.0, (self.columns - 1) as f64);
+        let gy = g.y.clamp(0.0, (self.rows - 1) as f64);
+        let (c0, r0) = (gx.floor() as usize, gy.floor() as usize);
+        let (c1, r1) = ((c0 + 1).min(self.columns - 1), (r0 + 1).min(self.rows - 1));
+        let (fx, fy) = (gx - c0 as f64, gy - r0 as f64);
+
+        let bottom = self.value(c0, r0) * (1.0 - fx) + self.value(c1, r0) * fx;
+        let top = self.value(c0, r1) * (1.0 - fx) + self.value(c1, r1) * fx;
+        bottom * (1.0 - fy) + top * fy
+    }
+
+    /// Direction of steepest increase, estimated with central differences
+    pub fn gradient(&self, point: &Vec2) -> Vec2 {
+        let h = self.cell_size;
+        let dx = self.sample(&(point + Vec2::new(h, 0.0))) - self.sample(&(point - Vec2::new(h, 0.0)));
+        let dy = self.sample(&(point + Vec2::new(0.0, h))) - self.sample(&(point - Vec2::new(0.0, h)));
+        Vec2::new(dx, dy) / (2.0 * h)
+    }
+}
+
+impl DistanceField {
+    /// Sample distance at the center of each cell_size square covering region
+    pub fn from_fn<F: FnMut(&Vec2) -> f64>(region: Aabb, cell_size: f64, mut distance: F) -> DistanceField {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+        let columns = ((region.width() / cell_size).ceil() as usize).max(1);
+        let rows = ((region.height() / cell_size).ceil() as usize).max(1);
+
+        let mut field = DistanceField {
+            region,
+            cell_size,
+            columns,
+            rows,
+            values: Vec::with_capacity(columns * rows),
+        };
+        for row in 0..rows {
+            for column in 0..columns {
+                let value = distance(&field.cell_center(column, row));
+                field.values.push(value);
+            }
+        }
+        field
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane() -> DistanceField {
+        // Distance to the line y = 1
+        DistanceField::from_fn(Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 2.0)), 0.5, |p| p.y - 1.0)
+    }
+
+    #[test]
+    fn test_field_grid() {
+        let field = plane();
+
+        assert_eq!((field.columns(), field.rows()), (8, 4));
+        assert_eq!(field.values().len(), 32);
+        assert_eq!(field.cell_center(1, 0), Vec2::new(0.75, 0.25));
+        assert_eq!(field.value(3, 0), -0.75);
+    }
+
+    #[test]
+    fn test_field_sample() {
+        let field = plane();
+
+        assert!((field.sample(&Vec2::new(2.1, 1.3)) - 0.3).abs() < 1e-12);
+        // Clamped outside the sampled centers
+        assert_eq!(field.sample(&Vec2::new(2.0, 10.0)), 0.75);
+        assert!((field.gradient(&Vec2::new(2.0, 1.0)) - Vec2::new(0.0, 1.0)).len() < 1e-12);
+    }
+}
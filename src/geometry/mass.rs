@@ -0,0 +1,71 @@
+//! Moments of inertia of the basic shapes and the parallel axis theorem.
+//!
+//! These are the formulas bodies get their inertia from, so tools building compound setups by
+//! hand end up with the same values as the engine. Each inertia is about the shape's centroid.
+
+use crate::math::Vec2;
+
+/// Solid disk of mass m and radius r
+pub fn inertia_circle(m: f64, r: f64) -> f64 {
+    0.5 * m * r * r
+}
+
+/// Solid polygon of mass m with vertices in order around it
+pub fn inertia_polygon(vertices: &[Vec2], m: f64) -> f64 {
+    let mut numerator = 0.0;
+    let mut denominator = 0.0;
+    let mut centroid = Vec2::new(0.0, 0.0);
+    let mut i = vertices.len() - 1;
+    for j in 0..vertices.len() {
+        let a = vertices[i];
+        let b = vertices[j];
+        let c = Vec2::cross(&a, &b);
+        numerator += c * (Vec2::dot(&a, &a) + Vec2::dot(&a, &b) + Vec2::dot(&b, &b));
+        denominator += c;
+        centroid = centroid + (a + b) * c;
+
+        i = j;
+    }
+    centroid = centroid / (3.0 * denominator);
+
+    // Inertia about the origin, moved to the centroid
+    let about_origin = m * numerator / (6.0 * denominator);
+    parallel_axis(about_origin, -m, &centroid)
+}
+
+/// Thin rod of mass m and the given length
+pub fn inertia_rod(m: f64, length: f64) -> f64 {
+    m * length * length / 12.0
+}
+
+/// Inertia about an axis offset by d from the centroid, given the inertia i about the centroid.
+/// A negative mass moves the axis the other way, from offset d back to the centroid.
+pub fn parallel_axis(i: f64, m: f64, d: &Vec2) -> f64 {
+    i + m * d.len_sq()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inertia_polygon() {
+        let square = |offset: Vec2| -> Vec<Vec2> {
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(|&(x, y)| Vec2::new(x, y) + offset)
+                .collect()
+        };
+        // m (w^2 + h^2) / 12 for a 2 x 2 square, wherever it is
+        assert!((inertia_polygon(&square(Vec2::new(0.0, 0.0)), 3.0) - 2.0).abs() < 1e-12);
+        assert!((inertia_polygon(&square(Vec2::new(5.0, -2.0)), 3.0) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parallel_axis() {
+        let i = inertia_circle(2.0, 1.0);
+        assert_eq!(i, 1.0);
+        assert_eq!(parallel_axis(i, 2.0, &Vec2::new(3.0, 4.0)), 51.0);
+        assert_eq!(inertia_rod(3.0, 2.0), 1.0);
+    }
+}
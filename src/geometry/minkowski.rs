@@ -0,0 +1,102 @@
+//! Minkowski sums and differences of convex shapes.
+//!
+//! The sum of two convex shapes is the convex hull of the sums of their vertices. Circles have
+//! no vertices, so they are replaced by a regular polygon drawn around them, which keeps the
+//! result slightly larger than the exact rounded outline. Compounds are treated as the convex
+//! hull of their children.
+//!
+//! The difference a - b contains the origin exactly when the shapes overlap, which is the set
+//! GJK searches, so drawing it helps when debugging collisions.
+
+use super::{GeometryError, Polygon, Shapes};
+use crate::math::{Transform2D, Vec2};
+
+/// Sides of the polygon standing in for a circle
+pub const CIRCLE_SEGMENTS: usize = 32;
+
+/// Every point a + b with a in a and b in b.
+/// Panics if the result has no area, such as for two parallel segments.
+pub fn sum(a: &Shapes, b: &Shapes) -> Polygon {
+    try_sum(a, b).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Every point a - b with a in a and b in b.
+/// Panics if the result has no area, such as for two parallel segments.
+pub fn difference(a: &Shapes, b: &Shapes) -> Polygon {
+    try_difference(a, b).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_sum(a: &Shapes, b: &Shapes) -> Result<Polygon, GeometryError> {
+    combine(&outline(a), &outline(b))
+}
+
+pub fn try_difference(a: &Shapes, b: &Shapes) -> Result<Polygon, GeometryError> {
+    let negated: Vec<Vec2> = outline(b).iter().map(|v| -v).collect();
+    combine(&outline(a), &negated)
+}
+
+/// Convex hull of every sum of a point of a and a point of b
+pub(crate) fn combine(a: &[Vec2], b: &[Vec2]) -> Result<Polygon, GeometryError> {
+    let points: Vec<Vec2> = a.iter().flat_map(|p| b.iter().map(move |q| p + q)).collect();
+    Polygon::try_convex_hull(&points)
+}
+
+/// Points whose convex hull is the shape, or encloses it for circles
+fn outline(shape: &Shapes) -> Vec<Vec2> {
+    outline_at(shape, &Transform2D::identity())
+}
+
+/// Like outline for a shape placed by transform
+pub(crate) fn outline_at(shape: &Shapes, transform: &Transform2D) -> Vec<Vec2> {
+    match shape {
+        Shapes::Circle(c) => c.enclosing_points(CIRCLE_SEGMENTS).iter().map(|p| transform.apply(p)).collect(),
+        Shapes::Polygon(p) => p.vertices().iter().map(|v| transform.apply(v)).collect(),
+        Shapes::Segment(s) => vec![transform.apply(&s.a()), transform.apply(&s.b())],
+        Shapes::Compound(c) => c
+            .children()
+            .iter()
+            .flat_map(|(t, child)| outline_at(child, &transform.mul(t)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Segment, Shape};
+
+    #[test]
+    fn test_sum() {
+        let square = Shapes::Polygon(Polygon::rect(2.0, 2.0));
+        let bar = Shapes::Segment(Segment::new(Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0)));
+
+        let swept = sum(&square, &bar);
+        assert!((swept.area() - 10.0).abs() < 1e-12);
+        assert_eq!(swept.aabb().min, Vec2::new(-1.0, -1.0));
+        assert_eq!(swept.aabb().max, Vec2::new(4.0, 1.0));
+
+        // A circle is inflated slightly beyond its radius
+        let inflated = sum(&square, &Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)));
+        let exact = 4.0 + 4.0 * 2.0 * 0.5 + std::f64::consts::PI * 0.25;
+        assert!(inflated.area() > exact && inflated.area() < exact + 0.01);
+    }
+
+    #[test]
+    fn test_difference() {
+        let a = Shapes::Polygon(Polygon::rect_at(Vec2::new(3.0, 0.0), 2.0, 2.0));
+        let b = Shapes::Polygon(Polygon::regular(3, 1.0));
+
+        // The origin is inside the difference only while the shapes overlap
+        assert!(!difference(&a, &b).contains(&Vec2::new(0.0, 0.0)));
+        let moved = Shapes::Polygon(Polygon::rect_at(Vec2::new(1.5, 0.0), 2.0, 2.0));
+        assert!(difference(&moved, &b).contains(&Vec2::new(0.0, 0.0)));
+        assert!((difference(&a, &b).area() - sum(&a, &b).area()).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parallel_segments() {
+        let s = Shapes::Segment(Segment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        sum(&s, &s);
+    }
+}
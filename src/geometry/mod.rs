@@ -1,17 +1,247 @@
+use std::fmt;
+
 use wasm_bindgen::prelude::*;
-use crate::math::Vec2;
 
+use crate::math::{Transform2D, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+pub mod aabb;
+pub mod boolean;
 pub mod circle;
+pub mod compound;
+pub mod decompose;
+pub mod field;
+pub mod hull;
+pub mod mass;
+pub mod minkowski;
 pub mod polygon;
+pub mod ray;
+pub mod segment;
 pub mod shape;
+pub mod sweep;
+pub mod transformed;
 
+pub use aabb::Aabb;
 pub use circle::Circle;
+pub use compound::{ChildMass, Compound, MassBreakdown};
+pub use decompose::decompose;
+pub use field::DistanceField;
 pub use polygon::Polygon;
-pub use shape::Shape;
+pub use ray::{Ray, RayHit};
+pub use segment::Segment;
+pub use shape::{ContentHasher, Shape};
+pub use sweep::swept_outline;
+pub use transformed::Transformed;
+
+/// Invalid input when building a shape
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeometryError {
+    /// A polygon needs at least 3 vertices
+    TooFewVertices(usize),
+    /// A flat [x0, y0, x1, y1, ...] list has an odd number of values
+    OddCoordinateCount(usize),
+    /// The points are collinear, so their hull has no area
+    DegenerateHull,
+    /// Two edges of a polygon cross or touch
+    SelfIntersecting,
+    /// A box has a side that isn't positive
+    NonPositiveSize(f64, f64),
+    /// A point has a NaN or infinite coordinate
+    NonFinitePoint(Vec2),
+}
 
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::TooFewVertices(n) => write!(f, "Polygon must have at least 3 vertices, got {}", n),
+            GeometryError::OddCoordinateCount(n) => write!(f, "Expected x, y pairs but got {} values", n),
+            GeometryError::DegenerateHull => write!(f, "Convex hull of points must have at least 3 vertices"),
+            GeometryError::SelfIntersecting => write!(f, "Polygon edges must not cross each other"),
+            GeometryError::NonPositiveSize(w, h) => write!(f, "Box sides must be positive, got {} x {}", w, h),
+            GeometryError::NonFinitePoint(p) => write!(f, "Points must be finite, got ({}, {})", p.x, p.y),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Which variant a Shapes value is, for callers that can't see the enum (e.g. JS)
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShapeKind {
+    Circle,
+    Polygon,
+    Compound,
+    Segment,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Shapes {
     Circle(Circle),
-    Polygon(Polygon)
+    Polygon(Polygon),
+    Compound(Compound),
+    Segment(Segment),
+}
+
+impl Shapes {
+    pub fn kind(&self) -> ShapeKind {
+        match self {
+            Shapes::Circle(_) => ShapeKind::Circle,
+            Shapes::Polygon(_) => ShapeKind::Polygon,
+            Shapes::Compound(_) => ShapeKind::Compound,
+            Shapes::Segment(_) => ShapeKind::Segment,
+        }
+    }
+
+    /// Mirror image across the line through the origin along axis
+    pub fn mirrored(&self, axis: &Vec2) -> Shapes {
+        match self {
+            Shapes::Circle(c) => Shapes::Circle(Circle::new(c.center().mirror(axis), c.radius())),
+            Shapes::Polygon(p) => Shapes::Polygon(p.mirrored(axis)),
+            Shapes::Compound(c) => Shapes::Compound(c.mirrored(axis)),
+            Shapes::Segment(s) => Shapes::Segment(s.mirrored(axis)),
+        }
+    }
+
+    /// The same shape moved by offset
+    pub fn translated(&self, offset: &Vec2) -> Shapes {
+        match self {
+            Shapes::Circle(c) => Shapes::Circle(Circle::new(c.center() + *offset, c.radius())),
+            Shapes::Polygon(p) => Shapes::Polygon(p.transformed(&Transform2D::new(*offset, 0.0))),
+            Shapes::Compound(c) => Shapes::Compound(c.translated(offset)),
+            Shapes::Segment(s) => Shapes::Segment(s.translated(offset)),
+        }
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        let mut v = Value::object();
+        match self {
+            Shapes::Circle(c) => {
+                v.insert("type", "circle".into());
+                v.insert("center", serialize::vec2_to_value(&c.center()));
+                v.insert("radius", c.radius().into());
+            }
+            Shapes::Polygon(p) => {
+                v.insert("type", "polygon".into());
+                v.insert("vertices", Value::Array(p.vertices().iter().map(serialize::vec2_to_value).collect()));
+            }
+            Shapes::Segment(s) => {
+                v.insert("type", "segment".into());
+                v.insert("a", serialize::vec2_to_value(&s.a()));
+                v.insert("b", serialize::vec2_to_value(&s.b()));
+                v.insert("one_sided", s.one_sided.into());
+            }
+            Shapes::Compound(c) => {
+                let children = c
+                    .children()
+                    .iter()
+                    .map(|(t, child)| {
+                        let mut entry = Value::object();
+                        entry.insert("transform", serialize::transform_to_value(t));
+                        entry.insert("shape", child.to_value());
+                        entry
+                    })
+                    .collect();
+                v.insert("type", "compound".into());
+                v.insert("children", Value::Array(children));
+            }
+        }
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<Shapes, SerializeError> {
+        match v.field_str("type")? {
+            "circle" => Ok(Shapes::Circle(Circle::new(
+                serialize::vec2_from_value(v.field("center")?)?,
+                v.field_f64("radius")?,
+            ))),
+            "polygon" => {
+                let vertices = v
+                    .field_array("vertices")?
+                    .iter()
+                    .map(serialize::vec2_from_value)
+                    .collect::<Result<Vec<Vec2>, _>>()?;
+                Polygon::try_new(&vertices)
+                    .map(Shapes::Polygon)
+                    .map_err(|e| SerializeError::InvalidValue(e.to_string()))
+            }
+            "segment" => {
+                let mut segment = Segment::new(
+                    serialize::vec2_from_value(v.field("a")?)?,
+                    serialize::vec2_from_value(v.field("b")?)?,
+                );
+                segment.one_sided = v.field_bool("one_sided")?;
+                Ok(Shapes::Segment(segment))
+            }
+            "compound" => {
+                let mut compound = Compound::new();
+                for child in v.field_array("children")? {
+                    compound.add_child(
+                        serialize::transform_from_value(child.field("transform")?)?,
+                        Shapes::from_value(child.field("shape")?)?,
+                    );
+                }
+                Ok(Shapes::Compound(compound))
+            }
+            other => Err(SerializeError::InvalidValue(format!("unknown shape type '{}'", other))),
+        }
+    }
+}
+
+impl Binary for Shapes {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Shapes::Circle(c) => {
+                w.put(&0u8);
+                w.put(&c.center());
+                w.put(&c.radius());
+            }
+            Shapes::Polygon(p) => {
+                w.put(&1u8);
+                w.put(&p.vertices().to_vec());
+            }
+            Shapes::Compound(c) => {
+                w.put(&2u8);
+                w.put(&c.children().len());
+                for (transform, child) in c.children() {
+                    w.put(transform);
+                    w.put(child);
+                }
+            }
+            Shapes::Segment(s) => {
+                w.put(&3u8);
+                w.put(&s.a());
+                w.put(&s.b());
+                w.put(&s.one_sided);
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Shapes, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(Shapes::Circle(Circle::new(r.get()?, r.get()?))),
+            1 => {
+                let vertices: Vec<Vec2> = r.get()?;
+                Polygon::try_new(&vertices)
+                    .map(Shapes::Polygon)
+                    .map_err(|e| SerializeError::InvalidValue(e.to_string()))
+            }
+            2 => {
+                let mut compound = Compound::new();
+                for (transform, child) in r.get::<Vec<(Transform2D, Shapes)>>()? {
+                    compound.add_child(transform, child);
+                }
+                Ok(Shapes::Compound(compound))
+            }
+            3 => {
+                let mut segment = Segment::new(r.get()?, r.get()?);
+                segment.one_sided = r.get()?;
+                Ok(Shapes::Segment(segment))
+            }
+            tag => Err(r.invalid_tag("shape", tag)),
+        }
+    }
 }
 
 impl Shape for Shapes {
@@ -19,6 +249,8 @@ impl Shape for Shapes {
         match self {
             Shapes::Circle(c) => c.support(dir),
             Shapes::Polygon(p) => p.support(dir),
+            Shapes::Compound(c) => c.support(dir),
+            Shapes::Segment(s) => s.support(dir),
         }
     }
 
@@ -26,6 +258,8 @@ impl Shape for Shapes {
         match self {
             Shapes::Circle(c) => c.area(),
             Shapes::Polygon(p) => p.area(),
+            Shapes::Compound(c) => c.area(),
+            Shapes::Segment(s) => s.area(),
         }
     }
 
@@ -33,6 +267,35 @@ impl Shape for Shapes {
         match self {
             Shapes::Circle(c) => c.center(),
             Shapes::Polygon(p) => p.center(),
+            Shapes::Compound(c) => c.center(),
+            Shapes::Segment(s) => s.center(),
+        }
+    }
+
+    fn inertia(&self, mass: f64) -> f64 {
+        match self {
+            Shapes::Circle(c) => c.inertia(mass),
+            Shapes::Polygon(p) => p.inertia(mass),
+            Shapes::Compound(c) => c.inertia(mass),
+            Shapes::Segment(s) => s.inertia(mass),
+        }
+    }
+
+    fn content_hash(&self) -> u64 {
+        match self {
+            Shapes::Circle(c) => c.content_hash(),
+            Shapes::Polygon(p) => p.content_hash(),
+            Shapes::Compound(c) => c.content_hash(),
+            Shapes::Segment(s) => s.content_hash(),
+        }
+    }
+
+    fn aabb(&self) -> Aabb {
+        match self {
+            Shapes::Circle(c) => c.aabb(),
+            Shapes::Polygon(p) => p.aabb(),
+            Shapes::Compound(c) => c.aabb(),
+            Shapes::Segment(s) => s.aabb(),
         }
     }
 }
@@ -1,7 +1,13 @@
 use wasm_bindgen::prelude::*;
 
-use super::shape::Shape;
+use super::aabb::Aabb;
+use super::hull;
+use super::mass;
+use super::shape::{ContentHasher, Shape};
+use super::{GeometryError, ShapeKind};
+use crate::math::trig;
 use crate::math::vec2::Vec2;
+use crate::math::Transform2D;
 
 #[wasm_bindgen]
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -11,34 +17,274 @@ pub struct Polygon {
 
 impl Polygon {
     pub fn new(v: &[Vec2]) -> Polygon {
-        if v.len() < 3 {
-            panic!("Polygon must have at least 3 verticies!")
+        Polygon::try_new(v).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Polygon with the given vertices in counter-clockwise order. Repeated vertices and ones on
+    /// a straight line between their neighbours are dropped, and clockwise lists are reversed.
+    /// Errors if a vertex isn't finite, fewer than 3 vertices remain or the edges cross.
+    pub fn try_new(v: &[Vec2]) -> Result<Polygon, GeometryError> {
+        check_finite(v)?;
+        let mut vertices = simplify(v);
+        if vertices.len() < 3 {
+            return Err(GeometryError::TooFewVertices(vertices.len()));
+        }
+        if self_intersects(&vertices) {
+            return Err(GeometryError::SelfIntersecting);
+        }
+        if signed_area(&vertices) < 0.0 {
+            // Keep the first vertex first
+            vertices[1..].reverse();
+        }
+        Ok(Polygon { vertices })
+    }
+
+    /// Polygon from a flat [x0, y0, x1, y1, ...] list, or an error if it isn't pairs of at least 3 points
+    pub fn try_from_flat(v: &[f64]) -> Result<Polygon, GeometryError> {
+        Polygon::try_new(&points_from_flat(v)?)
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    /// Box of width w and height h centered on center
+    pub fn rect_at(center: Vec2, w: f64, h: f64) -> Polygon {
+        Polygon::try_rect_at(center, w, h).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Box of width w and height h centered on center, or an error if a side isn't positive
+    pub fn try_rect_at(center: Vec2, w: f64, h: f64) -> Result<Polygon, GeometryError> {
+        if !(w > 0.0 && h > 0.0) {
+            return Err(GeometryError::NonPositiveSize(w, h));
+        }
+        let half = Vec2::new(w / 2.0, h / 2.0);
+        Polygon::try_new(&[
+            center - half,
+            center + Vec2::new(half.x, -half.y),
+            center + half,
+            center + Vec2::new(-half.x, half.y),
+        ])
+    }
+
+    /// Smallest convex polygon containing all of the points
+    pub fn convex_hull(points: &[Vec2]) -> Polygon {
+        Polygon::try_convex_hull(points).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Smallest convex polygon containing all of the points, or an error if they are collinear
+    /// or one isn't finite
+    pub fn try_convex_hull(points: &[Vec2]) -> Result<Polygon, GeometryError> {
+        check_finite(points)?;
+        let vertices = hull::convex_hull(points);
+        if vertices.len() < 3 {
+            return Err(GeometryError::DegenerateHull);
+        }
+        Ok(Polygon { vertices })
+    }
+
+    /// Mirror image across the line through the origin along axis.
+    /// Vertices are reversed so the winding stays the same.
+    pub fn mirrored(&self, axis: &Vec2) -> Polygon {
+        assert!(axis.len_sq() > 0.0, "Mirror axis must not be zero");
+        Polygon {
+            vertices: self.vertices.iter().rev().map(|v| v.mirror(axis)).collect(),
+        }
+    }
+
+    /// The polygon placed by a rigid transform
+    pub fn transformed(&self, transform: &Transform2D) -> Polygon {
+        Polygon {
+            vertices: self.vertices.iter().map(|v| transform.apply(v)).collect(),
+        }
+    }
+
+    /// The part of the polygon on the side of the line through point facing away from normal,
+    /// None if less than a sliver remains
+    pub fn clip(&self, point: &Vec2, normal: &Vec2) -> Option<Polygon> {
+        let side = |v: &Vec2| Vec2::dot(&(v - point), normal);
+        let mut out = Vec::with_capacity(self.vertices.len() + 1);
+        let mut prev = self.vertices[self.vertices.len() - 1];
+        for v in &self.vertices {
+            let (dp, dv) = (side(&prev), side(v));
+            if (dp <= 0.0) != (dv <= 0.0) {
+                out.push(prev + (v - prev) * (dp / (dp - dv)));
+            }
+            if dv <= 0.0 {
+                out.push(*v);
+            }
+            prev = *v;
+        }
+        out.dedup();
+        if out.len() > 1 && out[0] == out[out.len() - 1] {
+            out.pop();
+        }
+        if out.len() < 3 {
+            return None;
+        }
+        let clipped = Polygon { vertices: out };
+        if clipped.area().abs() <= 1e-12 {
+            return None;
         }
+        Some(clipped)
+    }
 
-        Polygon { vertices: v.to_vec() }
+    /// Split a convex polygon into the cells of the points nearest each site.
+    /// Sites sharing a position with an earlier site are ignored.
+    pub fn fracture(&self, sites: &[Vec2]) -> Vec<Polygon> {
+        let mut pieces = Vec::with_capacity(sites.len());
+        for (i, site) in sites.iter().enumerate() {
+            if sites[..i].contains(site) {
+                continue;
+            }
+            let mut cell = Some(self.clone());
+            for (j, other) in sites.iter().enumerate() {
+                if i == j || other == site {
+                    continue;
+                }
+                let mid = (site + other) / 2.0;
+                cell = cell.and_then(|c| c.clip(&mid, &(other - site)));
+            }
+            pieces.extend(cell);
+        }
+        pieces
+    }
+
+    /// True if point is inside or on the boundary of the convex polygon
+    pub fn contains(&self, point: &Vec2) -> bool {
+        let n = self.vertices.len();
+        let sign = self.area().signum();
+        (0..n).all(|i| {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            Vec2::cross(&(b - a), &(point - a)) * sign >= 0.0
+        })
     }
 }
 
+/// Sine of the angle below which a vertex counts as on the line between its neighbours
+const COLLINEAR_TOLERANCE: f64 = 1e-9;
+
+/// Vertices without consecutive repeats and without vertices on a line between their neighbours
+fn simplify(v: &[Vec2]) -> Vec<Vec2> {
+    let mut out = v.to_vec();
+    out.dedup();
+    while out.len() > 1 && out[0] == out[out.len() - 1] {
+        out.pop();
+    }
+    loop {
+        let n = out.len();
+        if n < 3 {
+            return out;
+        }
+        let straight = (0..n).find(|&i| {
+            let (e0, e1) = (out[i] - out[(i + n - 1) % n], out[(i + 1) % n] - out[i]);
+            Vec2::cross(&e0, &e1).abs() <= COLLINEAR_TOLERANCE * e0.len() * e1.len()
+        });
+        match straight {
+            Some(i) => {
+                out.remove(i);
+            }
+            None => return out,
+        }
+    }
+}
+
+fn signed_area(v: &[Vec2]) -> f64 {
+    let n = v.len();
+    (0..n).map(|i| Vec2::cross(&v[i], &v[(i + 1) % n])).sum::<f64>() / 2.0
+}
+
+/// True if any two edges that aren't neighbours cross or touch
+fn self_intersects(v: &[Vec2]) -> bool {
+    let n = v.len();
+    (0..n).any(|i| {
+        // Skip the edge itself, its neighbours, and pairs already checked
+        (i + 2..n)
+            .filter(|&j| (j + 1) % n != i)
+            .any(|j| edges_touch(&v[i], &v[(i + 1) % n], &v[j], &v[(j + 1) % n]))
+    })
+}
+
+fn edges_touch(a0: &Vec2, a1: &Vec2, b0: &Vec2, b1: &Vec2) -> bool {
+    // Points within rounding of the line count as on it, otherwise edges lying along one line,
+    // like the two stretches left of an edge clipped in the middle, get random sides
+    let side = |p: &Vec2, q: &Vec2, r: &Vec2| {
+        let (pq, pr) = (q - p, r - p);
+        let cross = Vec2::cross(&pq, &pr);
+        if cross.abs() <= COLLINEAR_TOLERANCE * pq.len() * pr.len() {
+            0.0
+        } else {
+            cross.signum()
+        }
+    };
+    let (s0, s1) = (side(a0, a1, b0), side(a0, a1, b1));
+    let (s2, s3) = (side(b0, b1, a0), side(b0, b1, a1));
+    if s0 == 0.0 && s1 == 0.0 {
+        // On one line, so they touch if their extents along it overlap
+        let dir = a1 - a0;
+        let t = |p: &Vec2| Vec2::dot(&(p - a0), &dir);
+        let (lo, hi) = (t(b0).min(t(b1)), t(b0).max(t(b1)));
+        return hi >= 0.0 && lo <= dir.len_sq();
+    }
+    s0 * s1 <= 0.0 && s2 * s3 <= 0.0
+}
+
+/// Convert a flat [x0, y0, x1, y1, ...] list to points
+fn check_finite(points: &[Vec2]) -> Result<(), GeometryError> {
+    match points.iter().find(|p| !(p.x.is_finite() && p.y.is_finite())) {
+        Some(&p) => Err(GeometryError::NonFinitePoint(p)),
+        None => Ok(()),
+    }
+}
+
+fn points_from_flat(v: &[f64]) -> Result<Vec<Vec2>, GeometryError> {
+    if !v.len().is_multiple_of(2) {
+        return Err(GeometryError::OddCoordinateCount(v.len()));
+    }
+    Ok(v.chunks(2).map(|p| Vec2::new(p[0], p[1])).collect())
+}
+
 #[wasm_bindgen]
 impl Polygon {
+    /// Polygon from a flat [x0, y0, x1, y1, ...] list, throwing if it isn't a valid polygon
     #[wasm_bindgen(constructor)]
-    pub fn wasm_new(v: &[f64]) -> Polygon {
-        if v.len() < 6 {
-            panic!("Polygon must have at least 3 verticies!")
-        }
-        if v.len() % 2 != 0 {
-            panic!("Length of argument must be even number.")
-        }
+    pub fn wasm_new(v: &[f64]) -> Result<Polygon, JsError> {
+        Polygon::try_from_flat(v).map_err(JsError::from)
+    }
+
+    /// Box of width w and height h centered on the origin
+    pub fn rect(w: f64, h: f64) -> Polygon {
+        Polygon::rect_at(Vec2::new(0.0, 0.0), w, h)
+    }
 
-        let num_verticies = v.len() / 2;
-        let mut vertices = Vec::with_capacity(num_verticies);
-        let mut i = 0;
-        while i < v.len() {
-            vertices.push(Vec2::new(v[i + 0], v[i + 1]));
-            i += 2;
+    /// Box of width w and height h centered on center, throwing if a side isn't positive
+    #[wasm_bindgen(js_name = rect_at)]
+    pub fn wasm_rect_at(center: Vec2, w: f64, h: f64) -> Result<Polygon, JsError> {
+        Polygon::try_rect_at(center, w, h).map_err(JsError::from)
+    }
+
+    /// Regular polygon with n vertices on a circle of radius around the origin, with a flat
+    /// bottom edge
+    pub fn regular(n: usize, radius: f64) -> Polygon {
+        assert!(n >= 3, "Regular polygon must have at least 3 vertices");
+        assert!(radius > 0.0, "Regular polygon radius must be positive");
+        let step = 2.0 * std::f64::consts::PI / n as f64;
+        let start = -std::f64::consts::FRAC_PI_2 - step / 2.0;
+        Polygon {
+            vertices: (0..n)
+                .map(|i| {
+                    let angle = start + step * i as f64;
+                    Vec2::new(trig::cos(angle), trig::sin(angle)) * radius
+                })
+                .collect(),
         }
+    }
 
-        Polygon { vertices }
+    /// Smallest convex polygon containing all points of a flat [x0, y0, x1, y1, ...] list,
+    /// throwing if there aren't pairs of points or they are collinear
+    #[wasm_bindgen(js_name = convexHull)]
+    pub fn wasm_convex_hull(points: &[f64]) -> Result<Polygon, JsError> {
+        Ok(Polygon::try_convex_hull(&points_from_flat(points)?)?)
     }
 }
 
@@ -89,9 +335,23 @@ impl Shape for Polygon {
         cy *= norm;
         Vec2::new(cx, cy)
     }
+
+    fn inertia(&self, mass: f64) -> f64 {
+        mass::inertia_polygon(&self.vertices, mass)
+    }
+
+    fn content_hash(&self) -> u64 {
+        let hasher = ContentHasher::new(ShapeKind::Polygon).u64(self.vertices.len() as u64);
+        self.vertices.iter().fold(hasher, |h, v| h.vec2(v)).finish()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_points(&self.vertices)
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec, clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use crate::math::vec2::Vec2;
@@ -131,4 +391,180 @@ mod tests {
 
         assert_eq!(a.center(), Vec2::new(2.5, 2.5));
     }
+
+    #[test]
+    fn test_polygon_convex_hull() {
+        let a = Polygon::convex_hull(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 4.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(0.0, 4.0),
+        ]);
+
+        assert_eq!(a.vertices().len(), 4);
+        assert_eq!(a.area(), 16.0);
+    }
+
+    #[test]
+    fn test_polygon_wasm_convex_hull() {
+        let a = Polygon::wasm_convex_hull(&[0.0, 0.0, 1.0, 0.0, 0.5, 0.2, 0.0, 1.0]).unwrap();
+
+        assert_eq!(a.vertices(), &[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_polygon_convex_hull_non_finite() {
+        let nan = Vec2::new(f64::NAN, 1.0);
+        let points = [Vec2::new(0.0, 0.0), nan, Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)];
+        assert!(matches!(Polygon::try_convex_hull(&points), Err(GeometryError::NonFinitePoint(p)) if p.x.is_nan()));
+        let infinite = Vec2::new(1.0, f64::INFINITY);
+        assert_eq!(Polygon::try_convex_hull(&[points[0], infinite, points[2]]), Err(GeometryError::NonFinitePoint(infinite)));
+        assert!(matches!(Polygon::try_new(&points), Err(GeometryError::NonFinitePoint(_))));
+        // The hull itself sorts NaN instead of panicking
+        hull::convex_hull(&points);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_polygon_convex_hull_collinear() {
+        Polygon::convex_hull(&[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_polygon_validation() {
+        // Clockwise, with a repeated vertex and one halfway along an edge
+        let p = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 0.0),
+        ]);
+        assert_eq!(
+            p.vertices(),
+            &[Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0), Vec2::new(0.0, 2.0)]
+        );
+        assert_eq!(p.area(), 4.0);
+
+        let bowtie = [Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)];
+        assert_eq!(Polygon::try_new(&bowtie), Err(GeometryError::SelfIntersecting));
+        let line = [Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)];
+        assert_eq!(Polygon::try_new(&line), Err(GeometryError::TooFewVertices(2)));
+
+        // Concave outlines are fine as long as they don't cross
+        let notched = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(1.5, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        assert_eq!(Polygon::try_new(&notched).unwrap().vertices().len(), 7);
+
+        // The union of two quads, where a notch splits an edge of the first into two stretches
+        // along one tilted line. Rounding puts them on either side of each other.
+        let split = [
+            Vec2::new(1.3033644061969736, 1.69547460104949),
+            Vec2::new(1.457629954685031, 1.2847253010291837),
+            Vec2::new(3.152765339524518, 0.7483370978582248),
+            Vec2::new(2.5054465230428082, 3.384296241947289),
+            Vec2::new(3.0929535115807756, 2.7684389398480365),
+            Vec2::new(2.8643428506050705, 4.877277267878993),
+            Vec2::new(2.767876623215058, 5.22176208891933),
+            Vec2::new(0.9903268595817535, 4.972528427267671),
+            Vec2::new(2.364909034411048, 3.5316154062825857),
+        ];
+        assert_eq!(Polygon::try_new(&split).unwrap().vertices().len(), 9);
+    }
+
+    #[test]
+    fn test_polygon_constructors() {
+        let r = Polygon::rect_at(Vec2::new(1.0, 1.0), 4.0, 2.0);
+        assert_eq!(r.vertices()[0], Vec2::new(-1.0, 0.0));
+        assert_eq!(r.area(), 8.0);
+        assert_eq!(r.center(), Vec2::new(1.0, 1.0));
+        assert_eq!(Polygon::rect(2.0, 2.0).aabb(), Aabb::new(Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)));
+        assert_eq!(Polygon::try_rect_at(Vec2::new(0.0, 0.0), 0.0, 1.0), Err(GeometryError::NonPositiveSize(0.0, 1.0)));
+        assert!(Polygon::try_rect_at(Vec2::new(0.0, 0.0), 1.0, f64::NAN).is_err());
+
+        let hexagon = Polygon::regular(6, 2.0);
+        assert_eq!(hexagon.vertices().len(), 6);
+        assert!(hexagon.area() > 0.0);
+        assert!(hexagon.center().len() < 1e-12);
+        // The bottom edge is flat
+        let (a, b) = (hexagon.vertices()[0], hexagon.vertices()[1]);
+        assert!((a.y - b.y).abs() < 1e-12 && a.y < 0.0);
+        assert!(hexagon.vertices().iter().all(|v| (v.len() - 2.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_polygon_inertia() {
+        let a = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+
+        assert!((a.inertia(12.0) - 5.0).abs() < 1e-12);
+    }
+
+    fn square() -> Polygon {
+        Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn test_polygon_clip() {
+        let half = square().clip(&Vec2::new(1.0, 0.0), &Vec2::new(1.0, 0.0)).unwrap();
+        assert_eq!(half.area(), 2.0);
+        assert!(half.contains(&Vec2::new(0.5, 1.0)));
+        assert!(!half.contains(&Vec2::new(1.5, 1.0)));
+
+        assert_eq!(square().clip(&Vec2::new(3.0, 0.0), &Vec2::new(1.0, 0.0)), Some(square()));
+        assert_eq!(square().clip(&Vec2::new(0.0, 0.0), &Vec2::new(1.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_polygon_transformed() {
+        let t = Transform2D::new(Vec2::new(5.0, 1.0), std::f64::consts::FRAC_PI_2);
+        let moved = square().transformed(&t);
+
+        assert_eq!(moved.vertices().len(), 4);
+        for (v, w) in square().vertices().iter().zip(moved.vertices()) {
+            assert!((t.apply(v) - w).len() < 1e-12);
+        }
+        assert!((moved.area() - square().area()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_polygon_fracture() {
+        let sites = [Vec2::new(0.5, 0.5), Vec2::new(1.5, 0.5), Vec2::new(1.0, 1.5), Vec2::new(1.0, 1.5)];
+        let pieces = square().fracture(&sites);
+
+        assert_eq!(pieces.len(), 3);
+        let total: f64 = pieces.iter().map(|p| p.area()).sum();
+        assert!((total - 4.0).abs() < 1e-12);
+        for (piece, site) in pieces.iter().zip(&sites) {
+            assert!(piece.contains(site));
+        }
+    }
+
+    #[test]
+    fn test_polygon_mirrored() {
+        let a = Polygon::new(&[Vec2::new(1.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(1.0, 1.0)]);
+        let m = a.mirrored(&Vec2::new(0.0, 1.0));
+
+        assert_eq!(m.vertices(), &[Vec2::new(-1.0, 1.0), Vec2::new(-3.0, 0.0), Vec2::new(-1.0, 0.0)]);
+        assert_eq!(m.area(), a.area());
+    }
 }
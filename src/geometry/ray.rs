@@ -0,0 +1,66 @@
+use wasm_bindgen::prelude::*;
+
+use super::Aabb;
+use crate::math::Vec2;
+
+/// A half line starting at origin
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec2,
+    /// Unit direction
+    pub direction: Vec2,
+}
+
+#[wasm_bindgen]
+impl Ray {
+    /// direction is normalized
+    #[wasm_bindgen(constructor)]
+    pub fn new(origin: Vec2, direction: Vec2) -> Ray {
+        Ray {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Point at distance along the ray
+    pub fn at(&self, distance: f64) -> Vec2 {
+        self.origin + self.direction * distance
+    }
+}
+
+impl Ray {
+    /// Box around the ray up to max_distance
+    pub fn aabb(&self, max_distance: f64) -> Aabb {
+        let end = self.at(max_distance);
+        Aabb::new(
+            Vec2::new(self.origin.x.min(end.x), self.origin.y.min(end.y)),
+            Vec2::new(self.origin.x.max(end.x), self.origin.y.max(end.y)),
+        )
+    }
+}
+
+/// Where a ray hits a shape
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Distance from the ray origin
+    pub distance: f64,
+    pub point: Vec2,
+    /// Surface normal at the hit, facing the ray origin
+    pub normal: Vec2,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ray_at() {
+        let r = Ray::new(Vec2::new(1.0, 0.0), Vec2::new(0.0, 2.0));
+
+        assert_eq!(r.direction, Vec2::new(0.0, 1.0));
+        assert_eq!(r.at(3.0), Vec2::new(1.0, 3.0));
+        assert_eq!(r.aabb(2.0), Aabb::new(Vec2::new(1.0, 0.0), Vec2::new(1.0, 2.0)));
+    }
+}
@@ -0,0 +1,209 @@
+use wasm_bindgen::prelude::*;
+
+use super::aabb::Aabb;
+use super::mass;
+use super::ray::{Ray, RayHit};
+use super::shape::{ContentHasher, Shape};
+use super::ShapeKind;
+use crate::math::vec2::Vec2;
+
+/// A straight edge between two points, for static level geometry such as terrain outlines.
+/// A one-sided segment only collides with shapes on the side of its normal, which points to
+/// the right looking from a to b, so a counter-clockwise outline has its normals facing out.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Segment {
+    a: Vec2,
+    b: Vec2,
+    pub one_sided: bool,
+}
+
+#[wasm_bindgen]
+impl Segment {
+    #[wasm_bindgen(constructor)]
+    pub fn new(a: Vec2, b: Vec2) -> Segment {
+        Segment { a, b, one_sided: false }
+    }
+
+    /// A segment that only collides from the side of its normal
+    pub fn one_sided(a: Vec2, b: Vec2) -> Segment {
+        Segment { a, b, one_sided: true }
+    }
+
+    pub fn a(&self) -> Vec2 {
+        self.a
+    }
+
+    pub fn b(&self) -> Vec2 {
+        self.b
+    }
+
+    pub fn length(&self) -> f64 {
+        (self.b - self.a).len()
+    }
+
+    /// Unit normal, to the right looking from a to b
+    pub fn normal(&self) -> Vec2 {
+        let d = self.b - self.a;
+        Vec2::new(d.y, -d.x).normalize()
+    }
+
+    /// Mirror image across the line through the origin along axis.
+    /// The ends are swapped so the normal faces the mirrored side.
+    pub fn mirrored(&self, axis: &Vec2) -> Segment {
+        Segment {
+            a: self.b.mirror(axis),
+            b: self.a.mirror(axis),
+            one_sided: self.one_sided,
+        }
+    }
+
+    /// The same segment moved by offset
+    pub fn translated(&self, offset: &Vec2) -> Segment {
+        Segment {
+            a: self.a + *offset,
+            b: self.b + *offset,
+            one_sided: self.one_sided,
+        }
+    }
+
+    /// Distance from p to the closest point of the segment
+    pub fn distance_to_point(&self, p: &Vec2) -> f64 {
+        (p - self.closest_point(p)).len()
+    }
+
+    pub fn closest_point(&self, p: &Vec2) -> Vec2 {
+        let ab = self.b - self.a;
+        let len_sq = ab.len_sq();
+        if len_sq == 0.0 {
+            return self.a;
+        }
+        let t = (Vec2::dot(&(p - self.a), &ab) / len_sq).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    /// First hit of the ray within max_distance. One-sided segments can't be hit from behind.
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let edge = self.b - self.a;
+        let denom = Vec2::cross(&ray.direction, &edge);
+        if denom.abs() <= f64::EPSILON {
+            return None;
+        }
+
+        let to_a = self.a - ray.origin;
+        let distance = Vec2::cross(&to_a, &edge) / denom;
+        let s = Vec2::cross(&to_a, &ray.direction) / denom;
+        if distance < 0.0 || distance > max_distance || !(0.0..=1.0).contains(&s) {
+            return None;
+        }
+
+        let mut normal = self.normal();
+        if Vec2::dot(&normal, &ray.direction) > 0.0 {
+            if self.one_sided {
+                return None;
+            }
+            normal = -normal;
+        }
+
+        Some(RayHit {
+            distance,
+            point: ray.at(distance),
+            normal,
+        })
+    }
+}
+
+impl Shape for Segment {
+    fn support(&self, dir: &Vec2) -> Vec2 {
+        if Vec2::dot(&self.b, dir) > Vec2::dot(&self.a, dir) {
+            self.b
+        } else {
+            self.a
+        }
+    }
+
+    fn center(&self) -> Vec2 {
+        (self.a + self.b) * 0.5
+    }
+
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    /// Inertia of a thin rod
+    fn inertia(&self, mass: f64) -> f64 {
+        mass::inertia_rod(mass, (self.b - self.a).len())
+    }
+
+    fn content_hash(&self) -> u64 {
+        let hasher = ContentHasher::new(ShapeKind::Segment).vec2(&self.a).vec2(&self.b);
+        hasher.u64(self.one_sided as u64).finish()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_points(&[self.a, self.b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor() -> Segment {
+        // Right to left so the normal points up
+        Segment::one_sided(Vec2::new(2.0, 0.0), Vec2::new(-2.0, 0.0))
+    }
+
+    #[test]
+    fn test_segment_shape() {
+        let s = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 2.0));
+
+        assert_eq!(s.support(&Vec2::new(1.0, 0.0)), Vec2::new(4.0, 2.0));
+        assert_eq!(s.support(&Vec2::new(-1.0, 1.0)), Vec2::new(0.0, 0.0));
+        assert_eq!(s.center(), Vec2::new(2.0, 1.0));
+        assert_eq!(s.aabb(), Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 2.0)));
+        assert_eq!(floor().normal(), Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn test_segment_distance() {
+        let s = floor();
+
+        assert_eq!(s.distance_to_point(&Vec2::new(1.0, 3.0)), 3.0);
+        assert_eq!(s.distance_to_point(&Vec2::new(5.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn test_segment_raycast() {
+        let s = Segment::new(Vec2::new(-2.0, 0.0), Vec2::new(2.0, 0.0));
+        let down = Ray::new(Vec2::new(1.0, 3.0), Vec2::new(0.0, -1.0));
+
+        let hit = s.raycast(&down, 10.0).unwrap();
+        assert_eq!(hit.distance, 3.0);
+        assert_eq!(hit.point, Vec2::new(1.0, 0.0));
+        assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+
+        assert!(s.raycast(&down, 2.0).is_none());
+        assert!(s.raycast(&Ray::new(Vec2::new(3.0, 3.0), Vec2::new(0.0, -1.0)), 10.0).is_none());
+        assert!(s.raycast(&Ray::new(Vec2::new(0.0, 3.0), Vec2::new(1.0, 0.0)), 10.0).is_none());
+    }
+
+    #[test]
+    fn test_one_sided_raycast() {
+        let up = Ray::new(Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0));
+        let down = Ray::new(Vec2::new(0.0, 1.0), Vec2::new(0.0, -1.0));
+
+        assert!(floor().raycast(&up, 10.0).is_none());
+        assert_eq!(floor().raycast(&down, 10.0).unwrap().normal, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn test_segment_mirrored() {
+        let s = Segment::one_sided(Vec2::new(1.0, 0.0), Vec2::new(1.0, 2.0));
+        let m = s.mirrored(&Vec2::new(0.0, 1.0));
+
+        assert_eq!((m.a(), m.b()), (Vec2::new(-1.0, 2.0), Vec2::new(-1.0, 0.0)));
+        assert_eq!(m.normal(), s.normal().mirror(&Vec2::new(0.0, 1.0)));
+        assert!(m.one_sided);
+    }
+}
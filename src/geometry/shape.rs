@@ -1,3 +1,5 @@
+use super::aabb::Aabb;
+use super::ShapeKind;
 use crate::math::vec2::Vec2;
 
 use std::f64;
@@ -6,4 +8,87 @@ pub trait Shape {
     fn center(&self) -> Vec2;
     fn support(&self, dir: &Vec2) -> Vec2;
     fn area(&self) -> f64;
+    /// Moment of inertia about the center for a shape of the given mass
+    fn inertia(&self, mass: f64) -> f64;
+    /// Hash of the values defining the shape, for caches of derived data to notice it changed.
+    /// Equal shapes hash the same on every platform.
+    fn content_hash(&self) -> u64;
+
+    /// Bounding box, found from the support function unless a shape knows better
+    fn aabb(&self) -> Aabb {
+        let max_x = self.support(&Vec2::new(1.0, 0.0)).x;
+        let min_x = self.support(&Vec2::new(-1.0, 0.0)).x;
+        let max_y = self.support(&Vec2::new(0.0, 1.0)).y;
+        let min_y = self.support(&Vec2::new(0.0, -1.0)).y;
+        Aabb::new(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y))
+    }
+}
+
+/// FNV-1a hash of the values defining a shape, see Shape::content_hash
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Start hashing a shape of the given kind, so different kinds with the same values differ
+    pub fn new(kind: ShapeKind) -> ContentHasher {
+        ContentHasher(ContentHasher::OFFSET).u64(kind as u64)
+    }
+
+    /// Keep hashing on from a finished hash, such as one of a shape being placed somewhere
+    pub fn from_hash(hash: u64) -> ContentHasher {
+        ContentHasher(hash)
+    }
+
+    pub fn u64(self, v: u64) -> ContentHasher {
+        let mut hash = self.0;
+        for byte in v.to_le_bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(ContentHasher::PRIME);
+        }
+        ContentHasher(hash)
+    }
+
+    /// -0 hashes like 0 since the two compare equal
+    pub fn f64(self, v: f64) -> ContentHasher {
+        self.u64(if v == 0.0 { 0 } else { v.to_bits() })
+    }
+
+    pub fn vec2(self, v: &Vec2) -> ContentHasher {
+        self.f64(v.x).f64(v.y)
+    }
+
+    pub fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Compound, Polygon, Segment, Shapes};
+    use crate::math::Transform2D;
+
+    #[test]
+    fn test_content_hash() {
+        let circle = Circle::new(Vec2::new(0.0, 0.0), 1.0);
+        assert_eq!(circle.content_hash(), Circle::new(Vec2::new(-0.0, 0.0), 1.0).content_hash());
+        assert_ne!(circle.content_hash(), Circle::new(Vec2::new(0.0, 0.0), 1.5).content_hash());
+        assert_eq!(Shapes::Circle(circle).content_hash(), circle.content_hash());
+
+        let mut segment = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0));
+        let before = segment.content_hash();
+        segment.one_sided = true;
+        assert_ne!(segment.content_hash(), before);
+
+        // Same numbers as a different kind of shape
+        let triangle = Polygon::new(&[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)]);
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::identity(), Shapes::Polygon(triangle.clone()));
+        let before = compound.content_hash();
+        assert_ne!(before, triangle.content_hash());
+        compound.add_child(Transform2D::new(Vec2::new(2.0, 0.0), 0.0), Shapes::Circle(circle));
+        assert_ne!(compound.content_hash(), before);
+    }
 }
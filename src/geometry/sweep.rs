@@ -0,0 +1,93 @@
+use super::minkowski;
+use super::polygon::Polygon;
+use super::Shapes;
+use crate::math::{Transform2D, Vec2};
+
+/// Points on the outline of a shape. Circles are replaced by a polygon of the given number of
+/// segments drawn around them, see Circle::enclosing_points.
+pub fn outline_points(shape: &Shapes, segments: usize) -> Vec<Vec2> {
+    match shape {
+        Shapes::Polygon(p) => p.vertices().to_vec(),
+        Shapes::Segment(s) => vec![s.a(), s.b()],
+        Shapes::Circle(c) => c.enclosing_points(segments),
+        Shapes::Compound(c) => c
+            .children()
+            .iter()
+            .flat_map(|(transform, child)| {
+                outline_points(child, segments)
+                    .into_iter()
+                    .map(move |p| transform.apply(&p))
+            })
+            .collect(),
+    }
+}
+
+/// Outline of the area covered by a convex shape moved along displacement.
+/// This is the Minkowski sum of the shape and the path it moves along, the convex hull of the
+/// shape at its start and end positions. Circles are enclosed by a polygon slightly larger than
+/// them, as for minkowski::sum. Returns None if the swept area is degenerate (a point or a line).
+pub fn swept_outline(shape: &Shapes, displacement: &Vec2) -> Option<Polygon> {
+    swept_outline_transformed(shape, &Transform2D::identity(), displacement)
+}
+
+/// Like swept_outline for a shape placed in the world by transform
+pub fn swept_outline_transformed(shape: &Shapes, transform: &Transform2D, displacement: &Vec2) -> Option<Polygon> {
+    let path = [Vec2::new(0.0, 0.0), *displacement];
+    minkowski::combine(&minkowski::outline_at(shape, transform), &path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Shape};
+
+    #[test]
+    fn test_swept_square() {
+        let square = Shapes::Polygon(Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]));
+        let outline = swept_outline(&square, &Vec2::new(2.0, 0.0)).unwrap();
+
+        assert_eq!(outline.area(), 3.0);
+        assert_eq!(outline.vertices().len(), 4);
+    }
+
+    #[test]
+    fn test_swept_diagonal() {
+        let square = Shapes::Polygon(Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]));
+        let outline = swept_outline(&square, &Vec2::new(1.0, 1.0)).unwrap();
+
+        assert_eq!(outline.vertices().len(), 6);
+        assert_eq!(outline.area(), 3.0);
+    }
+
+    #[test]
+    fn test_swept_circle() {
+        let circle = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 1.0));
+        let outline = swept_outline(&circle, &Vec2::new(4.0, 0.0)).unwrap();
+
+        // The edges of the polygon standing in for the circle touch it at the ends
+        assert!((outline.support(&Vec2::new(1.0, 0.0)).x - 5.0).abs() < 1e-12);
+        assert!((outline.support(&Vec2::new(-1.0, 0.0)).x + 1.0).abs() < 1e-12);
+        for i in 0..64 {
+            let angle = i as f64 / 64.0 * 2.0 * std::f64::consts::PI;
+            let p = Vec2::new(angle.cos(), angle.sin()) * (1.0 - 1e-9);
+            assert!(outline.contains(&p) && outline.contains(&(p + Vec2::new(4.0, 0.0))));
+        }
+    }
+
+    #[test]
+    fn test_swept_point_is_degenerate() {
+        let point = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.0));
+
+        assert_eq!(swept_outline(&point, &Vec2::new(1.0, 0.0)), None);
+    }
+}
@@ -0,0 +1,59 @@
+use super::shape::{ContentHasher, Shape};
+use crate::math::{Transform2D, Vec2};
+
+/// A shape placed in world space by a transform
+pub struct Transformed<'a, S: Shape + ?Sized> {
+    pub shape: &'a S,
+    pub transform: Transform2D,
+}
+
+impl<'a, S: Shape + ?Sized> Transformed<'a, S> {
+    pub fn new(shape: &'a S, transform: Transform2D) -> Transformed<'a, S> {
+        Transformed { shape, transform }
+    }
+}
+
+impl<S: Shape + ?Sized> Shape for Transformed<'_, S> {
+    fn center(&self) -> Vec2 {
+        self.transform.apply(&self.shape.center())
+    }
+
+    fn support(&self, dir: &Vec2) -> Vec2 {
+        let local = self.shape.support(&self.transform.inverse_apply_vector(dir));
+        self.transform.apply(&local)
+    }
+
+    fn area(&self) -> f64 {
+        self.shape.area()
+    }
+
+    fn inertia(&self, mass: f64) -> f64 {
+        self.shape.inertia(mass)
+    }
+
+    fn content_hash(&self) -> u64 {
+        let hasher = ContentHasher::from_hash(self.shape.content_hash());
+        hasher.vec2(&self.transform.position).f64(self.transform.rotation).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Polygon;
+
+    #[test]
+    fn test_transformed_support() {
+        let square = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+        let t = Transformed::new(&square, Transform2D::new(Vec2::new(5.0, 0.0), std::f64::consts::PI));
+
+        let s = t.support(&Vec2::new(1.0, 1.0));
+        assert!((s - Vec2::new(5.0, 0.0)).len() < 1e-12);
+        assert!((t.center() - Vec2::new(4.5, -0.5)).len() < 1e-12);
+    }
+}
@@ -1,8 +1,13 @@
-mod collision;
-mod geometry;
-mod math;
-mod physics;
-mod utils;
+pub mod collision;
+pub mod error;
+pub mod geometry;
+pub mod logging;
+pub mod math;
+pub mod physics;
+pub mod serialize;
+pub mod utils;
+
+pub use error::Error;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -0,0 +1,67 @@
+//! Engine diagnostics.
+//!
+//! The engine logs through the `log` facade, so messages reach whichever logger the host
+//! application installs. Each message's target is the module it came from, e.g.
+//! `newton_2d::collision::gjk`, which lets loggers like env_logger filter per module.
+//!
+//! Nothing is logged until a logger is installed. In the browser, builds with the
+//! `console_log` feature can forward messages to the console with `init_console_log`.
+
+#[cfg(feature = "console_log")]
+use wasm_bindgen::prelude::*;
+
+/// Send engine logs to the browser console.
+/// level: 0 = off, 1 = error, 2 = warn, 3 = info, 4 = debug, 5 = trace
+#[cfg(feature = "console_log")]
+#[wasm_bindgen]
+pub fn init_console_log(level: usize) {
+    let level = match level {
+        1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        5 => log::Level::Trace,
+        _ => {
+            log::set_max_level(log::LevelFilter::Off);
+            return;
+        }
+    };
+    // Only the first logger installed is kept, so calling this again just changes the level
+    if console_log::init_with_level(level).is_err() {
+        log::set_max_level(level.to_level_filter());
+    }
+}
+
+/// Records of every message as "LEVEL target message". The logger is shared by all tests,
+/// so each should only look at records of its own targets.
+#[cfg(test)]
+pub(crate) fn capture() -> &'static std::sync::Mutex<Vec<String>> {
+    use std::sync::{Mutex, Once};
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static INSTALL: Once = Once::new();
+
+    struct Capture;
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            RECORDS
+                .lock()
+                .unwrap()
+                .push(format!("{} {} {}", record.level(), record.target(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture;
+    INSTALL.call_once(|| {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+    &RECORDS
+}
@@ -0,0 +1,286 @@
+//! Iterative solvers for Ax=b.
+//!
+//! Jacobi and Gauss-Seidel converge for diagonally dominant or symmetric positive definite A and
+//! only need A's diagonal to be non zero. Projected Gauss-Seidel clamps each unknown into bounds
+//! after every update, which solves the box constrained problems contact solvers build: normal
+//! impulses bounded below by zero and friction impulses bounded by a multiple of their normal.
+
+use wasm_bindgen::prelude::*;
+
+use super::matrix::{check_size, MathError};
+use super::{Matrix, Vector};
+
+/// When an iterative solve stops
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IterativeConfig {
+    pub max_iterations: usize,
+    /// Stop once no unknown changes by more than this in a pass
+    pub tolerance: f64,
+}
+
+impl Default for IterativeConfig {
+    fn default() -> IterativeConfig {
+        IterativeConfig::new()
+    }
+}
+
+#[wasm_bindgen]
+impl IterativeConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> IterativeConfig {
+        IterativeConfig {
+            max_iterations: 100,
+            tolerance: 1e-10,
+        }
+    }
+}
+
+/// Result of an iterative solve
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterativeSolution {
+    x: Vector,
+    /// Passes made
+    pub iterations: usize,
+    /// Largest change of an unknown in the last pass
+    pub change: f64,
+    /// Whether change got within the tolerance before max_iterations
+    pub converged: bool,
+}
+
+#[wasm_bindgen]
+impl IterativeSolution {
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vector {
+        self.x.clone()
+    }
+}
+
+impl IterativeSolution {
+    pub fn into_x(self) -> Vector {
+        self.x
+    }
+}
+
+#[wasm_bindgen]
+impl Matrix {
+    /// Solves Ax=b for x by Jacobi iteration, starting from zero. Every unknown of a pass is
+    /// computed from the previous pass.
+    pub fn jacobi(&self, b: &Vector, config: &IterativeConfig) -> IterativeSolution {
+        self.check_iterative(b.n);
+        let n = self.n;
+        iterate(n, config, |x| {
+            let previous = x.clone();
+            let mut change: f64 = 0.0;
+            for i in 0..n {
+                let sum = (0..n).filter(|&j| j != i).fold(b[i], |sum, j| sum - self[[i, j]] * previous[j]);
+                x[i] = sum / self[[i, i]];
+                change = change.max((x[i] - previous[i]).abs());
+            }
+            change
+        })
+    }
+
+    /// Solves Ax=b for x by Gauss-Seidel iteration, starting from zero. Unknowns are updated in
+    /// place, so later rows of a pass already see the new values of earlier ones.
+    pub fn gauss_seidel(&self, b: &Vector, config: &IterativeConfig) -> IterativeSolution {
+        self.check_iterative(b.n);
+        let n = self.n;
+        iterate(n, config, |x| self.gauss_seidel_pass(b, x, |_, _, v| v))
+    }
+
+    /// Gauss-Seidel keeping every unknown x[i] within [lo[i], hi[i]], starting from zero clamped
+    /// into the bounds. Use infinite bounds for unconstrained unknowns.
+    pub fn projected_gauss_seidel(
+        &self,
+        b: &Vector,
+        lo: &Vector,
+        hi: &Vector,
+        config: &IterativeConfig,
+    ) -> IterativeSolution {
+        self.projected_gauss_seidel_coupled(b, lo, hi, &vec![None; b.n], config)
+    }
+}
+
+impl Matrix {
+    /// Projected Gauss-Seidel where an unknown can have bounds scaled by another, as friction is
+    /// bounded by the normal impulse. With coupling[i] = Some(j) x[i] is kept within
+    /// [lo[i] * x[j], hi[i] * x[j]], otherwise within [lo[i], hi[i]].
+    pub fn projected_gauss_seidel_coupled(
+        &self,
+        b: &Vector,
+        lo: &Vector,
+        hi: &Vector,
+        coupling: &[Option<usize>],
+        config: &IterativeConfig,
+    ) -> IterativeSolution {
+        self.check_iterative(b.n);
+        if lo.n != self.n || hi.n != self.n || coupling.len() != self.n {
+            panic!("Bounds must be the same size as the matrix");
+        }
+        let bounds = |x: &Vector, i: usize| match coupling[i] {
+            Some(j) => (lo[i] * x[j], hi[i] * x[j]),
+            None => (lo[i], hi[i]),
+        };
+        iterate(self.n, config, |x| {
+            self.gauss_seidel_pass(b, x, |x, i, v| {
+                let (lo, hi) = bounds(x, i);
+                v.max(lo).min(hi)
+            })
+        })
+    }
+
+    /// Jacobi iteration, or an error if A isn't square, b doesn't match it or A has a zero on
+    /// its diagonal
+    pub fn try_jacobi(&self, b: &Vector, config: &IterativeConfig) -> Result<IterativeSolution, MathError> {
+        self.try_check_iterative(b.n)?;
+        Ok(self.jacobi(b, config))
+    }
+
+    /// Gauss-Seidel iteration, or an error like try_jacobi
+    pub fn try_gauss_seidel(&self, b: &Vector, config: &IterativeConfig) -> Result<IterativeSolution, MathError> {
+        self.try_check_iterative(b.n)?;
+        Ok(self.gauss_seidel(b, config))
+    }
+
+    /// Projected Gauss-Seidel, or an error like try_jacobi or if the bounds don't match A
+    pub fn try_projected_gauss_seidel(
+        &self,
+        b: &Vector,
+        lo: &Vector,
+        hi: &Vector,
+        config: &IterativeConfig,
+    ) -> Result<IterativeSolution, MathError> {
+        self.try_check_iterative(b.n)?;
+        check_size(self.n, lo.n)?;
+        check_size(self.n, hi.n)?;
+        Ok(self.projected_gauss_seidel(b, lo, hi, config))
+    }
+
+    /// One in place pass, with project deciding the value each unknown is set to
+    fn gauss_seidel_pass<F>(&self, b: &Vector, x: &mut Vector, project: F) -> f64
+    where
+        F: Fn(&Vector, usize, f64) -> f64,
+    {
+        let mut change: f64 = 0.0;
+        for i in 0..self.n {
+            let sum = (0..self.n).filter(|&j| j != i).fold(b[i], |sum, j| sum - self[[i, j]] * x[j]);
+            let value = project(x, i, sum / self[[i, i]]);
+            change = change.max((value - x[i]).abs());
+            x[i] = value;
+        }
+        change
+    }
+
+    fn check_iterative(&self, size: usize) {
+        if self.n != self.m {
+            panic!("Matrix must be square to solve");
+        }
+        if self.n != size {
+            panic!("Matrix and vector must be same size");
+        }
+        if let Some(row) = self.zero_diagonal() {
+            panic!("Matrix has a zero on its diagonal at row {}", row);
+        }
+    }
+
+    fn try_check_iterative(&self, size: usize) -> Result<(), MathError> {
+        self.check_square()?;
+        check_size(self.n, size)?;
+        match self.zero_diagonal() {
+            Some(row) => Err(MathError::ZeroDiagonal { row }),
+            None => Ok(()),
+        }
+    }
+
+    fn zero_diagonal(&self) -> Option<usize> {
+        (0..self.n).find(|&i| self[[i, i]] == 0.0)
+    }
+}
+
+/// Runs pass over x until its change is within the tolerance
+fn iterate<F: FnMut(&mut Vector) -> f64>(n: usize, config: &IterativeConfig, mut pass: F) -> IterativeSolution {
+    let mut x = Vector::new(n);
+    let mut change = 0.0;
+    for iteration in 1..=config.max_iterations {
+        change = pass(&mut x);
+        if change <= config.tolerance {
+            return IterativeSolution { x, iterations: iteration, change, converged: true };
+        }
+    }
+    IterativeSolution { x, iterations: config.max_iterations, change, converged: false }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system() -> (Matrix, Vector) {
+        let a = Matrix::from(3, 3, &[4.0, -1.0, 0.0, -1.0, 4.0, -1.0, 0.0, -1.0, 4.0]);
+        (a, Vector::from(&[2.0, 4.0, 10.0]))
+    }
+
+    fn assert_close(x: &Vector, expected: &[f64]) {
+        for (i, e) in expected.iter().enumerate() {
+            assert!((x[i] - e).abs() < 1e-8, "{} != {:?}", x, expected);
+        }
+    }
+
+    #[test]
+    fn test_jacobi() {
+        let (a, b) = system();
+        let solution = a.jacobi(&b, &IterativeConfig::new());
+        assert!(solution.converged);
+        assert_close(&solution.x(), &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_gauss_seidel() {
+        let (a, b) = system();
+        let config = IterativeConfig::new();
+        let solution = a.gauss_seidel(&b, &config);
+        assert!(solution.converged);
+        assert_close(&solution.x(), &[1.0, 2.0, 3.0]);
+        // Using new values straight away converges faster
+        assert!(solution.iterations < a.jacobi(&b, &config).iterations);
+
+        let short = IterativeConfig { max_iterations: 2, ..config };
+        let stopped = a.gauss_seidel(&b, &short);
+        assert!(!stopped.converged && stopped.iterations == 2);
+    }
+
+    #[test]
+    fn test_projected_gauss_seidel() {
+        let (a, b) = system();
+        let lo = Vector::from(&[0.0, 0.0, 0.0]);
+        let hi = Vector::from(&[f64::INFINITY, 1.5, f64::INFINITY]);
+        let x = a.projected_gauss_seidel(&b, &lo, &hi, &IterativeConfig::new()).into_x();
+        // x[1] sits on its bound and the others solve their rows given it
+        assert_close(&x, &[0.875, 1.5, 2.875]);
+    }
+
+    #[test]
+    fn test_friction_coupling() {
+        // A normal impulse of 2 bounds the friction impulse to 0.5 * 2
+        let a = Matrix::from(2, 2, &[1.0, 0.0, 0.0, 1.0]);
+        let b = Vector::from(&[2.0, 3.0]);
+        let lo = Vector::from(&[0.0, -0.5]);
+        let hi = Vector::from(&[f64::INFINITY, 0.5]);
+        let x = a.projected_gauss_seidel_coupled(&b, &lo, &hi, &[None, Some(0)], &IterativeConfig::new()).into_x();
+        assert_close(&x, &[2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_try_errors() {
+        let (a, b) = system();
+        let config = IterativeConfig::new();
+        let zero = Matrix::from(2, 2, &[0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(zero.try_jacobi(&Vector::new(2), &config), Err(MathError::ZeroDiagonal { row: 0 }));
+        assert_eq!(
+            a.try_gauss_seidel(&Vector::new(2), &config),
+            Err(MathError::SizeMismatch { expected: 3, found: 2 })
+        );
+        assert!(a.try_projected_gauss_seidel(&b, &Vector::new(3), &Vector::new(2), &config).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops;
+
+extern crate overload;
+use overload::overload;
+use wasm_bindgen::prelude::*;
+
+use super::trig;
+use super::Vec2;
+
+/// A 2x2 matrix stored inline, for rotations and small systems like point constraints
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Mat2 {
+    pub m00: f64,
+    pub m01: f64,
+    pub m10: f64,
+    pub m11: f64,
+}
+
+#[wasm_bindgen]
+impl Mat2 {
+    #[wasm_bindgen(constructor)]
+    /// Create a matrix from its rows
+    pub fn new(m00: f64, m01: f64, m10: f64, m11: f64) -> Mat2 {
+        Mat2 { m00, m01, m10, m11 }
+    }
+
+    pub fn identity() -> Mat2 {
+        Mat2::new(1.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Counter clockwise rotation by angle radians
+    pub fn rotation(angle: f64) -> Mat2 {
+        let (sin, cos) = trig::sin_cos(angle);
+        Mat2::new(cos, -sin, sin, cos)
+    }
+
+    pub fn mul_vec(&self, v: &Vec2) -> Vec2 {
+        self * v
+    }
+
+    pub fn mul_mat(&self, other: &Mat2) -> Mat2 {
+        self * other
+    }
+
+    pub fn transpose(&self) -> Mat2 {
+        Mat2::new(self.m00, self.m10, self.m01, self.m11)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.m00 * self.m11 - self.m01 * self.m10
+    }
+
+    /// Inverse matrix, or zero if the matrix is singular
+    pub fn inverse(&self) -> Mat2 {
+        let det = self.determinant();
+        if det == 0.0 {
+            return Mat2::default();
+        }
+        let inv = 1.0 / det;
+        Mat2::new(self.m11 * inv, -self.m01 * inv, -self.m10 * inv, self.m00 * inv)
+    }
+
+    /// Solve Ax=b for x without forming the inverse. Zero if the matrix is singular.
+    pub fn solve(&self, b: &Vec2) -> Vec2 {
+        let det = self.determinant();
+        if det == 0.0 {
+            return Vec2::new(0.0, 0.0);
+        }
+        let inv = 1.0 / det;
+        Vec2::new(inv * (self.m11 * b.x - self.m01 * b.y), inv * (self.m00 * b.y - self.m10 * b.x))
+    }
+}
+
+overload!((a: ?Mat2) + (b: ?Mat2) -> Mat2 { Mat2::new(a.m00 + b.m00, a.m01 + b.m01, a.m10 + b.m10, a.m11 + b.m11) });
+overload!((a: ?Mat2) * (b: ?Vec2) -> Vec2 { Vec2::new(a.m00 * b.x + a.m01 * b.y, a.m10 * b.x + a.m11 * b.y) });
+overload!((a: ?Mat2) * (b: ?Mat2) -> Mat2 {
+    Mat2::new(
+        a.m00 * b.m00 + a.m01 * b.m10,
+        a.m00 * b.m01 + a.m01 * b.m11,
+        a.m10 * b.m00 + a.m11 * b.m10,
+        a.m10 * b.m01 + a.m11 * b.m11,
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!((a - b).len() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let r = Mat2::rotation(std::f64::consts::FRAC_PI_2);
+        assert_near(r * Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0));
+        assert_near(r.transpose() * Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0));
+        let p = Vec2::new(0.3, -2.0);
+        assert_near(Mat2::rotation(0.7).mul_vec(&p), p.rotate(0.7));
+    }
+
+    #[test]
+    fn test_inverse_solve() {
+        let m = Mat2::new(4.0, 7.0, 2.0, 6.0);
+        assert_eq!(m.determinant(), 10.0);
+        assert_near(m * m.inverse() * Vec2::new(3.0, -1.0), Vec2::new(3.0, -1.0));
+        let b = Vec2::new(1.0, 2.0);
+        assert_near(m * m.solve(&b), b);
+
+        let singular = Mat2::new(1.0, 2.0, 2.0, 4.0);
+        assert_eq!(singular.inverse(), Mat2::default());
+        assert_eq!(singular.solve(&b), Vec2::new(0.0, 0.0));
+    }
+}
@@ -1,12 +1,44 @@
 use std::fmt;
 use std::ops;
 
-use super::Vector;
+use super::{Vec2, Vector};
 
 extern crate overload;
+use js_sys::Float64Array;
 use overload::overload;
 use wasm_bindgen::prelude::*;
 
+/// Matrix and vector sizes that don't fit an operation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MathError {
+    /// The operation needs a square matrix but this one is rows x cols
+    NotSquare { rows: usize, cols: usize },
+    /// A vector or data buffer has the wrong length
+    SizeMismatch { expected: usize, found: usize },
+    /// An iterative solve divides by the diagonal, which is zero at row
+    ZeroDiagonal { row: usize },
+    /// Least squares needs at least as many rows as columns but the matrix is rows x cols
+    Underdetermined { rows: usize, cols: usize },
+    /// The matrix has no inverse
+    Singular,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::NotSquare { rows, cols } => write!(f, "Matrix must be square but is {}x{}", rows, cols),
+            MathError::SizeMismatch { expected, found } => write!(f, "Expected size {} but got {}", expected, found),
+            MathError::ZeroDiagonal { row } => write!(f, "Matrix has a zero on its diagonal at row {}", row),
+            MathError::Underdetermined { rows, cols } => {
+                write!(f, "Matrix needs at least as many rows as columns but is {}x{}", rows, cols)
+            }
+            MathError::Singular => write!(f, "Matrix is singular"),
+        }
+    }
+}
+
+impl std::error::Error for MathError {}
+
 /// An nxm Matrix
 #[wasm_bindgen]
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -32,6 +64,15 @@ impl Matrix {
         Matrix {m, n, data: data.to_vec()}
     }
 
+    /// n x n identity matrix
+    pub fn identity(n: usize) -> Matrix {
+        let mut mat = Matrix::new(n, n);
+        for i in 0..n {
+            mat[[i, i]] = 1.0;
+        }
+        mat
+    }
+
     /// Get value at row i and column j
     pub fn get(&self, i: usize, j: usize) -> f64 {
         self[[i, j]]
@@ -42,6 +83,22 @@ impl Matrix {
         self[[i, j]] = v;
     }
 
+    /// Create a matrix with m rows and n columns from a typed array of length m*n, copied
+    /// straight into the matrix
+    pub fn from_typed_array(m: usize, n: usize, data: &Float64Array) -> Matrix {
+        if m * n != data.length() as usize {
+            panic!("Wrong amout of data to make a {}x{} matrix", m, n);
+        }
+        let mut mat = Matrix::new(m, n);
+        data.copy_to(&mut mat.data);
+        mat
+    }
+
+    /// Row by row copy of the values
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.data.clone()
+    }
+
     /// Get matrix with select
     pub fn get_rows(&self, r: &[usize]) -> Matrix {
         let mut data = Vec::with_capacity(r.len() * self.n);
@@ -116,6 +173,31 @@ impl Matrix {
         self * other
     }
 
+    /// Matrix with rows and columns swapped
+    pub fn transpose(&self) -> Matrix {
+        let mut mat = Matrix::new(self.n, self.m);
+        for i in 0..self.m {
+            for j in 0..self.n {
+                mat[[j, i]] = self[[i, j]];
+            }
+        }
+        mat
+    }
+
+    /// Determinant of a square matrix
+    pub fn determinant(&self) -> f64 {
+        if self.n != self.m {
+            panic!("Matrix must be square to take its determinant");
+        }
+        self.lu().determinant()
+    }
+
+    /// Inverse of a square matrix, throwing if it is singular
+    #[wasm_bindgen(js_name = inverse)]
+    pub fn wasm_inverse(&self) -> Result<Matrix, JsError> {
+        self.inverse().map_err(JsError::from)
+    }
+
     fn gaussian_elimination(&mut self, b: &mut Vector) {
         for i in 0..(self.n-1) { // Rows
 
@@ -147,6 +229,9 @@ impl Matrix {
     fn back_substitution(&self, y: &Vector) -> Vector {
         let mut x = Vector::new(self.n);
         for i in (0..=(self.n-1)).rev() {
+            if self[[i, i]] == 0.0 {
+                log::warn!("Solving singular {}x{} matrix", self.m, self.n);
+            }
             x[i] = y[i];
             for j in (i+1)..=(self.n-1) {
                 x[i] -= self[[i, j]] * x[j];
@@ -182,6 +267,90 @@ impl Matrix {
         self.back_substitution(b)
     }
 
+    /// Factor the matrix once so Ax=b can be solved cheaply for many b.
+    pub fn lu(&self) -> LuDecomposition {
+        if self.n != self.m {
+            panic!("Matrix must be square to factor");
+        }
+        let mut lu = self.clone();
+        let mut pivots: Vec<usize> = (0..self.n).collect();
+        let mut swaps = 0;
+        for i in 0..self.n {
+            // Partial Pivot
+            let mut max_idx = i;
+            for ii in (i+1)..self.n {
+                if lu[[ii, i]].abs() > lu[[max_idx, i]].abs() {
+                    max_idx = ii;
+                }
+            }
+            if max_idx != i {
+                lu.swap_rows(i, max_idx);
+                pivots.swap(i, max_idx);
+                swaps += 1;
+            }
+            if lu[[i, i]] == 0.0 {
+                continue;
+            }
+
+            // Store the multipliers of L below the diagonal
+            for j in (i+1)..self.n {
+                let m = lu[[j, i]] / lu[[i, i]];
+                lu[[j, i]] = m;
+                for k in (i+1)..self.n {
+                    lu[[j, k]] -= m * lu[[i, k]];
+                }
+            }
+        }
+        LuDecomposition {lu, pivots, swaps}
+    }
+
+    /// Factor the matrix into an orthogonal Q and upper triangular R with Householder
+    /// reflections. Needs at least as many rows as columns.
+    pub fn qr(&self) -> QrDecomposition {
+        if self.m < self.n {
+            panic!("Matrix needs at least as many rows as columns to factor");
+        }
+        let (m, n) = (self.m, self.n);
+        let mut q = Matrix::identity(m);
+        let mut r = self.clone();
+        for k in 0..n.min(m - 1) {
+            // Reflect column k below the diagonal onto the diagonal
+            let norm = (k..m).map(|i| r[[i, k]] * r[[i, k]]).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                continue;
+            }
+            let alpha = if r[[k, k]] > 0.0 { -norm } else { norm };
+            let mut v: Vec<f64> = (k..m).map(|i| r[[i, k]]).collect();
+            v[0] -= alpha;
+            let v_len = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+            v.iter_mut().for_each(|x| *x /= v_len);
+
+            for j in k..n {
+                let d = 2.0 * (k..m).map(|i| v[i - k] * r[[i, j]]).sum::<f64>();
+                for i in k..m {
+                    r[[i, j]] -= d * v[i - k];
+                }
+            }
+            for i in 0..m {
+                let d = 2.0 * (k..m).map(|j| q[[i, j]] * v[j - k]).sum::<f64>();
+                for j in k..m {
+                    q[[i, j]] -= d * v[j - k];
+                }
+            }
+        }
+        QrDecomposition {q, r}
+    }
+
+    /// x minimizing |Ax - b|, the exact solution when there is one. Needs at least as many rows
+    /// as columns.
+    pub fn solve_least_squares(&self, b: &Vector) -> Vector {
+        if self.m != b.n {
+            panic!("Matrix and vector must have the same number of rows");
+        }
+        self.qr().solve_least_squares(b)
+    }
+
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         const PRECISION: usize = 3;
         let mut s = String::new();
@@ -195,7 +364,7 @@ impl Matrix {
         }
 
         for i in 0..self.m {
-            s.push_str("|");
+            s.push('|');
             for j in 0..self.n {
                 s.push_str(format!("{num:>0$.1$}", max_len+1, PRECISION, num=self[[i, j]]).as_str());
             }
@@ -205,6 +374,205 @@ impl Matrix {
     }
 }
 
+impl Matrix {
+    /// Matrix with a row of x, y for each point
+    pub fn from_vec2s(points: &[Vec2]) -> Matrix {
+        Matrix {m: points.len(), n: 2, data: points.iter().flat_map(|p| [p.x, p.y]).collect()}
+    }
+
+    /// Points from the rows of a matrix with 2 columns
+    pub fn to_vec2s(&self) -> Vec<Vec2> {
+        if self.n != 2 {
+            panic!("Matrix must have 2 columns to hold points but has {}", self.n);
+        }
+        self.data.chunks(2).map(|row| Vec2::new(row[0], row[1])).collect()
+    }
+
+    /// Values row by row
+    pub fn as_slice(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Matrix with m rows and n columns from data, or an error if data isn't length m*n
+    pub fn try_from(m: usize, n: usize, data: &[f64]) -> Result<Matrix, MathError> {
+        check_size(m * n, data.len())?;
+        Ok(Matrix::from(m, n, data))
+    }
+
+    /// Solves Ax=b for x, or an error if A isn't square or b doesn't match it
+    pub fn try_solve(&self, b: &Vector) -> Result<Vector, MathError> {
+        self.check_square()?;
+        check_size(self.n, b.n)?;
+        Ok(self.solve(b))
+    }
+
+    /// LU factors of the matrix, or an error if it isn't square
+    pub fn try_lu(&self) -> Result<LuDecomposition, MathError> {
+        self.check_square()?;
+        Ok(self.lu())
+    }
+
+    /// Determinant, or an error if the matrix isn't square
+    pub fn try_determinant(&self) -> Result<f64, MathError> {
+        self.check_square()?;
+        Ok(self.determinant())
+    }
+
+    /// Inverse, or an error if the matrix isn't square or is singular. Pivots this small next
+    /// to the largest value count as zero.
+    pub fn inverse(&self) -> Result<Matrix, MathError> {
+        self.check_square()?;
+        let lu = self.lu();
+        let scale = self.data.iter().fold(0.0_f64, |max, v| max.max(v.abs()));
+        let tolerance = f64::EPSILON * scale * self.n as f64;
+        if (0..self.n).any(|i| lu.lu[[i, i]].abs() <= tolerance) {
+            return Err(MathError::Singular);
+        }
+        let mut inverse = Matrix::new(self.n, self.n);
+        for j in 0..self.n {
+            let mut e = Vector::new(self.n);
+            e[j] = 1.0;
+            let column = lu.solve(&e);
+            for i in 0..self.n {
+                inverse[[i, j]] = column[i];
+            }
+        }
+        Ok(inverse)
+    }
+
+    /// QR factors of the matrix, or an error if it has fewer rows than columns
+    pub fn try_qr(&self) -> Result<QrDecomposition, MathError> {
+        self.check_overdetermined()?;
+        Ok(self.qr())
+    }
+
+    /// Least squares solution, or an error if the matrix has fewer rows than columns or b
+    /// doesn't match its rows
+    pub fn try_solve_least_squares(&self, b: &Vector) -> Result<Vector, MathError> {
+        self.check_overdetermined()?;
+        check_size(self.m, b.n)?;
+        Ok(self.solve_least_squares(b))
+    }
+
+    fn check_overdetermined(&self) -> Result<(), MathError> {
+        if self.m < self.n {
+            return Err(MathError::Underdetermined { rows: self.m, cols: self.n });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_square(&self) -> Result<(), MathError> {
+        if self.m != self.n {
+            return Err(MathError::NotSquare { rows: self.m, cols: self.n });
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn check_size(expected: usize, found: usize) -> Result<(), MathError> {
+    if expected != found {
+        return Err(MathError::SizeMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// LU factors of a square matrix with partial pivoting, PA = LU
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LuDecomposition {
+    /// L below the diagonal with an implied unit diagonal, U on and above it
+    lu: Matrix,
+    /// Row of the original matrix at each row of the factors
+    pivots: Vec<usize>,
+    swaps: usize,
+}
+
+#[wasm_bindgen]
+impl LuDecomposition {
+    /// Solves Ax=b for x by forward and back substitution.
+    pub fn solve(&self, b: &Vector) -> Vector {
+        let n = self.lu.n;
+        if n != b.n {
+            panic!("Matrix and vector must be same size");
+        }
+
+        // Ly = Pb
+        let mut y = Vector::new(n);
+        for i in 0..n {
+            y[i] = b[self.pivots[i]];
+            for j in 0..i {
+                y[i] -= self.lu[[i, j]] * y[j];
+            }
+        }
+        self.lu.back_substitution(&y)
+    }
+
+    /// Determinant of the factored matrix
+    pub fn determinant(&self) -> f64 {
+        let sign = if self.swaps.is_multiple_of(2) { 1.0 } else { -1.0 };
+        (0..self.lu.n).fold(sign, |det, i| det * self.lu[[i, i]])
+    }
+
+    pub fn size(&self) -> usize {
+        self.lu.n
+    }
+}
+
+impl LuDecomposition {
+    /// Solves Ax=b for x, or an error if b doesn't match the matrix size
+    pub fn try_solve(&self, b: &Vector) -> Result<Vector, MathError> {
+        check_size(self.lu.n, b.n)?;
+        Ok(self.solve(b))
+    }
+}
+
+/// QR factors of a matrix with at least as many rows as columns, A = QR
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrDecomposition {
+    /// m x m orthogonal
+    q: Matrix,
+    /// m x n upper triangular
+    r: Matrix,
+}
+
+#[wasm_bindgen]
+impl QrDecomposition {
+    #[wasm_bindgen(getter)]
+    pub fn q(&self) -> Matrix {
+        self.q.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn r(&self) -> Matrix {
+        self.r.clone()
+    }
+
+    /// x minimizing |Ax - b| by back substitution of Rx = Q^T b
+    pub fn solve_least_squares(&self, b: &Vector) -> Vector {
+        let (m, n) = (self.r.m, self.r.n);
+        if m != b.n {
+            panic!("Matrix and vector must have the same number of rows");
+        }
+        let mut qtb = Vector::new(m);
+        for i in 0..m {
+            qtb[i] = (0..m).map(|k| self.q[[k, i]] * b[k]).sum();
+        }
+        if n == 0 {
+            return Vector::new(0);
+        }
+        self.r.back_substitution(&qtb)
+    }
+}
+
+impl QrDecomposition {
+    /// Least squares solution, or an error if b doesn't match the rows of the matrix
+    pub fn try_solve_least_squares(&self, b: &Vector) -> Result<Vector, MathError> {
+        check_size(self.r.m, b.n)?;
+        Ok(self.solve_least_squares(b))
+    }
+}
+
 impl fmt::Display for Matrix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.to_string())
@@ -261,7 +629,7 @@ overload!((a: ?Matrix) * (b: ?Vector) -> Vector {
     if a.n != b.n {
         panic!("Can not multiply {}x{} to {}", a.m, a.n, b.n);
     }
-    let mut vec = Vector::new(a.n);
+    let mut vec = Vector::new(a.m);
 
     for i in 0..a.m {
         let mut sum = 0.0;
@@ -272,6 +640,10 @@ overload!((a: ?Matrix) * (b: ?Vector) -> Vector {
     }
     vec
 });
+overload!((a: ?Matrix) * (b: f64) -> Matrix {
+    Matrix {m: a.m, n: a.n, data: a.data.iter().map(|v| v * b).collect()}
+});
+overload!((a: f64) * (b: ?Matrix) -> Matrix { b * a });
 
 
 impl ops::Index<[usize; 2]> for Matrix {
@@ -312,6 +684,69 @@ mod tests {
         let m4 = Matrix::from(4, 4, &DATA);
         let v0 = Vector::from(&[2.0, 3.0, 1.0, 5.0]);
         assert_eq!(&m4 * &v0, Vector::from(&[4.0, 36.0, 43.0, 30.0]));
+
+        assert_eq!(&m0 * 2.0, Matrix::from(2, 2, &[2.0, 4.0, 6.0, 8.0]));
+        assert_eq!(0.5 * &m0, Matrix::from(2, 2, &[0.5, 1.0, 1.5, 2.0]));
+    }
+
+    #[test]
+    fn test_qr() {
+        let a = Matrix::from(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let qr = a.qr();
+        let (q, r) = (qr.q(), qr.r());
+        let back = &q * &r;
+        let orthogonal = &Matrix::from(3, 3, &(0..9).map(|i| q.get(i % 3, i / 3)).collect::<Vec<f64>>()) * &q;
+        for i in 0..3 {
+            for j in 0..3 {
+                if j < 2 {
+                    assert!((back.get(i, j) - a.get(i, j)).abs() < 1e-12);
+                }
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((orthogonal.get(i, j) - expected).abs() < 1e-12);
+            }
+        }
+        assert!(r.get(1, 0).abs() < 1e-12 && r.get(2, 0).abs() < 1e-12 && r.get(2, 1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_least_squares() {
+        // Fit y = c + m x through points off the line y = 1 + 2x
+        let a = Matrix::from(4, 2, &[1.0, 0.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0]);
+        let b = Vector::from(&[1.1, 2.9, 5.1, 6.9]);
+        let x = a.solve_least_squares(&b);
+        assert!((x[0] - 1.06).abs() < 1e-12 && (x[1] - 1.96).abs() < 1e-12);
+
+        // Square systems are solved exactly
+        let m = Matrix::from(4, 4, &DATA);
+        let b = Vector::from(&[4.0, 36.0, 43.0, 30.0]);
+        let x = m.solve_least_squares(&b);
+        for (i, e) in [2.0, 3.0, 1.0, 5.0].iter().enumerate() {
+            assert!((x[i] - e).abs() < 1e-12);
+        }
+
+        let wide = Matrix::new(2, 3);
+        assert_eq!(wide.try_qr(), Err(MathError::Underdetermined { rows: 2, cols: 3 }));
+        assert_eq!(a.try_solve_least_squares(&Vector::new(3)), Err(MathError::SizeMismatch { expected: 4, found: 3 }));
+    }
+
+    #[test]
+    fn test_vec2s() {
+        let points = [Vec2::new(1.0, 2.0), Vec2::new(3.0, 4.0), Vec2::new(5.0, 6.0)];
+        let mat = Matrix::from_vec2s(&points);
+        assert_eq!(mat, Matrix::from(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+        assert_eq!(mat.as_slice(), mat.to_vec().as_slice());
+        assert_eq!(mat.to_vec2s(), points.to_vec());
+
+        // Rotating every point at once
+        let turn = Matrix::from(2, 2, &[0.0, 1.0, -1.0, 0.0]);
+        assert_eq!((&mat * &turn).to_vec2s()[0], Vec2::new(-2.0, 1.0));
+    }
+
+    #[test]
+    fn test_identity() {
+        let mat = Matrix::from(4, 4, &DATA);
+        assert_eq!(&Matrix::identity(4) * &mat, mat);
+        assert_eq!(Matrix::identity(2), Matrix::from(2, 2, &[1.0, 0.0, 0.0, 1.0]));
     }
 
     #[test]
@@ -380,4 +815,56 @@ mod tests {
         assert_eq!(x.get(2), -2.0);
         assert_eq!(x.get(3), -3.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_lu_solve() {
+        let mat = Matrix::from(4, 4, &DATA);
+        let lu = mat.lu();
+
+        for b in &[Vector::from(&[5.0, 16.0, 22.0, 15.0]), Vector::from(&[1.0, 0.0, -2.0, 3.5])] {
+            let x = lu.solve(b);
+            let expected = mat.solve(b);
+            for i in 0..4 {
+                assert!((x.get(i) - expected.get(i)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lu_determinant() {
+        let mat = Matrix::from(3, 3, &[0.0, 2.0, 1.0, 1.0, 1.0, 0.0, 2.0, 0.0, 3.0]);
+        assert!((mat.lu().determinant() - -8.0).abs() < 1e-12);
+        assert_eq!(Matrix::from(2, 2, &[1.0, 2.0, 2.0, 4.0]).lu().determinant(), 0.0);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mat = Matrix::from(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(mat.transpose(), Matrix::from(3, 2, &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]));
+        assert_eq!(mat.transpose().transpose(), mat);
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert!((Matrix::from(3, 3, &[0.0, 2.0, 1.0, 1.0, 1.0, 0.0, 2.0, 0.0, 3.0]).determinant() - -8.0).abs() < 1e-12);
+        assert_eq!(Matrix::from(2, 2, &[1.0, 0.0, 0.0, 1.0]).determinant(), 1.0);
+        assert_eq!(Matrix::new(2, 3).try_determinant(), Err(MathError::NotSquare { rows: 2, cols: 3 }));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let mat = Matrix::from(4, 4, &DATA);
+        let inverse = mat.inverse().unwrap();
+        let product = &mat * &inverse;
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.get(i, j) - expected).abs() < 1e-12);
+            }
+        }
+
+        assert_eq!(Matrix::from(2, 2, &[1.0, 2.0, 2.0, 4.0]).inverse(), Err(MathError::Singular));
+        assert_eq!(Matrix::from(3, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]).inverse(), Err(MathError::Singular));
+        assert_eq!(Matrix::new(2, 3).inverse(), Err(MathError::NotSquare { rows: 2, cols: 3 }));
+    }
+}
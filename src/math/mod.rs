@@ -1,7 +1,16 @@
 pub mod vec2;
+pub mod mat2;
+pub mod iterative;
 pub mod matrix;
+pub mod random;
+pub mod transform;
+pub mod trig;
 pub mod vector;
 
 pub use vec2::Vec2;
-pub use matrix::Matrix;
+pub use mat2::Mat2;
+pub use iterative::{IterativeConfig, IterativeSolution};
+pub use matrix::{LuDecomposition, MathError, Matrix, QrDecomposition};
+pub use random::Rng;
+pub use transform::Transform2D;
 pub use vector::Vector;
@@ -0,0 +1,86 @@
+use super::trig;
+use super::vec2::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+/// Small xorshift64* generator. Seeded explicitly so a simulation replays identically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Rng {
+        Rng::new(0)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck at zero
+        let state = if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed };
+        Rng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [min, max)
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    /// Uniform point inside a disk
+    pub fn in_disk(&mut self, center: &Vec2, radius: f64) -> Vec2 {
+        let r = radius * self.next_f64().sqrt();
+        let angle = self.range(0.0, 2.0 * std::f64::consts::PI);
+        center + Vec2::new(r * trig::cos(angle), r * trig::sin(angle))
+    }
+}
+
+impl Binary for Rng {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.state);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Rng, SerializeError> {
+        Ok(Rng { state: r.get()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_rng_ranges() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let x = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&x));
+            let p = rng.in_disk(&Vec2::new(1.0, 1.0), 0.5);
+            assert!((p - Vec2::new(1.0, 1.0)).len() <= 0.5);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use wasm_bindgen::prelude::*;
+
+use super::{Mat2, Vec2};
+
+/// A rigid 2d transform: rotation followed by translation
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Transform2D {
+    pub position: Vec2,
+    pub rotation: f64,
+}
+
+#[wasm_bindgen]
+impl Transform2D {
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: Vec2, rotation: f64) -> Transform2D {
+        Transform2D { position, rotation }
+    }
+
+    pub fn identity() -> Transform2D {
+        Transform2D::new(Vec2::new(0.0, 0.0), 0.0)
+    }
+
+    /// Matrix rotating directions from local to world space
+    pub fn rotation_matrix(&self) -> Mat2 {
+        Mat2::rotation(self.rotation)
+    }
+
+    /// Transform a point from local to world space
+    pub fn apply(&self, point: &Vec2) -> Vec2 {
+        self.rotation_matrix() * point + self.position
+    }
+
+    /// Rotate a direction from local to world space
+    pub fn apply_vector(&self, vector: &Vec2) -> Vec2 {
+        self.rotation_matrix() * vector
+    }
+
+    /// Transform a point from world to local space
+    pub fn inverse_apply(&self, point: &Vec2) -> Vec2 {
+        self.rotation_matrix().transpose() * (point - self.position)
+    }
+
+    /// Rotate a direction from world to local space
+    pub fn inverse_apply_vector(&self, vector: &Vec2) -> Vec2 {
+        self.rotation_matrix().transpose() * vector
+    }
+
+    /// The transform equivalent to applying other then self
+    pub fn mul(&self, other: &Transform2D) -> Transform2D {
+        Transform2D::new(self.apply(&other.position), self.rotation + other.rotation)
+    }
+
+    pub fn inverse(&self) -> Transform2D {
+        Transform2D::new((-self.position).rotate(-self.rotation), -self.rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!((a - b).len() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_apply() {
+        let t = Transform2D::new(Vec2::new(1.0, 2.0), std::f64::consts::FRAC_PI_2);
+        assert_near(t.apply(&Vec2::new(1.0, 0.0)), Vec2::new(1.0, 3.0));
+        assert_near(t.inverse_apply(&Vec2::new(1.0, 3.0)), Vec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_mul_inverse() {
+        let t = Transform2D::new(Vec2::new(1.0, 2.0), 0.3);
+        let p = Vec2::new(-2.0, 5.0);
+        assert_near(t.mul(&t.inverse()).apply(&p), p);
+        assert_near(t.mul(&t).apply(&p), t.apply(&t.apply(&p)));
+    }
+}
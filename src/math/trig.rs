@@ -0,0 +1,260 @@
+//! Trigonometry the engine goes through, so builds can swap in a portable version.
+//!
+//! std's sin, cos and atan2 call the platform's libm, whose last bit differs between targets.
+//! With the `deterministic` feature the functions here use fdlibm's kernels written out in plain
+//! f64 arithmetic instead, which gives the same bits everywhere: Rust never fuses a * b + c into an
+//! fma on its own and sqrt is exactly rounded by IEEE 754. Without the feature they forward to std.
+
+// Constants are copied from fdlibm digit for digit
+#![allow(clippy::excessive_precision, clippy::approx_constant)]
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+#[cfg(feature = "deterministic")]
+pub fn sin_cos(x: f64) -> (f64, f64) {
+    portable_sin_cos(x)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn sin_cos(x: f64) -> (f64, f64) {
+    x.sin_cos()
+}
+
+pub fn sin(x: f64) -> f64 {
+    sin_cos(x).0
+}
+
+pub fn cos(x: f64) -> f64 {
+    sin_cos(x).1
+}
+
+#[cfg(feature = "deterministic")]
+pub fn atan2(y: f64, x: f64) -> f64 {
+    portable_atan2(y, x)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+/// Arc cosine, NaN outside [-1, 1]
+pub fn acos(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return f64::NAN;
+    }
+    atan2(((1.0 - x) * (1.0 + x)).sqrt(), x)
+}
+
+/// pi/2 split into three parts so n * part is exact for the n range reduction sees
+const PIO2_1: f64 = 1.57079632673412561417e+00;
+const PIO2_2: f64 = 6.07710050630396597660e-11;
+const PIO2_3: f64 = 2.02226624871116645580e-21;
+
+const S: [f64; 6] = [
+    -1.66666666666666324348e-01,
+    8.33333333332248946124e-03,
+    -1.98412698298579493134e-04,
+    2.75573137070700676789e-06,
+    -2.50507602534068634195e-08,
+    1.58969099521155010221e-10,
+];
+
+const C: [f64; 6] = [
+    4.16666666666666019037e-02,
+    -1.38888888888741095749e-03,
+    2.48015872894767294178e-05,
+    -2.75573143513906633035e-07,
+    2.08757232129817482790e-09,
+    -1.13596475577881948265e-11,
+];
+
+/// sin and cos on [-pi/4, pi/4]
+fn kernel_sin_cos(x: f64) -> (f64, f64) {
+    if x.abs() < 7.450580596923828e-9 {
+        return (x, 1.0);
+    }
+    let z = x * x;
+    let v = z * x;
+    let r = S[1] + z * (S[2] + z * (S[3] + z * (S[4] + z * S[5])));
+    let sin = x + v * (S[0] + z * r);
+
+    let w = z * z;
+    let r = z * (C[0] + z * (C[1] + z * C[2])) + w * w * (C[3] + z * (C[4] + z * C[5]));
+    let hz = 0.5 * z;
+    let w = 1.0 - hz;
+    let cos = w + (((1.0 - w) - hz) + z * r);
+    (sin, cos)
+}
+
+/// sin and cos giving the same bits on every platform. Accurate to about an ulp for |x| below
+/// 2^20 * pi, less beyond, where simulations don't go.
+pub fn portable_sin_cos(x: f64) -> (f64, f64) {
+    if !x.is_finite() {
+        return (f64::NAN, f64::NAN);
+    }
+    if x.abs() <= FRAC_PI_4 {
+        return kernel_sin_cos(x);
+    }
+    let n = (x * (2.0 / PI)).round();
+    let r = x - n * PIO2_1 - n * PIO2_2 - n * PIO2_3;
+    let (s, c) = kernel_sin_cos(r);
+    match (n as i64) & 3 {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    }
+}
+
+const ATAN_HI: [f64; 4] = [
+    4.63647609000806093515e-01,
+    7.85398163397448278999e-01,
+    9.82793723247329054082e-01,
+    1.57079632679489655800e+00,
+];
+
+const ATAN_LO: [f64; 4] = [
+    2.26987774529616870924e-17,
+    3.06161699786838301793e-17,
+    1.39033110312309984516e-17,
+    6.12323399573676603587e-17,
+];
+
+const AT: [f64; 11] = [
+    3.33333333333329318027e-01,
+    -1.99999999998764832476e-01,
+    1.42857142725034663711e-01,
+    -1.11111104054623557880e-01,
+    9.09088713343650656196e-02,
+    -7.69187620504482999495e-02,
+    6.66107313738753120669e-02,
+    -5.83357013379057348645e-02,
+    4.97687799461593236017e-02,
+    -3.65315727442169155270e-02,
+    1.62858201153657823623e-02,
+];
+
+/// Lower part of pi, with PI giving pi to twice the precision
+const PI_LO: f64 = 1.2246467991473531772e-16;
+
+/// Arc tangent of x >= 0
+fn portable_atan(x: f64) -> f64 {
+    if x >= 7.378697629483821e19 {
+        return ATAN_HI[3] + ATAN_LO[3];
+    }
+    let (id, x) = if x < 0.4375 {
+        if x < 7.450580596923828e-9 {
+            return x;
+        }
+        (None, x)
+    } else if x < 0.6875 {
+        (Some(0), (2.0 * x - 1.0) / (2.0 + x))
+    } else if x < 1.1875 {
+        (Some(1), (x - 1.0) / (x + 1.0))
+    } else if x < 2.4375 {
+        (Some(2), (x - 1.5) / (1.0 + 1.5 * x))
+    } else {
+        (Some(3), -1.0 / x)
+    };
+
+    let z = x * x;
+    let w = z * z;
+    let s1 = z * (AT[0] + w * (AT[2] + w * (AT[4] + w * (AT[6] + w * (AT[8] + w * AT[10])))));
+    let s2 = w * (AT[1] + w * (AT[3] + w * (AT[5] + w * (AT[7] + w * AT[9]))));
+    match id {
+        None => x - x * (s1 + s2),
+        Some(id) => ATAN_HI[id] - ((x * (s1 + s2) - ATAN_LO[id]) - x),
+    }
+}
+
+/// atan2 giving the same bits on every platform, with std's results for zeros and infinities
+pub fn portable_atan2(y: f64, x: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        return x + y;
+    }
+    // Which half plane each argument is in, counting signed zeros
+    let (left, below) = (x.is_sign_negative(), y.is_sign_negative());
+    let signed = |z: f64| if below { -z } else { z };
+
+    if y == 0.0 {
+        return if left { signed(PI) } else { y };
+    }
+    if x == 0.0 {
+        return signed(FRAC_PI_2);
+    }
+    if x.is_infinite() {
+        return match (left, y.is_infinite()) {
+            (false, true) => signed(FRAC_PI_4),
+            (true, true) => signed(3.0 * FRAC_PI_4),
+            (false, false) => signed(0.0),
+            (true, false) => signed(PI),
+        };
+    }
+    if y.is_infinite() {
+        return signed(FRAC_PI_2);
+    }
+
+    let ratio = (y / x).abs();
+    let z = if ratio > 1.152921504606847e18 {
+        FRAC_PI_2 + 0.5 * PI_LO
+    } else if left && ratio < 8.673617379884035e-19 {
+        0.0
+    } else {
+        portable_atan(ratio)
+    };
+    match (left, below) {
+        (false, false) => z,
+        (false, true) => -z,
+        (true, false) => PI - (z - PI_LO),
+        (true, true) => (z - PI_LO) - PI,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-15 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_portable_sin_cos() {
+        for i in -2000..=2000 {
+            let x = i as f64 * 0.0173;
+            let (s, c) = portable_sin_cos(x);
+            assert!(close(s, x.sin()) && close(c, x.cos()), "{}", x);
+        }
+        assert_eq!(portable_sin_cos(0.0), (0.0, 1.0));
+        assert!(portable_sin_cos(-0.0).0.is_sign_negative());
+        assert!(portable_sin_cos(f64::INFINITY).0.is_nan());
+    }
+
+    #[test]
+    fn test_portable_atan2() {
+        for i in -40..=40 {
+            for j in -40..=40 {
+                let (y, x) = (i as f64 * 0.37, j as f64 * 0.53);
+                assert!(close(portable_atan2(y, x), y.atan2(x)), "{} {}", y, x);
+            }
+        }
+        let special = [0.0, -0.0, 1.0, -1.0, 1e-300, 1e300, f64::INFINITY, f64::NEG_INFINITY];
+        for &y in &special {
+            for &x in &special {
+                let (a, b) = (portable_atan2(y, x), y.atan2(x));
+                assert!(close(a, b) && a.is_sign_negative() == b.is_sign_negative(), "{} {}", y, x);
+            }
+        }
+        assert!(portable_atan2(f64::NAN, 1.0).is_nan());
+    }
+
+    #[test]
+    fn test_acos() {
+        for i in -10..=10 {
+            let x = i as f64 * 0.1;
+            assert!((acos(x) - x.acos()).abs() < 1e-15, "{}", x);
+        }
+        assert!(acos(1.5).is_nan());
+    }
+}
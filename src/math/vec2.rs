@@ -4,6 +4,8 @@ extern crate overload;
 use overload::overload;
 use wasm_bindgen::prelude::*;
 
+use super::trig;
+
 /// A 2d vector
 #[wasm_bindgen]
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
@@ -37,19 +39,20 @@ impl Vec2 {
 
     /// Angle needed to rotate this vector to lay on another vector.
     pub fn angle_to(&self, other: &Vec2) -> f64 {
-        ((self.x * other.x + self.y * other.y) / (self.len() * other.len())).acos()
+        trig::acos((self.x * other.x + self.y * other.y) / (self.len() * other.len()))
     }
 
     /// Angle in radians
     pub fn angle(&self) -> f64 {
-        self.y.atan2(self.x)
+        trig::atan2(self.y, self.x)
     }
 
     /// Rotate
     pub fn rotate(&self, angle: f64) -> Vec2 {
+        let (sin, cos) = trig::sin_cos(angle);
         Vec2 {
-            x: angle.cos() * self.x - angle.sin() * self.y,
-            y: angle.sin() * self.x + angle.cos() * self.y,
+            x: cos * self.x - sin * self.y,
+            y: sin * self.x + cos * self.y,
         }
     }
 
@@ -58,8 +61,90 @@ impl Vec2 {
         self / self.len()
     }
 
+    /// Point percent of the way along the line from v to w
     pub fn lerp(v: &Vec2, w: &Vec2, percent: f64) -> Vec2 {
-        (v + w) * percent
+        v + (w - v) * percent
+    }
+
+    /// Turns v percent of the way towards w along the shorter way round, while its length goes
+    /// linearly from v's to w's
+    pub fn slerp(v: &Vec2, w: &Vec2, percent: f64) -> Vec2 {
+        let angle = trig::atan2(Vec2::cross(v, w), Vec2::dot(v, w));
+        let (from, to) = (v.len(), w.len());
+        if from == 0.0 {
+            return w * percent;
+        }
+        v.rotate(angle * percent) * ((from + (to - from) * percent) / from)
+    }
+
+    /// Steps towards target by at most max_delta, stopping on it
+    pub fn move_toward(&self, target: &Vec2, max_delta: f64) -> Vec2 {
+        let delta = target - self;
+        let distance = delta.len();
+        if distance <= max_delta || distance == 0.0 {
+            *target
+        } else {
+            self + delta * (max_delta / distance)
+        }
+    }
+
+    /// Mirror across the line through the origin along axis
+    pub fn mirror(&self, axis: &Vec2) -> Vec2 {
+        let d = axis.normalize();
+        d * (2.0 * Vec2::dot(self, &d)) - self
+    }
+
+    /// Rotated a quarter turn counter clockwise
+    pub fn perp(&self) -> Vec2 {
+        Vec2::new(-self.y, self.x)
+    }
+
+    /// Dot product of perp() with other, the same as cross(self, other)
+    pub fn perp_dot(&self, other: &Vec2) -> f64 {
+        Vec2::cross(self, other)
+    }
+
+    /// Component along other
+    pub fn project_onto(&self, other: &Vec2) -> Vec2 {
+        other * (Vec2::dot(self, other) / other.len_sq())
+    }
+
+    /// Bounced off a surface with unit normal
+    pub fn reflect(&self, normal: &Vec2) -> Vec2 {
+        self - normal * (2.0 * Vec2::dot(self, normal))
+    }
+
+    /// Shortened to max if longer
+    pub fn clamp_length(&self, max: f64) -> Vec2 {
+        let len_sq = self.len_sq();
+        if len_sq > max * max {
+            self * (max / len_sq.sqrt())
+        } else {
+            *self
+        }
+    }
+
+    /// Componentwise absolute value
+    pub fn abs(&self) -> Vec2 {
+        Vec2::new(self.x.abs(), self.y.abs())
+    }
+
+    pub fn distance(v: &Vec2, w: &Vec2) -> f64 {
+        (v - w).len()
+    }
+
+    pub fn distance_sq(v: &Vec2, w: &Vec2) -> f64 {
+        (v - w).len_sq()
+    }
+
+    /// Componentwise minimum
+    pub fn min(v: &Vec2, w: &Vec2) -> Vec2 {
+        Vec2::new(v.x.min(w.x), v.y.min(w.y))
+    }
+
+    /// Componentwise maximum
+    pub fn max(v: &Vec2, w: &Vec2) -> Vec2 {
+        Vec2::new(v.x.max(w.x), v.y.max(w.y))
     }
 
     pub fn dot(v: &Vec2, w: &Vec2) -> f64 {
@@ -86,13 +171,21 @@ impl Vec2 {
         self / other
     }
 
+    /// Cross product of scalar s, as a vector out of the plane, with v. For an angular velocity
+    /// s this is the velocity of the point at offset v from the center of rotation.
+    pub fn cross_scalar(s: f64, v: &Vec2) -> Vec2 {
+        Vec2::new(-s * v.y, s * v.x)
+    }
+
+    /// (a x b) x c, used by GJK to find a direction perpendicular to c towards the origin.
+    /// Falls back to a's perpendicular when a and b are close to parallel, judged relative to
+    /// their lengths so the result doesn't depend on the scale of the inputs.
     pub fn triple_product(a: &Vec2, b: &Vec2, c: &Vec2) -> Vec2 {
         let first = Vec2::cross(a, b);
-        let prod = Vec2::new(-c.y * first, c.x * first);
-        if prod.len_sq() < 0.01 {
-            return Vec2::new(a.y, -a.x);
+        if first.abs() <= f64::EPSILON * a.len() * b.len() {
+            Vec2::new(a.y, -a.x)
         } else {
-            return prod;
+            Vec2::new(-c.y * first, c.x * first)
         }
     }
 }
@@ -189,6 +282,35 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_lerp_ends() {
+        let (a, b) = (Vec2::new(1.0, 2.0), Vec2::new(3.0, -2.0));
+        assert_eq!(Vec2::lerp(&a, &b, 0.0), a);
+        assert_eq!(Vec2::lerp(&a, &b, 1.0), b);
+        assert_eq!(Vec2::lerp(&a, &b, 0.25), Vec2::new(1.5, 1.0));
+    }
+
+    #[test]
+    fn test_slerp() {
+        let (a, b) = (Vec2::new(1.0, 0.0), Vec2::new(0.0, 3.0));
+        let half = Vec2::slerp(&a, &b, 0.5);
+        assert!((half.len() - 2.0).abs() < 1e-12);
+        assert!((half.angle() - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+        assert!((Vec2::slerp(&a, &b, 1.0) - b).len() < 1e-12);
+        // The shorter way round is clockwise here
+        let below = Vec2::slerp(&a, &Vec2::new(0.0, -1.0), 0.5);
+        assert!(below.y < 0.0);
+    }
+
+    #[test]
+    fn test_move_toward() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(3.0, 4.0);
+        assert!((a.move_toward(&b, 1.0) - Vec2::new(0.6, 0.8)).len() < 1e-12);
+        assert_eq!(a.move_toward(&b, 10.0), b);
+        assert_eq!(b.move_toward(&b, 1.0), b);
+    }
+
     #[test]
     fn test_dot() {
         assert_eq!(Vec2::dot(&Vec2::new(2.0, 3.0), &Vec2::new(5.0, 6.0)), 28.0)
@@ -202,6 +324,43 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_perp() {
+        let v = Vec2::new(2.0, 1.0);
+        assert_eq!(v.perp(), Vec2::new(-1.0, 2.0));
+        assert_eq!(Vec2::dot(&v, &v.perp()), 0.0);
+        assert_eq!(v.perp_dot(&Vec2::new(5.0, 6.0)), 7.0);
+    }
+
+    #[test]
+    fn test_project_reflect() {
+        let v = Vec2::new(3.0, 4.0);
+        assert_eq!(v.project_onto(&Vec2::new(2.0, 0.0)), Vec2::new(3.0, 0.0));
+        assert_eq!(v.reflect(&Vec2::new(0.0, 1.0)), Vec2::new(3.0, -4.0));
+    }
+
+    #[test]
+    fn test_clamp_length() {
+        let v = Vec2::new(3.0, 4.0);
+        assert_eq!(v.clamp_length(10.0), v);
+        assert_eq!(v.clamp_length(2.5), Vec2::new(1.5, 2.0));
+    }
+
+    #[test]
+    fn test_distance() {
+        let (a, b) = (Vec2::new(1.0, 1.0), Vec2::new(4.0, -3.0));
+        assert_eq!(Vec2::distance(&a, &b), 5.0);
+        assert_eq!(Vec2::distance_sq(&a, &b), 25.0);
+    }
+
+    #[test]
+    fn test_min_max_abs() {
+        let (a, b) = (Vec2::new(1.0, -3.0), Vec2::new(-2.0, 4.0));
+        assert_eq!(Vec2::min(&a, &b), Vec2::new(-2.0, -3.0));
+        assert_eq!(Vec2::max(&a, &b), Vec2::new(1.0, 4.0));
+        assert_eq!(a.abs(), Vec2::new(1.0, 3.0));
+    }
+
     #[test]
     fn test_triple_product() {
         let a = Vec2::new(4.0, 3.0);
@@ -210,4 +369,17 @@ mod tests {
 
         assert_eq!(Vec2::triple_product(&a, &b, &c), Vec2::new(-78.0, 52.0))
     }
+
+    #[test]
+    fn test_triple_product_parallel() {
+        let a = Vec2::new(1.0, 0.0);
+        assert_eq!(Vec2::triple_product(&a, &Vec2::new(2.0, 0.0), &a), Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn test_cross_scalar() {
+        let r = Vec2::new(2.0, 0.0);
+        assert_eq!(Vec2::cross_scalar(3.0, &r), Vec2::new(0.0, 6.0));
+        assert_eq!(Vec2::cross_scalar(1.0, &Vec2::new(0.0, 1.0)), Vec2::new(-1.0, 0.0));
+    }
 }
@@ -46,6 +46,22 @@ impl Vector {
         dot
     }
 
+    /// Every element multiplied by s
+    pub fn scale(&self, s: f64) -> Vector {
+        Vector::from(&self.data.iter().map(|v| v * s).collect::<Vec<f64>>())
+    }
+
+    /// Euclidean length
+    pub fn norm(&self) -> f64 {
+        Vector::dot(self, self).sqrt()
+    }
+
+    /// Largest absolute element, 0 for an empty vector
+    pub fn norm_inf(&self) -> f64 {
+        self.data.iter().fold(0.0, |max, v| v.abs().max(max))
+    }
+
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         const PRECISION: usize = 3;
         let mut s = String::new();
@@ -143,4 +159,17 @@ mod tests {
         assert_eq!(vec.get(3), 11.0);
     }
 
+    #[test]
+    fn test_scale() {
+        assert_eq!(Vector::from(&DATA).scale(2.0), Vector::from(&[2.0, 6.0, 8.0, 4.0]));
+    }
+
+    #[test]
+    fn test_norm() {
+        let vec = Vector::from(&[3.0, -4.0]);
+        assert_eq!(vec.norm(), 5.0);
+        assert_eq!(vec.norm_inf(), 4.0);
+        assert_eq!(Vector::new(0).norm_inf(), 0.0);
+    }
+
 }
@@ -0,0 +1,120 @@
+//! Bodies described by a flat list of numbers, so JS can create many of them in one call.
+//!
+//! Each body is `[kind, mass, x, y, rotation, vx, vy, param_count, params...]` where kind is a
+//! ShapeKind and the params are
+//! - circle: `[radius]`
+//! - polygon: `[x0, y0, x1, y1, ...]` with at least 3 vertices. As with RigidBody::with_shape,
+//!   a dynamic body's polygon is moved so its center of mass is at `x, y`.
+//! - segment: `[ax, ay, bx, by, one_sided]`, always static so mass is ignored
+//!
+//! Compound shapes can't be described this way.
+
+use super::body::RigidBody;
+use crate::geometry::{Circle, Polygon, Segment, ShapeKind, Shapes};
+use crate::math::Vec2;
+
+/// Numbers before the params of each body
+pub const DESCRIPTOR_HEADER: usize = 8;
+
+/// Bodies described by buffer, or the offset of the first invalid descriptor
+pub fn parse_bodies(buffer: &[f64]) -> Result<Vec<RigidBody>, usize> {
+    let mut bodies = Vec::new();
+    let mut offset = 0;
+    while offset < buffer.len() {
+        let header = buffer.get(offset..offset + DESCRIPTOR_HEADER).ok_or(offset)?;
+        let param_count = header[7];
+        if param_count < 0.0 || param_count.fract() != 0.0 {
+            return Err(offset);
+        }
+        let start = offset + DESCRIPTOR_HEADER;
+        let params = buffer.get(start..start + param_count as usize).ok_or(offset)?;
+        let shape = shape(header[0], params).ok_or(offset)?;
+
+        let mass = match shape {
+            Shapes::Segment(_) => 0.0,
+            _ => header[1],
+        };
+        RigidBody::try_new(mass).map_err(|_| offset)?;
+        let mut body = RigidBody::with_shape(mass, shape);
+        body.set_position(Vec2::new(header[2], header[3]));
+        body.set_rotation(header[4]);
+        body.set_velocity(Vec2::new(header[5], header[6]));
+        bodies.push(body);
+        offset = start + params.len();
+    }
+    Ok(bodies)
+}
+
+fn shape(kind: f64, params: &[f64]) -> Option<Shapes> {
+    let kind = match kind {
+        k if k == ShapeKind::Circle as u8 as f64 => ShapeKind::Circle,
+        k if k == ShapeKind::Polygon as u8 as f64 => ShapeKind::Polygon,
+        k if k == ShapeKind::Segment as u8 as f64 => ShapeKind::Segment,
+        _ => return None,
+    };
+    match (kind, params) {
+        (ShapeKind::Circle, &[radius]) if radius >= 0.0 => Some(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), radius))),
+        (ShapeKind::Polygon, flat) if flat.len() >= 6 && flat.len().is_multiple_of(2) => {
+            let vertices: Vec<Vec2> = flat.chunks(2).map(|p| Vec2::new(p[0], p[1])).collect();
+            Some(Shapes::Polygon(Polygon::new(&vertices)))
+        }
+        (ShapeKind::Segment, &[ax, ay, bx, by, one_sided]) => {
+            let mut segment = Segment::new(Vec2::new(ax, ay), Vec2::new(bx, by));
+            segment.one_sided = one_sided != 0.0;
+            Some(Shapes::Segment(segment))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bodies() {
+        #[rustfmt::skip]
+        let buffer = [
+            0.0, 2.0, 1.0, 2.0, 0.5, 3.0, 0.0, 1.0, 0.25,
+            1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 6.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+            3.0, 5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 5.0, -5.0, 0.0, 5.0, 0.0, 1.0,
+        ];
+        let bodies = parse_bodies(&buffer).unwrap();
+
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[0].shape_radius(), 0.25);
+        assert_eq!(bodies[0].mass(), 2.0);
+        assert_eq!(bodies[0].position(), Vec2::new(1.0, 2.0));
+        assert_eq!(bodies[0].rotation(), 0.5);
+        assert_eq!(bodies[0].velocity(), Vec2::new(3.0, 0.0));
+        // The triangle is centered on the body at the given position
+        let third = 1.0 / 3.0;
+        let expected = [-third, -third, 1.0 - third, -third, -third, 1.0 - third];
+        assert!(bodies[1].shape_vertices().iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-12));
+        assert_eq!(bodies[1].position(), Vec2::new(-1.0, 0.0));
+        assert_eq!(bodies[2].mass(), 0.0);
+        match bodies[2].shape() {
+            Shapes::Segment(s) => assert!(s.one_sided),
+            other => panic!("expected a segment, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let circle = [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.5];
+        assert_eq!(parse_bodies(&[]).unwrap().len(), 0);
+        // Truncated header and params
+        assert_eq!(parse_bodies(&circle[..5]).err(), Some(0));
+        assert_eq!(parse_bodies(&circle[..8]).err(), Some(0));
+        // Errors give the offset of the bad descriptor
+        let mut two = circle.to_vec();
+        two.extend_from_slice(&[2.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(parse_bodies(&two).err(), Some(9));
+        let mut polygon = circle.to_vec();
+        polygon[0] = 1.0;
+        assert_eq!(parse_bodies(&polygon).err(), Some(0));
+        let mut heavy = circle.to_vec();
+        heavy[1] = -1.0;
+        assert_eq!(parse_bodies(&heavy).err(), Some(0));
+    }
+}
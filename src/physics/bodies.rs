@@ -0,0 +1,254 @@
+//! Generational storage for world bodies.
+//!
+//! Bodies are kept densely packed so the solver can work on a plain slice. Removing a body
+//! moves the last body into its place, and handles go through a slot table that tracks where
+//! each body currently lives. Each slot counts how often it has been reused, so a handle to a
+//! removed body stays invalid even after its slot is given to a new body.
+
+use super::body::RigidBody;
+use super::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Slot {
+    generation: u32,
+    /// Position of the body in the dense array, None while the slot is free
+    index: Option<usize>,
+}
+
+/// Bodies addressed by generational handles
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BodySet {
+    bodies: Vec<RigidBody>,
+    /// Handle of each body in the dense array
+    handles: Vec<BodyHandle>,
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+impl BodySet {
+    pub fn new() -> BodySet {
+        BodySet::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    pub fn insert(&mut self, body: RigidBody) -> BodyHandle {
+        let index = self.bodies.len();
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    index: None,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.slots[slot].index = Some(index);
+
+        let handle = BodyHandle::new(slot, self.slots[slot].generation);
+        self.bodies.push(body);
+        self.handles.push(handle);
+        handle
+    }
+
+    /// Remove a body, returning it with the dense index it was stored at.
+    /// The last body is moved into that index.
+    pub fn remove(&mut self, handle: BodyHandle) -> Option<(usize, RigidBody)> {
+        let index = self.index_of(handle)?;
+        let slot = &mut self.slots[handle.index()];
+        slot.index = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index());
+
+        let body = self.bodies.swap_remove(index);
+        self.handles.swap_remove(index);
+        if let Some(moved) = self.handles.get(index) {
+            self.slots[moved.index()].index = Some(index);
+        }
+        Some((index, body))
+    }
+
+    pub fn contains(&self, handle: BodyHandle) -> bool {
+        self.index_of(handle).is_some()
+    }
+
+    /// Dense index of a body, None if the handle is stale
+    pub fn index_of(&self, handle: BodyHandle) -> Option<usize> {
+        self.slots
+            .get(handle.index())
+            .filter(|slot| slot.generation == handle.generation())
+            .and_then(|slot| slot.index)
+    }
+
+    /// Handle of the body at a dense index
+    pub fn handle(&self, index: usize) -> BodyHandle {
+        self.handles[index]
+    }
+
+    pub fn handles(&self) -> &[BodyHandle] {
+        &self.handles
+    }
+
+    pub fn get(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        self.index_of(handle).map(|i| &self.bodies[i])
+    }
+
+    pub fn get_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        self.index_of(handle).map(move |i| &mut self.bodies[i])
+    }
+
+    pub fn as_slice(&self) -> &[RigidBody] {
+        &self.bodies
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [RigidBody] {
+        &mut self.bodies
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.handles.iter().copied().zip(&self.bodies)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (BodyHandle, &mut RigidBody)> {
+        self.handles.iter().copied().zip(&mut self.bodies)
+    }
+}
+
+impl Binary for BodySet {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.bodies);
+        w.put(&self.handles);
+        w.put(&self.slots.len());
+        for slot in &self.slots {
+            w.put(&slot.generation);
+            w.put(&slot.index);
+        }
+        w.put(&self.free);
+    }
+
+    fn decode(r: &mut Reader) -> Result<BodySet, SerializeError> {
+        let bodies: Vec<RigidBody> = r.get()?;
+        let handles: Vec<BodyHandle> = r.get()?;
+        let slots = r
+            .get::<Vec<(u32, Option<usize>)>>()?
+            .into_iter()
+            .map(|(generation, index)| Slot { generation, index })
+            .collect();
+        let free = r.get()?;
+        let set = BodySet {
+            bodies,
+            handles,
+            slots,
+            free,
+        };
+        set.validate()?;
+        Ok(set)
+    }
+}
+
+impl BodySet {
+    /// Check that handles, slots and free slots all agree, so a decoded set can't index out of
+    /// bounds later
+    fn validate(&self) -> Result<(), SerializeError> {
+        let invalid = |what: &str| Err(SerializeError::InvalidValue(what.to_string()));
+        if self.handles.len() != self.bodies.len() {
+            return invalid("body and handle counts differ");
+        }
+        for (i, handle) in self.handles.iter().enumerate() {
+            match self.slots.get(handle.index()) {
+                Some(slot) if slot.index == Some(i) && slot.generation == handle.generation() => {}
+                _ => return invalid("body handle doesn't match its slot"),
+            }
+        }
+        let occupied = self.slots.iter().filter(|slot| slot.index.is_some()).count();
+        if occupied != self.bodies.len() {
+            return invalid("slot points at a missing body");
+        }
+        let mut listed = vec![false; self.slots.len()];
+        for &slot in &self.free {
+            match self.slots.get(slot) {
+                Some(s) if s.index.is_none() && !listed[slot] => listed[slot] = true,
+                _ => return invalid("free slot is out of range, in use or listed twice"),
+            }
+        }
+        if self.free.len() + occupied != self.slots.len() {
+            return invalid("free slot missing from the free list");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = BodySet::new();
+        let a = set.insert(RigidBody::new(1.0));
+        let b = set.insert(RigidBody::new(2.0));
+        let c = set.insert(RigidBody::new(3.0));
+
+        let (index, removed) = set.remove(a).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(removed.mass(), 1.0);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.index_of(c), Some(0));
+        assert_eq!(set.get(c).unwrap().mass(), 3.0);
+        assert_eq!(set.get(b).unwrap().mass(), 2.0);
+        assert!(set.remove(a).is_none());
+    }
+
+    #[test]
+    fn test_decode_validates_slots() {
+        let mut set = BodySet::new();
+        let a = set.insert(RigidBody::new(1.0));
+        set.insert(RigidBody::new(2.0));
+        set.insert(RigidBody::new(3.0));
+        set.remove(a);
+        let decode = |set: &BodySet| {
+            let mut w = Writer::new();
+            w.put(set);
+            Reader::new(&w.finish()).get::<BodySet>()
+        };
+        assert_eq!(decode(&set), Ok(set.clone()));
+
+        let mut broken = set.clone();
+        broken.slots[1].index = Some(2);
+        assert!(decode(&broken).is_err());
+        let mut broken = set.clone();
+        broken.slots[2].generation += 1;
+        assert!(decode(&broken).is_err());
+        let mut broken = set.clone();
+        broken.free = vec![2];
+        assert!(decode(&broken).is_err());
+        let mut broken = set.clone();
+        broken.free = vec![0, 0];
+        assert!(decode(&broken).is_err());
+        let mut broken = set.clone();
+        broken.free = vec![7];
+        assert!(decode(&broken).is_err());
+    }
+
+    #[test]
+    fn test_stale_handle() {
+        let mut set = BodySet::new();
+        let a = set.insert(RigidBody::new(1.0));
+        set.remove(a);
+        let b = set.insert(RigidBody::new(2.0));
+
+        assert_eq!(a.index(), b.index());
+        assert!(set.get(a).is_none());
+        assert!(!set.contains(a));
+        assert_eq!(set.get(b).unwrap().mass(), 2.0);
+    }
+}
@@ -0,0 +1,672 @@
+use wasm_bindgen::prelude::*;
+
+use super::fixture::{CollisionFilter, Fixture};
+use super::material::Material;
+use super::world::WorldError;
+use crate::geometry::*;
+use crate::math::{Transform2D, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RigidBody {
+    mass: f64,
+    material: Material,
+    inertia: f64,
+    position: Vec2,
+    rotation: f64,
+    velocity: Vec2,
+    angular_velocity: f64,
+    acceleration: Vec2,
+    force: Vec2,
+    torque: f64,
+    shape: Shapes,
+    filter: CollisionFilter,
+    /// Material and filter of each compound child for bodies built from fixtures, else empty
+    fixtures: Vec<(Material, CollisionFilter)>,
+    user_data: u64,
+}
+
+#[wasm_bindgen]
+impl RigidBody {
+    /// A body with a massless point shape. A mass of 0 makes a static body that forces, impulses
+    /// and contacts never move. Panics on a negative or non-finite mass, see try_new.
+    pub fn new(mass: f64) -> RigidBody {
+        RigidBody::try_new(mass).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// A body with a circle of radius centered on its position
+    pub fn with_circle(mass: f64, radius: f64) -> RigidBody {
+        RigidBody::with_shape(mass, Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), radius)))
+    }
+
+    /// A body with a convex polygon given as a flat [x0, y0, x1, y1, ...] list in body coordinates
+    pub fn with_polygon(mass: f64, vertices: &[f64]) -> RigidBody {
+        RigidBody::with_shape(mass, Shapes::Polygon(Polygon::try_from_flat(vertices).unwrap_or_else(|e| panic!("{}", e))))
+    }
+
+    /// A static edge between two points in body coordinates
+    pub fn with_segment(a: Vec2, b: Vec2, one_sided: bool) -> RigidBody {
+        let mut segment = Segment::new(a, b);
+        segment.one_sided = one_sided;
+        RigidBody::with_shape(0.0, Shapes::Segment(segment))
+    }
+
+    pub fn shape_kind(&self) -> ShapeKind {
+        self.shape.kind()
+    }
+
+    /// Shape vertices as a flat [x0, y0, x1, y1, ...] list in body coordinates.
+    /// Polygons give their vertices and segments their end points. Empty for other shapes.
+    pub fn shape_vertices(&self) -> Vec<f64> {
+        let points: &[Vec2] = match &self.shape {
+            Shapes::Polygon(p) => p.vertices(),
+            Shapes::Segment(s) => return vec![s.a().x, s.a().y, s.b().x, s.b().y],
+            _ => &[],
+        };
+        points.iter().flat_map(|p| vec![p.x, p.y]).collect()
+    }
+
+    /// Radius of a circle shape, 0 for other shapes
+    pub fn shape_radius(&self) -> f64 {
+        match &self.shape {
+            Shapes::Circle(c) => c.radius(),
+            _ => 0.0,
+        }
+    }
+
+    /// Centroid of the shape in body coordinates
+    pub fn shape_center(&self) -> Vec2 {
+        self.shape.center()
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    /// True for bodies with zero mass, which only move when their position or velocity is set
+    pub fn is_static(&self) -> bool {
+        self.mass == 0.0
+    }
+
+    /// 1 / mass, or 0 for bodies that can't be moved
+    pub fn inv_mass(&self) -> f64 {
+        if self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    pub fn density(&self) -> f64 {
+        self.material.density
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    /// Change the surface properties. Mass is kept, see with_material to get it from density.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn inertia(&self) -> f64 {
+        self.inertia
+    }
+
+    pub fn set_inertia(&mut self, inertia: f64) {
+        self.inertia = inertia;
+    }
+
+    /// 1 / inertia, or 0 for bodies that can't be rotated. Static bodies can't be, whatever
+    /// their inertia.
+    pub fn inv_inertia(&self) -> f64 {
+        if self.inertia > 0.0 && !self.is_static() {
+            1.0 / self.inertia
+        } else {
+            0.0
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.position = position;
+    }
+
+    /// Rotation in radians
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.rotation = rotation;
+    }
+
+    /// Maps the body's local coordinates, where its shape is defined, to world space
+    pub fn transform(&self) -> Transform2D {
+        Transform2D::new(self.position, self.rotation)
+    }
+
+    /// A point given in body coordinates, in world coordinates
+    pub fn get_world_point(&self, local: &Vec2) -> Vec2 {
+        self.transform().apply(local)
+    }
+
+    /// A world point in body coordinates
+    pub fn get_local_point(&self, world: &Vec2) -> Vec2 {
+        self.transform().inverse_apply(world)
+    }
+
+    /// A direction given in body coordinates rotated into world coordinates
+    pub fn get_world_vector(&self, local: &Vec2) -> Vec2 {
+        self.transform().apply_vector(local)
+    }
+
+    /// A world direction rotated into body coordinates
+    pub fn get_local_vector(&self, world: &Vec2) -> Vec2 {
+        self.transform().inverse_apply_vector(world)
+    }
+
+    /// Velocity of the material point of the body currently at a world point
+    pub fn velocity_at_point(&self, world_point: &Vec2) -> Vec2 {
+        self.velocity + Vec2::cross_scalar(self.angular_velocity, &(world_point - self.position))
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec2) {
+        self.velocity = velocity;
+    }
+
+    pub fn angular_velocity(&self) -> f64 {
+        self.angular_velocity
+    }
+
+    pub fn set_angular_velocity(&mut self, angular_velocity: f64) {
+        self.angular_velocity = angular_velocity;
+    }
+
+    /// Acceleration applied during the last integration
+    pub fn acceleration(&self) -> Vec2 {
+        self.acceleration
+    }
+
+    /// Force accumulated for the next step
+    pub fn force(&self) -> Vec2 {
+        self.force
+    }
+
+    /// Torque accumulated for the next step
+    pub fn torque(&self) -> f64 {
+        self.torque
+    }
+
+    /// Add a force that acts on the center of mass for the next step
+    pub fn apply_force(&mut self, force: &Vec2) {
+        self.force = self.force + force;
+    }
+
+    /// Add a torque that acts for the next step
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.torque += torque;
+    }
+
+    /// Instantly change the velocity by impulse / mass
+    pub fn apply_impulse(&mut self, impulse: &Vec2) {
+        self.velocity = self.velocity + impulse * self.inv_mass();
+    }
+
+    /// Instantly change the angular velocity by impulse / inertia
+    pub fn apply_angular_impulse(&mut self, impulse: f64) {
+        self.angular_velocity += impulse * self.inv_inertia();
+    }
+
+    /// Reset the accumulated force and torque
+    pub fn clear_forces(&mut self) {
+        self.force = Vec2::new(0.0, 0.0);
+        self.torque = 0.0;
+    }
+
+    /// Filter of the whole body, used by every shape not built with its own fixture filter
+    pub fn filter(&self) -> CollisionFilter {
+        self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: CollisionFilter) {
+        self.filter = filter;
+    }
+
+    /// Number of fixtures the body was built from, 0 for a single shape body
+    pub fn fixture_count(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    /// Value the game stores on the body to find it again, e.g. an entity id or index. 0 by default.
+    pub fn user_data(&self) -> u64 {
+        self.user_data
+    }
+
+    pub fn set_user_data(&mut self, user_data: u64) {
+        self.user_data = user_data;
+    }
+}
+
+impl RigidBody {
+    /// Like new, but returns an error instead of panicking on a negative or non-finite mass
+    pub fn try_new(mass: f64) -> Result<RigidBody, WorldError> {
+        if !(mass >= 0.0 && mass.is_finite()) {
+            return Err(WorldError::InvalidMass(mass));
+        }
+        Ok(RigidBody {
+            mass,
+            material: Material::new(),
+            inertia: 0.0,
+            position: Vec2::new(0.0, 0.0),
+            rotation: 0.0,
+            velocity: Vec2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+            acceleration: Vec2::new(0.0, 0.0),
+            force: Vec2::new(0.0, 0.0),
+            torque: 0.0,
+            shape: Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.0)),
+            filter: CollisionFilter::new(),
+            fixtures: Vec::new(),
+            user_data: 0,
+        })
+    }
+
+    /// A body with the given shape and the inertia of that shape for mass.
+    /// Bodies turn about their position, so a dynamic body is placed at the shape's center of
+    /// mass with the shape moved to sit around the body origin, leaving it where it was in world
+    /// coordinates. Static bodies keep the shape as given.
+    pub fn with_shape(mass: f64, shape: Shapes) -> RigidBody {
+        let mut body = RigidBody::new(mass);
+        body.inertia = shape.inertia(mass);
+        body.shape = shape;
+        if !body.is_static() {
+            body.center_on(body.shape.center());
+        }
+        body
+    }
+
+    /// A body with the given shape and material, weighing the material density times the shape area.
+    /// A density of 0 makes a static body.
+    pub fn with_material(shape: Shapes, material: Material) -> RigidBody {
+        let mut body = RigidBody::with_shape(material.density * shape.area().abs(), shape);
+        body.material = material;
+        body
+    }
+
+    /// A body owning several shapes, each weighed by its own material density. The mass is the
+    /// sum over the fixtures and the inertia is taken about their combined center of mass, which
+    /// a dynamic body is placed at as in with_shape. The body material is the first fixture's.
+    /// Panics on an empty list.
+    pub fn with_fixtures(fixtures: &[Fixture]) -> RigidBody {
+        assert!(!fixtures.is_empty(), "A body needs at least one fixture");
+        let mut compound = Compound::new();
+        for f in fixtures {
+            compound.add_child(f.transform, f.shape.clone());
+        }
+        let densities: Vec<f64> = fixtures.iter().map(|f| f.material.density).collect();
+        let breakdown = compound.mass_breakdown_with(&densities);
+        let mut body = RigidBody::new(breakdown.mass);
+        body.inertia = breakdown.inertia;
+        body.shape = Shapes::Compound(compound);
+        body.material = fixtures[0].material;
+        body.fixtures = fixtures.iter().map(|f| (f.material, f.filter)).collect();
+        if !body.is_static() {
+            body.center_on(breakdown.center);
+        }
+        body
+    }
+
+    /// Move the shape so center, in body coordinates, is at the body origin, and the body to
+    /// where center was so the shape stays put
+    fn center_on(&mut self, center: Vec2) {
+        self.position = self.transform().apply(&center);
+        self.shape = self.shape.translated(&-center);
+    }
+
+    /// Make the body static where it is, clearing its velocity
+    pub(crate) fn freeze(&mut self) {
+        self.mass = 0.0;
+        self.velocity = Vec2::new(0.0, 0.0);
+        self.angular_velocity = 0.0;
+    }
+
+    pub fn shape(&self) -> &Shapes {
+        &self.shape
+    }
+
+    /// Replace the shape, dropping any fixture materials and filters
+    pub fn set_shape(&mut self, shape: Shapes) {
+        self.shape = shape;
+        self.fixtures.clear();
+    }
+
+    /// Material of the compound child a contact touched, or the body material
+    pub fn fixture_material(&self, child: usize) -> Material {
+        self.fixtures.get(child).map_or(self.material, |f| f.0)
+    }
+
+    /// Filter of the compound child a contact touched, or the body filter
+    pub fn fixture_filter(&self, child: usize) -> CollisionFilter {
+        self.fixtures.get(child).map_or(self.filter, |f| f.1)
+    }
+
+    /// Saved state. Forces are left out since they are cleared every step.
+    pub(crate) fn to_value(&self) -> Value {
+        let mut v = Value::object();
+        v.insert("mass", self.mass.into());
+        v.insert("material", self.material.to_value());
+        v.insert("inertia", self.inertia.into());
+        v.insert("position", serialize::vec2_to_value(&self.position));
+        v.insert("rotation", self.rotation.into());
+        v.insert("velocity", serialize::vec2_to_value(&self.velocity));
+        v.insert("angular_velocity", self.angular_velocity.into());
+        v.insert("acceleration", serialize::vec2_to_value(&self.acceleration));
+        v.insert("shape", self.shape.to_value());
+        v.insert("filter", self.filter.to_value());
+        if !self.fixtures.is_empty() {
+            let fixtures = self
+                .fixtures
+                .iter()
+                .map(|(material, filter)| {
+                    let mut f = Value::object();
+                    f.insert("material", material.to_value());
+                    f.insert("filter", filter.to_value());
+                    f
+                })
+                .collect();
+            v.insert("fixtures", Value::Array(fixtures));
+        }
+        v.insert("user_data", serialize::user_data_to_value(self.user_data));
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<RigidBody, SerializeError> {
+        let mass = v.field_f64("mass")?;
+        let mut body = RigidBody::try_new(mass).map_err(|e| SerializeError::InvalidValue(e.to_string()))?;
+        // Bodies saved before materials only had a density
+        if v.get("material").is_some() {
+            body.material = Material::from_value(v.field("material")?)?;
+        } else {
+            body.material.density = v.field_f64("density")?;
+        }
+        body.inertia = v.field_f64("inertia")?;
+        body.position = serialize::vec2_from_value(v.field("position")?)?;
+        body.rotation = v.field_f64("rotation")?;
+        body.velocity = serialize::vec2_from_value(v.field("velocity")?)?;
+        body.angular_velocity = v.field_f64("angular_velocity")?;
+        body.acceleration = serialize::vec2_from_value(v.field("acceleration")?)?;
+        body.shape = Shapes::from_value(v.field("shape")?)?;
+        if let Some(filter) = v.get("filter") {
+            body.filter = CollisionFilter::from_value(filter)?;
+        }
+        if v.get("fixtures").is_some() {
+            for f in v.field_array("fixtures")? {
+                let material = Material::from_value(f.field("material")?)?;
+                body.fixtures.push((material, CollisionFilter::from_value(f.field("filter")?)?));
+            }
+        }
+        body.user_data = serialize::user_data_from_value(v)?;
+        Ok(body)
+    }
+
+    /// Semi-implicit euler integration over dt with the given extra force and torque
+    pub(crate) fn integrate(&mut self, gravity: &Vec2, force: &Vec2, torque: f64, dt: f64) {
+        self.integrate_velocity(gravity, force, torque, dt);
+        self.integrate_position(dt);
+    }
+
+    /// Velocity half of integrate, so constraints can be solved before the body moves
+    pub(crate) fn integrate_velocity(&mut self, gravity: &Vec2, force: &Vec2, torque: f64, dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        self.acceleration = gravity + (self.force + force) * self.inv_mass();
+        self.velocity = self.velocity + self.acceleration * dt;
+        self.angular_velocity += (self.torque + torque) * self.inv_inertia() * dt;
+    }
+
+    /// Move by the current velocity over dt
+    pub(crate) fn integrate_position(&mut self, dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        self.position = self.position + self.velocity * dt;
+        self.rotation += self.angular_velocity * dt;
+    }
+
+    /// Linear and angular acceleration from gravity, the accumulated forces and an extra force
+    /// and torque. Static bodies don't accelerate.
+    pub(crate) fn accelerations(&self, gravity: &Vec2, force: &Vec2, torque: f64) -> (Vec2, f64) {
+        if self.is_static() {
+            return (Vec2::new(0.0, 0.0), 0.0);
+        }
+        (gravity + (self.force + force) * self.inv_mass(), (self.torque + torque) * self.inv_inertia())
+    }
+
+    pub(crate) fn set_acceleration(&mut self, acceleration: Vec2) {
+        self.acceleration = acceleration;
+    }
+
+    /// First half of a velocity Verlet step: move by the velocity and acceleration over dt and
+    /// add half the acceleration to the velocity
+    pub(crate) fn verlet_drift(&mut self, gravity: &Vec2, force: &Vec2, torque: f64, dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        self.acceleration = gravity + (self.force + force) * self.inv_mass();
+        let angular_acceleration = (self.torque + torque) * self.inv_inertia();
+
+        self.position = self.position + self.velocity * dt + self.acceleration * (0.5 * dt * dt);
+        self.rotation += self.angular_velocity * dt + angular_acceleration * (0.5 * dt * dt);
+        self.velocity = self.velocity + self.acceleration * (0.5 * dt);
+        self.angular_velocity += angular_acceleration * 0.5 * dt;
+    }
+
+    /// Second half of a velocity Verlet step: add half the acceleration of the forces at the new
+    /// position to the velocity
+    pub(crate) fn verlet_kick(&mut self, gravity: &Vec2, force: &Vec2, torque: f64, dt: f64) {
+        if self.is_static() {
+            return;
+        }
+        self.acceleration = gravity + (self.force + force) * self.inv_mass();
+        self.velocity = self.velocity + self.acceleration * (0.5 * dt);
+        self.angular_velocity += (self.torque + torque) * self.inv_inertia() * 0.5 * dt;
+    }
+}
+
+impl Binary for RigidBody {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.mass);
+        w.put(&self.material);
+        w.put(&self.inertia);
+        w.put(&self.position);
+        w.put(&self.rotation);
+        w.put(&self.velocity);
+        w.put(&self.angular_velocity);
+        w.put(&self.acceleration);
+        w.put(&self.force);
+        w.put(&self.torque);
+        w.put(&self.shape);
+        w.put(&self.filter);
+        w.put(&self.fixtures);
+        w.put(&self.user_data);
+    }
+
+    fn decode(r: &mut Reader) -> Result<RigidBody, SerializeError> {
+        Ok(RigidBody {
+            mass: r.get()?,
+            material: r.get()?,
+            inertia: r.get()?,
+            position: r.get()?,
+            rotation: r.get()?,
+            velocity: r.get()?,
+            angular_velocity: r.get()?,
+            acceleration: r.get()?,
+            force: r.get()?,
+            torque: r.get()?,
+            shape: r.get()?,
+            filter: r.get()?,
+            fixtures: r.get()?,
+            user_data: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_impulse() {
+        let mut body = RigidBody::new(2.0);
+        body.apply_impulse(&Vec2::new(4.0, 0.0));
+
+        assert_eq!(body.velocity(), Vec2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn test_integrate() {
+        let mut body = RigidBody::new(1.0);
+        body.apply_force(&Vec2::new(1.0, 0.0));
+        body.integrate(&Vec2::new(0.0, -1.0), &Vec2::new(0.0, 0.0), 0.0, 1.0);
+
+        assert_eq!(body.velocity(), Vec2::new(1.0, -1.0));
+        assert_eq!(body.position(), Vec2::new(1.0, -1.0));
+    }
+
+    #[test]
+    fn test_with_circle() {
+        let body = RigidBody::with_circle(2.0, 3.0);
+
+        assert_eq!(body.shape_kind(), ShapeKind::Circle);
+        assert_eq!(body.shape_radius(), 3.0);
+        assert_eq!(body.inertia(), 9.0);
+        assert!(body.shape_vertices().is_empty());
+    }
+
+    #[test]
+    fn test_with_polygon() {
+        let vertices = [0.0, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0, 2.0];
+        let body = RigidBody::with_polygon(1.0, &vertices);
+
+        assert_eq!(body.shape_kind(), ShapeKind::Polygon);
+        assert_eq!(body.shape_radius(), 0.0);
+        // Centered on the body, which sits where the square's center was
+        assert_eq!(body.shape_vertices(), vec![-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0]);
+        assert_eq!(body.shape_center(), Vec2::new(0.0, 0.0));
+        assert_eq!(body.position(), Vec2::new(1.0, 1.0));
+
+        // Static bodies keep the shape where it was given
+        let wall = RigidBody::with_polygon(0.0, &vertices);
+        assert_eq!(wall.shape_vertices(), vertices.to_vec());
+        assert_eq!(wall.position(), Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_with_segment() {
+        let body = RigidBody::with_segment(Vec2::new(1.0, 0.0), Vec2::new(-1.0, 0.0), true);
+
+        assert_eq!(body.shape_kind(), ShapeKind::Segment);
+        assert_eq!(body.shape_vertices(), vec![1.0, 0.0, -1.0, 0.0]);
+        assert_eq!(body.inv_mass(), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_mass() {
+        assert_eq!(RigidBody::try_new(-1.0), Err(WorldError::InvalidMass(-1.0)));
+        assert!(RigidBody::try_new(f64::INFINITY).is_err());
+        assert!(RigidBody::try_new(f64::NAN).is_err());
+
+        let mut body = RigidBody::try_new(0.0).unwrap();
+        body.set_inertia(2.0);
+        assert!(body.is_static());
+        assert_eq!(body.inv_inertia(), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_negative_mass_panics() {
+        RigidBody::new(-2.0);
+    }
+
+    #[test]
+    fn test_massless_body_does_not_move() {
+        let mut body = RigidBody::new(0.0);
+        body.apply_impulse(&Vec2::new(1.0, 0.0));
+        body.integrate(&Vec2::new(0.0, -1.0), &Vec2::new(0.0, 0.0), 0.0, 1.0);
+
+        assert_eq!(body.position(), Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_with_fixtures() {
+        let mut heavy = Material::new();
+        heavy.density = 3.0;
+        let mut slick = Material::new();
+        slick.friction = 0.0;
+        // A 2x2 hull of density 3 with a unit turret of density 1 on top
+        let hull = Fixture::new(Shapes::Polygon(Polygon::rect(2.0, 2.0)), heavy);
+        let mut turret = Fixture::new(Shapes::Polygon(Polygon::rect(1.0, 1.0)), slick);
+        turret.transform = Transform2D::new(Vec2::new(0.0, 1.5), 0.0);
+        turret.filter.category = 2;
+        let body = RigidBody::with_fixtures(&[hull, turret]);
+
+        assert_eq!(body.mass(), 13.0);
+        assert_eq!(body.fixture_count(), 2);
+        let center_y = 1.5 / 13.0;
+        let hull_inertia = 12.0 * 8.0 / 12.0 + 12.0 * center_y * center_y;
+        let turret_inertia = 2.0 / 12.0 + (1.5 - center_y) * (1.5 - center_y);
+        assert!((body.inertia() - hull_inertia - turret_inertia).abs() < 1e-9);
+        assert!((body.position() - Vec2::new(0.0, center_y)).len() < 1e-12);
+        match body.shape() {
+            Shapes::Compound(c) => assert!((c.children()[0].0.position + Vec2::new(0.0, center_y)).len() < 1e-12),
+            _ => unreachable!(),
+        }
+        assert_eq!(body.material(), heavy);
+        assert_eq!(body.fixture_material(1), slick);
+        assert_eq!(body.fixture_filter(1).category, 2);
+        assert_eq!(body.fixture_filter(0), CollisionFilter::new());
+
+        let loaded = RigidBody::from_value(&body.to_value()).unwrap();
+        assert_eq!(loaded, body);
+        let mut w = Writer::new();
+        w.put(&body);
+        let bytes = w.finish();
+        assert_eq!(Reader::new(&bytes).get::<RigidBody>(), Ok(body.clone()));
+
+        let mut body = body;
+        body.set_shape(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 1.0)));
+        assert_eq!(body.fixture_count(), 0);
+        assert_eq!(body.fixture_material(1), heavy);
+    }
+
+    #[test]
+    fn test_coordinate_conversion() {
+        let mut body = RigidBody::new(1.0);
+        body.set_position(Vec2::new(1.0, 2.0));
+        body.set_rotation(std::f64::consts::FRAC_PI_2);
+        body.set_angular_velocity(2.0);
+        body.set_velocity(Vec2::new(1.0, 0.0));
+
+        let world = body.get_world_point(&Vec2::new(1.0, 0.0));
+        assert!((world - Vec2::new(1.0, 3.0)).len() < 1e-12);
+        assert!((body.get_local_point(&world) - Vec2::new(1.0, 0.0)).len() < 1e-12);
+        let up = body.get_world_vector(&Vec2::new(1.0, 0.0));
+        assert!((up - Vec2::new(0.0, 1.0)).len() < 1e-12);
+        assert!((body.get_local_vector(&up) - Vec2::new(1.0, 0.0)).len() < 1e-12);
+        // Spinning counterclockwise, a point above the center moves left
+        assert!((body.velocity_at_point(&world) - Vec2::new(-1.0, 0.0)).len() < 1e-12);
+        assert_eq!(body.velocity_at_point(&body.position()), body.velocity());
+    }
+}
@@ -0,0 +1,131 @@
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::Aabb;
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// What happens to a body whose position leaves the world bounds
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundsPolicy {
+    /// Remove the body from the world
+    Remove,
+    /// Make the body static where it is, with its velocity cleared
+    Freeze,
+    /// Move the body to the opposite side, keeping its velocity
+    Wrap,
+    /// Leave the body alone, only reporting that it left
+    Report,
+}
+
+impl BoundsPolicy {
+    fn name(self) -> &'static str {
+        match self {
+            BoundsPolicy::Remove => "remove",
+            BoundsPolicy::Freeze => "freeze",
+            BoundsPolicy::Wrap => "wrap",
+            BoundsPolicy::Report => "report",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<BoundsPolicy, SerializeError> {
+        match name {
+            "remove" => Ok(BoundsPolicy::Remove),
+            "freeze" => Ok(BoundsPolicy::Freeze),
+            "wrap" => Ok(BoundsPolicy::Wrap),
+            "report" => Ok(BoundsPolicy::Report),
+            other => Err(SerializeError::InvalidValue(format!("unknown bounds policy '{}'", other))),
+        }
+    }
+}
+
+/// Region bodies are kept in, see World::set_bounds
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldBounds {
+    pub aabb: Aabb,
+    pub policy: BoundsPolicy,
+}
+
+#[wasm_bindgen]
+impl WorldBounds {
+    #[wasm_bindgen(constructor)]
+    pub fn new(aabb: Aabb, policy: BoundsPolicy) -> WorldBounds {
+        WorldBounds { aabb, policy }
+    }
+
+    /// Point moved into the bounds by whole widths and heights, like a torus
+    pub fn wrap(&self, p: &Vec2) -> Vec2 {
+        let wrap = |v: f64, min: f64, size: f64| if size > 0.0 { min + (v - min).rem_euclid(size) } else { v };
+        Vec2::new(
+            wrap(p.x, self.aabb.min.x, self.aabb.width()),
+            wrap(p.y, self.aabb.min.y, self.aabb.height()),
+        )
+    }
+}
+
+impl WorldBounds {
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("min", serialize::vec2_to_value(&self.aabb.min));
+        v.insert("max", serialize::vec2_to_value(&self.aabb.max));
+        v.insert("policy", self.policy.name().into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<WorldBounds, SerializeError> {
+        Ok(WorldBounds {
+            aabb: Aabb::new(
+                serialize::vec2_from_value(v.field("min")?)?,
+                serialize::vec2_from_value(v.field("max")?)?,
+            ),
+            policy: BoundsPolicy::from_name(v.field_str("policy")?)?,
+        })
+    }
+}
+
+impl Binary for BoundsPolicy {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<BoundsPolicy, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(BoundsPolicy::Remove),
+            1 => Ok(BoundsPolicy::Freeze),
+            2 => Ok(BoundsPolicy::Wrap),
+            3 => Ok(BoundsPolicy::Report),
+            tag => Err(r.invalid_tag("bounds policy", tag)),
+        }
+    }
+}
+
+impl Binary for WorldBounds {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.aabb.min);
+        w.put(&self.aabb.max);
+        w.put(&self.policy);
+    }
+
+    fn decode(r: &mut Reader) -> Result<WorldBounds, SerializeError> {
+        Ok(WorldBounds {
+            aabb: Aabb::new(r.get()?, r.get()?),
+            policy: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let bounds = WorldBounds::new(Aabb::new(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 5.0)), BoundsPolicy::Wrap);
+
+        assert_eq!(bounds.wrap(&Vec2::new(12.0, 2.0)), Vec2::new(-8.0, 2.0));
+        assert_eq!(bounds.wrap(&Vec2::new(-31.0, -1.0)), Vec2::new(9.0, 4.0));
+        assert_eq!(bounds.wrap(&Vec2::new(1.0, 1.0)), Vec2::new(1.0, 1.0));
+    }
+}
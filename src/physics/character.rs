@@ -0,0 +1,302 @@
+//! Kinematic character controller built on shape casts.
+//!
+//! The character is an upright capsule moved by the game rather than by the solver. A move is cast
+//! against the world and, when blocked, the rest of it slides along the surface that was hit.
+//! Surfaces no steeper than the max slope are ground, which the character stands on instead of
+//! sliding down. A move blocked by a wall climbs onto it if the top is within the step height.
+//! Up is against the world gravity.
+
+use wasm_bindgen::prelude::*;
+
+use super::world::World;
+use crate::collision::cast::CastHit;
+use crate::geometry::{Circle, Compound, Polygon, Shapes};
+use crate::math::{trig, Transform2D, Vec2};
+
+/// Surfaces a single move may slide along before it stops
+pub const MAX_SLIDES: usize = 4;
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharacterController {
+    pub position: Vec2,
+    /// Steepest slope in radians the character can stand on
+    pub max_slope: f64,
+    /// Tallest ledge the character climbs onto without jumping
+    pub step_height: f64,
+    /// Gap kept between the capsule and whatever it touches
+    pub skin: f64,
+    radius: f64,
+    height: f64,
+    shape: Shapes,
+    on_ground: bool,
+    ground_normal: Vec2,
+}
+
+#[wasm_bindgen]
+impl CharacterController {
+    /// Capsule of the given radius and total height standing at position
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: Vec2, radius: f64, height: f64) -> CharacterController {
+        assert!(radius > 0.0, "Character radius must be positive");
+        let half = (height / 2.0 - radius).max(0.0);
+        let shape = if half == 0.0 {
+            Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), radius))
+        } else {
+            let mut capsule = Compound::new();
+            let body = Polygon::new(&[
+                Vec2::new(-radius, -half),
+                Vec2::new(radius, -half),
+                Vec2::new(radius, half),
+                Vec2::new(-radius, half),
+            ]);
+            capsule.add_child(Transform2D::identity(), Shapes::Polygon(body));
+            for y in [-half, half] {
+                let cap = Circle::new(Vec2::new(0.0, 0.0), radius);
+                capsule.add_child(Transform2D::new(Vec2::new(0.0, y), 0.0), Shapes::Circle(cap));
+            }
+            Shapes::Compound(capsule)
+        };
+        CharacterController {
+            position,
+            max_slope: 45f64.to_radians(),
+            step_height: 0.25,
+            skin: 0.01,
+            radius,
+            height: height.max(2.0 * radius),
+            shape,
+            on_ground: false,
+            ground_normal: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// True if the character stood on ground after the last move
+    pub fn on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    /// Normal of the ground stood on after the last move, zero in the air
+    pub fn ground_normal(&self) -> Vec2 {
+        self.ground_normal
+    }
+
+    /// Move by motion, sliding along whatever blocks it, returning how far the character moved
+    pub fn move_and_slide(&mut self, world: &World, motion: Vec2) -> Vec2 {
+        let start = self.position;
+        let up = up(world);
+        let mut remaining = motion;
+        for _ in 0..MAX_SLIDES {
+            if remaining.len_sq() == 0.0 {
+                break;
+            }
+            let hit = match self.cast(world, &remaining) {
+                Some(hit) => hit,
+                None => {
+                    self.position = self.position + remaining;
+                    break;
+                }
+            };
+            let mut leftover = remaining - self.move_to_hit(&remaining, &hit);
+            if self.walkable(&hit.normal, &up) {
+                // Standing on ground, so drop the part of the move going down into it
+                let down = Vec2::dot(&leftover, &up).min(0.0);
+                leftover = leftover - up * down;
+            } else if self.try_step(world, &leftover, &up) {
+                break;
+            }
+            remaining = leftover - hit.normal * Vec2::dot(&leftover, &hit.normal);
+        }
+        self.probe_ground(world, &up);
+        self.position - start
+    }
+}
+
+impl CharacterController {
+    /// Capsule shape, upright in its own frame
+    pub fn shape(&self) -> &Shapes {
+        &self.shape
+    }
+
+    fn transform(&self, up: &Vec2) -> Transform2D {
+        Transform2D::new(self.position, up.angle() - std::f64::consts::FRAC_PI_2)
+    }
+
+    fn cast(&self, world: &World, motion: &Vec2) -> Option<CastHit> {
+        let transform = self.transform(&up(world));
+        world.shape_cast(&self.shape, &transform, motion, |_, _| true).map(|(_, hit)| hit)
+    }
+
+    /// Move along motion until skin away from the surface it hit, returning how far it went
+    fn move_to_hit(&mut self, motion: &Vec2, hit: &CastHit) -> Vec2 {
+        let len = motion.len();
+        // Backing off along the motion takes further the more it grazes the surface
+        let facing = -Vec2::dot(motion, &hit.normal) / len;
+        let back_off = if facing > 0.0 { self.skin / facing } else { self.skin };
+        let travel = (hit.fraction * len - back_off).max(0.0);
+        let moved = motion * (travel / len);
+        self.position = self.position + moved;
+        moved
+    }
+
+    fn walkable(&self, normal: &Vec2, up: &Vec2) -> bool {
+        Vec2::dot(normal, up) >= trig::cos(self.max_slope)
+    }
+
+    /// Climb over a ledge blocking the sideways part of motion. Leaves the character where it
+    /// was and returns false if there is no ground to land on within the step height.
+    fn try_step(&mut self, world: &World, motion: &Vec2, up: &Vec2) -> bool {
+        let forward = motion - up * Vec2::dot(motion, up);
+        if self.step_height <= 0.0 || forward.len_sq() == 0.0 {
+            return false;
+        }
+        let saved = self.position;
+        let lift = up * self.step_height;
+        match self.cast(world, &lift) {
+            Some(hit) => {
+                self.move_to_hit(&lift, &hit);
+            }
+            None => self.position = self.position + lift,
+        }
+        let risen = Vec2::dot(&(self.position - saved), up);
+
+        let landed = match self.cast(world, &forward) {
+            Some(_) => None,
+            None => {
+                self.position = self.position + forward;
+                let drop = -up * (risen + self.skin);
+                self.cast(world, &drop).map(|hit| (drop, hit))
+            }
+        };
+        match landed {
+            Some((drop, hit)) if self.walkable(&hit.normal, up) => {
+                self.move_to_hit(&drop, &hit);
+                true
+            }
+            _ => {
+                self.position = saved;
+                false
+            }
+        }
+    }
+
+    fn probe_ground(&mut self, world: &World, up: &Vec2) {
+        let ground = self.cast(world, &(-up * (2.0 * self.skin))).filter(|hit| self.walkable(&hit.normal, up));
+        self.on_ground = ground.is_some();
+        self.ground_normal = ground.map_or(Vec2::new(0.0, 0.0), |hit| hit.normal);
+    }
+}
+
+/// Direction against gravity, +y without gravity
+fn up(world: &World) -> Vec2 {
+    let gravity = world.gravity();
+    if gravity.len_sq() > 0.0 {
+        -gravity.normalize()
+    } else {
+        Vec2::new(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::RigidBody;
+
+    /// Static box with its top left corner at (x, y)
+    fn block(world: &mut World, x: f64, y: f64, w: f64, h: f64) {
+        let mut body = RigidBody::with_polygon(0.0, &[0.0, -h, w, -h, w, 0.0, 0.0, 0.0]);
+        body.set_position(Vec2::new(x, y));
+        world.create_body(body);
+    }
+
+    fn floor() -> World {
+        let mut world = World::new();
+        block(&mut world, -50.0, 0.0, 100.0, 1.0);
+        world.update_broadphase();
+        world
+    }
+
+    #[test]
+    fn test_lands_on_ground() {
+        let world = floor();
+        let mut character = CharacterController::new(Vec2::new(0.0, 3.0), 0.5, 2.0);
+        assert!(!character.on_ground());
+
+        let moved = character.move_and_slide(&world, Vec2::new(0.0, -5.0));
+        assert!((character.position.y - 1.0 - character.skin).abs() < 1e-3);
+        assert!((moved.y + 2.0 - character.skin).abs() < 1e-3);
+        assert!(character.on_ground());
+        assert!((character.ground_normal() - Vec2::new(0.0, 1.0)).len() < 1e-6);
+
+        // Walking along the floor keeps the character on it
+        character.move_and_slide(&world, Vec2::new(1.0, -0.1));
+        assert!((character.position.x - 1.0).abs() < 1e-6);
+        assert!(character.on_ground());
+    }
+
+    #[test]
+    fn test_slides_along_wall() {
+        let mut world = floor();
+        block(&mut world, 2.0, 10.0, 1.0, 10.0);
+        world.update_broadphase();
+        let mut character = CharacterController::new(Vec2::new(0.0, 5.0), 0.5, 1.0);
+
+        character.move_and_slide(&world, Vec2::new(4.0, 2.0));
+        assert!((character.position.x - 1.5 + character.skin).abs() < 1e-6);
+        assert!((character.position.y - 7.0).abs() < 1e-6);
+        assert!(!character.on_ground());
+    }
+
+    #[test]
+    fn test_slopes() {
+        let mut world = floor();
+        // A shallow ramp up to the right and a steep one up to the left
+        let mut shallow = RigidBody::with_polygon(0.0, &[0.0, 0.0, 10.0, 0.0, 10.0, 2.0]);
+        shallow.set_position(Vec2::new(5.0, 0.0));
+        world.create_body(shallow);
+        let mut steep = RigidBody::with_polygon(0.0, &[0.0, 0.0, -2.0, 6.0, -2.0, 0.0]);
+        steep.set_position(Vec2::new(-5.0, 0.0));
+        world.create_body(steep);
+        world.update_broadphase();
+
+        let mut character = CharacterController::new(Vec2::new(8.0, 3.0), 0.5, 1.0);
+        character.move_and_slide(&world, Vec2::new(0.0, -3.0));
+        assert!(character.on_ground());
+        assert!(character.ground_normal().y < 1.0);
+        // Standing still on the ramp doesn't slide down it
+        let x = character.position.x;
+        character.move_and_slide(&world, Vec2::new(0.0, -0.5));
+        assert!((character.position.x - x).abs() < 1e-6);
+
+        let mut character = CharacterController::new(Vec2::new(-5.5, 4.0), 0.5, 1.0);
+        character.move_and_slide(&world, Vec2::new(0.0, -3.0));
+        assert!(!character.on_ground());
+    }
+
+    #[test]
+    fn test_step_up() {
+        let mut world = floor();
+        block(&mut world, 2.0, 0.4, 5.0, 0.4);
+        world.update_broadphase();
+
+        let mut character = CharacterController::new(Vec2::new(0.0, 1.0 + 0.01), 0.5, 2.0);
+        character.step_height = 0.5;
+        character.move_and_slide(&world, Vec2::new(3.0, 0.0));
+        assert!(character.position.x > 2.9);
+        assert!((character.position.y - 1.4 - character.skin).abs() < 1e-3);
+        assert!(character.on_ground());
+
+        let mut character = CharacterController::new(Vec2::new(0.0, 1.0 + 0.01), 0.5, 2.0);
+        character.step_height = 0.1;
+        character.move_and_slide(&world, Vec2::new(3.0, 0.0));
+        // The rounded bottom rides up the corner a little but doesn't get on top
+        assert!(character.position.x < 2.0);
+    }
+}
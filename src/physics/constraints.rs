@@ -0,0 +1,605 @@
+//! Velocity and position correction for contacts and joints.
+//!
+//! Contacts and joints are solved together with the sequential impulse solver, or assembled
+//! into one system for the LCP backend, so a joint chain resting on the ground settles as a
+//! whole. Remaining contact overlap is then removed by moving the bodies apart. Under XPBD
+//! contacts and joints are first solved as position constraints, and the velocity pass only
+//! adds friction, restitution, motors and damping.
+
+use std::ops::Range;
+
+use super::bodies::BodySet;
+use super::body::RigidBody;
+use super::contact::Contact;
+use super::hooks::ContactSettings;
+use super::joint::{Joint, JointConstraint, JointEvent, JointPass};
+use super::material::Material;
+use super::solver::{lcp, ConstraintRow, ContactConstraint, ContactImpulse, LcpRow, PositionCorrection};
+use super::solver::contact::MAX_CONDITION;
+use super::solver::{Integrator, Jacobian, SolverBackend, SolverBody, SolverConfig};
+use crate::math::{IterativeConfig, Mat2, Vec2};
+
+/// Solve contacts and joints over a step of dt, returning joint limit and motor events.
+/// Contacts and joints attached to removed bodies are skipped.
+pub fn solve(
+    set: &mut BodySet,
+    contacts: &mut [Contact],
+    joints: &mut [Joint],
+    config: &SolverConfig,
+    dt: f64,
+) -> Vec<JointEvent> {
+    let settings: Vec<ContactSettings> = contacts
+        .iter()
+        .map(|c| match (set.get(c.body_a), set.get(c.body_b)) {
+            (Some(a), Some(b)) => ContactSettings::of_contact(c, a, b),
+            _ => ContactSettings { enabled: false, ..ContactSettings::mixed(&Material::new(), &Material::new()) },
+        })
+        .collect();
+    solve_with(set, contacts, &settings, joints, config, dt)
+}
+
+/// Like solve with the settings of each contact given, e.g. changed by a pre-solve hook.
+/// Disabled contacts are left out and lose their impulse.
+pub fn solve_with(
+    set: &mut BodySet,
+    contacts: &mut [Contact],
+    settings: &[ContactSettings],
+    joints: &mut [Joint],
+    config: &SolverConfig,
+    dt: f64,
+) -> Vec<JointEvent> {
+    solve_active(set, (contacts, settings), joints, config, dt, false)
+}
+
+/// Like solve_with, but the bodies are moved by their solved velocities before the remaining
+/// overlap is corrected. The contacts must have been found at the current positions, so a
+/// resting body never sinks into what it rests on before its weight is pushed back.
+pub(crate) fn solve_and_integrate(
+    set: &mut BodySet,
+    contacts: &mut [Contact],
+    settings: &[ContactSettings],
+    joints: &mut [Joint],
+    config: &SolverConfig,
+    dt: f64,
+) -> Vec<JointEvent> {
+    solve_active(set, (contacts, settings), joints, config, dt, true)
+}
+
+fn solve_active(
+    set: &mut BodySet,
+    contacts: (&mut [Contact], &[ContactSettings]),
+    joints: &mut [Joint],
+    config: &SolverConfig,
+    dt: f64,
+    integrate: bool,
+) -> Vec<JointEvent> {
+    let (contacts, settings) = contacts;
+    // Contacts of removed bodies are skipped like disabled ones
+    let (enabled, indices): (Vec<usize>, Vec<(usize, usize)>) = (0..contacts.len())
+        .filter(|&i| settings[i].enabled)
+        .filter_map(|i| Some((i, (set.index_of(contacts[i].body_a)?, set.index_of(contacts[i].body_b)?))))
+        .unzip();
+    let mut active: Vec<Contact> = enabled.iter().map(|&i| contacts[i]).collect();
+    let active_settings: Vec<ContactSettings> = enabled.iter().map(|&i| settings[i]).collect();
+    let events = solve_enabled(set, (&mut active, &active_settings, &indices), joints, config, dt, integrate);
+    for c in contacts.iter_mut() {
+        c.impulse = ContactImpulse::default();
+    }
+    for (&i, solved) in enabled.iter().zip(&active) {
+        contacts[i].impulse = solved.impulse;
+    }
+    events
+}
+
+fn solve_enabled(
+    set: &mut BodySet,
+    contacts: (&mut [Contact], &[ContactSettings], &[(usize, usize)]),
+    joints: &mut [Joint],
+    config: &SolverConfig,
+    dt: f64,
+    integrate: bool,
+) -> Vec<JointEvent> {
+    let (contacts, settings, contact_indices) = contacts;
+    let joint_indices: Vec<(usize, usize, usize)> = joints
+        .iter()
+        .enumerate()
+        .filter_map(|(j, joint)| Some((j, set.index_of(joint.body_a())?, set.index_of(joint.body_b())?)))
+        .collect();
+    let bodies = set.as_mut_slice();
+    // Where the contacts were found, which position correction measures separation from
+    let start: Vec<(Vec2, f64)> = bodies.iter().map(|b| (b.position(), b.rotation())).collect();
+    let mut solver_bodies: Vec<SolverBody> = bodies.iter().map(SolverBody::from_body).collect();
+
+    let mut contact_constraints: Vec<ContactConstraint> = contacts
+        .iter()
+        .zip(contact_indices)
+        .zip(settings)
+        .map(|((c, &(ia, ib)), s)| {
+            let constraint = ContactConstraint::new(
+                &solver_bodies[ia],
+                &solver_bodies[ib],
+                c.point - bodies[ia].position(),
+                c.point - bodies[ib].position(),
+                c.normal,
+                if config.friction { s.friction } else { 0.0 },
+                s.restitution,
+            )
+            .with_surface_velocity(s.surface_velocity);
+            if config.position_correction == PositionCorrection::Baumgarte && config.integrator != Integrator::Xpbd {
+                constraint.with_bias((c.depth - config.slop).max(0.0) * config.baumgarte / dt)
+            } else {
+                constraint
+            }
+        })
+        .collect();
+
+    let xpbd = config.integrator == Integrator::Xpbd;
+    let positions = if xpbd {
+        // The contact constraints were built from the predicted velocities, keeping the speed of
+        // approach for restitution. The velocities left after the position pass include its push.
+        let positions = solve_positions(
+            bodies,
+            (&*contacts, contact_indices, &contact_constraints),
+            (joints, &joint_indices),
+            config,
+            dt,
+        );
+        for ((body, solver_body), &(position, rotation)) in bodies.iter().zip(&mut solver_bodies).zip(&start) {
+            solver_body.velocity = solver_body.velocity + (body.position() - position) / dt;
+            solver_body.angular_velocity += (body.rotation() - rotation) / dt;
+        }
+        Some(positions)
+    } else {
+        None
+    };
+
+    let pass = if xpbd { JointPass::XpbdVelocity } else { JointPass::Velocity };
+    let mut joint_constraints: Vec<JointConstraint> = joint_indices
+        .iter()
+        .map(|&(j, ia, ib)| {
+            joints[j].prepare(
+                (&bodies[ia], &solver_bodies[ia]),
+                (&bodies[ib], &solver_bodies[ib]),
+                dt,
+                pass,
+            )
+        })
+        .collect();
+
+    if config.backend == SolverBackend::Lcp {
+        solve_lcp(
+            &mut solver_bodies,
+            (&joint_indices, &mut joint_constraints),
+            (contact_indices, &mut contact_constraints),
+        );
+    } else if config.warm_starting && config.integrator != Integrator::Xpbd {
+        for ((c, &(ia, ib)), constraint) in contacts.iter().zip(contact_indices).zip(&mut contact_constraints) {
+            let (a, b) = pair_mut(&mut solver_bodies, ia, ib);
+            constraint.set_impulse(a, b, c.impulse);
+        }
+    }
+
+    let manifolds = manifolds(contacts);
+    let iterations = if config.backend == SolverBackend::Lcp { 0 } else { config.velocity_iterations };
+    for _ in 0..iterations {
+        solve_joints(&mut solver_bodies, &joint_indices, &mut joint_constraints);
+        solve_contacts(&mut solver_bodies, (contact_indices, &manifolds), &mut contact_constraints);
+    }
+
+    let mut events = Vec::new();
+    for (&(j, ia, ib), constraint) in joint_indices.iter().zip(&joint_constraints) {
+        let kinds = joints[j].finish(
+            constraint,
+            (&bodies[ia], &solver_bodies[ia]),
+            (&bodies[ib], &solver_bodies[ib]),
+            dt,
+        );
+        events.extend(kinds.into_iter().map(|kind| JointEvent { kind, joint: j }));
+    }
+
+    for (body, solved) in bodies.iter_mut().zip(&solver_bodies) {
+        if !body.is_static() {
+            body.set_velocity(solved.velocity);
+            body.set_angular_velocity(solved.angular_velocity);
+            if integrate {
+                body.integrate_position(dt);
+            }
+        }
+    }
+
+    for (c, constraint) in contacts.iter_mut().zip(&contact_constraints) {
+        c.impulse = constraint.impulse();
+    }
+
+    if let Some((joint_positions, contact_positions)) = positions {
+        for (&(j, _, _), constraint) in joint_indices.iter().zip(&joint_positions) {
+            joints[j].add_position_reaction(constraint, dt);
+        }
+        for (c, constraint) in contacts.iter_mut().zip(&contact_positions) {
+            c.impulse.normal += constraint.impulse().normal / dt;
+        }
+        return events;
+    }
+    let contacts = (&*contacts, contact_indices, contact_constraints.as_slice());
+    match config.position_correction {
+        PositionCorrection::Baumgarte => {}
+        PositionCorrection::NonlinearGaussSeidel => correct_positions(bodies, contacts, &start, config),
+        PositionCorrection::SplitImpulse => split_impulses(bodies, contacts, config, dt),
+    }
+    events
+}
+
+/// One pass over the joints
+fn solve_joints(bodies: &mut [SolverBody], indices: &[(usize, usize, usize)], constraints: &mut [JointConstraint]) {
+    for (&(_, ia, ib), constraint) in indices.iter().zip(constraints) {
+        let (a, b) = pair_mut(bodies, ia, ib);
+        if let Some(point) = &mut constraint.point {
+            point.solve(a, b);
+        }
+        for row in &mut constraint.rows {
+            row.solve(a, b);
+        }
+    }
+}
+
+/// One pass over the contacts, the two points of a manifold solved together
+fn solve_contacts(
+    bodies: &mut [SolverBody],
+    indices: (&[(usize, usize)], &[Range<usize>]),
+    constraints: &mut [ContactConstraint],
+) {
+    let (indices, manifolds) = indices;
+    for group in manifolds {
+        let (ia, ib) = indices[group.start];
+        let (a, b) = pair_mut(bodies, ia, ib);
+        match &mut constraints[group.clone()] {
+            [first, second] => ContactConstraint::solve_pair(first, second, a, b),
+            [single] => single.solve(a, b),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The XPBD position pass: move the bodies until the contacts and joints hold at the predicted
+/// positions. Rows are solved for how far each body moves, with the position error as bias, so
+/// their accumulated impulses are the Lagrange multipliers and compliance softens them as
+/// alpha / dt^2. Returns the joint and contact constraints with their multipliers.
+fn solve_positions(
+    bodies: &mut [RigidBody],
+    contacts: (&[Contact], &[(usize, usize)], &[ContactConstraint]),
+    joints: (&[Joint], &[(usize, usize, usize)]),
+    config: &SolverConfig,
+    dt: f64,
+) -> (Vec<JointConstraint>, Vec<ContactConstraint>) {
+    let (contacts, contact_indices, constraints) = contacts;
+    let (joints, joint_indices) = joints;
+    // Velocities stand for how far the bodies have moved in the pass
+    let mut moved: Vec<SolverBody> = bodies
+        .iter()
+        .map(|b| SolverBody::from_body(b).with_velocity(Vec2::new(0.0, 0.0), 0.0))
+        .collect();
+    // A contact's error is its overlap past the slop, pushed out without friction or bounce
+    let mut contact_rows: Vec<ContactConstraint> = contacts
+        .iter()
+        .zip(contact_indices)
+        .zip(constraints)
+        .map(|((c, &(ia, ib)), constraint)| {
+            ContactConstraint::new(&moved[ia], &moved[ib], constraint.ra, constraint.rb, c.normal, 0.0, 0.0)
+                .with_bias(c.depth - config.slop)
+        })
+        .collect();
+    let pass = JointPass::Position {
+        compliance: config.compliance,
+    };
+    let mut joint_rows: Vec<JointConstraint> = joint_indices
+        .iter()
+        .map(|&(j, ia, ib)| joints[j].prepare((&bodies[ia], &moved[ia]), (&bodies[ib], &moved[ib]), dt, pass))
+        .collect();
+
+    let manifolds = manifolds(contacts);
+    for _ in 0..config.position_iterations {
+        solve_joints(&mut moved, joint_indices, &mut joint_rows);
+        solve_contacts(&mut moved, (contact_indices, &manifolds), &mut contact_rows);
+    }
+    for (body, m) in bodies.iter_mut().zip(&moved) {
+        if !body.is_static() {
+            body.set_position(body.position() + m.velocity);
+            body.set_rotation(body.rotation() + m.angular_velocity);
+        }
+    }
+    (joint_rows, contact_rows)
+}
+
+/// Contacts to solve together: two points in a row between the same shapes, or single points
+fn manifolds(contacts: &[Contact]) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut i = 0;
+    while i < contacts.len() {
+        let len = if i + 1 < contacts.len() && contacts[i].id() == contacts[i + 1].id() { 2 } else { 1 };
+        groups.push(i..i + len);
+        i += len;
+    }
+    groups
+}
+
+/// Move and turn the bodies apart over the position passes. The overlap left is estimated from
+/// how far the contact points have moved apart since start, where the contacts were found.
+fn correct_positions(
+    bodies: &mut [RigidBody],
+    contacts: (&[Contact], &[(usize, usize)], &[ContactConstraint]),
+    start: &[(Vec2, f64)],
+    config: &SolverConfig,
+) {
+    let (contacts, indices, constraints) = contacts;
+    let manifolds = manifolds(contacts);
+    for _ in 0..config.position_iterations {
+        for group in &manifolds {
+            let (ia, ib) = indices[group.start];
+            let moved = |i: usize, r: &Vec2| {
+                let (position, rotation) = start[i];
+                bodies[i].position() - position + Vec2::cross_scalar(bodies[i].rotation() - rotation, r)
+            };
+            // How far each point still has to be pushed apart along its normal
+            let push: Vec<f64> = contacts[group.clone()]
+                .iter()
+                .zip(&constraints[group.clone()])
+                .map(|(c, constraint)| {
+                    let separated = Vec2::dot(&(moved(ib, &constraint.rb) - moved(ia, &constraint.ra)), &c.normal);
+                    (c.depth - separated - config.slop).max(0.0) * config.baumgarte
+                })
+                .collect();
+            let mut a = SolverBody::from_body(&bodies[ia]).with_velocity(Vec2::new(0.0, 0.0), 0.0);
+            let mut b = SolverBody::from_body(&bodies[ib]).with_velocity(Vec2::new(0.0, 0.0), 0.0);
+            let jacobians: Vec<Jacobian> = constraints[group.clone()].iter().map(|c| c.normal_row().jacobian).collect();
+            match (jacobians.as_slice(), push.as_slice()) {
+                // Both corners of a face at once, or a correction lifting one corner tips the
+                // body onto the other and tall stacks walk over
+                (&[j1, j2], &[p1, p2]) => {
+                    let (k11, k22, k12) = (j1.inverse_effective_mass(&a, &b), j2.inverse_effective_mass(&a, &b), j1.coupling(&j2, &a, &b));
+                    let both = if k11 * k11 < MAX_CONDITION * (k11 * k22 - k12 * k12) {
+                        Some(Mat2::new(k11, k12, k12, k22).solve(&Vec2::new(p1, p2)))
+                    } else {
+                        None
+                    };
+                    match both {
+                        Some(lambda) if lambda.x >= 0.0 && lambda.y >= 0.0 => {
+                            j1.apply(&mut a, &mut b, lambda.x);
+                            j2.apply(&mut a, &mut b, lambda.y);
+                        }
+                        // Pushing the deeper point alone
+                        _ if p1 * k22 >= p2 * k11 && k11 > 0.0 => j1.apply(&mut a, &mut b, p1 / k11),
+                        _ if k22 > 0.0 => j2.apply(&mut a, &mut b, p2 / k22),
+                        _ => {}
+                    }
+                }
+                (&[j], &[p]) => {
+                    let k = j.inverse_effective_mass(&a, &b);
+                    if k > 0.0 {
+                        j.apply(&mut a, &mut b, p / k);
+                    }
+                }
+                _ => unreachable!(),
+            }
+            for (i, moved) in [(ia, a), (ib, b)] {
+                let body = &mut bodies[i];
+                body.set_position(body.position() + moved.velocity);
+                body.set_rotation(body.rotation() + moved.angular_velocity);
+            }
+        }
+    }
+}
+
+/// Solve separating velocities for the overlap alone and move the bodies by them, leaving the
+/// real velocities alone
+fn split_impulses(
+    bodies: &mut [RigidBody],
+    contacts: (&[Contact], &[(usize, usize)], &[ContactConstraint]),
+    config: &SolverConfig,
+    dt: f64,
+) {
+    let (contacts, indices, constraints) = contacts;
+    let mut pseudo: Vec<SolverBody> = bodies
+        .iter()
+        .map(|b| SolverBody::from_body(b).with_velocity(Vec2::new(0.0, 0.0), 0.0))
+        .collect();
+    let mut rows: Vec<ConstraintRow> = contacts
+        .iter()
+        .zip(indices)
+        .zip(constraints)
+        .map(|((c, &(ia, ib)), constraint)| {
+            let bias = -(c.depth - config.slop).max(0.0) * config.baumgarte / dt;
+            ConstraintRow::new(constraint.normal_row().jacobian, &pseudo[ia], &pseudo[ib], bias, 0.0, f64::INFINITY)
+        })
+        .collect();
+    for _ in 0..config.velocity_iterations {
+        for (row, &(ia, ib)) in rows.iter_mut().zip(indices) {
+            let (a, b) = pair_mut(&mut pseudo, ia, ib);
+            row.solve(a, b);
+        }
+    }
+    for (body, p) in bodies.iter_mut().zip(&pseudo) {
+        if !body.is_static() {
+            body.set_position(body.position() + p.velocity * dt);
+            body.set_rotation(body.rotation() + p.angular_velocity * dt);
+        }
+    }
+}
+
+/// Solve joints and contacts as one system per island, storing the impulses in their constraints
+fn solve_lcp(
+    solver_bodies: &mut [SolverBody],
+    joints: (&[(usize, usize, usize)], &mut [JointConstraint]),
+    contacts: (&[(usize, usize)], &mut [ContactConstraint]),
+) {
+    let (joint_indices, joint_constraints) = joints;
+    let (contact_indices, contact_constraints) = contacts;
+    let mut rows: Vec<LcpRow> = Vec::new();
+    for (&(_, ia, ib), constraint) in joint_indices.iter().zip(joint_constraints.iter()) {
+        if let Some(point) = &constraint.point {
+            rows.extend(LcpRow::from_point(ia, ib, point));
+        }
+        rows.extend(constraint.rows.iter().map(|row| LcpRow::from_row(ia, ib, row)));
+    }
+    for (&(ia, ib), constraint) in contact_indices.iter().zip(contact_constraints.iter()) {
+        rows.extend(constraint.lcp_rows(ia, ib, rows.len()));
+    }
+
+    let impulses = lcp::solve(solver_bodies, &rows, &IterativeConfig::new());
+    let mut impulses = impulses.into_iter();
+    let mut next = || impulses.next().unwrap();
+    for constraint in joint_constraints.iter_mut() {
+        if let Some(point) = &mut constraint.point {
+            point.impulse = Vec2::new(next(), next());
+        }
+        for row in &mut constraint.rows {
+            row.impulse = next();
+        }
+    }
+    for constraint in contact_constraints.iter_mut() {
+        let (normal, tangent) = (next(), next());
+        constraint.set_solved_impulse(ContactImpulse { normal, tangent });
+    }
+}
+
+/// Mutable references to two different elements, in the order asked for
+pub(crate) fn pair_mut<T>(items: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
+    assert_ne!(i, j, "Constraint between a body and itself");
+    if i < j {
+        let (left, right) = items.split_at_mut(j);
+        (&mut left[i], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(i);
+        (&mut right[0], &mut left[j])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::joint::RevoluteJoint;
+    use crate::physics::RigidBody;
+
+    /// A ball moving down at 2 and sunk 0.5 into static ground
+    fn ball_on_ground() -> (BodySet, Contact) {
+        let mut ground = RigidBody::new(0.0);
+        ground.set_position(Vec2::new(0.0, -1.0));
+        let mut ball = RigidBody::new(1.0);
+        ball.set_velocity(Vec2::new(0.0, -2.0));
+        let mut bodies = BodySet::new();
+        let ground = bodies.insert(ground);
+        let ball = bodies.insert(ball);
+        let c = Contact {
+            body_a: ground,
+            body_b: ball,
+            child_a: 0,
+            child_b: 0,
+            normal: Vec2::new(0.0, 1.0),
+            depth: 0.5,
+            point: Vec2::new(0.0, 0.0),
+            feature: Default::default(),
+            impulse: ContactImpulse::default(),
+        };
+        (bodies, c)
+    }
+
+    #[test]
+    fn test_solve_contacts() {
+        let (mut bodies, c) = ball_on_ground();
+        let (ground, ball) = (c.body_a, c.body_b);
+
+        solve(&mut bodies, &mut [c], &mut [], &SolverConfig::new(), 1.0 / 60.0);
+        assert_eq!(bodies.get(ball).unwrap().velocity(), Vec2::new(0.0, 0.0));
+        assert!(bodies.get(ball).unwrap().position().y > 0.0);
+        assert_eq!(bodies.get(ground).unwrap().position(), Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn test_stale_contact_skipped() {
+        let (mut bodies, mut c) = ball_on_ground();
+        c.impulse.normal = 1.0;
+        let ball = c.body_b;
+        bodies.remove(c.body_a);
+
+        let mut contacts = [c];
+        solve(&mut bodies, &mut contacts, &mut [], &SolverConfig::new(), 1.0 / 60.0);
+        assert_eq!(bodies.get(ball).unwrap().velocity(), Vec2::new(0.0, -2.0));
+        assert_eq!(contacts[0].impulse.normal, 0.0);
+    }
+
+    #[test]
+    fn test_position_iterations() {
+        let overlap_after = |position_iterations| {
+            let (mut bodies, c) = ball_on_ground();
+            let config = SolverConfig {
+                position_iterations,
+                ..SolverConfig::new()
+            };
+            solve(&mut bodies, &mut [c], &mut [], &config, 1.0 / 60.0);
+            c.depth - bodies.get(c.body_b).unwrap().position().y
+        };
+
+        let once = overlap_after(1);
+        assert!((once - 0.5 * 0.8 - 0.005 * 0.2).abs() < 1e-12);
+        let many = overlap_after(20);
+        assert!(many < once);
+        // Never pushed past the allowed slop
+        assert!(many > 0.005 - 1e-12);
+    }
+
+    #[test]
+    fn test_position_correction_modes() {
+        let correct = |position_correction| {
+            let (mut bodies, c) = ball_on_ground();
+            let config = SolverConfig {
+                position_correction,
+                position_iterations: 1,
+                ..SolverConfig::new()
+            };
+            solve(&mut bodies, &mut [c], &mut [], &config, 1.0 / 60.0);
+            let ball = bodies.get(c.body_b).unwrap();
+            (ball.position().y, ball.velocity().y)
+        };
+
+        // Baumgarte pushes the overlap out as velocity, the others move the ball and leave it at rest
+        let (y, vy) = correct(PositionCorrection::Baumgarte);
+        assert_eq!(y, 0.0);
+        assert!((vy - 0.495 * 0.2 * 60.0).abs() < 1e-9);
+        let (y, vy) = correct(PositionCorrection::NonlinearGaussSeidel);
+        assert!((y - 0.495 * 0.2).abs() < 1e-12 && vy == 0.0);
+        let (y, vy) = correct(PositionCorrection::SplitImpulse);
+        assert!((y - 0.495 * 0.2).abs() < 1e-9 && vy == 0.0);
+    }
+
+    #[test]
+    fn test_lcp_backend() {
+        let (mut bodies, c) = ball_on_ground();
+        let config = SolverConfig {
+            backend: SolverBackend::Lcp,
+            ..SolverConfig::new()
+        };
+        let mut contacts = [c];
+        solve(&mut bodies, &mut contacts, &mut [], &config, 1.0 / 60.0);
+        assert!(bodies.get(c.body_b).unwrap().velocity().len() < 1e-12);
+        assert!((contacts[0].impulse.normal - 2.0).abs() < 1e-12);
+
+        // A pendulum pinned to the ground keeps its anchor still
+        let mut bodies = BodySet::new();
+        let pivot = bodies.insert(RigidBody::new(0.0));
+        let mut bob = RigidBody::new(1.0);
+        bob.set_position(Vec2::new(1.0, 0.0));
+        bob.set_velocity(Vec2::new(1.0, -1.0));
+        let bob = bodies.insert(bob);
+        let joint = RevoluteJoint::new(pivot, bob, Vec2::new(0.0, 0.0), Vec2::new(-1.0, 0.0));
+        solve(&mut bodies, &mut [], &mut [Joint::Revolute(joint)], &config, 1.0 / 60.0);
+        let bob = bodies.get(bob).unwrap();
+        let anchor_velocity = bob.velocity() + Vec2::cross_scalar(bob.angular_velocity(), &Vec2::new(-1.0, 0.0));
+        assert!(anchor_velocity.len() < 1e-8);
+    }
+
+    #[test]
+    fn test_pair_mut_order() {
+        let mut items = [1, 2, 3];
+        let (a, b) = pair_mut(&mut items, 2, 0);
+        assert_eq!((*a, *b), (3, 1));
+    }
+}
@@ -0,0 +1,417 @@
+//! Contact detection and response between world bodies.
+//!
+//! Each step, broadphase pairs are run through the narrowphase to find touching child shapes,
+//! which are then resolved together with the joints by the constraints module. Comparing the
+//! touching pairs with those of the previous step gives begin and end events. Bodies with a
+//! hit cooldown only report one begin event per other body until the cooldown runs out.
+//!
+//! The ContactManager keeps contacts between steps. A contact touching in consecutive steps
+//! keeps the impulse the solver last found for it, which the solver starts from next time.
+//! Contacts are matched by their child shapes and the features their point was found between,
+//! so each corner of a resting box keeps its own impulse.
+//! Stacks settle much faster this way since their weight doesn't have to be rebuilt each step.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use wasm_bindgen::prelude::*;
+
+use super::bodies::BodySet;
+use super::solver::ContactImpulse;
+use super::world::BodyHandle;
+use crate::collision::broadphase::Broadphase;
+use crate::collision::narrowphase::{self, ContactFeature, NarrowphaseConfig};
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+pub const DEFAULT_FRICTION: f64 = 0.4;
+pub const DEFAULT_RESTITUTION: f64 = 0.0;
+/// Default solver passes over all contacts and joints each step
+pub const VELOCITY_ITERATIONS: usize = 8;
+/// Default passes pushing overlapping bodies apart each step
+pub const POSITION_ITERATIONS: usize = 3;
+/// Default overlap allowed without correction, which keeps resting contacts from jittering
+pub const SLOP: f64 = 0.005;
+/// Default fraction of the remaining overlap removed each step
+pub const POSITION_CORRECTION: f64 = 0.2;
+/// Contacts deeper than this many times the slop are logged as a warning, since they usually
+/// mean bodies were placed overlapping or are moving too fast for the step
+pub const DEEP_PENETRATION: f64 = 20.0;
+
+/// A touching pair of child shapes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    /// Compound child of body a's shape, 0 for other shapes
+    pub child_a: usize,
+    /// Compound child of body b's shape, 0 for other shapes
+    pub child_b: usize,
+    /// Unit normal pointing from a to b
+    pub normal: Vec2,
+    pub depth: f64,
+    /// World space contact point
+    pub point: Vec2,
+    /// Features of the shapes the point was found between
+    pub feature: ContactFeature,
+    /// Impulse the solver applied at the end of the last step the contact was solved
+    pub impulse: ContactImpulse,
+}
+
+/// Identifies a touching pair of child shapes across steps
+pub type ContactId = (BodyHandle, BodyHandle, usize, usize);
+
+impl Contact {
+    pub fn id(&self) -> ContactId {
+        (self.body_a, self.body_b, self.child_a, self.child_b)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactEventKind {
+    /// The shapes started touching this step
+    Begin,
+    /// The shapes stopped touching this step
+    End,
+}
+
+/// Two child shapes starting or stopping touching
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactEvent {
+    pub kind: ContactEventKind,
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    /// Compound child of body a's shape, 0 for other shapes
+    pub child_a: usize,
+    /// Compound child of body b's shape, 0 for other shapes
+    pub child_b: usize,
+}
+
+/// Contacts between all bodies whose broadphase boxes overlap
+pub fn find_contacts(bodies: &BodySet, broadphase: &dyn Broadphase, config: &NarrowphaseConfig) -> Vec<Contact> {
+    collide_pairs(bodies, &broadphase.pairs(), config).0
+}
+
+/// Contacts between the bodies of each pair of body indices, with the number of pairs that went
+/// through the narrowphase. Pairs of static bodies are skipped.
+pub fn collide_pairs(bodies: &BodySet, pairs: &[(usize, usize)], config: &NarrowphaseConfig) -> (Vec<Contact>, usize) {
+    let mut contacts = Vec::new();
+    let mut tests = 0;
+    let slice = bodies.as_slice();
+    for &(i, j) in pairs {
+        let (a, b) = (&slice[i], &slice[j]);
+        if a.is_static() && b.is_static() {
+            continue;
+        }
+        tests += 1;
+        for c in narrowphase::collide_with(config, a.shape(), &a.transform(), b.shape(), &b.transform()) {
+            let depth = c.penetration.len();
+            if depth <= 0.0 || !a.fixture_filter(c.child_a).should_collide(&b.fixture_filter(c.child_b)) {
+                continue;
+            }
+            contacts.push(Contact {
+                body_a: bodies.handle(i),
+                body_b: bodies.handle(j),
+                child_a: c.child_a,
+                child_b: c.child_b,
+                normal: c.penetration / depth,
+                depth,
+                point: c.point,
+                feature: c.feature,
+                impulse: ContactImpulse::default(),
+            });
+        }
+    }
+    (contacts, tests)
+}
+
+/// Begin events for pairs only in current and end events for pairs only in previous
+pub fn contact_events(previous: &[Contact], current: &[Contact]) -> Vec<ContactEvent> {
+    let before: BTreeSet<_> = previous.iter().map(Contact::id).collect();
+    let after: BTreeSet<_> = current.iter().map(Contact::id).collect();
+
+    let event = |kind, (a, b, child_a, child_b): &ContactId| ContactEvent {
+        kind,
+        body_a: *a,
+        body_b: *b,
+        child_a: *child_a,
+        child_b: *child_b,
+    };
+    let mut events: Vec<ContactEvent> = after.difference(&before).map(|k| event(ContactEventKind::Begin, k)).collect();
+    events.extend(before.difference(&after).map(|k| event(ContactEventKind::End, k)));
+    events
+}
+
+/// Contacts of the current step, kept to carry their impulses over to the next one
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContactManager {
+    contacts: Vec<Contact>,
+}
+
+impl ContactManager {
+    pub fn new() -> ContactManager {
+        ContactManager::default()
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    pub(crate) fn contacts_mut(&mut self) -> &mut [Contact] {
+        &mut self.contacts
+    }
+
+    /// Replace all contacts, e.g. with those of a restored state
+    pub fn set_contacts(&mut self, contacts: Vec<Contact>) {
+        self.contacts = contacts;
+    }
+
+    /// Find the contacts of this step between the bodies of the broadphase pairs, returning the
+    /// number of narrowphase tests. Contacts that were already touching keep their impulse.
+    /// Contacts deeper than DEEP_PENETRATION times slop are logged as warnings.
+    pub fn update(&mut self, bodies: &BodySet, pairs: &[(usize, usize)], config: &NarrowphaseConfig, slop: f64) -> usize {
+        // Shapes touching at several points are matched point by point by the features the
+        // points were found between
+        let previous: BTreeMap<(ContactId, ContactFeature), ContactImpulse> =
+            self.contacts.iter().map(|c| ((c.id(), c.feature), c.impulse)).collect();
+        let (contacts, tests) = collide_pairs(bodies, pairs, config);
+        self.contacts = contacts;
+        for c in &mut self.contacts {
+            if c.depth > DEEP_PENETRATION * slop {
+                log::warn!("Deep penetration of {} between bodies {:?} and {:?}", c.depth, c.body_a, c.body_b);
+            }
+            if let Some(&impulse) = previous.get(&(c.id(), c.feature)) {
+                c.impulse = impulse;
+            }
+        }
+        tests
+    }
+}
+
+/// Drops repeated begin events for bodies that should only hit each other body once, like
+/// projectiles that pierce through their targets
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HitFilter {
+    /// Cooldown in seconds of each filtered body
+    cooldowns: BTreeMap<BodyHandle, f64>,
+    /// Time until which begin events of a body pair are dropped
+    hits: BTreeMap<(BodyHandle, BodyHandle), f64>,
+}
+
+impl HitFilter {
+    pub fn new() -> HitFilter {
+        HitFilter::default()
+    }
+
+    /// Only report one begin event between body and any other body every cooldown seconds.
+    /// A cooldown of 0 turns filtering off.
+    pub fn set_cooldown(&mut self, body: BodyHandle, cooldown: f64) {
+        assert!(cooldown >= 0.0, "Hit cooldown must not be negative");
+        if cooldown > 0.0 {
+            self.cooldowns.insert(body, cooldown);
+        } else {
+            self.cooldowns.remove(&body);
+        }
+    }
+
+    /// Cooldown of body, 0 if it isn't filtered
+    pub fn cooldown(&self, body: BodyHandle) -> f64 {
+        self.cooldowns.get(&body).copied().unwrap_or(0.0)
+    }
+
+    /// Bodies with a cooldown
+    pub fn cooldowns(&self) -> impl Iterator<Item = (BodyHandle, f64)> + '_ {
+        self.cooldowns.iter().map(|(&body, &cooldown)| (body, cooldown))
+    }
+
+    /// Forget everything about a removed body
+    pub fn body_removed(&mut self, body: BodyHandle) {
+        self.cooldowns.remove(&body);
+        self.hits.retain(|&(a, b), _| a != body && b != body);
+    }
+
+    /// Remove begin events of pairs still cooling down at time and start the cooldown of new hits.
+    /// End events are kept.
+    pub fn filter(&mut self, events: &mut Vec<ContactEvent>, time: f64) {
+        self.hits.retain(|_, &mut until| until > time);
+        if self.cooldowns.is_empty() {
+            return;
+        }
+
+        let cooldowns = &self.cooldowns;
+        let hits = &mut self.hits;
+        events.retain(|e| {
+            if e.kind != ContactEventKind::Begin {
+                return true;
+            }
+            let cooldown = cooldowns
+                .get(&e.body_a)
+                .copied()
+                .unwrap_or(0.0)
+                .max(cooldowns.get(&e.body_b).copied().unwrap_or(0.0));
+            if cooldown == 0.0 {
+                return true;
+            }
+            let pair = (e.body_a.min(e.body_b), e.body_a.max(e.body_b));
+            if hits.contains_key(&pair) {
+                return false;
+            }
+            hits.insert(pair, time + cooldown);
+            true
+        });
+    }
+}
+
+impl Binary for HitFilter {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.cooldowns.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>());
+        w.put(&self.hits.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>());
+    }
+
+    fn decode(r: &mut Reader) -> Result<HitFilter, SerializeError> {
+        let cooldowns: Vec<(BodyHandle, f64)> = r.get()?;
+        let hits: Vec<((BodyHandle, BodyHandle), f64)> = r.get()?;
+        Ok(HitFilter {
+            cooldowns: cooldowns.into_iter().collect(),
+            hits: hits.into_iter().collect(),
+        })
+    }
+}
+
+impl Binary for Contact {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.child_a);
+        w.put(&self.child_b);
+        w.put(&self.normal);
+        w.put(&self.depth);
+        w.put(&self.point);
+        w.put(&self.feature);
+        w.put(&self.impulse);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Contact, SerializeError> {
+        Ok(Contact {
+            body_a: r.get()?,
+            body_b: r.get()?,
+            child_a: r.get()?,
+            child_b: r.get()?,
+            normal: r.get()?,
+            depth: r.get()?,
+            point: r.get()?,
+            feature: r.get()?,
+            impulse: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::RigidBody;
+
+    fn contact(a: usize, b: usize, child_a: usize) -> Contact {
+        Contact {
+            body_a: BodyHandle::new(a, 0),
+            body_b: BodyHandle::new(b, 0),
+            child_a,
+            child_b: 0,
+            normal: Vec2::new(0.0, 1.0),
+            depth: 0.1,
+            point: Vec2::new(0.0, 0.0),
+            feature: ContactFeature::default(),
+            impulse: ContactImpulse::default(),
+        }
+    }
+
+    #[test]
+    fn test_deep_penetration_warning() {
+        let records = crate::logging::capture();
+        let mut bodies = BodySet::new();
+        bodies.insert(RigidBody::with_circle(1.0, 1.0));
+        let mut deep = RigidBody::with_circle(1.0, 1.0);
+        deep.set_position(Vec2::new(0.5, 0.0));
+        bodies.insert(deep);
+
+        let mut manager = ContactManager::new();
+        manager.update(&bodies, &[(0, 1)], &NarrowphaseConfig::default(), SLOP);
+        assert_eq!(manager.contacts().len(), 1);
+        let warned = records.lock().unwrap().iter().any(|r| {
+            r.starts_with("WARN newton_2d::physics::contact Deep penetration of 1.5 between bodies")
+        });
+        assert!(warned);
+    }
+
+    #[test]
+    fn test_warm_start_by_feature() {
+        let square = [-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5];
+        let mut bodies = BodySet::new();
+        bodies.insert(RigidBody::with_polygon(1.0, &[-2.0, -0.5, 2.0, -0.5, 2.0, 0.5, -2.0, 0.5]));
+        let mut block = RigidBody::with_polygon(1.0, &square);
+        block.set_position(Vec2::new(0.0, 0.95));
+        let block = bodies.insert(block);
+
+        let mut manager = ContactManager::new();
+        manager.update(&bodies, &[(0, 1)], &NarrowphaseConfig::default(), SLOP);
+        assert_eq!(manager.contacts().len(), 2);
+        for c in manager.contacts_mut() {
+            c.impulse.normal = if c.point.x < 0.0 { 1.0 } else { 2.0 };
+        }
+
+        // Tipped onto its right corner, the second point found, the block keeps that corner's impulse
+        bodies.get_mut(block).unwrap().set_rotation(-0.15);
+        manager.update(&bodies, &[(0, 1)], &NarrowphaseConfig::default(), SLOP);
+        assert_eq!(manager.contacts().len(), 1);
+        assert!(manager.contacts()[0].point.x > 0.0);
+        assert_eq!(manager.contacts()[0].impulse.normal, 2.0);
+    }
+
+    #[test]
+    fn test_contact_events() {
+        let previous = [contact(0, 1, 0), contact(0, 2, 0)];
+        let current = [contact(0, 1, 0), contact(0, 1, 1), contact(0, 1, 1)];
+        let events = contact_events(&previous, &current);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, ContactEventKind::Begin);
+        assert_eq!(events[0].child_a, 1);
+        assert_eq!(events[1].kind, ContactEventKind::End);
+        assert_eq!(events[1].body_b, BodyHandle::new(2, 0));
+    }
+
+    fn begin(a: usize, b: usize, child_a: usize) -> ContactEvent {
+        ContactEvent {
+            kind: ContactEventKind::Begin,
+            body_a: BodyHandle::new(a, 0),
+            body_b: BodyHandle::new(b, 0),
+            child_a,
+            child_b: 0,
+        }
+    }
+
+    #[test]
+    fn test_hit_filter() {
+        let mut filter = HitFilter::new();
+        filter.set_cooldown(BodyHandle::new(1, 0), 0.5);
+
+        // One hit per pair, even when several children begin touching at once
+        let mut events = vec![begin(0, 1, 0), begin(0, 1, 1), begin(0, 2, 0), begin(1, 2, 0)];
+        filter.filter(&mut events, 0.0);
+        assert_eq!(events, vec![begin(0, 1, 0), begin(0, 2, 0), begin(1, 2, 0)]);
+
+        let mut events = vec![begin(0, 1, 0), ContactEvent { kind: ContactEventKind::End, ..begin(0, 1, 0) }];
+        filter.filter(&mut events, 0.25);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ContactEventKind::End);
+
+        let mut events = vec![begin(0, 1, 0)];
+        filter.filter(&mut events, 0.5);
+        assert_eq!(events.len(), 1);
+
+        filter.set_cooldown(BodyHandle::new(1, 0), 0.0);
+        assert_eq!(filter.cooldown(BodyHandle::new(1, 0)), 0.0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Drawing a world's internals for debugging.
+//!
+//! World::debug_draw describes bodies, their bounding boxes, contacts, joints and springs as
+//! simple primitives sent to a DebugDraw implementation, which renders them however it likes.
+//! CommandBuffer records them into a flat array for JS, see World::wasm_debug_draw.
+
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::Shapes;
+use crate::math::{Transform2D, Vec2};
+
+/// Length contact normals are drawn with
+pub const NORMAL_LENGTH: f64 = 0.5;
+
+/// What a primitive shows, so renderers can pick a style for each
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugColor {
+    DynamicBody,
+    StaticBody,
+    Aabb,
+    Contact,
+    Joint,
+    Spring,
+}
+
+/// Receives the primitives of World::debug_draw
+pub trait DebugDraw {
+    fn draw_segment(&mut self, a: Vec2, b: Vec2, color: DebugColor);
+    /// Closed outline through vertices
+    fn draw_polygon(&mut self, vertices: &[Vec2], color: DebugColor);
+    fn draw_circle(&mut self, center: Vec2, radius: f64, color: DebugColor);
+    /// Position and orientation of a body
+    fn draw_transform(&mut self, transform: &Transform2D);
+    fn draw_point(&mut self, point: Vec2, color: DebugColor);
+}
+
+/// Command codes in a CommandBuffer
+pub const DRAW_SEGMENT: f64 = 0.0;
+pub const DRAW_POLYGON: f64 = 1.0;
+pub const DRAW_CIRCLE: f64 = 2.0;
+pub const DRAW_TRANSFORM: f64 = 3.0;
+pub const DRAW_POINT: f64 = 4.0;
+
+/// Primitives recorded as a flat list of numbers, each command starting with its code:
+///
+/// - DRAW_SEGMENT, color, ax, ay, bx, by
+/// - DRAW_POLYGON, color, n, x0, y0, ... x(n-1), y(n-1)
+/// - DRAW_CIRCLE, color, x, y, radius
+/// - DRAW_TRANSFORM, x, y, rotation
+/// - DRAW_POINT, color, x, y
+///
+/// Colors are DebugColor values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandBuffer {
+    commands: Vec<f64>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> CommandBuffer {
+        CommandBuffer::default()
+    }
+
+    pub fn commands(&self) -> &[f64] {
+        &self.commands
+    }
+
+    pub fn into_commands(self) -> Vec<f64> {
+        self.commands
+    }
+}
+
+impl DebugDraw for CommandBuffer {
+    fn draw_segment(&mut self, a: Vec2, b: Vec2, color: DebugColor) {
+        self.commands.extend_from_slice(&[DRAW_SEGMENT, color as u8 as f64, a.x, a.y, b.x, b.y]);
+    }
+
+    fn draw_polygon(&mut self, vertices: &[Vec2], color: DebugColor) {
+        self.commands.extend_from_slice(&[DRAW_POLYGON, color as u8 as f64, vertices.len() as f64]);
+        self.commands.extend(vertices.iter().flat_map(|v| [v.x, v.y]));
+    }
+
+    fn draw_circle(&mut self, center: Vec2, radius: f64, color: DebugColor) {
+        self.commands.extend_from_slice(&[DRAW_CIRCLE, color as u8 as f64, center.x, center.y, radius]);
+    }
+
+    fn draw_transform(&mut self, transform: &Transform2D) {
+        let p = transform.position;
+        self.commands.extend_from_slice(&[DRAW_TRANSFORM, p.x, p.y, transform.rotation]);
+    }
+
+    fn draw_point(&mut self, point: Vec2, color: DebugColor) {
+        self.commands.extend_from_slice(&[DRAW_POINT, color as u8 as f64, point.x, point.y]);
+    }
+}
+
+/// Outline of shape placed by transform
+pub fn draw_shape<D: DebugDraw + ?Sized>(draw: &mut D, shape: &Shapes, transform: &Transform2D, color: DebugColor) {
+    match shape {
+        Shapes::Circle(c) => draw.draw_circle(transform.apply(&c.center()), c.radius(), color),
+        Shapes::Polygon(p) => {
+            let vertices: Vec<Vec2> = p.vertices().iter().map(|v| transform.apply(v)).collect();
+            draw.draw_polygon(&vertices, color);
+        }
+        Shapes::Segment(s) => draw.draw_segment(transform.apply(&s.a()), transform.apply(&s.b()), color),
+        Shapes::Compound(c) => {
+            for (local, child) in c.children() {
+                draw_shape(draw, child, &transform.mul(local), color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Compound, Polygon};
+
+    #[test]
+    fn test_command_buffer() {
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::identity(), Shapes::Polygon(Polygon::rect(2.0, 2.0)));
+        compound.add_child(
+            Transform2D::new(Vec2::new(0.0, 2.0), 0.0),
+            Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)),
+        );
+        let mut buffer = CommandBuffer::new();
+        let placed = Transform2D::new(Vec2::new(1.0, 0.0), 0.0);
+        draw_shape(&mut buffer, &Shapes::Compound(compound), &placed, DebugColor::StaticBody);
+        buffer.draw_transform(&placed);
+
+        let commands = buffer.commands();
+        assert_eq!(&commands[..5], &[DRAW_POLYGON, 1.0, 4.0, 0.0, -1.0]);
+        assert_eq!(&commands[11..16], &[DRAW_CIRCLE, 1.0, 1.0, 2.0, 0.5]);
+        assert_eq!(&commands[16..], &[DRAW_TRANSFORM, 1.0, 0.0, 0.0]);
+    }
+}
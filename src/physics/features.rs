@@ -0,0 +1,97 @@
+//! Switches for turning parts of the simulation off while debugging.
+
+use wasm_bindgen::prelude::*;
+
+use super::solver::SolverConfig;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{SerializeError, Value};
+
+/// Parts of the simulation that can be switched off at runtime, to see what each one changes.
+/// A feature switched off here stays off whatever the SolverConfig says.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldFeatures {
+    /// Start contacts from the impulses of the previous step
+    pub enable_warm_start: bool,
+    /// Friction between touching bodies
+    pub enable_friction: bool,
+}
+
+impl Default for WorldFeatures {
+    fn default() -> WorldFeatures {
+        WorldFeatures::new()
+    }
+}
+
+#[wasm_bindgen]
+impl WorldFeatures {
+    /// Everything enabled
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WorldFeatures {
+        WorldFeatures {
+            enable_warm_start: true,
+            enable_friction: true,
+        }
+    }
+}
+
+impl WorldFeatures {
+    /// config with the disabled features switched off
+    pub fn apply(&self, config: &SolverConfig) -> SolverConfig {
+        SolverConfig {
+            warm_starting: config.warm_starting && self.enable_warm_start,
+            friction: config.friction && self.enable_friction,
+            ..*config
+        }
+    }
+
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("enable_warm_start", self.enable_warm_start.into());
+        v.insert("enable_friction", self.enable_friction.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<WorldFeatures, SerializeError> {
+        Ok(WorldFeatures {
+            enable_warm_start: v.field_bool("enable_warm_start")?,
+            enable_friction: v.field_bool("enable_friction")?,
+        })
+    }
+}
+
+impl Binary for WorldFeatures {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.enable_warm_start);
+        w.put(&self.enable_friction);
+    }
+
+    fn decode(r: &mut Reader) -> Result<WorldFeatures, SerializeError> {
+        Ok(WorldFeatures {
+            enable_warm_start: r.get()?,
+            enable_friction: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(WorldFeatures::new().apply(&SolverConfig::new()), SolverConfig::new());
+
+        let features = WorldFeatures {
+            enable_friction: false,
+            ..WorldFeatures::new()
+        };
+        let config = features.apply(&SolverConfig::new());
+        assert!(!config.friction && config.warm_starting);
+        let cold = SolverConfig {
+            warm_starting: false,
+            ..SolverConfig::new()
+        };
+        assert!(!WorldFeatures::new().apply(&cold).warm_starting);
+    }
+}
@@ -0,0 +1,153 @@
+//! Shapes attached to a body with their own material and collision filter.
+//!
+//! A body built from several fixtures holds them as children of a Compound shape, so contacts
+//! already know which fixture they touch through their child index. Each fixture is weighed
+//! with its own density.
+
+use wasm_bindgen::prelude::*;
+
+use super::material::Material;
+use crate::geometry::Shapes;
+use crate::math::Transform2D;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{SerializeError, Value};
+
+/// Which shapes may touch. Two shapes in the same nonzero group always collide if the group is
+/// positive and never if it is negative. Otherwise each shape's category must be in the other's mask.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionFilter {
+    /// Bits naming what this shape is
+    pub category: u32,
+    /// Bits of the categories this shape collides with
+    pub mask: u32,
+    pub group: i32,
+}
+
+impl Default for CollisionFilter {
+    fn default() -> CollisionFilter {
+        CollisionFilter::new()
+    }
+}
+
+#[wasm_bindgen]
+impl CollisionFilter {
+    /// Category 1 colliding with everything
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CollisionFilter {
+        CollisionFilter {
+            category: 1,
+            mask: u32::MAX,
+            group: 0,
+        }
+    }
+
+    pub fn should_collide(&self, other: &CollisionFilter) -> bool {
+        if self.group != 0 && self.group == other.group {
+            return self.group > 0;
+        }
+        self.mask & other.category != 0 && other.mask & self.category != 0
+    }
+}
+
+impl CollisionFilter {
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("category", (self.category as f64).into());
+        v.insert("mask", (self.mask as f64).into());
+        v.insert("group", (self.group as f64).into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<CollisionFilter, SerializeError> {
+        Ok(CollisionFilter {
+            category: v.field_f64("category")? as u32,
+            mask: v.field_f64("mask")? as u32,
+            group: v.field_f64("group")? as i32,
+        })
+    }
+}
+
+impl Binary for CollisionFilter {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.category);
+        w.put(&self.mask);
+        w.put(&(self.group as u32));
+    }
+
+    fn decode(r: &mut Reader) -> Result<CollisionFilter, SerializeError> {
+        Ok(CollisionFilter {
+            category: r.get()?,
+            mask: r.get()?,
+            group: r.get::<u32>()? as i32,
+        })
+    }
+}
+
+/// One shape of a body placed by a transform in body coordinates, see RigidBody::with_fixtures
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+    pub transform: Transform2D,
+    pub shape: Shapes,
+    pub material: Material,
+    pub filter: CollisionFilter,
+}
+
+impl Fixture {
+    /// A fixture at the body origin colliding with everything
+    pub fn new(shape: Shapes, material: Material) -> Fixture {
+        Fixture {
+            transform: Transform2D::identity(),
+            shape,
+            material,
+            filter: CollisionFilter::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_collide() {
+        let default = CollisionFilter::new();
+        assert!(default.should_collide(&default));
+
+        let debris = CollisionFilter {
+            category: 0b10,
+            mask: 0b01,
+            group: 0,
+        };
+        assert!(debris.should_collide(&default));
+        assert!(!debris.should_collide(&debris));
+
+        // Groups override the masks either way
+        let mut a = debris;
+        let mut b = debris;
+        a.group = 3;
+        b.group = 3;
+        assert!(a.should_collide(&b));
+        let mut c = default;
+        let mut d = default;
+        c.group = -1;
+        d.group = -1;
+        assert!(!c.should_collide(&d));
+        d.group = -2;
+        assert!(c.should_collide(&d));
+    }
+
+    #[test]
+    fn test_filter_round_trip() {
+        let filter = CollisionFilter {
+            category: 4,
+            mask: u32::MAX ^ 4,
+            group: -7,
+        };
+        assert_eq!(CollisionFilter::from_value(&filter.to_value()), Ok(filter));
+        let mut w = Writer::new();
+        w.put(&filter);
+        let bytes = w.finish();
+        assert_eq!(Reader::new(&bytes).get::<CollisionFilter>(), Ok(filter));
+    }
+}
@@ -0,0 +1,334 @@
+//! Forces applied to every dynamic body each step, such as planet gravity, blasts, wind and water.
+//!
+//! A ForceGenerator registered on a World is asked for the force on each body before the
+//! bodies are integrated, and again for each substep. Forces depending on several bodies at once,
+//! like the gravity between planets, can be given as a callback on the World instead. Neither
+//! generators nor callbacks are saved with the world.
+
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use super::body::RigidBody;
+use crate::geometry::{boolean, Polygon, Shape, Shapes};
+use crate::math::{trig, Transform2D, Vec2};
+
+/// Sides of the polygon standing in for a circle when measuring how much of it is under water
+const BUOYANCY_CIRCLE_SEGMENTS: usize = 32;
+
+pub trait ForceGenerator: ForceGeneratorClone + fmt::Debug {
+    /// Force and torque on a dynamic body over the next dt seconds, in a world with the given gravity
+    fn force(&self, body: &RigidBody, gravity: &Vec2, dt: f64) -> (Vec2, f64);
+
+    /// Move on by dt once the forces were applied. Returns false when done, removing the
+    /// generator from the world.
+    fn advance(&mut self, _dt: f64) -> bool {
+        true
+    }
+}
+
+/// Cloning boxed generators, implemented for every ForceGenerator that is Clone
+pub trait ForceGeneratorClone {
+    fn clone_box(&self) -> Box<dyn ForceGenerator>;
+}
+
+impl<T: ForceGenerator + Clone + 'static> ForceGeneratorClone for T {
+    fn clone_box(&self) -> Box<dyn ForceGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn ForceGenerator> {
+    fn clone(&self) -> Box<dyn ForceGenerator> {
+        self.clone_box()
+    }
+}
+
+/// Adds the force and torque on each body at time t to forces, which lines up with bodies
+pub type ForceFn = dyn Fn(f64, &[RigidBody], &mut [(Vec2, f64)]);
+
+/// Force callback of a World, see World::set_force_callback
+#[derive(Clone)]
+pub struct ForceCallback(pub Rc<ForceFn>);
+
+impl fmt::Debug for ForceCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ForceCallback")
+    }
+}
+
+/// Pulls bodies towards a point with an acceleration of strength / distance², like the gravity
+/// of a planet. A negative strength pushes them away.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attractor {
+    pub center: Vec2,
+    pub strength: f64,
+    /// Distance the pull is limited to, keeping it finite near the center
+    pub min_distance: f64,
+    /// Bodies further away than this are left alone. Infinite by default.
+    pub radius: f64,
+}
+
+#[wasm_bindgen]
+impl Attractor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(center: Vec2, strength: f64) -> Attractor {
+        Attractor {
+            center,
+            strength,
+            min_distance: 0.1,
+            radius: f64::INFINITY,
+        }
+    }
+}
+
+impl ForceGenerator for Attractor {
+    fn force(&self, body: &RigidBody, _gravity: &Vec2, _dt: f64) -> (Vec2, f64) {
+        let offset = self.center - body.position();
+        let distance = offset.len();
+        if distance == 0.0 || distance > self.radius {
+            return (Vec2::new(0.0, 0.0), 0.0);
+        }
+        let limited = distance.max(self.min_distance);
+        let acceleration = self.strength / (limited * limited);
+        (offset / distance * (acceleration * body.mass()), 0.0)
+    }
+}
+
+/// Pushes bodies away from a point once, with an impulse falling off linearly from impulse at
+/// the center to nothing at radius
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Explosion {
+    pub center: Vec2,
+    pub impulse: f64,
+    pub radius: f64,
+    /// Time the impulse is spread over. One 60 Hz step by default.
+    pub duration: f64,
+    elapsed: f64,
+}
+
+#[wasm_bindgen]
+impl Explosion {
+    #[wasm_bindgen(constructor)]
+    pub fn new(center: Vec2, impulse: f64, radius: f64) -> Explosion {
+        Explosion {
+            center,
+            impulse,
+            radius,
+            duration: 1.0 / 60.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl ForceGenerator for Explosion {
+    fn force(&self, body: &RigidBody, _gravity: &Vec2, dt: f64) -> (Vec2, f64) {
+        let offset = body.position() - self.center;
+        let distance = offset.len();
+        let remaining = (self.duration - self.elapsed).min(dt);
+        if distance == 0.0 || distance >= self.radius || remaining <= 0.0 || dt <= 0.0 {
+            return (Vec2::new(0.0, 0.0), 0.0);
+        }
+        let impulse = self.impulse * (1.0 - distance / self.radius) * remaining / self.duration;
+        (offset / distance * (impulse / dt), 0.0)
+    }
+
+    fn advance(&mut self, dt: f64) -> bool {
+        self.elapsed += dt;
+        self.elapsed < self.duration
+    }
+}
+
+/// Drags bodies towards the velocity of the air with a force of drag times the difference
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wind {
+    pub velocity: Vec2,
+    pub drag: f64,
+}
+
+#[wasm_bindgen]
+impl Wind {
+    #[wasm_bindgen(constructor)]
+    pub fn new(velocity: Vec2, drag: f64) -> Wind {
+        Wind { velocity, drag }
+    }
+}
+
+impl ForceGenerator for Wind {
+    fn force(&self, body: &RigidBody, _gravity: &Vec2, _dt: f64) -> (Vec2, f64) {
+        ((self.velocity - body.velocity()) * self.drag, 0.0)
+    }
+}
+
+/// Lifts and slows bodies in a fluid, pushing up on the submerged part of each shape with the
+/// weight of the fluid it displaces. Segments have no area and don't float.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Buoyancy {
+    surface: Vec2,
+    normal: Vec2,
+    region: Option<Polygon>,
+    /// Mass per unit area of the fluid. Bodies less dense than this float.
+    pub density: f64,
+    /// Force slowing the submerged part per unit area and speed relative to the fluid
+    pub linear_drag: f64,
+    /// Torque slowing the spin per unit of submerged area and angular speed
+    pub angular_drag: f64,
+    /// Velocity of the current
+    pub velocity: Vec2,
+}
+
+#[wasm_bindgen]
+impl Buoyancy {
+    /// Fluid filling everything below the line through surface, with normal pointing up out of it
+    #[wasm_bindgen(constructor)]
+    pub fn new(surface: Vec2, normal: Vec2, density: f64) -> Buoyancy {
+        assert!(normal.len_sq() > 0.0, "Fluid surface normal must not be zero");
+        Buoyancy {
+            surface,
+            normal: normal.normalize(),
+            region: None,
+            density,
+            linear_drag: 2.0,
+            angular_drag: 1.0,
+            velocity: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    /// Fluid filling a polygon in world coordinates, like a pool
+    pub fn with_region(region: &Polygon, density: f64) -> Buoyancy {
+        Buoyancy {
+            region: Some(region.clone()),
+            ..Buoyancy::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0), density)
+        }
+    }
+
+    /// Area of body under the fluid
+    pub fn submerged_area(&self, body: &RigidBody) -> f64 {
+        self.submerged(body).iter().map(|p| p.area().abs()).sum()
+    }
+}
+
+impl Buoyancy {
+    /// Parts of body's shapes inside the fluid, in world coordinates
+    fn submerged(&self, body: &RigidBody) -> Vec<Polygon> {
+        let mut pieces = Vec::new();
+        for polygon in world_polygons(body.shape(), &body.transform()) {
+            match &self.region {
+                Some(region) => pieces.extend(boolean::intersection(&polygon, region)),
+                None => pieces.extend(polygon.clip(&self.surface, &self.normal)),
+            }
+        }
+        pieces
+    }
+}
+
+/// Convex pieces of a shape placed by transform. Circles become polygons of the same area.
+fn world_polygons(shape: &Shapes, transform: &Transform2D) -> Vec<Polygon> {
+    match shape {
+        Shapes::Polygon(p) => vec![p.transformed(transform)],
+        Shapes::Circle(c) if c.radius() > 0.0 => {
+            let n = BUOYANCY_CIRCLE_SEGMENTS as f64;
+            let step = 2.0 * std::f64::consts::PI / n;
+            let radius = c.radius() * (std::f64::consts::PI / (n / 2.0 * trig::sin(step))).sqrt();
+            let center = transform.apply(&c.center());
+            vec![Polygon::regular(BUOYANCY_CIRCLE_SEGMENTS, radius).transformed(&Transform2D::new(center, 0.0))]
+        }
+        Shapes::Compound(c) => c
+            .children()
+            .iter()
+            .flat_map(|(t, child)| world_polygons(child, &transform.mul(t)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl ForceGenerator for Buoyancy {
+    fn force(&self, body: &RigidBody, gravity: &Vec2, _dt: f64) -> (Vec2, f64) {
+        let mut force = Vec2::new(0.0, 0.0);
+        let mut torque = 0.0;
+        let mut area = 0.0;
+        for piece in self.submerged(body) {
+            let piece_area = piece.area().abs();
+            let center = piece.center();
+            let relative = body.velocity_at_point(&center) - self.velocity;
+            let f = gravity * (-self.density * piece_area) - relative * (self.linear_drag * piece_area);
+            force = force + f;
+            torque += Vec2::cross(&(center - body.position()), &f);
+            area += piece_area;
+        }
+        torque -= self.angular_drag * area * body.angular_velocity();
+        (force, torque)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attractor() {
+        let mut body = RigidBody::new(2.0);
+        body.set_position(Vec2::new(3.0, 4.0));
+        let mut planet = Attractor::new(Vec2::new(0.0, 0.0), 50.0);
+
+        let (force, _) = planet.force(&body, &Vec2::new(0.0, 0.0), 1.0);
+        assert!((force - Vec2::new(-0.6, -0.8) * 4.0).len() < 1e-12);
+        planet.strength = -50.0;
+        assert!(planet.force(&body, &Vec2::new(0.0, 0.0), 1.0).0.x > 0.0);
+        planet.radius = 4.0;
+        assert_eq!(planet.force(&body, &Vec2::new(0.0, 0.0), 1.0).0, Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_buoyancy() {
+        let water = Buoyancy::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0), 2.0);
+        let mut crate_ = RigidBody::with_polygon(1.0, &[-2.0, -1.0, 2.0, -1.0, 2.0, 1.0, -2.0, 1.0]);
+        crate_.set_position(Vec2::new(0.0, 0.5));
+
+        // A quarter under water, pushed up by the weight of the water it displaces
+        assert!((water.submerged_area(&crate_) - 2.0).abs() < 1e-12);
+        let (force, torque) = water.force(&crate_, &Vec2::new(0.0, -9.81), 1.0);
+        assert!((force - Vec2::new(0.0, 2.0 * 2.0 * 9.81)).len() < 1e-9);
+        assert!(torque.abs() < 1e-9);
+        // Lift comes from the weight of the water, so follows the world's gravity
+        let (force, _) = water.force(&crate_, &Vec2::new(0.0, -1.0), 1.0);
+        assert!((force - Vec2::new(0.0, 2.0 * 2.0)).len() < 1e-9);
+
+        // Tilted, the lift moves off center and turns the wide crate back upright
+        crate_.set_rotation(0.3);
+        assert!(water.force(&crate_, &Vec2::new(0.0, -9.81), 1.0).1 < 0.0);
+
+        // Circles displace their full area once under
+        let mut ball = RigidBody::with_circle(1.0, 1.0);
+        ball.set_position(Vec2::new(0.0, -5.0));
+        assert!((water.submerged_area(&ball) - std::f64::consts::PI).abs() < 1e-9);
+
+        let pool = Buoyancy::with_region(&Polygon::rect(2.0, 2.0), 1.0);
+        ball.set_position(Vec2::new(1.0, 0.0));
+        assert!((pool.submerged_area(&ball) - std::f64::consts::FRAC_PI_2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_explosion_total_impulse() {
+        let mut body = RigidBody::new(1.0);
+        body.set_position(Vec2::new(0.0, 1.0));
+        let mut blast = Explosion::new(Vec2::new(0.0, 0.0), 10.0, 4.0);
+        blast.duration = 0.1;
+
+        // Split into uneven substeps that run past the duration, the impulse adds up the same
+        let mut total = Vec2::new(0.0, 0.0);
+        let mut alive = true;
+        for &dt in &[0.03, 0.05, 0.04] {
+            assert!(alive);
+            total = total + blast.force(&body, &Vec2::new(0.0, 0.0), dt).0 * dt;
+            alive = blast.advance(dt);
+        }
+        assert!(!alive);
+        assert!((total - Vec2::new(0.0, 7.5)).len() < 1e-12);
+    }
+}
@@ -0,0 +1,182 @@
+//! Game code deciding which bodies touch and how their contacts are solved.
+//!
+//! Rust code sets closures on the World: a contact filter run on each broadphase pair, a
+//! pre-solve hook that can change or disable each contact before it is solved, and a post-solve
+//! hook that sees the impulses applied, e.g. to pick the volume of an impact sound. Closures
+//! can't cross into wasm, so there the same control is given by flags set on body pairs.
+//! Neither closures nor pair flags are saved with the world, but snapshots keep the pair flags.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+
+use super::body::RigidBody;
+use super::contact::Contact;
+use super::material::Material;
+use super::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+/// Decides whether two bodies may touch
+pub type ContactFilterFn = dyn Fn(&RigidBody, &RigidBody) -> bool;
+/// Changes how a contact is solved this step
+pub type PreSolveFn = dyn Fn(&Contact, &mut ContactSettings);
+/// Sees a contact with the impulse the solver applied
+pub type PostSolveFn = dyn Fn(&Contact);
+
+/// How a contact is solved, which the pre-solve hook may change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactSettings {
+    /// Disabled contacts are still reported by contact events but don't push the bodies apart
+    pub enabled: bool,
+    pub friction: f64,
+    pub restitution: f64,
+    /// Speed b is dragged along the contact tangent relative to a, see Material::surface_velocity
+    pub surface_velocity: f64,
+}
+
+impl ContactSettings {
+    /// Settings mixed from the materials of the two touching fixtures
+    pub fn mixed(a: &Material, b: &Material) -> ContactSettings {
+        ContactSettings {
+            enabled: true,
+            friction: Material::mix_friction(a, b),
+            restitution: Material::mix_restitution(a, b),
+            // Surfaces moving clockwise around their body run against the tangent on a and along it on b
+            surface_velocity: -(a.surface_velocity + b.surface_velocity),
+        }
+    }
+
+    /// Settings of a contact from the materials of the fixtures it touches
+    pub fn of_contact(contact: &Contact, a: &RigidBody, b: &RigidBody) -> ContactSettings {
+        ContactSettings::mixed(&a.fixture_material(contact.child_a), &b.fixture_material(contact.child_b))
+    }
+}
+
+/// Pair key with the smaller handle first
+fn pair(a: BodyHandle, b: BodyHandle) -> (BodyHandle, BodyHandle) {
+    (a.min(b), a.max(b))
+}
+
+/// Body pairs kept from touching or given their own friction and restitution
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PairFlags {
+    disabled: BTreeSet<(BodyHandle, BodyHandle)>,
+    /// Friction and restitution replacing the mixed materials of a pair
+    materials: BTreeMap<(BodyHandle, BodyHandle), (f64, f64)>,
+}
+
+impl Binary for PairFlags {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.disabled.iter().copied().collect::<Vec<_>>());
+        w.put(&self.materials.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>());
+    }
+
+    fn decode(r: &mut Reader) -> Result<PairFlags, SerializeError> {
+        let disabled: Vec<(BodyHandle, BodyHandle)> = r.get()?;
+        let materials: Vec<((BodyHandle, BodyHandle), (f64, f64))> = r.get()?;
+        Ok(PairFlags {
+            disabled: disabled.into_iter().collect(),
+            materials: materials.into_iter().collect(),
+        })
+    }
+}
+
+/// Contact closures and pair flags of a World
+#[derive(Clone, Default)]
+pub struct ContactHooks {
+    filter: Option<Rc<ContactFilterFn>>,
+    pre_solve: Option<Rc<PreSolveFn>>,
+    post_solve: Option<Rc<PostSolveFn>>,
+    pairs: PairFlags,
+}
+
+impl fmt::Debug for ContactHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContactHooks")
+            .field("filter", &self.filter.is_some())
+            .field("pre_solve", &self.pre_solve.is_some())
+            .field("post_solve", &self.post_solve.is_some())
+            .field("pairs", &self.pairs)
+            .finish()
+    }
+}
+
+impl ContactHooks {
+    pub fn new() -> ContactHooks {
+        ContactHooks::default()
+    }
+
+    pub fn set_filter(&mut self, filter: Option<Rc<ContactFilterFn>>) {
+        self.filter = filter;
+    }
+
+    pub fn set_pre_solve(&mut self, pre_solve: Option<Rc<PreSolveFn>>) {
+        self.pre_solve = pre_solve;
+    }
+
+    pub fn set_post_solve(&mut self, post_solve: Option<Rc<PostSolveFn>>) {
+        self.post_solve = post_solve;
+    }
+
+    pub fn pair_flags(&self) -> &PairFlags {
+        &self.pairs
+    }
+
+    /// Replace the pair flags, keeping the closures
+    pub fn set_pair_flags(&mut self, pairs: PairFlags) {
+        self.pairs = pairs;
+    }
+
+    /// Let two bodies touch or pass through each other
+    pub fn set_pair_enabled(&mut self, a: BodyHandle, b: BodyHandle, enabled: bool) {
+        if enabled {
+            self.pairs.disabled.remove(&pair(a, b));
+        } else {
+            self.pairs.disabled.insert(pair(a, b));
+        }
+    }
+
+    /// Solve contacts between two bodies with this friction and restitution, or go back to
+    /// mixing their materials with None
+    pub fn set_pair_material(&mut self, a: BodyHandle, b: BodyHandle, material: Option<(f64, f64)>) {
+        match material {
+            Some(material) => self.pairs.materials.insert(pair(a, b), material),
+            None => self.pairs.materials.remove(&pair(a, b)),
+        };
+    }
+
+    /// True if there is anything to check for each broadphase pair
+    pub fn filters_pairs(&self) -> bool {
+        self.filter.is_some() || !self.pairs.disabled.is_empty()
+    }
+
+    pub fn should_collide(&self, (ha, a): (BodyHandle, &RigidBody), (hb, b): (BodyHandle, &RigidBody)) -> bool {
+        !self.pairs.disabled.contains(&pair(ha, hb)) && self.filter.as_ref().is_none_or(|filter| filter(a, b))
+    }
+
+    /// Settings of a contact after the pair flags and pre-solve hook had their say
+    pub fn settings(&self, contact: &Contact, a: &RigidBody, b: &RigidBody) -> ContactSettings {
+        let mut settings = ContactSettings::of_contact(contact, a, b);
+        if let Some(&(friction, restitution)) = self.pairs.materials.get(&pair(contact.body_a, contact.body_b)) {
+            settings.friction = friction;
+            settings.restitution = restitution;
+        }
+        if let Some(pre_solve) = &self.pre_solve {
+            pre_solve(contact, &mut settings);
+        }
+        settings
+    }
+
+    pub fn post_solve(&self, contacts: &[Contact]) {
+        if let Some(post_solve) = &self.post_solve {
+            contacts.iter().for_each(|c| post_solve(c));
+        }
+    }
+
+    /// Forget the pair flags of a removed body
+    pub fn body_removed(&mut self, body: BodyHandle) {
+        self.pairs.disabled.retain(|&(a, b)| a != body && b != body);
+        self.pairs.materials.retain(|&(a, b), _| a != body && b != body);
+    }
+}
@@ -0,0 +1,106 @@
+use wasm_bindgen::prelude::*;
+
+use super::{JointConstraint, JointEventKind, JointPass, JointReaction};
+use crate::physics::body::RigidBody;
+use crate::physics::solver::{ConstraintRow, Jacobian, SolverBody};
+use crate::physics::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{SerializeError, Value};
+
+/// Keeps the rotation of b relative to a fixed, leaving both free to move.
+/// Useful for platforms that must stay level while hanging from ropes.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AngleJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    /// Rotation of b relative to a that the joint holds
+    pub reference_angle: f64,
+    /// Value the game stores on the joint to find it again. 0 by default.
+    pub user_data: u64,
+    pub(crate) reaction: JointReaction,
+}
+
+#[wasm_bindgen]
+impl AngleJoint {
+    #[wasm_bindgen(constructor)]
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, reference_angle: f64) -> AngleJoint {
+        AngleJoint {
+            body_a,
+            body_b,
+            reference_angle,
+            user_data: 0,
+            reaction: JointReaction::default(),
+        }
+    }
+
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Rotation of b relative to a, minus the reference angle
+    pub fn angle(&self, a: &RigidBody, b: &RigidBody) -> f64 {
+        b.rotation() - a.rotation() - self.reference_angle
+    }
+}
+
+impl AngleJoint {
+    /// A single angular row driving the relative rotation to the reference angle
+    pub(crate) fn prepare(
+        &self,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+        pass: JointPass,
+    ) -> JointConstraint {
+        let bias = pass.bias_scale(dt) * self.angle(a.0, b.0);
+        pass.soften(
+            JointConstraint {
+                point: None,
+                rows: vec![ConstraintRow::equality(Jacobian::angular(), a.1, b.1, bias)],
+                motor: None,
+            },
+            dt,
+        )
+    }
+
+    /// Angle joints have no limit or motor to report on
+    pub(crate) fn finish(&mut self) -> Vec<JointEventKind> {
+        Vec::new()
+    }
+
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let mut v = Value::object();
+        v.insert("body_a", (body_a as f64).into());
+        v.insert("body_b", (body_b as f64).into());
+        v.insert("reference_angle", self.reference_angle.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<AngleJoint, SerializeError> {
+        Ok(AngleJoint::new(
+            super::body_from_value(v, "body_a", bodies)?,
+            super::body_from_value(v, "body_b", bodies)?,
+            v.field_f64("reference_angle")?,
+        ))
+    }
+}
+
+impl Binary for AngleJoint {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.reference_angle);
+        w.put(&self.reaction);
+    }
+
+    fn decode(r: &mut Reader) -> Result<AngleJoint, SerializeError> {
+        let mut joint = AngleJoint::new(r.get()?, r.get()?, r.get()?);
+        joint.reaction = r.get()?;
+        Ok(joint)
+    }
+}
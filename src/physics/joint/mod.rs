@@ -0,0 +1,379 @@
+//! Joints constraining the relative motion of two bodies.
+//!
+//! Each step a joint turns into solver rows that are solved together with the contacts.
+//! Afterwards the joint looks at the solved impulses to track whether it rests on a limit or
+//! whether its motor is stalled, and reports changes as events. The impulses are kept until the
+//! next step so the joint's reaction force and torque can be read back.
+
+use wasm_bindgen::prelude::*;
+
+use super::body::RigidBody;
+use super::solver::{ConstraintRow, PointConstraint, SolverBody};
+use super::world::BodyHandle;
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+pub mod angle;
+pub mod mouse;
+pub mod prismatic;
+pub mod revolute;
+
+pub use angle::AngleJoint;
+pub use mouse::MouseJoint;
+pub use prismatic::PrismaticJoint;
+pub use revolute::RevoluteJoint;
+
+/// Fraction of the position error corrected each step
+pub const BAUMGARTE: f64 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Joint {
+    Revolute(RevoluteJoint),
+    Angle(AngleJoint),
+    Prismatic(PrismaticJoint),
+    Mouse(MouseJoint),
+}
+
+impl From<RevoluteJoint> for Joint {
+    fn from(joint: RevoluteJoint) -> Joint {
+        Joint::Revolute(joint)
+    }
+}
+
+impl From<AngleJoint> for Joint {
+    fn from(joint: AngleJoint) -> Joint {
+        Joint::Angle(joint)
+    }
+}
+
+impl From<PrismaticJoint> for Joint {
+    fn from(joint: PrismaticJoint) -> Joint {
+        Joint::Prismatic(joint)
+    }
+}
+
+impl From<MouseJoint> for Joint {
+    fn from(joint: MouseJoint) -> Joint {
+        Joint::Mouse(joint)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JointEventKind {
+    /// The joint came to rest on its lower or upper limit
+    LimitReached,
+    /// The joint moved off the limit it was resting on
+    LimitLeft,
+    /// The motor has been at its maximum torque or force without reaching its speed for stall_steps steps
+    MotorStalled,
+    /// A stalled motor reached its speed or stopped being at its maximum torque or force
+    MotorRecovered,
+}
+
+/// A change in the limit or motor state of a joint
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointEvent {
+    pub kind: JointEventKind,
+    /// Index of the joint in the world
+    pub joint: usize,
+}
+
+/// What a joint's rows are built to solve
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum JointPass {
+    /// Velocities, with BAUMGARTE of the position error fed back as bias each step
+    Velocity,
+    /// XPBD positions: the rows solve for how far the bodies move over the step, with the
+    /// position error as bias, so their impulses are Lagrange multipliers. Rows give by
+    /// compliance, and motors are left to the velocity pass.
+    Position { compliance: f64 },
+    /// XPBD velocities, after the position pass has taken out the position error
+    XpbdVelocity,
+}
+
+impl JointPass {
+    /// Bias of a row per unit of position error
+    fn bias_scale(self, dt: f64) -> f64 {
+        match self {
+            JointPass::Velocity => BAUMGARTE / dt,
+            JointPass::Position { .. } => 1.0,
+            JointPass::XpbdVelocity => 0.0,
+        }
+    }
+
+    fn motors(self) -> bool {
+        !matches!(self, JointPass::Position { .. })
+    }
+
+    /// Soften the rigid rows of a position pass by its compliance
+    fn soften(self, mut constraint: JointConstraint, dt: f64) -> JointConstraint {
+        if let JointPass::Position { compliance } = self {
+            if compliance > 0.0 {
+                let softness = compliance / (dt * dt);
+                constraint.point = constraint.point.map(|p| p.with_softness(softness));
+                constraint.rows = constraint.rows.into_iter().map(|r| r.with_softness(softness)).collect();
+            }
+        }
+        constraint
+    }
+}
+
+/// Solver rows for one step of a joint
+pub(crate) struct JointConstraint {
+    /// Anchors held together, solved before the rows
+    pub point: Option<PointConstraint>,
+    pub rows: Vec<ConstraintRow>,
+    /// Index of the motor row, if the motor is enabled
+    pub motor: Option<usize>,
+}
+
+/// Impulses a joint applied to body b during the last step, body a received the opposite
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct JointReaction {
+    pub impulse: Vec2,
+    /// Impulse of the purely angular rows, such as motors and angle limits
+    pub angular_impulse: f64,
+}
+
+impl JointReaction {
+    fn from_constraint(constraint: &JointConstraint) -> JointReaction {
+        let mut reaction = JointReaction {
+            impulse: constraint.point.map_or(Vec2::new(0.0, 0.0), |p| p.impulse),
+            angular_impulse: 0.0,
+        };
+        for row in &constraint.rows {
+            let linear = row.jacobian.linear_b;
+            if linear == Vec2::new(0.0, 0.0) {
+                reaction.angular_impulse += row.jacobian.angular_b * row.impulse;
+            } else {
+                reaction.impulse = reaction.impulse + linear * row.impulse;
+            }
+        }
+        reaction
+    }
+}
+
+impl Binary for JointReaction {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.impulse);
+        w.put(&self.angular_impulse);
+    }
+
+    fn decode(r: &mut Reader) -> Result<JointReaction, SerializeError> {
+        Ok(JointReaction {
+            impulse: r.get()?,
+            angular_impulse: r.get()?,
+        })
+    }
+}
+
+impl Joint {
+    pub fn body_a(&self) -> BodyHandle {
+        match self {
+            Joint::Revolute(j) => j.body_a(),
+            Joint::Angle(j) => j.body_a(),
+            Joint::Prismatic(j) => j.body_a(),
+            Joint::Mouse(j) => j.body_a(),
+        }
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        match self {
+            Joint::Revolute(j) => j.body_b(),
+            Joint::Angle(j) => j.body_b(),
+            Joint::Prismatic(j) => j.body_b(),
+            Joint::Mouse(j) => j.body_b(),
+        }
+    }
+
+    pub(crate) fn prepare(
+        &self,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+        pass: JointPass,
+    ) -> JointConstraint {
+        match self {
+            Joint::Revolute(j) => j.prepare(a, b, dt, pass),
+            Joint::Angle(j) => j.prepare(a, b, dt, pass),
+            Joint::Prismatic(j) => j.prepare(a, b, dt, pass),
+            Joint::Mouse(j) => j.prepare(a, b, dt, pass),
+        }
+    }
+
+    pub(crate) fn finish(
+        &mut self,
+        constraint: &JointConstraint,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+    ) -> Vec<JointEventKind> {
+        *self.reaction_mut() = JointReaction::from_constraint(constraint);
+        match self {
+            Joint::Revolute(j) => j.finish(constraint, a, b, dt),
+            Joint::Angle(j) => j.finish(),
+            Joint::Prismatic(j) => j.finish(constraint, a, b, dt),
+            Joint::Mouse(j) => j.finish(),
+        }
+    }
+
+    /// Add the Lagrange multipliers of an XPBD position pass to the reaction, as the impulses
+    /// they amount to over dt
+    pub(crate) fn add_position_reaction(&mut self, constraint: &JointConstraint, dt: f64) {
+        let position = JointReaction::from_constraint(constraint);
+        let reaction = self.reaction_mut();
+        reaction.impulse = reaction.impulse + position.impulse / dt;
+        reaction.angular_impulse += position.angular_impulse / dt;
+    }
+
+    /// Force the joint applied to body b over the last step, given 1 / dt of that step
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2 {
+        self.reaction().impulse * inv_dt
+    }
+
+    /// Torque the joint's motor, limit or angle rows applied to body b over the last step, given
+    /// 1 / dt of that step. Torque from the anchor force acting off b's center isn't included.
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        self.reaction().angular_impulse * inv_dt
+    }
+
+    /// The joint's anchor on a in world space, a's position for joints without anchors.
+    /// A mouse joint's anchor on a is its target.
+    pub fn anchor_a(&self, a: &RigidBody) -> Vec2 {
+        match self {
+            Joint::Revolute(j) => a.transform().apply(&j.local_anchor_a()),
+            Joint::Prismatic(j) => a.transform().apply(&j.local_anchor_a()),
+            Joint::Mouse(j) => j.target(),
+            Joint::Angle(_) => a.position(),
+        }
+    }
+
+    /// The joint's anchor on b in world space, b's position for joints without anchors
+    pub fn anchor_b(&self, b: &RigidBody) -> Vec2 {
+        match self {
+            Joint::Revolute(j) => b.transform().apply(&j.local_anchor_b()),
+            Joint::Prismatic(j) => b.transform().apply(&j.local_anchor_b()),
+            Joint::Mouse(j) => b.transform().apply(&j.local_anchor_b()),
+            Joint::Angle(_) => b.position(),
+        }
+    }
+
+    /// Value the game stored on the joint, see RevoluteJoint::user_data
+    pub fn user_data(&self) -> u64 {
+        match self {
+            Joint::Revolute(j) => j.user_data,
+            Joint::Angle(j) => j.user_data,
+            Joint::Prismatic(j) => j.user_data,
+            Joint::Mouse(j) => j.user_data,
+        }
+    }
+
+    pub fn set_user_data(&mut self, user_data: u64) {
+        match self {
+            Joint::Revolute(j) => j.user_data = user_data,
+            Joint::Angle(j) => j.user_data = user_data,
+            Joint::Prismatic(j) => j.user_data = user_data,
+            Joint::Mouse(j) => j.user_data = user_data,
+        }
+    }
+
+    fn reaction(&self) -> JointReaction {
+        match self {
+            Joint::Revolute(j) => j.reaction,
+            Joint::Angle(j) => j.reaction,
+            Joint::Prismatic(j) => j.reaction,
+            Joint::Mouse(j) => j.reaction,
+        }
+    }
+
+    fn reaction_mut(&mut self) -> &mut JointReaction {
+        match self {
+            Joint::Revolute(j) => &mut j.reaction,
+            Joint::Angle(j) => &mut j.reaction,
+            Joint::Prismatic(j) => &mut j.reaction,
+            Joint::Mouse(j) => &mut j.reaction,
+        }
+    }
+
+    /// Saved joint with its bodies given as positions in the saved body list
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let (mut v, kind) = match self {
+            Joint::Revolute(j) => (j.to_value(body_a, body_b), "revolute"),
+            Joint::Angle(j) => (j.to_value(body_a, body_b), "angle"),
+            Joint::Prismatic(j) => (j.to_value(body_a, body_b), "prismatic"),
+            Joint::Mouse(j) => (j.to_value(body_a, body_b), "mouse"),
+        };
+        v.insert("type", kind.into());
+        v.insert("user_data", serialize::user_data_to_value(self.user_data()));
+        v
+    }
+
+    /// Read a joint, looking up its bodies in the handles of the loaded bodies
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<Joint, SerializeError> {
+        let mut joint = match v.field_str("type")? {
+            "revolute" => Joint::Revolute(RevoluteJoint::from_value(v, bodies)?),
+            "angle" => Joint::Angle(AngleJoint::from_value(v, bodies)?),
+            "prismatic" => Joint::Prismatic(PrismaticJoint::from_value(v, bodies)?),
+            "mouse" => Joint::Mouse(MouseJoint::from_value(v, bodies)?),
+            other => return Err(SerializeError::InvalidValue(format!("unknown joint type {}", other))),
+        };
+        if joint.body_a() == joint.body_b() {
+            return Err(SerializeError::InvalidValue(format!(
+                "joint between body {} and itself",
+                v.field_f64("body_a")?
+            )));
+        }
+        joint.set_user_data(serialize::user_data_from_value(v)?);
+        Ok(joint)
+    }
+}
+
+fn body_from_value(v: &Value, key: &str, bodies: &[BodyHandle]) -> Result<BodyHandle, SerializeError> {
+    let index = v.field_f64(key)?;
+    if index < 0.0 || index.fract() != 0.0 {
+        return Err(SerializeError::InvalidValue(format!("no body {} for joint", index)));
+    }
+    bodies
+        .get(index as usize)
+        .copied()
+        .ok_or_else(|| SerializeError::InvalidValue(format!("no body {} for joint", index)))
+}
+
+impl Binary for Joint {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Joint::Revolute(j) => {
+                w.put(&0u8);
+                w.put(j);
+            }
+            Joint::Angle(j) => {
+                w.put(&1u8);
+                w.put(j);
+            }
+            Joint::Prismatic(j) => {
+                w.put(&2u8);
+                w.put(j);
+            }
+            Joint::Mouse(j) => {
+                w.put(&3u8);
+                w.put(j);
+            }
+        }
+        w.put(&self.user_data());
+    }
+
+    fn decode(r: &mut Reader) -> Result<Joint, SerializeError> {
+        let mut joint = match r.get::<u8>()? {
+            0 => Joint::Revolute(r.get()?),
+            1 => Joint::Angle(r.get()?),
+            2 => Joint::Prismatic(r.get()?),
+            3 => Joint::Mouse(r.get()?),
+            tag => return Err(r.invalid_tag("joint", tag)),
+        };
+        joint.set_user_data(r.get()?);
+        Ok(joint)
+    }
+}
@@ -0,0 +1,191 @@
+use wasm_bindgen::prelude::*;
+
+use super::{JointConstraint, JointEventKind, JointPass, JointReaction};
+use crate::math::Vec2;
+use crate::physics::body::RigidBody;
+use crate::physics::solver::{ConstraintRow, Jacobian, SolverBody};
+use crate::physics::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// Pulls an anchor on b towards a target point in world space like a damped spring, for dragging
+/// bodies with the pointer. Body a isn't moved and is usually a static ground body.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_b: Vec2,
+    target: Vec2,
+    /// How fast the anchor springs to the target, in Hz
+    pub frequency: f64,
+    /// 1 to reach the target without overshooting, less to bounce around it
+    pub damping_ratio: f64,
+    /// Largest force on each axis, keeping a fast pointer from flinging the body
+    pub max_force: f64,
+    /// Value the game stores on the joint to find it again. 0 by default.
+    pub user_data: u64,
+    pub(crate) reaction: JointReaction,
+}
+
+#[wasm_bindgen]
+impl MouseJoint {
+    /// The anchor is given in b's local coordinates and the target in world coordinates
+    #[wasm_bindgen(constructor)]
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, local_anchor_b: Vec2, target: Vec2) -> MouseJoint {
+        MouseJoint {
+            body_a,
+            body_b,
+            local_anchor_b,
+            target,
+            frequency: 5.0,
+            damping_ratio: 0.7,
+            max_force: 1000.0,
+            user_data: 0,
+            reaction: JointReaction::default(),
+        }
+    }
+
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    pub fn local_anchor_b(&self) -> Vec2 {
+        self.local_anchor_b
+    }
+
+    pub fn target(&self) -> Vec2 {
+        self.target
+    }
+
+    /// Move the point the anchor is pulled towards
+    pub fn set_target(&mut self, x: f64, y: f64) {
+        self.target = Vec2::new(x, y);
+    }
+}
+
+impl MouseJoint {
+    /// A soft row along each world axis pulling b's anchor to the target. Under XPBD the
+    /// position pass pulls with the spring's stiffness as compliance and the velocity pass
+    /// after it adds the damping.
+    pub(crate) fn prepare(
+        &self,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+        pass: JointPass,
+    ) -> JointConstraint {
+        let (body_b, sb) = b;
+        let rb = self.local_anchor_b.rotate(body_b.rotation());
+        let error = body_b.position() + rb - self.target;
+        let omega = 2.0 * std::f64::consts::PI * self.frequency;
+        let stiffness = body_b.mass() * omega * omega;
+        let damping = 2.0 * body_b.mass() * self.damping_ratio * omega;
+        // Lagrange multipliers of the position pass are impulses times dt
+        let max_impulse = match pass {
+            JointPass::Position { .. } => self.max_force * dt * dt,
+            _ => self.max_force * dt,
+        };
+        let axis = |n: Vec2, error: f64| {
+            let jacobian = Jacobian {
+                linear_a: Vec2::new(0.0, 0.0),
+                angular_a: 0.0,
+                ..Jacobian::along(&n, &Vec2::new(0.0, 0.0), &rb)
+            };
+            let row = match pass {
+                JointPass::Velocity => Some(ConstraintRow::spring(
+                    jacobian,
+                    a.1,
+                    sb,
+                    error,
+                    body_b.mass(),
+                    self.frequency,
+                    self.damping_ratio,
+                    dt,
+                )),
+                JointPass::Position { .. } if stiffness > 0.0 => {
+                    Some(ConstraintRow::equality(jacobian, a.1, sb, error).with_softness(1.0 / (stiffness * dt * dt)))
+                }
+                JointPass::XpbdVelocity if stiffness > 0.0 => {
+                    (damping > 0.0).then(|| ConstraintRow::equality(jacobian, a.1, sb, 0.0).with_softness(1.0 / (damping * dt)))
+                }
+                // A frequency of 0 holds the anchor rigidly
+                _ => Some(ConstraintRow::equality(jacobian, a.1, sb, pass.bias_scale(dt) * error)),
+            };
+            row.map(|mut row| {
+                row.lower = -max_impulse;
+                row.upper = max_impulse;
+                row
+            })
+        };
+        JointConstraint {
+            point: None,
+            rows: axis(Vec2::new(1.0, 0.0), error.x).into_iter().chain(axis(Vec2::new(0.0, 1.0), error.y)).collect(),
+            motor: None,
+        }
+    }
+
+    /// Copy the settings and target of other, keeping bodies and anchor
+    pub(crate) fn set_params(&mut self, other: &MouseJoint) {
+        self.target = other.target;
+        self.frequency = other.frequency;
+        self.damping_ratio = other.damping_ratio;
+        self.max_force = other.max_force;
+    }
+
+    /// Mouse joints have no limit or motor to report on
+    pub(crate) fn finish(&mut self) -> Vec<JointEventKind> {
+        Vec::new()
+    }
+
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let mut v = Value::object();
+        v.insert("body_a", (body_a as f64).into());
+        v.insert("body_b", (body_b as f64).into());
+        v.insert("local_anchor_b", serialize::vec2_to_value(&self.local_anchor_b));
+        v.insert("target", serialize::vec2_to_value(&self.target));
+        v.insert("frequency", self.frequency.into());
+        v.insert("damping_ratio", self.damping_ratio.into());
+        v.insert("max_force", self.max_force.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<MouseJoint, SerializeError> {
+        let mut joint = MouseJoint::new(
+            super::body_from_value(v, "body_a", bodies)?,
+            super::body_from_value(v, "body_b", bodies)?,
+            serialize::vec2_from_value(v.field("local_anchor_b")?)?,
+            serialize::vec2_from_value(v.field("target")?)?,
+        );
+        joint.frequency = v.field_f64("frequency")?;
+        joint.damping_ratio = v.field_f64("damping_ratio")?;
+        joint.max_force = v.field_f64("max_force")?;
+        Ok(joint)
+    }
+}
+
+impl Binary for MouseJoint {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.local_anchor_b);
+        w.put(&self.target);
+        w.put(&self.frequency);
+        w.put(&self.damping_ratio);
+        w.put(&self.max_force);
+        w.put(&self.reaction);
+    }
+
+    fn decode(r: &mut Reader) -> Result<MouseJoint, SerializeError> {
+        let mut joint = MouseJoint::new(r.get()?, r.get()?, r.get()?, r.get()?);
+        joint.frequency = r.get()?;
+        joint.damping_ratio = r.get()?;
+        joint.max_force = r.get()?;
+        joint.reaction = r.get()?;
+        Ok(joint)
+    }
+}
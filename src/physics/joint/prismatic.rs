@@ -0,0 +1,332 @@
+use wasm_bindgen::prelude::*;
+
+use super::{JointConstraint, JointEventKind, JointPass, JointReaction};
+use crate::math::Vec2;
+use crate::physics::body::RigidBody;
+use crate::physics::solver::{ConstraintRow, Jacobian, SolverBody};
+use crate::physics::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// Distance within which a joint counts as resting on its limit
+pub const LINEAR_SLOP: f64 = 0.005;
+/// Relative speed within which a motor counts as keeping up with its target
+pub const STALL_SPEED_TOLERANCE: f64 = 0.05;
+
+/// Which limit a joint is resting on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LimitState {
+    Free,
+    Lower,
+    Upper,
+}
+
+/// Lets b slide along an axis fixed in a, keeping their relative rotation.
+/// Translation can be bounded by a limit and driven by a motor with a maximum force.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrismaticJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2,
+    local_anchor_b: Vec2,
+    local_axis: Vec2,
+    /// Rotation of b relative to a that the joint holds
+    pub reference_angle: f64,
+    pub enable_limit: bool,
+    pub lower_translation: f64,
+    pub upper_translation: f64,
+    pub enable_motor: bool,
+    /// Target speed of b along the axis relative to a
+    pub motor_speed: f64,
+    pub max_motor_force: f64,
+    /// Solver steps the motor has to be saturated and behind its target before it counts as stalled
+    pub stall_steps: u32,
+    limit_state: LimitState,
+    saturated_steps: u32,
+    stalled: bool,
+    /// Value the game stores on the joint to find it again. 0 by default.
+    pub user_data: u64,
+    pub(crate) reaction: JointReaction,
+}
+
+#[wasm_bindgen]
+impl PrismaticJoint {
+    /// Anchors and axis are given in each body's local coordinates, the axis in a's.
+    /// The axis is normalized.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        body_a: BodyHandle,
+        body_b: BodyHandle,
+        local_anchor_a: Vec2,
+        local_anchor_b: Vec2,
+        local_axis: Vec2,
+    ) -> PrismaticJoint {
+        PrismaticJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            local_axis: local_axis.normalize(),
+            reference_angle: 0.0,
+            enable_limit: false,
+            lower_translation: 0.0,
+            upper_translation: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_force: 0.0,
+            stall_steps: 10,
+            limit_state: LimitState::Free,
+            saturated_steps: 0,
+            stalled: false,
+            user_data: 0,
+            reaction: JointReaction::default(),
+        }
+    }
+
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    pub fn local_anchor_a(&self) -> Vec2 {
+        self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> Vec2 {
+        self.local_anchor_b
+    }
+
+    pub fn local_axis(&self) -> Vec2 {
+        self.local_axis
+    }
+
+    /// Distance of b's anchor from a's along the axis
+    pub fn translation(&self, a: &RigidBody, b: &RigidBody) -> f64 {
+        let (_, d, axis) = self.frame(a, b);
+        Vec2::dot(&d, &axis)
+    }
+
+    /// True while the limit is enabled and the joint rests on it
+    pub fn at_limit(&self) -> bool {
+        self.limit_state != LimitState::Free
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+}
+
+impl PrismaticJoint {
+    /// Rows keeping b on the axis at the reference angle, with motor and limit rows when enabled
+    pub(crate) fn prepare(
+        &self,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+        pass: JointPass,
+    ) -> JointConstraint {
+        let (body_a, sa) = a;
+        let (body_b, sb) = b;
+        let (ra, d, axis) = self.frame(body_a, body_b);
+        let rb = self.local_anchor_b.rotate(body_b.rotation());
+        let perp = axis.perp();
+        // Body a's lever arm reaches b's anchor, since the axis turns with a
+        let along = |n: &Vec2| Jacobian::along(n, &(ra + d), &rb);
+
+        let angle = body_b.rotation() - body_a.rotation() - self.reference_angle;
+        let scale = pass.bias_scale(dt);
+        let mut rows = vec![
+            ConstraintRow::equality(along(&perp), sa, sb, scale * Vec2::dot(&d, &perp)),
+            ConstraintRow::equality(Jacobian::angular(), sa, sb, scale * angle),
+        ];
+
+        let mut motor = None;
+        if self.enable_motor && pass.motors() {
+            let max_impulse = self.max_motor_force * dt;
+            motor = Some(rows.len());
+            rows.push(ConstraintRow::new(along(&axis), sa, sb, -self.motor_speed, -max_impulse, max_impulse));
+        }
+
+        if self.enable_limit {
+            let translation = Vec2::dot(&d, &axis);
+            if translation <= self.lower_translation + LINEAR_SLOP {
+                let bias = scale * (translation - self.lower_translation).min(0.0);
+                rows.push(ConstraintRow::new(along(&axis), sa, sb, bias, 0.0, f64::INFINITY));
+            } else if translation >= self.upper_translation - LINEAR_SLOP {
+                let bias = scale * (self.upper_translation - translation).min(0.0);
+                rows.push(ConstraintRow::new(along(&-axis), sa, sb, bias, 0.0, f64::INFINITY));
+            }
+        }
+
+        pass.soften(
+            JointConstraint {
+                point: None,
+                rows,
+                motor,
+            },
+            dt,
+        )
+    }
+
+    /// a's anchor offset, the offset from a's anchor to b's and the axis, all in world space
+    fn frame(&self, a: &RigidBody, b: &RigidBody) -> (Vec2, Vec2, Vec2) {
+        let ra = self.local_anchor_a.rotate(a.rotation());
+        let rb = self.local_anchor_b.rotate(b.rotation());
+        let d = (b.position() + rb) - (a.position() + ra);
+        (ra, d, self.local_axis.rotate(a.rotation()))
+    }
+
+    /// Copy the settings of other, keeping bodies, anchors, axis and runtime state
+    pub(crate) fn set_params(&mut self, other: &PrismaticJoint) {
+        self.reference_angle = other.reference_angle;
+        self.enable_limit = other.enable_limit;
+        self.lower_translation = other.lower_translation;
+        self.upper_translation = other.upper_translation;
+        self.enable_motor = other.enable_motor;
+        self.motor_speed = other.motor_speed;
+        self.max_motor_force = other.max_motor_force;
+        self.stall_steps = other.stall_steps;
+    }
+
+    /// Update limit and stall tracking after solving, returning any changes
+    pub(crate) fn finish(
+        &mut self,
+        constraint: &JointConstraint,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+    ) -> Vec<JointEventKind> {
+        let mut events = Vec::new();
+
+        let limit_state = if !self.enable_limit {
+            LimitState::Free
+        } else {
+            let translation = self.translation(a.0, b.0);
+            if translation <= self.lower_translation + LINEAR_SLOP {
+                LimitState::Lower
+            } else if translation >= self.upper_translation - LINEAR_SLOP {
+                LimitState::Upper
+            } else {
+                LimitState::Free
+            }
+        };
+        if limit_state != self.limit_state {
+            if self.limit_state != LimitState::Free {
+                events.push(JointEventKind::LimitLeft);
+            }
+            if limit_state != LimitState::Free {
+                events.push(JointEventKind::LimitReached);
+            }
+            self.limit_state = limit_state;
+        }
+
+        let saturated = constraint.motor.is_some_and(|i| {
+            let row = &constraint.rows[i];
+            let speed = row.jacobian.velocity(a.1, b.1);
+            let max_impulse = self.max_motor_force * dt;
+            row.impulse.abs() >= max_impulse * (1.0 - 1e-9) && (speed - self.motor_speed).abs() > STALL_SPEED_TOLERANCE
+        });
+        self.saturated_steps = if saturated { self.saturated_steps.saturating_add(1) } else { 0 };
+        let stalled = self.saturated_steps >= self.stall_steps.max(1);
+        if stalled != self.stalled {
+            events.push(if stalled {
+                JointEventKind::MotorStalled
+            } else {
+                JointEventKind::MotorRecovered
+            });
+            self.stalled = stalled;
+        }
+        events
+    }
+
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let mut v = Value::object();
+        v.insert("body_a", (body_a as f64).into());
+        v.insert("body_b", (body_b as f64).into());
+        v.insert("local_anchor_a", serialize::vec2_to_value(&self.local_anchor_a));
+        v.insert("local_anchor_b", serialize::vec2_to_value(&self.local_anchor_b));
+        v.insert("local_axis", serialize::vec2_to_value(&self.local_axis));
+        v.insert("reference_angle", self.reference_angle.into());
+        v.insert("enable_limit", self.enable_limit.into());
+        v.insert("lower_translation", self.lower_translation.into());
+        v.insert("upper_translation", self.upper_translation.into());
+        v.insert("enable_motor", self.enable_motor.into());
+        v.insert("motor_speed", self.motor_speed.into());
+        v.insert("max_motor_force", self.max_motor_force.into());
+        v.insert("stall_steps", (self.stall_steps as f64).into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<PrismaticJoint, SerializeError> {
+        let mut joint = PrismaticJoint::new(
+            super::body_from_value(v, "body_a", bodies)?,
+            super::body_from_value(v, "body_b", bodies)?,
+            serialize::vec2_from_value(v.field("local_anchor_a")?)?,
+            serialize::vec2_from_value(v.field("local_anchor_b")?)?,
+            serialize::vec2_from_value(v.field("local_axis")?)?,
+        );
+        joint.reference_angle = v.field_f64("reference_angle")?;
+        joint.enable_limit = v.field_bool("enable_limit")?;
+        joint.lower_translation = v.field_f64("lower_translation")?;
+        joint.upper_translation = v.field_f64("upper_translation")?;
+        joint.enable_motor = v.field_bool("enable_motor")?;
+        joint.motor_speed = v.field_f64("motor_speed")?;
+        joint.max_motor_force = v.field_f64("max_motor_force")?;
+        joint.stall_steps = v.field_f64("stall_steps")? as u32;
+        Ok(joint)
+    }
+}
+
+impl Binary for PrismaticJoint {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.local_anchor_a);
+        w.put(&self.local_anchor_b);
+        w.put(&self.local_axis);
+        w.put(&self.reference_angle);
+        w.put(&self.enable_limit);
+        w.put(&self.lower_translation);
+        w.put(&self.upper_translation);
+        w.put(&self.enable_motor);
+        w.put(&self.motor_speed);
+        w.put(&self.max_motor_force);
+        w.put(&self.stall_steps);
+        let limit: u8 = match self.limit_state {
+            LimitState::Free => 0,
+            LimitState::Lower => 1,
+            LimitState::Upper => 2,
+        };
+        w.put(&limit);
+        w.put(&self.saturated_steps);
+        w.put(&self.stalled);
+        w.put(&self.reaction);
+    }
+
+    fn decode(r: &mut Reader) -> Result<PrismaticJoint, SerializeError> {
+        let mut joint = PrismaticJoint::new(r.get()?, r.get()?, r.get()?, r.get()?, Vec2::new(1.0, 0.0));
+        joint.local_axis = r.get()?;
+        joint.reference_angle = r.get()?;
+        joint.enable_limit = r.get()?;
+        joint.lower_translation = r.get()?;
+        joint.upper_translation = r.get()?;
+        joint.enable_motor = r.get()?;
+        joint.motor_speed = r.get()?;
+        joint.max_motor_force = r.get()?;
+        joint.stall_steps = r.get()?;
+        joint.limit_state = match r.get::<u8>()? {
+            0 => LimitState::Free,
+            1 => LimitState::Lower,
+            2 => LimitState::Upper,
+            tag => return Err(r.invalid_tag("limit state", tag)),
+        };
+        joint.saturated_steps = r.get()?;
+        joint.stalled = r.get()?;
+        joint.reaction = r.get()?;
+        Ok(joint)
+    }
+}
@@ -0,0 +1,307 @@
+use wasm_bindgen::prelude::*;
+
+use super::{JointConstraint, JointEventKind, JointPass, JointReaction};
+use crate::math::Vec2;
+use crate::physics::body::RigidBody;
+use crate::physics::solver::{ConstraintRow, Jacobian, PointConstraint, SolverBody};
+use crate::physics::world::BodyHandle;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// Angle within which a joint counts as resting on its limit
+pub const ANGULAR_SLOP: f64 = 0.01;
+/// Relative speed within which a motor counts as keeping up with its target
+pub const STALL_SPEED_TOLERANCE: f64 = 0.05;
+
+/// Which limit a joint is resting on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LimitState {
+    Free,
+    Lower,
+    Upper,
+}
+
+/// Pins an anchor on each body together, leaving rotation free.
+/// Rotation can be bounded by a limit and driven by a motor with a maximum torque.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RevoluteJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2,
+    local_anchor_b: Vec2,
+    /// Rotation of b relative to a at which the joint angle is 0
+    pub reference_angle: f64,
+    pub enable_limit: bool,
+    pub lower_angle: f64,
+    pub upper_angle: f64,
+    pub enable_motor: bool,
+    /// Target relative angular velocity of b with respect to a
+    pub motor_speed: f64,
+    pub max_motor_torque: f64,
+    /// Solver steps the motor has to be saturated and behind its target before it counts as stalled
+    pub stall_steps: u32,
+    limit_state: LimitState,
+    saturated_steps: u32,
+    stalled: bool,
+    /// Value the game stores on the joint to find it again. 0 by default.
+    pub user_data: u64,
+    pub(crate) reaction: JointReaction,
+}
+
+#[wasm_bindgen]
+impl RevoluteJoint {
+    /// Anchors are given in each body's local coordinates
+    #[wasm_bindgen(constructor)]
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, local_anchor_a: Vec2, local_anchor_b: Vec2) -> RevoluteJoint {
+        RevoluteJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            reference_angle: 0.0,
+            enable_limit: false,
+            lower_angle: 0.0,
+            upper_angle: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_torque: 0.0,
+            stall_steps: 10,
+            limit_state: LimitState::Free,
+            saturated_steps: 0,
+            stalled: false,
+            user_data: 0,
+            reaction: JointReaction::default(),
+        }
+    }
+
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    pub fn local_anchor_a(&self) -> Vec2 {
+        self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> Vec2 {
+        self.local_anchor_b
+    }
+
+    /// Rotation of b relative to a, minus the reference angle
+    pub fn angle(&self, a: &RigidBody, b: &RigidBody) -> f64 {
+        b.rotation() - a.rotation() - self.reference_angle
+    }
+
+    /// True while the limit is enabled and the joint rests on it
+    pub fn at_limit(&self) -> bool {
+        self.limit_state != LimitState::Free
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+}
+
+impl RevoluteJoint {
+    /// Constraint keeping the anchors together, with motor and limit rows when enabled
+    pub(crate) fn prepare(
+        &self,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+        pass: JointPass,
+    ) -> JointConstraint {
+        let (body_a, sa) = a;
+        let (body_b, sb) = b;
+        let ra = self.local_anchor_a.rotate(body_a.rotation());
+        let rb = self.local_anchor_b.rotate(body_b.rotation());
+        let error = (body_b.position() + rb) - (body_a.position() + ra);
+        let scale = pass.bias_scale(dt);
+
+        let point = PointConstraint::new(sa, sb, ra, rb, error * scale);
+
+        let mut rows = Vec::with_capacity(2);
+
+        let mut motor = None;
+        if self.enable_motor && pass.motors() {
+            let max_impulse = self.max_motor_torque * dt;
+            motor = Some(rows.len());
+            rows.push(ConstraintRow::new(Jacobian::angular(), sa, sb, -self.motor_speed, -max_impulse, max_impulse));
+        }
+
+        if self.enable_limit {
+            let angle = self.angle(body_a, body_b);
+            if angle <= self.lower_angle + ANGULAR_SLOP {
+                let bias = scale * (angle - self.lower_angle).min(0.0);
+                rows.push(ConstraintRow::new(Jacobian::angular(), sa, sb, bias, 0.0, f64::INFINITY));
+            } else if angle >= self.upper_angle - ANGULAR_SLOP {
+                let reversed = Jacobian {
+                    angular_a: 1.0,
+                    angular_b: -1.0,
+                    ..Jacobian::angular()
+                };
+                let bias = scale * (self.upper_angle - angle).min(0.0);
+                rows.push(ConstraintRow::new(reversed, sa, sb, bias, 0.0, f64::INFINITY));
+            }
+        }
+
+        pass.soften(
+            JointConstraint {
+                point: Some(point),
+                rows,
+                motor,
+            },
+            dt,
+        )
+    }
+
+    /// Copy the settings of other, keeping bodies, anchors and runtime state
+    pub(crate) fn set_params(&mut self, other: &RevoluteJoint) {
+        self.reference_angle = other.reference_angle;
+        self.enable_limit = other.enable_limit;
+        self.lower_angle = other.lower_angle;
+        self.upper_angle = other.upper_angle;
+        self.enable_motor = other.enable_motor;
+        self.motor_speed = other.motor_speed;
+        self.max_motor_torque = other.max_motor_torque;
+        self.stall_steps = other.stall_steps;
+    }
+
+    /// Update limit and stall tracking after solving, returning any changes
+    pub(crate) fn finish(
+        &mut self,
+        constraint: &JointConstraint,
+        a: (&RigidBody, &SolverBody),
+        b: (&RigidBody, &SolverBody),
+        dt: f64,
+    ) -> Vec<JointEventKind> {
+        let mut events = Vec::new();
+
+        let limit_state = if !self.enable_limit {
+            LimitState::Free
+        } else {
+            let angle = self.angle(a.0, b.0);
+            if angle <= self.lower_angle + ANGULAR_SLOP {
+                LimitState::Lower
+            } else if angle >= self.upper_angle - ANGULAR_SLOP {
+                LimitState::Upper
+            } else {
+                LimitState::Free
+            }
+        };
+        if limit_state != self.limit_state {
+            if self.limit_state != LimitState::Free {
+                events.push(JointEventKind::LimitLeft);
+            }
+            if limit_state != LimitState::Free {
+                events.push(JointEventKind::LimitReached);
+            }
+            self.limit_state = limit_state;
+        }
+
+        let saturated = constraint.motor.is_some_and(|i| {
+            let row = &constraint.rows[i];
+            let speed = b.1.angular_velocity - a.1.angular_velocity;
+            let max_impulse = self.max_motor_torque * dt;
+            row.impulse.abs() >= max_impulse * (1.0 - 1e-9) && (speed - self.motor_speed).abs() > STALL_SPEED_TOLERANCE
+        });
+        self.saturated_steps = if saturated { self.saturated_steps.saturating_add(1) } else { 0 };
+        let stalled = self.saturated_steps >= self.stall_steps.max(1);
+        if stalled != self.stalled {
+            events.push(if stalled {
+                JointEventKind::MotorStalled
+            } else {
+                JointEventKind::MotorRecovered
+            });
+            self.stalled = stalled;
+        }
+        events
+    }
+
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let mut v = Value::object();
+        v.insert("body_a", (body_a as f64).into());
+        v.insert("body_b", (body_b as f64).into());
+        v.insert("local_anchor_a", serialize::vec2_to_value(&self.local_anchor_a));
+        v.insert("local_anchor_b", serialize::vec2_to_value(&self.local_anchor_b));
+        v.insert("reference_angle", self.reference_angle.into());
+        v.insert("enable_limit", self.enable_limit.into());
+        v.insert("lower_angle", self.lower_angle.into());
+        v.insert("upper_angle", self.upper_angle.into());
+        v.insert("enable_motor", self.enable_motor.into());
+        v.insert("motor_speed", self.motor_speed.into());
+        v.insert("max_motor_torque", self.max_motor_torque.into());
+        v.insert("stall_steps", (self.stall_steps as f64).into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<RevoluteJoint, SerializeError> {
+        let mut joint = RevoluteJoint::new(
+            super::body_from_value(v, "body_a", bodies)?,
+            super::body_from_value(v, "body_b", bodies)?,
+            serialize::vec2_from_value(v.field("local_anchor_a")?)?,
+            serialize::vec2_from_value(v.field("local_anchor_b")?)?,
+        );
+        joint.reference_angle = v.field_f64("reference_angle")?;
+        joint.enable_limit = v.field_bool("enable_limit")?;
+        joint.lower_angle = v.field_f64("lower_angle")?;
+        joint.upper_angle = v.field_f64("upper_angle")?;
+        joint.enable_motor = v.field_bool("enable_motor")?;
+        joint.motor_speed = v.field_f64("motor_speed")?;
+        joint.max_motor_torque = v.field_f64("max_motor_torque")?;
+        joint.stall_steps = v.field_f64("stall_steps")? as u32;
+        Ok(joint)
+    }
+}
+
+impl Binary for RevoluteJoint {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.local_anchor_a);
+        w.put(&self.local_anchor_b);
+        w.put(&self.reference_angle);
+        w.put(&self.enable_limit);
+        w.put(&self.lower_angle);
+        w.put(&self.upper_angle);
+        w.put(&self.enable_motor);
+        w.put(&self.motor_speed);
+        w.put(&self.max_motor_torque);
+        w.put(&self.stall_steps);
+        let limit: u8 = match self.limit_state {
+            LimitState::Free => 0,
+            LimitState::Lower => 1,
+            LimitState::Upper => 2,
+        };
+        w.put(&limit);
+        w.put(&self.saturated_steps);
+        w.put(&self.stalled);
+        w.put(&self.reaction);
+    }
+
+    fn decode(r: &mut Reader) -> Result<RevoluteJoint, SerializeError> {
+        let mut joint = RevoluteJoint::new(r.get()?, r.get()?, r.get()?, r.get()?);
+        joint.reference_angle = r.get()?;
+        joint.enable_limit = r.get()?;
+        joint.lower_angle = r.get()?;
+        joint.upper_angle = r.get()?;
+        joint.enable_motor = r.get()?;
+        joint.motor_speed = r.get()?;
+        joint.max_motor_torque = r.get()?;
+        joint.stall_steps = r.get()?;
+        joint.limit_state = match r.get::<u8>()? {
+            0 => LimitState::Free,
+            1 => LimitState::Lower,
+            2 => LimitState::Upper,
+            tag => return Err(r.invalid_tag("limit state", tag)),
+        };
+        joint.saturated_steps = r.get()?;
+        joint.stalled = r.get()?;
+        joint.reaction = r.get()?;
+        Ok(joint)
+    }
+}
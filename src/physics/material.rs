@@ -0,0 +1,204 @@
+//! Surface and mass properties of bodies.
+//!
+//! Each body has a Material. When two bodies touch, their friction and restitution are combined
+//! into one value for the contact by a CombineRule. If the two materials ask for different rules
+//! the one listed later in CombineRule wins, so a Max material always gets its way.
+
+use wasm_bindgen::prelude::*;
+
+use super::contact::{DEFAULT_FRICTION, DEFAULT_RESTITUTION};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{SerializeError, Value};
+
+/// How the values of two touching materials are combined
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CombineRule {
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    /// Combine a and b with the stronger of the two rules
+    pub fn combine(rule_a: CombineRule, a: f64, rule_b: CombineRule, b: f64) -> f64 {
+        match rule_a.max(rule_b) {
+            CombineRule::Average => (a + b) / 2.0,
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            CombineRule::Average => "average",
+            CombineRule::Min => "min",
+            CombineRule::Multiply => "multiply",
+            CombineRule::Max => "max",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<CombineRule, SerializeError> {
+        match name {
+            "average" => Ok(CombineRule::Average),
+            "min" => Ok(CombineRule::Min),
+            "multiply" => Ok(CombineRule::Multiply),
+            "max" => Ok(CombineRule::Max),
+            other => Err(SerializeError::InvalidValue(format!("unknown combine rule '{}'", other))),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Coulomb friction coefficient
+    pub friction: f64,
+    /// Fraction of the approach speed kept after a bounce
+    pub restitution: f64,
+    /// Mass per unit area, used by RigidBody::with_material
+    pub density: f64,
+    pub friction_combine: CombineRule,
+    pub restitution_combine: CombineRule,
+    /// Speed the surface slides along itself without the body moving, like a conveyor belt.
+    /// Positive speeds run clockwise around the body, carrying things on top of it to the right.
+    pub surface_velocity: f64,
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Material {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Material {
+        Material {
+            friction: DEFAULT_FRICTION,
+            restitution: DEFAULT_RESTITUTION,
+            density: 1.0,
+            friction_combine: CombineRule::Average,
+            restitution_combine: CombineRule::Average,
+            surface_velocity: 0.0,
+        }
+    }
+
+    /// Friction of a contact between bodies of materials a and b
+    pub fn mix_friction(a: &Material, b: &Material) -> f64 {
+        CombineRule::combine(a.friction_combine, a.friction, b.friction_combine, b.friction)
+    }
+
+    /// Restitution of a contact between bodies of materials a and b
+    pub fn mix_restitution(a: &Material, b: &Material) -> f64 {
+        CombineRule::combine(a.restitution_combine, a.restitution, b.restitution_combine, b.restitution)
+    }
+}
+
+impl Material {
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("friction", self.friction.into());
+        v.insert("restitution", self.restitution.into());
+        v.insert("density", self.density.into());
+        v.insert("friction_combine", self.friction_combine.name().into());
+        v.insert("restitution_combine", self.restitution_combine.name().into());
+        v.insert("surface_velocity", self.surface_velocity.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<Material, SerializeError> {
+        // Materials saved before conveyor surfaces don't move
+        let surface_velocity = match v.get("surface_velocity") {
+            Some(_) => v.field_f64("surface_velocity")?,
+            None => 0.0,
+        };
+        Ok(Material {
+            friction: v.field_f64("friction")?,
+            restitution: v.field_f64("restitution")?,
+            density: v.field_f64("density")?,
+            friction_combine: CombineRule::from_name(v.field_str("friction_combine")?)?,
+            restitution_combine: CombineRule::from_name(v.field_str("restitution_combine")?)?,
+            surface_velocity,
+        })
+    }
+}
+
+impl Binary for CombineRule {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<CombineRule, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(CombineRule::Average),
+            1 => Ok(CombineRule::Min),
+            2 => Ok(CombineRule::Multiply),
+            3 => Ok(CombineRule::Max),
+            tag => Err(r.invalid_tag("combine rule", tag)),
+        }
+    }
+}
+
+impl Binary for Material {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.friction);
+        w.put(&self.restitution);
+        w.put(&self.density);
+        w.put(&self.friction_combine);
+        w.put(&self.restitution_combine);
+        w.put(&self.surface_velocity);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Material, SerializeError> {
+        Ok(Material {
+            friction: r.get()?,
+            restitution: r.get()?,
+            density: r.get()?,
+            friction_combine: r.get()?,
+            restitution_combine: r.get()?,
+            surface_velocity: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combine_rules() {
+        let mut ice = Material::new();
+        ice.friction = 0.1;
+        let mut rubber = Material::new();
+        rubber.friction = 0.9;
+        rubber.restitution = 0.8;
+
+        assert!((Material::mix_friction(&ice, &rubber) - 0.5).abs() < 1e-12);
+        ice.friction_combine = CombineRule::Min;
+        assert_eq!(Material::mix_friction(&ice, &rubber), 0.1);
+        // The stronger rule wins whichever side it is on
+        rubber.friction_combine = CombineRule::Multiply;
+        assert!((Material::mix_friction(&ice, &rubber) - 0.09).abs() < 1e-12);
+        assert!((Material::mix_friction(&rubber, &ice) - 0.09).abs() < 1e-12);
+        rubber.restitution_combine = CombineRule::Max;
+        assert_eq!(Material::mix_restitution(&ice, &rubber), 0.8);
+    }
+
+    #[test]
+    fn test_material_round_trip() {
+        let mut m = Material::new();
+        m.friction = 0.7;
+        m.restitution_combine = CombineRule::Multiply;
+        m.surface_velocity = -2.0;
+
+        assert_eq!(Material::from_value(&m.to_value()), Ok(m));
+        let mut w = Writer::new();
+        w.put(&m);
+        let bytes = w.finish();
+        assert_eq!(Reader::new(&bytes).get::<Material>(), Ok(m));
+    }
+}
@@ -0,0 +1,57 @@
+pub mod batch;
+pub mod bodies;
+pub mod character;
+pub mod body;
+pub mod bounds;
+pub mod constraints;
+pub mod contact;
+pub mod debug_draw;
+pub mod features;
+pub mod fixture;
+pub mod forces;
+pub mod hooks;
+pub mod joint;
+pub mod material;
+pub mod particles;
+pub mod prediction;
+pub mod query;
+pub mod ray_world;
+pub mod replay;
+pub mod rope;
+pub mod schedule;
+pub mod settings;
+pub mod snapshot;
+pub mod softbody;
+pub mod solver;
+pub mod spring;
+pub mod stats;
+pub mod steering;
+pub mod world;
+
+pub use bodies::BodySet;
+pub use body::RigidBody;
+pub use bounds::{BoundsPolicy, WorldBounds};
+pub use character::CharacterController;
+pub use contact::{Contact, ContactEvent, ContactEventKind, ContactManager};
+pub use debug_draw::{CommandBuffer, DebugColor, DebugDraw};
+pub use features::WorldFeatures;
+pub use fixture::{CollisionFilter, Fixture};
+pub use forces::{Attractor, Buoyancy, Explosion, ForceCallback, ForceGenerator, Wind};
+pub use hooks::{ContactHooks, ContactSettings, PairFlags};
+pub use joint::{AngleJoint, Joint, JointEvent, JointEventKind, MouseJoint, PrismaticJoint, RevoluteJoint};
+pub use material::{CombineRule, Material};
+pub use particles::{Emitter, Particle, ParticleCollision, ParticleSystem};
+pub use prediction::SavedStates;
+pub use query::{QueryOptions, QueryPage};
+pub use ray_world::StaticRayWorld;
+pub use replay::Replay;
+pub use rope::Rope;
+pub use schedule::{Schedule, ScheduledForce, ScheduledImpulse};
+pub use settings::WorldSettings;
+pub use snapshot::WorldState;
+pub use softbody::SoftBody;
+pub use solver::{Integrator, PositionCorrection, SolverBackend, SolverConfig};
+pub use spring::Spring;
+pub use stats::{StatsHistory, StatsMetric, WorldStats};
+pub use steering::FlowField;
+pub use world::{BodyHandle, World, WorldError};
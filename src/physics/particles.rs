@@ -0,0 +1,521 @@
+//! Lightweight particles for visual effects.
+//!
+//! Particles are points with a small radius. They fall under gravity and collide with the
+//! shapes of world bodies, but never push back on them. Emitters spawn particles spread over a
+//! disk with a minimum spacing (Poisson-disk sampling) so bursts don't clump.
+
+use wasm_bindgen::prelude::*;
+
+use super::body::RigidBody;
+use crate::collision::narrowphase::{self, NarrowphaseConfig};
+use crate::geometry::{Circle, Shapes};
+use crate::math::{trig, Rng, Transform2D, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// Candidate positions tried before giving up on a spawn
+const POISSON_ATTEMPTS: usize = 30;
+
+/// What happens to a particle when it hits a body
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticleCollision {
+    /// Reflect off the surface, scaled by the emitter restitution
+    Bounce,
+    /// Remove the particle
+    Kill,
+    /// Attach to the body and move with it
+    Stick,
+}
+
+/// Spawns particles at a steady rate
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    pub position: Vec2,
+    /// Particles spawned per second
+    pub rate: f64,
+    /// Radius of the disk particles are spawned in
+    pub spread: f64,
+    /// Minimum distance between particles spawned near the emitter
+    pub min_distance: f64,
+    /// Mean direction of the initial velocity, in radians
+    pub direction: f64,
+    /// Full width of the cone of initial directions, in radians
+    pub angle_spread: f64,
+    pub speed: f64,
+    /// Initial speed varies uniformly by up to this much either way
+    pub speed_variance: f64,
+    /// Seconds a particle lives for
+    pub lifetime: f64,
+    pub particle_radius: f64,
+    pub collision: ParticleCollision,
+    pub restitution: f64,
+    pub enabled: bool,
+    pub(crate) accumulator: f64,
+}
+
+#[wasm_bindgen]
+impl Emitter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(position: Vec2, rate: f64, lifetime: f64) -> Emitter {
+        Emitter {
+            position,
+            rate,
+            spread: 0.0,
+            min_distance: 0.0,
+            direction: std::f64::consts::FRAC_PI_2,
+            angle_spread: 0.0,
+            speed: 0.0,
+            speed_variance: 0.0,
+            lifetime,
+            particle_radius: 0.05,
+            collision: ParticleCollision::Bounce,
+            restitution: 0.5,
+            enabled: true,
+            accumulator: 0.0,
+        }
+    }
+}
+
+impl Emitter {
+    pub(crate) fn to_value(self) -> Value {
+        let collision = match self.collision {
+            ParticleCollision::Bounce => "bounce",
+            ParticleCollision::Kill => "kill",
+            ParticleCollision::Stick => "stick",
+        };
+        let mut v = Value::object();
+        v.insert("position", serialize::vec2_to_value(&self.position));
+        v.insert("rate", self.rate.into());
+        v.insert("spread", self.spread.into());
+        v.insert("min_distance", self.min_distance.into());
+        v.insert("direction", self.direction.into());
+        v.insert("angle_spread", self.angle_spread.into());
+        v.insert("speed", self.speed.into());
+        v.insert("speed_variance", self.speed_variance.into());
+        v.insert("lifetime", self.lifetime.into());
+        v.insert("particle_radius", self.particle_radius.into());
+        v.insert("collision", collision.into());
+        v.insert("restitution", self.restitution.into());
+        v.insert("enabled", self.enabled.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<Emitter, SerializeError> {
+        let collision = match v.field_str("collision")? {
+            "bounce" => ParticleCollision::Bounce,
+            "kill" => ParticleCollision::Kill,
+            "stick" => ParticleCollision::Stick,
+            other => return Err(SerializeError::InvalidValue(format!("unknown particle collision '{}'", other))),
+        };
+        Ok(Emitter {
+            position: serialize::vec2_from_value(v.field("position")?)?,
+            rate: v.field_f64("rate")?,
+            spread: v.field_f64("spread")?,
+            min_distance: v.field_f64("min_distance")?,
+            direction: v.field_f64("direction")?,
+            angle_spread: v.field_f64("angle_spread")?,
+            speed: v.field_f64("speed")?,
+            speed_variance: v.field_f64("speed_variance")?,
+            lifetime: v.field_f64("lifetime")?,
+            particle_radius: v.field_f64("particle_radius")?,
+            collision,
+            restitution: v.field_f64("restitution")?,
+            enabled: v.field_bool("enabled")?,
+            accumulator: 0.0,
+        })
+    }
+}
+
+/// A single live particle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub age: f64,
+    pub lifetime: f64,
+    pub radius: f64,
+    collision: ParticleCollision,
+    restitution: f64,
+    /// Body index and position in its local frame while stuck
+    stuck: Option<(usize, Vec2)>,
+}
+
+impl Particle {
+    pub fn is_stuck(&self) -> bool {
+        self.stuck.is_some()
+    }
+}
+
+/// Emitters and the particles they have spawned
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ParticleSystem {
+    emitters: Vec<Emitter>,
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl ParticleSystem {
+    pub fn new() -> ParticleSystem {
+        ParticleSystem::default()
+    }
+
+    /// A system whose spawn positions and velocities are drawn from seed
+    pub fn with_seed(seed: u64) -> ParticleSystem {
+        ParticleSystem {
+            rng: Rng::new(seed),
+            ..ParticleSystem::default()
+        }
+    }
+
+    /// Add an emitter, returning its index
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    /// Remove the emitter at index. Particles it already spawned live on.
+    pub fn remove_emitter(&mut self, index: usize) -> Emitter {
+        self.emitters.remove(index)
+    }
+
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    pub fn emitter_mut(&mut self, index: usize) -> Option<&mut Emitter> {
+        self.emitters.get_mut(index)
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn clear_particles(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Release particles stuck to the body removed from index,
+    /// and follow the body moved from moved_from into its place
+    pub(crate) fn body_removed(&mut self, index: usize, moved_from: usize) {
+        for particle in &mut self.particles {
+            match particle.stuck {
+                Some((i, _)) if i == index => particle.stuck = None,
+                Some((ref mut i, _)) if *i == moved_from => *i = index,
+                _ => {}
+            }
+        }
+    }
+
+    /// Move particles, resolve collisions with bodies, then spawn new particles
+    pub fn step(&mut self, dt: f64, gravity: &Vec2, bodies: &[RigidBody], config: &NarrowphaseConfig) {
+        for particle in &mut self.particles {
+            particle.age += dt;
+            match particle.stuck {
+                Some((index, local)) => match bodies.get(index) {
+                    Some(body) => particle.position = body.transform().apply(&local),
+                    None => particle.stuck = None,
+                },
+                None => {
+                    particle.velocity = particle.velocity + gravity * dt;
+                    particle.position = particle.position + particle.velocity * dt;
+                    collide(particle, bodies, config);
+                }
+            }
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+
+        for i in 0..self.emitters.len() {
+            let emitter = &mut self.emitters[i];
+            if !emitter.enabled {
+                continue;
+            }
+            emitter.accumulator += emitter.rate * dt;
+            let count = emitter.accumulator.floor();
+            emitter.accumulator -= count;
+
+            let emitter = *emitter;
+            for _ in 0..count as usize {
+                match self.spawn_position(&emitter) {
+                    Some(position) => {
+                        let particle = self.spawn(&emitter, position);
+                        self.particles.push(particle);
+                    }
+                    None => log::trace!("Emitter {} is saturated, skipping spawn", i),
+                }
+            }
+        }
+    }
+
+    /// Poisson-disk sample inside the emitter disk, away from particles already there
+    fn spawn_position(&mut self, emitter: &Emitter) -> Option<Vec2> {
+        if emitter.min_distance <= 0.0 {
+            return Some(self.rng.in_disk(&emitter.position, emitter.spread));
+        }
+
+        let reach = emitter.spread + emitter.min_distance;
+        let nearby: Vec<Vec2> = self
+            .particles
+            .iter()
+            .map(|p| p.position)
+            .filter(|p| (p - emitter.position).len() < reach)
+            .collect();
+
+        let min_sq = emitter.min_distance * emitter.min_distance;
+        (0..POISSON_ATTEMPTS)
+            .map(|_| self.rng.in_disk(&emitter.position, emitter.spread))
+            .find(|candidate| nearby.iter().all(|p| (p - candidate).len_sq() >= min_sq))
+    }
+
+    fn spawn(&mut self, emitter: &Emitter, position: Vec2) -> Particle {
+        let half_angle = emitter.angle_spread / 2.0;
+        let angle = emitter.direction + self.rng.range(-half_angle, half_angle);
+        let speed = emitter.speed + self.rng.range(-emitter.speed_variance, emitter.speed_variance);
+
+        Particle {
+            position,
+            velocity: Vec2::new(trig::cos(angle), trig::sin(angle)) * speed,
+            age: 0.0,
+            lifetime: emitter.lifetime,
+            radius: emitter.particle_radius,
+            collision: emitter.collision,
+            restitution: emitter.restitution,
+            stuck: None,
+        }
+    }
+}
+
+/// Push a particle out of any body it overlaps and apply its collision policy
+fn collide(particle: &mut Particle, bodies: &[RigidBody], config: &NarrowphaseConfig) {
+    let shape = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), particle.radius));
+    for (index, body) in bodies.iter().enumerate() {
+        let transform = body.transform();
+        let at = Transform2D::new(particle.position, 0.0);
+        let contact = match narrowphase::collide_with(config, body.shape(), &transform, &shape, &at).first() {
+            Some(contact) => *contact,
+            None => continue,
+        };
+
+        particle.position = particle.position + contact.penetration;
+        match particle.collision {
+            ParticleCollision::Bounce => {
+                let normal = contact.penetration.normalize();
+                let approach = Vec2::dot(&particle.velocity, &normal);
+                if approach < 0.0 {
+                    particle.velocity = particle.velocity - normal * ((1.0 + particle.restitution) * approach);
+                }
+            }
+            ParticleCollision::Kill => {
+                particle.age = particle.lifetime;
+                return;
+            }
+            ParticleCollision::Stick => {
+                particle.velocity = Vec2::new(0.0, 0.0);
+                particle.stuck = Some((index, transform.inverse_apply(&particle.position)));
+                return;
+            }
+        }
+    }
+}
+
+impl Binary for ParticleCollision {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<ParticleCollision, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(ParticleCollision::Bounce),
+            1 => Ok(ParticleCollision::Kill),
+            2 => Ok(ParticleCollision::Stick),
+            tag => Err(r.invalid_tag("particle collision", tag)),
+        }
+    }
+}
+
+impl Binary for Emitter {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.position);
+        w.put(&self.rate);
+        w.put(&self.spread);
+        w.put(&self.min_distance);
+        w.put(&self.direction);
+        w.put(&self.angle_spread);
+        w.put(&self.speed);
+        w.put(&self.speed_variance);
+        w.put(&self.lifetime);
+        w.put(&self.particle_radius);
+        w.put(&self.collision);
+        w.put(&self.restitution);
+        w.put(&self.enabled);
+        w.put(&self.accumulator);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Emitter, SerializeError> {
+        Ok(Emitter {
+            position: r.get()?,
+            rate: r.get()?,
+            spread: r.get()?,
+            min_distance: r.get()?,
+            direction: r.get()?,
+            angle_spread: r.get()?,
+            speed: r.get()?,
+            speed_variance: r.get()?,
+            lifetime: r.get()?,
+            particle_radius: r.get()?,
+            collision: r.get()?,
+            restitution: r.get()?,
+            enabled: r.get()?,
+            accumulator: r.get()?,
+        })
+    }
+}
+
+impl Binary for Particle {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.position);
+        w.put(&self.velocity);
+        w.put(&self.age);
+        w.put(&self.lifetime);
+        w.put(&self.radius);
+        w.put(&self.collision);
+        w.put(&self.restitution);
+        w.put(&self.stuck);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Particle, SerializeError> {
+        Ok(Particle {
+            position: r.get()?,
+            velocity: r.get()?,
+            age: r.get()?,
+            lifetime: r.get()?,
+            radius: r.get()?,
+            collision: r.get()?,
+            restitution: r.get()?,
+            stuck: r.get()?,
+        })
+    }
+}
+
+impl Binary for ParticleSystem {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.emitters);
+        w.put(&self.particles);
+        w.put(&self.rng);
+    }
+
+    fn decode(r: &mut Reader) -> Result<ParticleSystem, SerializeError> {
+        Ok(ParticleSystem {
+            emitters: r.get()?,
+            particles: r.get()?,
+            rng: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Polygon;
+
+    fn ground() -> RigidBody {
+        let mut body = RigidBody::new(0.0);
+        body.set_shape(Shapes::Polygon(Polygon::new(&[
+            Vec2::new(-10.0, -1.0),
+            Vec2::new(10.0, -1.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(-10.0, 0.0),
+        ])));
+        body
+    }
+
+    fn falling(collision: ParticleCollision) -> ParticleSystem {
+        let mut emitter = Emitter::new(Vec2::new(0.0, 0.5), 1.0, 10.0);
+        emitter.speed = 5.0;
+        emitter.direction = -std::f64::consts::FRAC_PI_2;
+        emitter.collision = collision;
+        emitter.restitution = 1.0;
+
+        let mut system = ParticleSystem::new();
+        system.add_emitter(emitter);
+        system.step(1.0, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        system.emitter_mut(0).unwrap().enabled = false;
+        system
+    }
+
+    #[test]
+    fn test_emitter_rate() {
+        let mut system = ParticleSystem::new();
+        system.add_emitter(Emitter::new(Vec2::new(0.0, 0.0), 10.0, 1.0));
+
+        system.step(0.25, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        assert_eq!(system.particles().len(), 2);
+        system.step(0.25, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        assert_eq!(system.particles().len(), 5);
+    }
+
+    #[test]
+    fn test_particles_expire() {
+        let mut system = ParticleSystem::new();
+        system.add_emitter(Emitter::new(Vec2::new(0.0, 0.0), 1.0, 1.5));
+        system.step(1.0, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        system.emitter_mut(0).unwrap().enabled = false;
+
+        system.step(1.0, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        assert_eq!(system.particles().len(), 1);
+        system.step(1.0, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+        assert!(system.particles().is_empty());
+    }
+
+    #[test]
+    fn test_poisson_spacing() {
+        let mut emitter = Emitter::new(Vec2::new(0.0, 0.0), 50.0, 10.0);
+        emitter.spread = 1.0;
+        emitter.min_distance = 0.3;
+        let mut system = ParticleSystem::with_seed(3);
+        system.add_emitter(emitter);
+        system.step(1.0, &Vec2::new(0.0, 0.0), &[], &NarrowphaseConfig::new());
+
+        let particles = system.particles();
+        // The disk fills up well before 50 particles fit
+        assert!(particles.len() > 5 && particles.len() < 50);
+        for (i, a) in particles.iter().enumerate() {
+            assert!(a.position.len() <= 1.0);
+            for b in &particles[i + 1..] {
+                assert!((a.position - b.position).len() >= 0.3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounce() {
+        let mut system = falling(ParticleCollision::Bounce);
+        system.step(0.1, &Vec2::new(0.0, 0.0), &[ground()], &NarrowphaseConfig::new());
+
+        let p = system.particles()[0];
+        assert!(p.position.y >= 0.05 - 1e-6);
+        assert!((p.velocity - Vec2::new(0.0, 5.0)).len() < 1e-3);
+    }
+
+    #[test]
+    fn test_kill() {
+        let mut system = falling(ParticleCollision::Kill);
+        system.step(0.1, &Vec2::new(0.0, 0.0), &[ground()], &NarrowphaseConfig::new());
+
+        assert!(system.particles().is_empty());
+    }
+
+    #[test]
+    fn test_stick_follows_body() {
+        let mut system = falling(ParticleCollision::Stick);
+        let mut bodies = [ground()];
+        system.step(0.1, &Vec2::new(0.0, 0.0), &bodies, &NarrowphaseConfig::new());
+        let stuck_at = system.particles()[0].position;
+        assert!(system.particles()[0].is_stuck());
+
+        bodies[0].set_position(Vec2::new(1.0, 0.0));
+        system.step(0.1, &Vec2::new(0.0, 0.0), &bodies, &NarrowphaseConfig::new());
+        assert!((system.particles()[0].position - stuck_at - Vec2::new(1.0, 0.0)).len() < 1e-9);
+    }
+}
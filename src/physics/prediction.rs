@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+
+use super::snapshot::WorldState;
+
+/// Ring buffer of keyframes, oldest first, used to rewind and resimulate a World
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedStates {
+    capacity: usize,
+    states: VecDeque<WorldState>,
+}
+
+impl Default for SavedStates {
+    fn default() -> SavedStates {
+        SavedStates::new(64)
+    }
+}
+
+impl SavedStates {
+    pub fn new(capacity: usize) -> SavedStates {
+        SavedStates {
+            capacity,
+            states: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the number of kept keyframes, dropping the oldest if needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.states.len() > capacity {
+            self.states.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    /// Store a keyframe, replacing any keyframe at the same or a later tick
+    pub fn push(&mut self, state: WorldState) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.truncate_after(state.tick());
+        if self.states.back().map(|s| s.tick()) == Some(state.tick()) {
+            self.states.pop_back();
+        }
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+
+    pub fn get(&self, tick: u64) -> Option<&WorldState> {
+        self.states.iter().find(|s| s.tick() == tick)
+    }
+
+    pub fn oldest_tick(&self) -> Option<u64> {
+        self.states.front().map(|s| s.tick())
+    }
+
+    pub fn newest_tick(&self) -> Option<u64> {
+        self.states.back().map(|s| s.tick())
+    }
+
+    /// Drop every keyframe newer than tick
+    pub fn truncate_after(&mut self, tick: u64) {
+        while self.states.back().is_some_and(|s| s.tick() > tick) {
+            self.states.pop_back();
+        }
+    }
+}
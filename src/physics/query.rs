@@ -0,0 +1,110 @@
+//! Ordering and paging of world query results.
+//!
+//! Queries that can hit many bodies take QueryOptions to sort the hits by distance and return
+//! them a page at a time. Asking for the nearest few only sorts those few, and JS only gets the
+//! page it asked for rather than the full list.
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::Vec2;
+
+/// How many results a world query returns and in what order
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct QueryOptions {
+    /// Nearest to origin first instead of in slot order
+    pub sort_by_distance: bool,
+    /// Point distances are measured from when sorting
+    pub origin: Vec2,
+    /// Most results to return, 0 for all of them
+    pub limit: usize,
+    /// Number of results to skip, such as the next cursor of the previous page
+    pub cursor: usize,
+}
+
+#[wasm_bindgen]
+impl QueryOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> QueryOptions {
+        QueryOptions::default()
+    }
+
+    /// Results nearest to origin first
+    pub fn nearest(origin: Vec2, limit: usize) -> QueryOptions {
+        QueryOptions {
+            sort_by_distance: true,
+            origin,
+            limit,
+            cursor: 0,
+        }
+    }
+}
+
+/// One page of query results
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryPage<T> {
+    pub results: Vec<T>,
+    /// Cursor of the next page, None if this was the last one
+    pub next: Option<usize>,
+}
+
+impl QueryOptions {
+    /// The page of items asked for. Items are expected in slot order, which also breaks
+    /// distance ties so pages never overlap.
+    pub fn page<T, F: FnMut(&T) -> f64>(&self, items: Vec<T>, mut distance: F) -> QueryPage<T> {
+        let total = items.len();
+        let end = if self.limit == 0 {
+            total
+        } else {
+            self.cursor.saturating_add(self.limit).min(total)
+        };
+
+        let mut items = items;
+        if self.sort_by_distance {
+            let mut keyed: Vec<(f64, usize, T)> =
+                items.into_iter().enumerate().map(|(i, t)| (distance(&t), i, t)).collect();
+            let order = |a: &(f64, usize, T), b: &(f64, usize, T)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+            // Only the results up to the end of the page need sorting
+            if end > 0 && end < total {
+                keyed.select_nth_unstable_by(end - 1, order);
+                keyed.truncate(end);
+            }
+            keyed.sort_unstable_by(order);
+            items = keyed.into_iter().map(|(_, _, t)| t).collect();
+        }
+
+        let results: Vec<T> = items.into_iter().take(end).skip(self.cursor).collect();
+        QueryPage {
+            results,
+            next: if end < total { Some(end) } else { None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_pages() {
+        let items: Vec<f64> = vec![5.0, 1.0, 4.0, 1.0, 3.0];
+        let all = QueryOptions::new().page(items.clone(), |&d| d);
+        assert_eq!(all.results, items);
+        assert_eq!(all.next, None);
+
+        let mut options = QueryOptions::nearest(Vec2::new(0.0, 0.0), 2);
+        let mut seen = Vec::new();
+        loop {
+            let page = options.page(items.clone(), |&d| d);
+            seen.extend(page.results);
+            match page.next {
+                Some(cursor) => options.cursor = cursor,
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec![1.0, 1.0, 3.0, 4.0, 5.0]);
+
+        options.cursor = 10;
+        assert!(options.page(items, |&d| d).results.is_empty());
+    }
+}
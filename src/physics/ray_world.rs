@@ -0,0 +1,131 @@
+//! Raycasts against a frozen copy of a world's static bodies.
+//!
+//! Line of sight checks for many agents only care about walls and terrain, which never move. A
+//! StaticRayWorld copies those bodies out of a World once, into a tree that is never updated, so
+//! it can be shared between threads and queried while the World itself keeps stepping.
+
+use wasm_bindgen::prelude::*;
+
+use super::world::{BodyHandle, World};
+use crate::collision::broadphase::Bvh;
+use crate::collision::cast;
+use crate::collision::narrowphase;
+use crate::geometry::{Ray, RayHit, Shapes};
+use crate::math::{Transform2D, Vec2};
+
+/// The static bodies of a World at the time it was built, for raycasts only
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct StaticRayWorld {
+    bodies: Vec<(BodyHandle, Shapes, Transform2D)>,
+    bvh: Bvh,
+}
+
+#[wasm_bindgen]
+impl StaticRayWorld {
+    #[wasm_bindgen(constructor)]
+    pub fn new(world: &World) -> StaticRayWorld {
+        let bodies: Vec<(BodyHandle, Shapes, Transform2D)> = world
+            .bodies()
+            .filter(|(_, body)| body.is_static())
+            .map(|(handle, body)| (handle, body.shape().clone(), body.transform()))
+            .collect();
+        // Nothing moves, so the boxes don't need fattening
+        let mut bvh = Bvh::new(0.0);
+        for (index, (_, shape, transform)) in bodies.iter().enumerate() {
+            bvh.insert(narrowphase::bounds(shape, transform), index);
+        }
+        StaticRayWorld { bodies, bvh }
+    }
+
+    /// Number of static bodies
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// True if no static body lies between from and to
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let distance = (to - from).len();
+        distance == 0.0 || self.raycast(&Ray::new(from, to - from), distance).is_none()
+    }
+
+    /// Where ray first hits a static body within max_distance. See StaticRayWorld::raycast.
+    #[wasm_bindgen(js_name = raycast)]
+    pub fn wasm_raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        self.raycast(ray, max_distance).map(|(_, hit)| hit)
+    }
+}
+
+impl StaticRayWorld {
+    /// Closest static body hit by ray within max_distance, with where it was hit
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(BodyHandle, RayHit)> {
+        self.bvh
+            .query(&ray.aabb(max_distance))
+            .into_iter()
+            .filter_map(|index| {
+                let (handle, shape, transform) = &self.bodies[index];
+                Some((*handle, cast::raycast(ray, max_distance, shape, transform)?))
+            })
+            .min_by(|a, b| a.1.distance.total_cmp(&b.1.distance).then(a.0.cmp(&b.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::RigidBody;
+
+    fn maze() -> (World, Vec<BodyHandle>) {
+        let mut world = World::new();
+        let mut walls = Vec::new();
+        for x in [3.0, 6.0] {
+            let mut wall = RigidBody::with_polygon(0.0, &[-0.5, -2.0, 0.5, -2.0, 0.5, 2.0, -0.5, 2.0]);
+            wall.set_position(Vec2::new(x, 0.0));
+            walls.push(world.create_body(wall));
+        }
+        let mut crate_ = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        crate_.set_position(Vec2::new(1.5, 3.0));
+        world.create_body(crate_);
+        (world, walls)
+    }
+
+    #[test]
+    fn test_raycast() {
+        let (world, walls) = maze();
+        let rays = StaticRayWorld::new(&world);
+        assert_eq!(rays.len(), 2);
+
+        let (handle, hit) = rays.raycast(&Ray::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)), 10.0).unwrap();
+        assert_eq!(handle, walls[0]);
+        assert!((hit.distance - 2.5).abs() < 1e-4);
+        // Dynamic bodies don't block the view
+        assert!(rays.line_of_sight(Vec2::new(0.0, 3.0), Vec2::new(8.0, 3.0)));
+        assert!(!rays.line_of_sight(Vec2::new(0.0, 0.0), Vec2::new(8.0, 1.0)));
+        assert!(rays.line_of_sight(Vec2::new(4.0, 0.0), Vec2::new(5.0, 0.0)));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let (mut world, _) = maze();
+        let rays = StaticRayWorld::new(&world);
+        let visible: Vec<bool> = std::thread::scope(|scope| {
+            let agents: Vec<_> = [0.0, 2.0, 4.0, 5.0]
+                .iter()
+                .map(|&x| {
+                    let rays = &rays;
+                    scope.spawn(move || rays.line_of_sight(Vec2::new(x, 1.0), Vec2::new(4.5, 1.0)))
+                })
+                .collect();
+            agents.into_iter().map(|a| a.join().unwrap()).collect()
+        });
+        assert_eq!(visible, vec![false, false, true, true]);
+
+        // Later changes to the world aren't seen
+        world.create_body(RigidBody::with_polygon(0.0, &[4.0, 0.0, 5.0, 0.0, 5.0, 2.0]));
+        assert!(rays.line_of_sight(Vec2::new(4.0, 1.0), Vec2::new(5.0, 1.0)));
+    }
+}
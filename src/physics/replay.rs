@@ -0,0 +1,285 @@
+//! Recorded simulations that can be scrubbed forwards and backwards.
+//!
+//! Every frame is the binary state of a World. Every keyframe_interval frames the full state is
+//! stored, and the frames in between only store the byte ranges that changed since the frame
+//! before. Any frame is rebuilt by patching forward from the keyframe before it. The frames of
+//! the segment being played are kept decoded, so stepping backwards through a segment only
+//! decodes it once.
+
+use wasm_bindgen::prelude::*;
+
+use super::world::World;
+use crate::serialize::binary::{Reader, Writer};
+use crate::serialize::SerializeError;
+
+/// Unchanged bytes needed to end a changed run, so nearby changes share a run
+const MIN_GAP: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Frame {
+    Key(Vec<u8>),
+    Delta(Vec<u8>),
+}
+
+/// Recorded world states for playback in either direction
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    keyframe_interval: usize,
+    frames: Vec<Frame>,
+    /// State of the last recorded frame
+    last: Vec<u8>,
+    /// Frame the world was last set to
+    position: usize,
+    /// Decoded frames starting at cache_start
+    cache_start: usize,
+    cache: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl Replay {
+    /// Store a full state every keyframe_interval frames
+    #[wasm_bindgen(constructor)]
+    pub fn new(keyframe_interval: usize) -> Replay {
+        assert!(keyframe_interval > 0, "Keyframe interval must be positive");
+        Replay {
+            keyframe_interval,
+            frames: Vec::new(),
+            last: Vec::new(),
+            position: 0,
+            cache_start: 0,
+            cache: Vec::new(),
+        }
+    }
+
+    pub fn keyframe_interval(&self) -> usize {
+        self.keyframe_interval
+    }
+
+    /// Number of recorded frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Frame the world was last set to by playback
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Append the current state of world as a new frame
+    pub fn record(&mut self, world: &World) {
+        let state = world.save_state();
+        let frame = if self.frames.len().is_multiple_of(self.keyframe_interval) {
+            Frame::Key(state.clone())
+        } else {
+            Frame::Delta(diff(&self.last, &state))
+        };
+        self.frames.push(frame);
+        self.last = state;
+    }
+
+    /// Drop every frame after frame, so recording can continue from it
+    pub fn truncate_after(&mut self, frame: usize) {
+        if frame + 1 >= self.frames.len() {
+            return;
+        }
+        self.last = self.state(frame);
+        self.frames.truncate(frame + 1);
+        self.position = self.position.min(frame);
+        if self.cache_start + self.cache.len() > self.frames.len() {
+            self.cache.truncate(self.frames.len().saturating_sub(self.cache_start));
+        }
+    }
+
+    /// Set world to the state recorded at frame
+    pub fn seek(&mut self, frame: usize, world: &mut World) {
+        assert!(frame < self.frames.len(), "Frame {} out of range for {} frames", frame, self.frames.len());
+        let state = self.state(frame);
+        world.load_state(&state).expect("Recorded state is valid");
+        self.position = frame;
+    }
+
+    /// Set world to the frame after the current one. Returns false at the last frame.
+    pub fn step_forward(&mut self, world: &mut World) -> bool {
+        if self.position + 1 >= self.frames.len() {
+            return false;
+        }
+        self.seek(self.position + 1, world);
+        true
+    }
+
+    /// Set world to the frame before the current one. Returns false at the first frame.
+    pub fn step_backward(&mut self, world: &mut World) -> bool {
+        if self.position == 0 || self.frames.is_empty() {
+            return false;
+        }
+        self.seek(self.position - 1, world);
+        true
+    }
+
+    /// Bytes used by the recorded frames
+    pub fn byte_len(&self) -> usize {
+        self.frames
+            .iter()
+            .map(|f| match f {
+                Frame::Key(bytes) | Frame::Delta(bytes) => bytes.len(),
+            })
+            .sum()
+    }
+}
+
+impl Replay {
+    /// Binary world state at frame, as written by World::save_state
+    pub fn state(&mut self, frame: usize) -> Vec<u8> {
+        if frame < self.cache_start || frame >= self.cache_start + self.cache.len() {
+            self.decode_segment(frame);
+        }
+        self.cache[frame - self.cache_start].clone()
+    }
+
+    /// Decode every frame of the segment containing frame
+    fn decode_segment(&mut self, frame: usize) {
+        let start = frame - frame % self.keyframe_interval;
+        let end = (start + self.keyframe_interval).min(self.frames.len());
+        let mut states: Vec<Vec<u8>> = Vec::with_capacity(end - start);
+        for f in &self.frames[start..end] {
+            let state = match f {
+                Frame::Key(bytes) => bytes.clone(),
+                Frame::Delta(delta) => patch(states.last().unwrap(), delta).expect("Recorded delta is valid"),
+            };
+            states.push(state);
+        }
+        self.cache_start = start;
+        self.cache = states;
+    }
+}
+
+/// Byte ranges of new that differ from old, with the length of new
+fn diff(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+    let mut i = 0;
+    while i < new.len() {
+        if old.get(i) == Some(&new[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut same = 0;
+        while i < new.len() && same < MIN_GAP {
+            same = if old.get(i) == Some(&new[i]) { same + 1 } else { 0 };
+            i += 1;
+        }
+        runs.push((start, new[start..i - same].to_vec()));
+    }
+
+    let mut w = Writer::new();
+    w.put(&new.len());
+    w.put(&runs);
+    w.finish()
+}
+
+/// Apply a delta written by diff to old
+fn patch(old: &[u8], delta: &[u8]) -> Result<Vec<u8>, SerializeError> {
+    let mut r = Reader::new(delta);
+    let len: usize = r.get()?;
+    let runs: Vec<(usize, Vec<u8>)> = r.get()?;
+    r.finish()?;
+
+    let mut out = old.to_vec();
+    out.resize(len, 0);
+    for (start, bytes) in runs {
+        let target = start
+            .checked_add(bytes.len())
+            .and_then(|end| out.get_mut(start..end))
+            .ok_or_else(|| SerializeError::InvalidValue("delta run out of range".to_string()))?;
+        target.copy_from_slice(&bytes);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+    use crate::physics::{Emitter, RigidBody, WorldState};
+
+    fn recorded(frames: usize, interval: usize) -> (World, Replay, Vec<WorldState>) {
+        let mut world = World::new();
+        world.create_body(RigidBody::with_segment(Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0), false));
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 2.0));
+        world.create_body(ball);
+        world.add_emitter(Emitter::new(Vec2::new(1.0, 3.0), 20.0, 0.5));
+
+        let mut replay = Replay::new(interval);
+        let mut states = Vec::new();
+        for _ in 0..frames {
+            replay.record(&world);
+            states.push(world.snapshot());
+            world.step(1.0 / 60.0);
+        }
+        (world, replay, states)
+    }
+
+    #[test]
+    fn test_diff_patch() {
+        let old = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20];
+        let mut new = old.clone();
+        new[2] = 0;
+        new[4] = 0;
+        new[18] = 0;
+        new.push(21);
+
+        assert_eq!(patch(&old, &diff(&old, &new)).unwrap(), new);
+        assert_eq!(patch(&new, &diff(&new, &old)).unwrap(), old);
+        assert_eq!(patch(&old, &diff(&old, &[])).unwrap(), Vec::<u8>::new());
+        assert!(patch(&old, &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_play_backwards() {
+        let (mut world, mut replay, states) = recorded(40, 8);
+
+        replay.seek(39, &mut world);
+        assert_eq!(world.snapshot(), states[39]);
+        let mut frame = 39;
+        while replay.step_backward(&mut world) {
+            frame -= 1;
+            assert_eq!(replay.position(), frame);
+            assert_eq!(world.snapshot(), states[frame]);
+        }
+        assert_eq!(frame, 0);
+        assert!(replay.step_forward(&mut world));
+        assert_eq!(world.snapshot(), states[1]);
+    }
+
+    #[test]
+    fn test_deltas_are_smaller_than_states() {
+        let (world, replay, _) = recorded(32, 16);
+        assert!(replay.byte_len() < 32 * world.save_state().len() / 2);
+    }
+
+    #[test]
+    fn test_truncate_and_rerecord() {
+        let (mut world, mut replay, states) = recorded(20, 8);
+
+        replay.truncate_after(10);
+        assert_eq!(replay.len(), 11);
+        replay.seek(10, &mut world);
+        world.step(1.0 / 60.0);
+        replay.record(&world);
+        replay.seek(11, &mut world);
+        assert_eq!(world.snapshot(), states[11]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_seek_out_of_range() {
+        let (mut world, mut replay, _) = recorded(3, 8);
+        replay.seek(3, &mut world);
+    }
+}
@@ -0,0 +1,270 @@
+//! Ropes simulated as a line of nodes joined by distance constraints.
+//!
+//! A Rope is positional (XPBD): every step the free nodes move on under gravity, then each
+//! segment is pulled back towards its rest length, sweeping forwards and backwards along the rope
+//! so a pull at one end reaches the other within a few iterations. The two end nodes are pinned
+//! to anchor points on bodies of a World and follow them, but the rope doesn't pull on those
+//! bodies. Nodes with a radius are pushed out of other bodies they overlap.
+
+use wasm_bindgen::prelude::*;
+
+use super::world::{BodyHandle, World, WorldError};
+use crate::collision::narrowphase;
+use crate::geometry::{Circle, Shapes};
+use crate::math::{Transform2D, Vec2};
+
+/// A point on a body, given in the body's local coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Anchor {
+    body: BodyHandle,
+    local: Vec2,
+}
+
+impl Anchor {
+    /// Where the anchor is now, None if its body was removed
+    fn position(&self, world: &World) -> Option<Vec2> {
+        world.body(self.body).map(|body| body.transform().apply(&self.local))
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rope {
+    /// Stretch per unit of tension, 0 for a rope that doesn't stretch
+    pub compliance: f64,
+    /// Constraint passes each step
+    pub iterations: usize,
+    /// Fraction of node velocity lost each step
+    pub damping: f64,
+    /// Radius nodes collide with, 0 to pass through bodies
+    pub radius: f64,
+    start: Anchor,
+    end: Anchor,
+    rest_length: f64,
+    nodes: Vec<Vec2>,
+    previous: Vec<Vec2>,
+}
+
+#[wasm_bindgen]
+impl Rope {
+    /// A rope of length split into segments, from local_a on body_a to local_b on body_b. Nodes
+    /// start evenly spaced on the straight line between the anchors. Panics if a body doesn't exist.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        world: &World,
+        body_a: BodyHandle,
+        local_a: Vec2,
+        body_b: BodyHandle,
+        local_b: Vec2,
+        segments: usize,
+        length: f64,
+    ) -> Rope {
+        Rope::try_new(world, (body_a, local_a), (body_b, local_b), segments, length).unwrap()
+    }
+
+    /// Segments between nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Length each segment is pulled towards
+    pub fn rest_length(&self) -> f64 {
+        self.rest_length
+    }
+
+    /// Sum of the current segment lengths
+    pub fn length(&self) -> f64 {
+        self.nodes.windows(2).map(|s| Vec2::distance(&s[0], &s[1])).sum()
+    }
+
+    /// Node positions as a flat x, y list from the first anchor to the second, for drawing
+    #[wasm_bindgen(js_name = nodes)]
+    pub fn wasm_nodes(&self) -> Vec<f64> {
+        self.nodes.iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    /// Move the rope on by dt under world's gravity, keeping its ends on their anchors
+    pub fn step(&mut self, world: &World, dt: f64) {
+        if dt <= 0.0 {
+            return;
+        }
+        let last = self.nodes.len() - 1;
+        let gravity = world.gravity() * (dt * dt);
+        let keep = 1.0 - self.damping.clamp(0.0, 1.0);
+        for i in 1..last {
+            let velocity = self.nodes[i] - self.previous[i];
+            self.previous[i] = self.nodes[i];
+            self.nodes[i] = self.nodes[i] + velocity * keep + gravity;
+        }
+        for (i, anchor) in [(0, self.start), (last, self.end)] {
+            self.previous[i] = self.nodes[i];
+            if let Some(position) = anchor.position(world) {
+                self.nodes[i] = position;
+            }
+        }
+
+        let alpha = self.compliance / (dt * dt);
+        let mut lambdas = vec![0.0; last];
+        for _ in 0..self.iterations {
+            for (s, lambda) in lambdas.iter_mut().enumerate() {
+                self.solve_segment(s, alpha, lambda);
+            }
+            for (s, lambda) in lambdas.iter_mut().enumerate().rev() {
+                self.solve_segment(s, alpha, lambda);
+            }
+            if self.radius > 0.0 {
+                self.collide(world);
+            }
+        }
+    }
+}
+
+impl Rope {
+    /// A rope between two anchors, each a body and a point in its local coordinates, or an error
+    /// if a body doesn't exist
+    pub fn try_new(
+        world: &World,
+        anchor_a: (BodyHandle, Vec2),
+        anchor_b: (BodyHandle, Vec2),
+        segments: usize,
+        length: f64,
+    ) -> Result<Rope, WorldError> {
+        let segments = segments.max(1);
+        let start = Anchor { body: anchor_a.0, local: anchor_a.1 };
+        let end = Anchor { body: anchor_b.0, local: anchor_b.1 };
+        let from = start.position(world).ok_or(WorldError::InvalidHandle(start.body))?;
+        let to = end.position(world).ok_or(WorldError::InvalidHandle(end.body))?;
+        let nodes: Vec<Vec2> = (0..=segments)
+            .map(|i| Vec2::lerp(&from, &to, i as f64 / segments as f64))
+            .collect();
+        Ok(Rope {
+            compliance: 0.0,
+            iterations: 8,
+            damping: 0.01,
+            radius: 0.0,
+            start,
+            end,
+            rest_length: length / segments as f64,
+            previous: nodes.clone(),
+            nodes,
+        })
+    }
+
+    /// Node positions from the first anchor to the second
+    pub fn nodes(&self) -> &[Vec2] {
+        &self.nodes
+    }
+
+    /// Pull the nodes of segment s towards the rest length, ends staying on their anchors
+    fn solve_segment(&mut self, s: usize, alpha: f64, lambda: &mut f64) {
+        let last = self.nodes.len() - 1;
+        let weight = |i: usize| if i == 0 || i == last { 0.0 } else { 1.0 };
+        let (wa, wb) = (weight(s), weight(s + 1));
+        let delta = self.nodes[s + 1] - self.nodes[s];
+        let distance = delta.len();
+        if wa + wb == 0.0 || distance == 0.0 {
+            return;
+        }
+        let error = distance - self.rest_length;
+        let d_lambda = (-error - alpha * *lambda) / (wa + wb + alpha);
+        *lambda += d_lambda;
+        let correction = delta * (d_lambda / distance);
+        self.nodes[s] = self.nodes[s] - correction * wa;
+        self.nodes[s + 1] = self.nodes[s + 1] + correction * wb;
+    }
+
+    /// Push free nodes out of bodies other than the anchor bodies
+    fn collide(&mut self, world: &World) {
+        let config = world.narrowphase_config();
+        let shape = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), self.radius));
+        let reach = Vec2::splat(self.radius);
+        let last = self.nodes.len() - 1;
+        for node in &mut self.nodes[1..last] {
+            for handle in world.query_aabb(*node - reach, *node + reach) {
+                if handle == self.start.body || handle == self.end.body {
+                    continue;
+                }
+                let body = world.body(handle).unwrap();
+                let at = Transform2D::new(*node, 0.0);
+                let contacts = narrowphase::collide_with(&config, body.shape(), &body.transform(), &shape, &at);
+                if let Some(contact) = contacts.first() {
+                    *node = *node + contact.penetration;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::RigidBody;
+
+    fn posts(world: &mut World, gap: f64) -> (BodyHandle, BodyHandle) {
+        let mut left = RigidBody::new(0.0);
+        left.set_position(Vec2::new(0.0, 5.0));
+        let mut right = RigidBody::new(0.0);
+        right.set_position(Vec2::new(gap, 5.0));
+        (world.create_body(left), world.create_body(right))
+    }
+
+    #[test]
+    fn test_rope_sags_between_anchors() {
+        let mut world = World::new();
+        let (left, right) = posts(&mut world, 4.0);
+        let origin = Vec2::new(0.0, 0.0);
+        let mut rope = Rope::new(&world, left, origin, right, origin, 10, 6.0);
+        assert_eq!((rope.len(), rope.nodes().len()), (10, 11));
+        assert!((rope.rest_length() - 0.6).abs() < 1e-12);
+
+        for _ in 0..300 {
+            rope.step(&world, 1.0 / 60.0);
+        }
+        let nodes = rope.nodes();
+        assert_eq!(nodes[0], Vec2::new(0.0, 5.0));
+        assert_eq!(nodes[10], Vec2::new(4.0, 5.0));
+        // Slack rope hangs down in the middle without stretching
+        assert!(nodes[5].y < 3.5);
+        assert!((rope.length() - 6.0).abs() < 0.06);
+        assert_eq!(rope.wasm_nodes().len(), 22);
+    }
+
+    #[test]
+    fn test_rope_follows_anchor() {
+        let mut world = World::new();
+        let (left, right) = posts(&mut world, 2.0);
+        let mut rope = Rope::new(&world, left, Vec2::new(0.0, 0.0), right, Vec2::new(0.5, 0.0), 4, 2.5);
+        world.body_mut(right).unwrap().set_position(Vec2::new(2.0, 3.0));
+        rope.step(&world, 1.0 / 60.0);
+        assert_eq!(rope.nodes()[4], Vec2::new(2.5, 3.0));
+
+        let missing = BodyHandle::new(7, 0);
+        let anchor = (missing, Vec2::new(0.0, 0.0));
+        assert_eq!(
+            Rope::try_new(&world, (left, Vec2::new(0.0, 0.0)), anchor, 4, 1.0).err(),
+            Some(WorldError::InvalidHandle(missing))
+        );
+    }
+
+    #[test]
+    fn test_rope_rests_on_body() {
+        let mut world = World::new();
+        let (left, right) = posts(&mut world, 4.0);
+        let mut bar = RigidBody::with_polygon(0.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        bar.set_position(Vec2::new(2.0, 3.0));
+        world.create_body(bar);
+        let origin = Vec2::new(0.0, 0.0);
+        let mut rope = Rope::new(&world, left, origin, right, origin, 16, 8.0);
+        rope.radius = 0.05;
+        for _ in 0..300 {
+            rope.step(&world, 1.0 / 60.0);
+        }
+        // The middle drapes over the top of the bar instead of falling through it
+        let middle = rope.nodes()[8];
+        assert!(middle.y > 3.5 && middle.y < 3.7, "{:?}", middle);
+    }
+}
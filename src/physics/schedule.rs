@@ -0,0 +1,244 @@
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+use super::world::{BodyHandle, WorldError};
+
+/// An impulse queued to be applied at a simulation time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledImpulse {
+    pub body: BodyHandle,
+    pub time: f64,
+    pub impulse: Vec2,
+}
+
+/// A force queued to act over the simulation time range [start, end)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledForce {
+    pub body: BodyHandle,
+    pub start: f64,
+    pub end: f64,
+    pub force: Vec2,
+}
+
+/// Timeline of forces and impulses waiting to be applied by the world
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schedule {
+    impulses: Vec<ScheduledImpulse>,
+    forces: Vec<ScheduledForce>,
+}
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        Schedule {
+            impulses: Vec::new(),
+            forces: Vec::new(),
+        }
+    }
+
+    /// Queue an impulse. Impulses at the same time are applied in the order they were added.
+    /// The time must be finite, since the queue is kept sorted by it.
+    pub fn add_impulse(&mut self, impulse: ScheduledImpulse) -> Result<(), WorldError> {
+        if !impulse.time.is_finite() {
+            return Err(WorldError::InvalidTime(impulse.time));
+        }
+        let idx = self.impulses.iter().position(|i| i.time > impulse.time).unwrap_or(self.impulses.len());
+        self.impulses.insert(idx, impulse);
+        Ok(())
+    }
+
+    /// Queue a force. The start must be finite; an infinite end keeps the force acting forever.
+    pub fn add_force(&mut self, force: ScheduledForce) -> Result<(), WorldError> {
+        if !force.start.is_finite() {
+            return Err(WorldError::InvalidTime(force.start));
+        }
+        if force.end.is_nan() {
+            return Err(WorldError::InvalidTime(force.end));
+        }
+        self.forces.push(force);
+        Ok(())
+    }
+
+    pub fn impulses(&self) -> &[ScheduledImpulse] {
+        &self.impulses
+    }
+
+    pub fn forces(&self) -> &[ScheduledForce] {
+        &self.forces
+    }
+
+    /// Number of queued impulses and forces
+    pub fn len(&self) -> usize {
+        self.impulses.len() + self.forces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.impulses.clear();
+        self.forces.clear();
+    }
+
+    /// The first time after t at which an impulse fires or a force starts or stops, capped at end
+    pub(crate) fn next_boundary(&self, t: f64, end: f64) -> f64 {
+        let mut next = end;
+        for i in &self.impulses {
+            if i.time > t {
+                next = next.min(i.time);
+                break;
+            }
+        }
+        for f in &self.forces {
+            if f.start > t {
+                next = next.min(f.start);
+            }
+            if f.end > t {
+                next = next.min(f.end);
+            }
+        }
+        next
+    }
+
+    /// Remove and return all impulses due at or before t
+    pub(crate) fn take_due_impulses(&mut self, t: f64) -> Vec<ScheduledImpulse> {
+        let count = self.impulses.iter().take_while(|i| i.time <= t).count();
+        self.impulses.drain(..count).collect()
+    }
+
+    /// Forces acting at time t
+    pub(crate) fn active_forces(&self, t: f64) -> impl Iterator<Item = &ScheduledForce> {
+        self.forces.iter().filter(move |f| f.start <= t && t < f.end)
+    }
+
+    /// Drop forces that have stopped acting by time t
+    pub(crate) fn prune_forces(&mut self, t: f64) {
+        self.forces.retain(|f| f.end > t);
+    }
+}
+
+impl Binary for ScheduledImpulse {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body);
+        w.put(&self.time);
+        w.put(&self.impulse);
+    }
+
+    fn decode(r: &mut Reader) -> Result<ScheduledImpulse, SerializeError> {
+        Ok(ScheduledImpulse {
+            body: r.get()?,
+            time: r.get()?,
+            impulse: r.get()?,
+        })
+    }
+}
+
+impl Binary for ScheduledForce {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body);
+        w.put(&self.start);
+        w.put(&self.end);
+        w.put(&self.force);
+    }
+
+    fn decode(r: &mut Reader) -> Result<ScheduledForce, SerializeError> {
+        Ok(ScheduledForce {
+            body: r.get()?,
+            start: r.get()?,
+            end: r.get()?,
+            force: r.get()?,
+        })
+    }
+}
+
+impl Binary for Schedule {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.impulses);
+        w.put(&self.forces);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Schedule, SerializeError> {
+        let impulses: Vec<ScheduledImpulse> = r.get()?;
+        let forces: Vec<ScheduledForce> = r.get()?;
+        let mut schedule = Schedule::new();
+        for impulse in impulses {
+            schedule.add_impulse(impulse).map_err(|e| SerializeError::InvalidValue(e.to_string()))?;
+        }
+        for force in forces {
+            schedule.add_force(force).map_err(|e| SerializeError::InvalidValue(e.to_string()))?;
+        }
+        Ok(schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impulse(time: f64, x: f64) -> ScheduledImpulse {
+        ScheduledImpulse {
+            body: BodyHandle::new(0, 0),
+            time,
+            impulse: Vec2::new(x, 0.0),
+        }
+    }
+
+    #[test]
+    fn test_impulses_sorted() {
+        let mut schedule = Schedule::new();
+        schedule.add_impulse(impulse(2.0, 1.0)).unwrap();
+        schedule.add_impulse(impulse(1.0, 2.0)).unwrap();
+        schedule.add_impulse(impulse(2.0, 3.0)).unwrap();
+
+        let xs: Vec<f64> = schedule.impulses().iter().map(|i| i.impulse.x).collect();
+        assert_eq!(xs, vec![2.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_take_due_impulses() {
+        let mut schedule = Schedule::new();
+        schedule.add_impulse(impulse(1.0, 1.0)).unwrap();
+        schedule.add_impulse(impulse(3.0, 2.0)).unwrap();
+
+        assert_eq!(schedule.take_due_impulses(2.0).len(), 1);
+        assert_eq!(schedule.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_non_finite_times() {
+        let mut schedule = Schedule::new();
+        assert!(matches!(schedule.add_impulse(impulse(f64::NAN, 1.0)), Err(WorldError::InvalidTime(_))));
+        assert_eq!(schedule.add_impulse(impulse(f64::INFINITY, 1.0)), Err(WorldError::InvalidTime(f64::INFINITY)));
+        let force = ScheduledForce {
+            body: BodyHandle::new(0, 0),
+            start: f64::NAN,
+            end: 1.0,
+            force: Vec2::new(1.0, 0.0),
+        };
+        assert!(schedule.add_force(force).is_err());
+        assert!(schedule.is_empty());
+
+        // A rejected impulse doesn't hold up the ones behind it
+        schedule.add_impulse(impulse(0.01, 1.0)).unwrap();
+        assert_eq!(schedule.take_due_impulses(0.01).len(), 1);
+    }
+
+    #[test]
+    fn test_next_boundary() {
+        let mut schedule = Schedule::new();
+        schedule.add_impulse(impulse(0.75, 1.0)).unwrap();
+        schedule.add_force(ScheduledForce {
+            body: BodyHandle::new(0, 0),
+            start: 0.25,
+            end: 0.5,
+            force: Vec2::new(1.0, 0.0),
+        })
+        .unwrap();
+
+        assert_eq!(schedule.next_boundary(0.0, 1.0), 0.25);
+        assert_eq!(schedule.next_boundary(0.25, 1.0), 0.5);
+        assert_eq!(schedule.next_boundary(0.5, 1.0), 0.75);
+        assert_eq!(schedule.next_boundary(0.75, 1.0), 1.0);
+    }
+}
@@ -0,0 +1,106 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, json, SerializeError, Value};
+
+/// Configuration of a World
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldSettings {
+    pub gravity: Vec2,
+    /// Number of keyframes kept for rewinding
+    pub keyframe_capacity: usize,
+}
+
+impl Default for WorldSettings {
+    fn default() -> WorldSettings {
+        WorldSettings::new()
+    }
+}
+
+#[wasm_bindgen]
+impl WorldSettings {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WorldSettings {
+        WorldSettings {
+            gravity: Vec2::new(0.0, -9.81),
+            keyframe_capacity: 64,
+        }
+    }
+
+    /// Save file version written by to_json
+    pub fn version() -> u32 {
+        serialize::FORMAT_VERSION
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// Load settings written by this or an older version of the crate
+    #[wasm_bindgen(js_name = from_json)]
+    pub fn wasm_from_json(s: &str) -> Result<WorldSettings, JsError> {
+        WorldSettings::from_json(s).map_err(JsError::from)
+    }
+}
+
+impl WorldSettings {
+    /// Load settings written by this or an older version of the crate
+    pub fn from_json(s: &str) -> Result<WorldSettings, SerializeError> {
+        let doc = serialize::upgrade(json::parse(s)?)?;
+        WorldSettings::from_value(&doc)
+    }
+
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("version", Value::Number(serialize::FORMAT_VERSION as f64));
+        v.insert("gravity", serialize::vec2_to_value(&self.gravity));
+        v.insert("keyframe_capacity", Value::Number(self.keyframe_capacity as f64));
+        v
+    }
+
+    /// Read from an upgraded document
+    pub(crate) fn from_value(v: &Value) -> Result<WorldSettings, SerializeError> {
+        Ok(WorldSettings {
+            gravity: serialize::vec2_from_value(v.field("gravity")?)?,
+            keyframe_capacity: v.field_f64("keyframe_capacity")? as usize,
+        })
+    }
+}
+
+impl Binary for WorldSettings {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.gravity);
+        w.put(&self.keyframe_capacity);
+    }
+
+    fn decode(r: &mut Reader) -> Result<WorldSettings, SerializeError> {
+        Ok(WorldSettings {
+            gravity: r.get()?,
+            keyframe_capacity: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut settings = WorldSettings::new();
+        settings.gravity = Vec2::new(1.0, 2.0);
+        settings.keyframe_capacity = 8;
+
+        assert_eq!(WorldSettings::from_json(&settings.to_json()), Ok(settings));
+    }
+
+    #[test]
+    fn test_settings_from_old_version() {
+        let settings = WorldSettings::from_json(r#"{"gravity":[0,-1]}"#).unwrap();
+
+        assert_eq!(settings.gravity, Vec2::new(0.0, -1.0));
+        assert_eq!(settings.keyframe_capacity, 64);
+    }
+}
@@ -0,0 +1,166 @@
+use super::bodies::BodySet;
+use super::bounds::WorldBounds;
+use super::contact::{Contact, HitFilter};
+use super::features::WorldFeatures;
+use super::forces::ForceGenerator;
+use super::hooks::PairFlags;
+use super::joint::Joint;
+use super::particles::ParticleSystem;
+use super::schedule::Schedule;
+use super::settings::WorldSettings;
+use super::solver::SolverConfig;
+use super::spring::Spring;
+use crate::math::Rng;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+/// Leading bytes of an encoded state
+const MAGIC: &[u8; 4] = b"N2DS";
+/// Version of the binary state layout
+pub const STATE_VERSION: u32 = 12;
+/// Solver passes or substeps above this in decoded bytes are taken as corrupt, since stepping
+/// would never finish
+const MAX_SOLVER_PASSES: usize = 1 << 16;
+
+/// A full copy of the simulation state of a World.
+/// The broadphase isn't stored since it is rebuilt from the bodies on restore.
+/// Force generators can be of any type, so to_bytes leaves them out, restoring a decoded state
+/// keeps the world's current generators, and equality doesn't compare them.
+#[derive(Clone, Debug)]
+pub struct WorldState {
+    pub(crate) objects: BodySet,
+    pub(crate) settings: WorldSettings,
+    pub(crate) time: f64,
+    pub(crate) tick: u64,
+    pub(crate) schedule: Schedule,
+    pub(crate) springs: Vec<Spring>,
+    pub(crate) joints: Vec<Joint>,
+    pub(crate) particles: ParticleSystem,
+    pub(crate) contacts: Vec<Contact>,
+    pub(crate) hit_filter: HitFilter,
+    pub(crate) rng: Rng,
+    pub(crate) pairs: PairFlags,
+    pub(crate) solver: SolverConfig,
+    pub(crate) features: WorldFeatures,
+    pub(crate) bounds: Option<WorldBounds>,
+    /// None for states decoded from bytes
+    pub(crate) force_generators: Option<Vec<Box<dyn ForceGenerator>>>,
+}
+
+impl PartialEq for WorldState {
+    fn eq(&self, other: &WorldState) -> bool {
+        self.objects == other.objects
+            && self.settings == other.settings
+            && self.time == other.time
+            && self.tick == other.tick
+            && self.schedule == other.schedule
+            && self.springs == other.springs
+            && self.joints == other.joints
+            && self.particles == other.particles
+            && self.contacts == other.contacts
+            && self.hit_filter == other.hit_filter
+            && self.rng == other.rng
+            && self.pairs == other.pairs
+            && self.solver == other.solver
+            && self.features == other.features
+            && self.bounds == other.bounds
+    }
+}
+
+impl WorldState {
+    /// Simulation time the state was captured at
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Step count the state was captured at
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Compact binary encoding with exact float bits, for rollback netcode and replays
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.put(&STATE_VERSION);
+        w.put(self);
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<WorldState, SerializeError> {
+        let mut r = Reader::new(bytes);
+        if r.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(SerializeError::InvalidValue("not a world state".to_string()));
+        }
+        let version: u32 = r.get()?;
+        if version != STATE_VERSION {
+            return Err(SerializeError::UnsupportedVersion(version));
+        }
+        let state = r.get()?;
+        r.finish()?;
+        Ok(state)
+    }
+}
+
+impl Binary for WorldState {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.objects);
+        w.put(&self.settings);
+        w.put(&self.time);
+        w.put(&self.tick);
+        w.put(&self.schedule);
+        w.put(&self.springs);
+        w.put(&self.joints);
+        w.put(&self.particles);
+        w.put(&self.contacts);
+        w.put(&self.hit_filter);
+        w.put(&self.rng);
+        w.put(&self.pairs);
+        w.put(&self.solver);
+        w.put(&self.features);
+        w.put(&self.bounds);
+    }
+
+    fn decode(r: &mut Reader) -> Result<WorldState, SerializeError> {
+        let state = WorldState {
+            objects: r.get()?,
+            settings: r.get()?,
+            time: r.get()?,
+            tick: r.get()?,
+            schedule: r.get()?,
+            springs: r.get()?,
+            joints: r.get()?,
+            particles: r.get()?,
+            contacts: r.get()?,
+            hit_filter: r.get()?,
+            rng: r.get()?,
+            pairs: r.get()?,
+            solver: r.get()?,
+            features: r.get()?,
+            bounds: r.get()?,
+            force_generators: None,
+        };
+        state.validate()?;
+        Ok(state)
+    }
+}
+
+impl WorldState {
+    /// Error if a joint or cached contact links a body to itself, a contact names a missing body,
+    /// or the solver would take too many passes
+    fn validate(&self) -> Result<(), SerializeError> {
+        let passes = [self.solver.velocity_iterations, self.solver.position_iterations, self.solver.substeps];
+        if passes.iter().any(|&n| n > MAX_SOLVER_PASSES) {
+            return Err(SerializeError::InvalidValue("too many solver passes".to_string()));
+        }
+        if self.joints.iter().any(|j| j.body_a() == j.body_b()) {
+            return Err(SerializeError::InvalidValue("joint between a body and itself".to_string()));
+        }
+        for c in &self.contacts {
+            if c.body_a == c.body_b || !self.objects.contains(c.body_a) || !self.objects.contains(c.body_b) {
+                return Err(SerializeError::InvalidValue("contact with an invalid body pair".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
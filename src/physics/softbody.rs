@@ -0,0 +1,326 @@
+//! Soft bodies simulated as a ring of perimeter nodes, for blobs and jelly.
+//!
+//! Like a Rope, a SoftBody is positional (XPBD): every step the nodes move on under gravity, then
+//! each edge is pulled back towards its rest length and, with pressure on, the enclosed area is
+//! pushed towards a target so the ring doesn't collapse. Bending springs between every other node
+//! can stiffen the outline instead of or as well as pressure. Nodes are pushed out of the bodies of
+//! a World through the narrowphase, but the soft body doesn't push on those bodies, and it doesn't
+//! collide with itself or other soft bodies.
+
+use wasm_bindgen::prelude::*;
+
+use super::world::World;
+use crate::collision::narrowphase;
+use crate::geometry::{Circle, Shapes};
+use crate::math::{trig, Transform2D, Vec2};
+
+/// Distance constraint between two nodes
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Link {
+    a: usize,
+    b: usize,
+    rest_length: f64,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftBody {
+    /// Stretch of the outline per unit of tension, 0 for edges that don't stretch
+    pub edge_compliance: f64,
+    /// Compliance of the springs between every other node, infinite to leave them out
+    pub bend_compliance: f64,
+    /// Area the outline is pushed towards as a multiple of its rest area, 0 for no pressure
+    pub pressure: f64,
+    /// Change in area per unit of pressure, 0 for an outline that keeps its area exactly
+    pub pressure_compliance: f64,
+    /// Constraint passes each step
+    pub iterations: usize,
+    /// Fraction of node velocity lost each step
+    pub damping: f64,
+    /// Radius nodes collide with
+    pub radius: f64,
+    /// Fraction of the push out of a body that also holds a node from sliding along it
+    pub friction: f64,
+    nodes: Vec<Vec2>,
+    previous: Vec<Vec2>,
+    edges: Vec<Link>,
+    bends: Vec<Link>,
+    rest_area: f64,
+}
+
+#[wasm_bindgen]
+impl SoftBody {
+    /// A round blob of radius around center with segments nodes on its outline
+    #[wasm_bindgen(constructor)]
+    pub fn new(center: Vec2, radius: f64, segments: usize) -> SoftBody {
+        let segments = segments.max(3);
+        let step = 2.0 * std::f64::consts::PI / segments as f64;
+        let points: Vec<Vec2> = (0..segments)
+            .map(|i| center + Vec2::new(trig::cos(step * i as f64), trig::sin(step * i as f64)) * radius)
+            .collect();
+        SoftBody::from_points(&points)
+    }
+
+    /// A soft body with an outline given as a flat [x0, y0, x1, y1, ...] list in world coordinates.
+    /// Panics with fewer than 3 points.
+    pub fn with_polygon(points: &[f64]) -> SoftBody {
+        SoftBody::from_points(&points.chunks_exact(2).map(|p| Vec2::new(p[0], p[1])).collect::<Vec<_>>())
+    }
+
+    /// Nodes on the outline
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Area enclosed by the outline when nothing pushes on it
+    pub fn rest_area(&self) -> f64 {
+        self.rest_area.abs()
+    }
+
+    /// Area the outline encloses now
+    pub fn area(&self) -> f64 {
+        signed_area(&self.nodes).abs()
+    }
+
+    /// Average of the node positions
+    pub fn center(&self) -> Vec2 {
+        self.nodes.iter().fold(Vec2::new(0.0, 0.0), |sum, p| sum + *p) / self.nodes.len() as f64
+    }
+
+    /// Average of the node velocities over the last step of dt seconds
+    pub fn velocity(&self, dt: f64) -> Vec2 {
+        let moved = self.nodes.iter().zip(&self.previous).fold(Vec2::new(0.0, 0.0), |sum, (p, q)| sum + (*p - *q));
+        moved / (self.nodes.len() as f64 * dt)
+    }
+
+    /// Give every node the same velocity, as it will be moving over the next step of dt seconds
+    pub fn set_velocity(&mut self, velocity: Vec2, dt: f64) {
+        for (p, q) in self.nodes.iter().zip(self.previous.iter_mut()) {
+            *q = *p - velocity * dt;
+        }
+    }
+
+    /// Move every node by offset without changing its velocity
+    pub fn translate(&mut self, offset: Vec2) {
+        for p in self.nodes.iter_mut().chain(self.previous.iter_mut()) {
+            *p = *p + offset;
+        }
+    }
+
+    /// Node positions as a flat x, y list around the outline, for drawing
+    #[wasm_bindgen(js_name = nodes)]
+    pub fn wasm_nodes(&self) -> Vec<f64> {
+        self.nodes.iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    /// Move the soft body on by dt under world's gravity, pushing it out of world's bodies
+    pub fn step(&mut self, world: &World, dt: f64) {
+        if dt <= 0.0 {
+            return;
+        }
+        let gravity = world.gravity() * (dt * dt);
+        let keep = 1.0 - self.damping.clamp(0.0, 1.0);
+        for (p, q) in self.nodes.iter_mut().zip(self.previous.iter_mut()) {
+            let velocity = *p - *q;
+            *q = *p;
+            *p = *p + velocity * keep + gravity;
+        }
+
+        let dt2 = dt * dt;
+        let mut edge_lambdas = vec![0.0; self.edges.len()];
+        let mut bend_lambdas = vec![0.0; self.bends.len()];
+        let mut area_lambda = 0.0;
+        for _ in 0..self.iterations {
+            // Sweeping forwards then backwards, so the order links are solved in doesn't spin the ring
+            for (link, lambda) in self.edges.iter().zip(edge_lambdas.iter_mut()) {
+                solve_link(&mut self.nodes, link, self.edge_compliance / dt2, lambda);
+            }
+            for (link, lambda) in self.edges.iter().zip(edge_lambdas.iter_mut()).rev() {
+                solve_link(&mut self.nodes, link, self.edge_compliance / dt2, lambda);
+            }
+            if self.bend_compliance.is_finite() {
+                for (link, lambda) in self.bends.iter().zip(bend_lambdas.iter_mut()) {
+                    solve_link(&mut self.nodes, link, self.bend_compliance / dt2, lambda);
+                }
+                for (link, lambda) in self.bends.iter().zip(bend_lambdas.iter_mut()).rev() {
+                    solve_link(&mut self.nodes, link, self.bend_compliance / dt2, lambda);
+                }
+            }
+            if self.pressure > 0.0 {
+                self.solve_area(self.pressure_compliance / dt2, &mut area_lambda);
+            }
+            self.collide(world);
+        }
+    }
+}
+
+impl SoftBody {
+    /// A soft body with this outline in world coordinates, in either winding. Panics with fewer
+    /// than 3 points.
+    pub fn from_points(points: &[Vec2]) -> SoftBody {
+        assert!(points.len() >= 3, "Soft body needs at least 3 nodes");
+        let n = points.len();
+        let link = |a: usize, b: usize| Link {
+            a,
+            b,
+            rest_length: Vec2::distance(&points[a], &points[b]),
+        };
+        SoftBody {
+            edge_compliance: 0.0,
+            bend_compliance: f64::INFINITY,
+            pressure: 1.0,
+            pressure_compliance: 0.0,
+            iterations: 8,
+            damping: 0.01,
+            radius: 0.05,
+            friction: 0.5,
+            nodes: points.to_vec(),
+            previous: points.to_vec(),
+            edges: (0..n).map(|i| link(i, (i + 1) % n)).collect(),
+            bends: if n > 3 { (0..n).map(|i| link(i, (i + 2) % n)).collect() } else { Vec::new() },
+            rest_area: signed_area(points),
+        }
+    }
+
+    /// Node positions around the outline
+    pub fn nodes(&self) -> &[Vec2] {
+        &self.nodes
+    }
+
+    /// Push the nodes along the gradient of the area towards pressure times the rest area
+    fn solve_area(&mut self, alpha: f64, lambda: &mut f64) {
+        let n = self.nodes.len();
+        let gradient: Vec<Vec2> = (0..n)
+            .map(|i| {
+                let (prev, next) = (self.nodes[(i + n - 1) % n], self.nodes[(i + 1) % n]);
+                Vec2::new(next.y - prev.y, prev.x - next.x) * 0.5
+            })
+            .collect();
+        let weight: f64 = gradient.iter().map(|g| g.len_sq()).sum();
+        if weight == 0.0 {
+            return;
+        }
+        let error = signed_area(&self.nodes) - self.rest_area * self.pressure;
+        let d_lambda = (-error - alpha * *lambda) / (weight + alpha);
+        *lambda += d_lambda;
+        for (p, g) in self.nodes.iter_mut().zip(&gradient) {
+            *p = *p + *g * d_lambda;
+        }
+    }
+
+    /// Push nodes out of the world's bodies, holding back how far they slide along them
+    fn collide(&mut self, world: &World) {
+        if self.radius <= 0.0 {
+            return;
+        }
+        let config = world.narrowphase_config();
+        let shape = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), self.radius));
+        let reach = Vec2::splat(self.radius);
+        for (node, previous) in self.nodes.iter_mut().zip(self.previous.iter_mut()) {
+            for handle in world.query_aabb(*node - reach, *node + reach) {
+                let body = world.body(handle).unwrap();
+                let at = Transform2D::new(*node, 0.0);
+                let contacts = narrowphase::collide_with(&config, body.shape(), &body.transform(), &shape, &at);
+                let push = match contacts.first() {
+                    Some(contact) => contact.penetration,
+                    None => continue,
+                };
+                *node = *node + push;
+                let depth = push.len();
+                if depth > 0.0 {
+                    let normal = push / depth;
+                    let moved = *node - *previous;
+                    let slide = moved - normal * Vec2::dot(&moved, &normal);
+                    *previous = *previous + slide.clamp_length(self.friction * depth);
+                }
+            }
+        }
+    }
+}
+
+/// Pull the two nodes of a link towards its rest length
+fn solve_link(nodes: &mut [Vec2], link: &Link, alpha: f64, lambda: &mut f64) {
+    let delta = nodes[link.b] - nodes[link.a];
+    let distance = delta.len();
+    if distance == 0.0 {
+        return;
+    }
+    let error = distance - link.rest_length;
+    let d_lambda = (-error - alpha * *lambda) / (2.0 + alpha);
+    *lambda += d_lambda;
+    let correction = delta * (d_lambda / distance);
+    nodes[link.a] = nodes[link.a] - correction;
+    nodes[link.b] = nodes[link.b] + correction;
+}
+
+/// Area of a closed outline, positive when counter-clockwise
+fn signed_area(points: &[Vec2]) -> f64 {
+    let n = points.len();
+    (0..n).map(|i| Vec2::cross(&points[i], &points[(i + 1) % n])).sum::<f64>() * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::RigidBody;
+
+    fn ground(world: &mut World) {
+        world.create_body(RigidBody::with_polygon(0.0, &[-10.0, -1.0, 10.0, -1.0, 10.0, 0.0, -10.0, 0.0]));
+    }
+
+    #[test]
+    fn test_blob_rests_on_ground() {
+        let mut world = World::new();
+        ground(&mut world);
+        let mut blob = SoftBody::new(Vec2::new(0.0, 2.0), 1.0, 16);
+        assert_eq!(blob.len(), 16);
+        assert!((blob.area() - blob.rest_area()).abs() < 1e-12);
+
+        for _ in 0..300 {
+            blob.step(&world, 1.0 / 60.0);
+        }
+        // Squashed a little against the ground but holding most of its area off it
+        let lowest = blob.nodes().iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        assert!(lowest > 0.0 && lowest < 0.1, "{}", lowest);
+        assert!((blob.area() - blob.rest_area()).abs() < 0.05 * blob.rest_area());
+        assert!(blob.center().y > 0.8);
+        assert!(blob.velocity(1.0 / 60.0).len() < 0.05, "{:?}", blob.velocity(1.0 / 60.0));
+    }
+
+    #[test]
+    fn test_pressure() {
+        let mut world = World::new();
+        ground(&mut world);
+        let mut empty = SoftBody::new(Vec2::new(0.0, 1.0), 1.0, 16);
+        empty.pressure = 0.0;
+        let mut inflated = SoftBody::with_polygon(&[-1.0, 0.0, 1.0, 0.0, 1.0, 2.0, -1.0, 2.0]);
+        inflated.pressure = 1.5;
+        for _ in 0..300 {
+            empty.step(&world, 1.0 / 60.0);
+            inflated.step(&world, 1.0 / 60.0);
+        }
+        // Without pressure or bending springs the outline folds flat, with it swells
+        assert!(empty.area() < 1.0, "{}", empty.area());
+        assert!(inflated.area() > 4.5, "{}", inflated.area());
+        assert_eq!(inflated.wasm_nodes().len(), 8);
+    }
+
+    #[test]
+    fn test_set_velocity() {
+        let world = World::new();
+        let mut blob = SoftBody::new(Vec2::new(0.0, 0.0), 1.0, 8);
+        blob.damping = 0.0;
+        blob.set_velocity(Vec2::new(2.0, 0.0), 0.1);
+        blob.step(&world, 0.1);
+        let center = blob.center();
+        assert!((center.x - 0.2).abs() < 1e-9);
+        // Falling under gravity as a whole
+        assert!((center.y - world.gravity().y * 0.01).abs() < 1e-9);
+        blob.translate(Vec2::new(0.0, 5.0));
+        assert!((blob.velocity(0.1) - Vec2::new(2.0, world.gravity().y * 0.1)).len() < 1e-9);
+    }
+}
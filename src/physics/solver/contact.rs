@@ -0,0 +1,253 @@
+use crate::math::{Mat2, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::SerializeError;
+
+use super::{ConstraintRow, Jacobian, LcpRow, SolverBody};
+
+/// Relative normal speed below which contacts don't bounce
+pub const RESTITUTION_THRESHOLD: f64 = 1.0;
+/// Largest condition number of the mass matrix of two contacts solved together. Past it the
+/// points are nearly the same and are solved one at a time instead.
+pub const MAX_CONDITION: f64 = 1000.0;
+
+/// Impulses applied by a contact
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct ContactImpulse {
+    pub normal: f64,
+    pub tangent: f64,
+}
+
+impl Binary for ContactImpulse {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.normal);
+        w.put(&self.tangent);
+    }
+
+    fn decode(r: &mut Reader) -> Result<ContactImpulse, SerializeError> {
+        Ok(ContactImpulse {
+            normal: r.get()?,
+            tangent: r.get()?,
+        })
+    }
+}
+
+/// Non-penetration and friction constraint at a single contact point.
+/// The normal points from body a to body b.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactConstraint {
+    pub normal: Vec2,
+    /// Contact point relative to the center of body a
+    pub ra: Vec2,
+    /// Contact point relative to the center of body b
+    pub rb: Vec2,
+    pub friction: f64,
+    normal_row: ConstraintRow,
+    tangent_row: ConstraintRow,
+}
+
+impl ContactConstraint {
+    pub fn new(
+        a: &SolverBody,
+        b: &SolverBody,
+        ra: Vec2,
+        rb: Vec2,
+        normal: Vec2,
+        friction: f64,
+        restitution: f64,
+    ) -> ContactConstraint {
+        let tangent = normal.perp();
+        let normal_j = Jacobian::along(&normal, &ra, &rb);
+        let tangent_j = Jacobian::along(&tangent, &ra, &rb);
+
+        let approach = normal_j.velocity(a, b);
+        let bounce = if approach < -RESTITUTION_THRESHOLD {
+            restitution * approach
+        } else {
+            0.0
+        };
+
+        ContactConstraint {
+            normal,
+            ra,
+            rb,
+            friction,
+            normal_row: ConstraintRow::new(normal_j, a, b, bounce, 0.0, f64::INFINITY),
+            tangent_row: ConstraintRow::equality(tangent_j, a, b, 0.0),
+        }
+    }
+
+    /// Add an extra separating velocity, e.g. to push overlapping bodies apart
+    pub fn with_bias(mut self, bias: f64) -> ContactConstraint {
+        self.normal_row.bias -= bias;
+        self
+    }
+
+    /// Make friction drag b towards sliding along the tangent at speed relative to a, as if one
+    /// of the surfaces were a moving belt
+    pub fn with_surface_velocity(mut self, speed: f64) -> ContactConstraint {
+        self.tangent_row.bias -= speed;
+        self
+    }
+
+    pub fn tangent(&self) -> Vec2 {
+        self.normal.perp()
+    }
+
+    pub fn normal_row(&self) -> &ConstraintRow {
+        &self.normal_row
+    }
+
+    pub fn tangent_row(&self) -> &ConstraintRow {
+        &self.tangent_row
+    }
+
+    pub fn impulse(&self) -> ContactImpulse {
+        ContactImpulse {
+            normal: self.normal_row.impulse,
+            tangent: self.tangent_row.impulse,
+        }
+    }
+
+    /// Start from previously accumulated impulses
+    pub fn set_impulse(&mut self, a: &mut SolverBody, b: &mut SolverBody, impulse: ContactImpulse) {
+        self.normal_row.impulse = impulse.normal;
+        self.tangent_row.impulse = impulse.tangent;
+        self.normal_row.warm_start(a, b);
+        self.tangent_row.warm_start(a, b);
+    }
+
+    /// The normal row, then the friction row bounded by it, for solving with solver::lcp.
+    /// a and b index the bodies and normal is the index the normal row will have.
+    pub fn lcp_rows(&self, a: usize, b: usize, normal: usize) -> [LcpRow; 2] {
+        [
+            LcpRow::from_row(a, b, &self.normal_row),
+            LcpRow {
+                scaled_by: Some(normal),
+                ..LcpRow::new(a, b, self.tangent_row.jacobian, self.tangent_row.bias, -self.friction, self.friction)
+            },
+        ]
+    }
+
+    /// Take impulses solved and applied to the bodies elsewhere
+    pub fn set_solved_impulse(&mut self, impulse: ContactImpulse) {
+        self.normal_row.impulse = impulse.normal;
+        self.tangent_row.impulse = impulse.tangent;
+    }
+
+    /// One friction then one normal iteration
+    pub fn solve(&mut self, a: &mut SolverBody, b: &mut SolverBody) {
+        self.solve_friction(a, b);
+        self.normal_row.solve(a, b);
+    }
+
+    fn solve_friction(&mut self, a: &mut SolverBody, b: &mut SolverBody) {
+        let max_friction = self.friction * self.normal_row.impulse;
+        self.tangent_row.lower = -max_friction;
+        self.tangent_row.upper = max_friction;
+        self.tangent_row.solve(a, b);
+    }
+
+    /// One iteration of two contacts between the same bodies, like the corners of a box resting
+    /// on the ground. Both normal impulses are found at once, so one corner can't take more than
+    /// its share of the weight the way it does when the corners are solved in turn, which tips
+    /// tall stacks over.
+    pub fn solve_pair(first: &mut ContactConstraint, second: &mut ContactConstraint, a: &mut SolverBody, b: &mut SolverBody) {
+        first.solve_friction(a, b);
+        second.solve_friction(a, b);
+
+        let (j1, j2) = (first.normal_row.jacobian, second.normal_row.jacobian);
+        let (k11, k22, k12) = (j1.inverse_effective_mass(a, b), j2.inverse_effective_mass(a, b), j1.coupling(&j2, a, b));
+        if k11 * k11 >= MAX_CONDITION * (k11 * k22 - k12 * k12) {
+            first.normal_row.solve(a, b);
+            second.normal_row.solve(a, b);
+            return;
+        }
+        let k = Mat2::new(k11, k12, k12, k22);
+        let old = Vec2::new(first.normal_row.impulse, second.normal_row.impulse);
+        // Velocity errors if the accumulated impulses were taken back out
+        let error = Vec2::new(first.normal_row.velocity_error(a, b), second.normal_row.velocity_error(a, b)) - k.mul_vec(&old);
+
+        // Try each pair of points touching or not, until the impulses push and the points left
+        // out aren't approaching
+        let both = -k.solve(&error);
+        let only_first = Vec2::new(-error.x / k11, 0.0);
+        let only_second = Vec2::new(0.0, -error.y / k22);
+        let total = if both.x >= 0.0 && both.y >= 0.0 {
+            both
+        } else if only_first.x >= 0.0 && k12 * only_first.x + error.y >= 0.0 {
+            only_first
+        } else if only_second.y >= 0.0 && k12 * only_second.y + error.x >= 0.0 {
+            only_second
+        } else if error.x >= 0.0 && error.y >= 0.0 {
+            Vec2::new(0.0, 0.0)
+        } else {
+            old
+        };
+
+        j1.apply(a, b, total.x - old.x);
+        j2.apply(a, b, total.y - old.y);
+        first.normal_row.impulse = total.x;
+        second.normal_row.impulse = total.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::solve_contact;
+    use super::*;
+
+    fn head_on(restitution: f64) -> (SolverBody, SolverBody, ContactConstraint) {
+        let a = SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(2.0, 0.0), 0.0);
+        let b = SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(-2.0, 0.0), 0.0);
+        let c = ContactConstraint::new(
+            &a,
+            &b,
+            Vec2::new(1.0, 0.0),
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            0.0,
+            restitution,
+        );
+        (a, b, c)
+    }
+
+    #[test]
+    fn test_inelastic_contact() {
+        let (mut a, mut b, mut c) = head_on(0.0);
+        let impulse = solve_contact(&mut a, &mut b, &mut c, 10);
+
+        assert_eq!(impulse.normal, 2.0);
+        assert_eq!(a.velocity, Vec2::new(0.0, 0.0));
+        assert_eq!(b.velocity, Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_elastic_contact_swaps_velocities() {
+        let (mut a, mut b, mut c) = head_on(1.0);
+        solve_contact(&mut a, &mut b, &mut c, 10);
+
+        assert_eq!(a.velocity, Vec2::new(-2.0, 0.0));
+        assert_eq!(b.velocity, Vec2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn test_friction_is_bounded() {
+        // Box sliding on static ground
+        let mut ground = SolverBody::new(0.0, 0.0);
+        let mut block = SolverBody::new(1.0, 0.0).with_velocity(Vec2::new(5.0, -1.0), 0.0);
+        let mut c = ContactConstraint::new(
+            &ground,
+            &block,
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, -0.5),
+            Vec2::new(0.0, 1.0),
+            0.5,
+            0.0,
+        );
+        let impulse = solve_contact(&mut ground, &mut block, &mut c, 10);
+
+        assert_eq!(impulse.normal, 1.0);
+        assert_eq!(impulse.tangent * c.tangent(), Vec2::new(-0.5, 0.0));
+        assert_eq!(block.velocity, Vec2::new(4.5, 0.0));
+    }
+}
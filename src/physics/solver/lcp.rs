@@ -0,0 +1,226 @@
+//! Solver backend assembling all constraint rows into one linear complementarity problem.
+//!
+//! Rows are grouped into islands of bodies that touch through constraints. For each island the
+//! system `A λ = -(J v + bias)` with `A = J M^-1 J^T`, plus the softness of soft rows on its
+//! diagonal, is built as a dense Matrix and solved by projected Gauss-Seidel, with every impulse
+//! kept within its row's bounds and friction rows bounded by their normal impulse. Unlike the
+//! sequential impulse loop this iterates until the impulses settle, which keeps long joint chains
+//! and gear trains stiffer, but the dense system costs O(rows²) memory per island.
+
+use crate::math::{IterativeConfig, Matrix, Vec2, Vector};
+use crate::physics::constraints::pair_mut;
+
+use super::{ConstraintRow, Jacobian, PointConstraint, SolverBody};
+
+/// One row of the problem between two bodies
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LcpRow {
+    /// Index of body a in the body slice
+    pub a: usize,
+    /// Index of body b in the body slice
+    pub b: usize,
+    pub jacobian: Jacobian,
+    pub bias: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// Row whose impulse scales lower and upper, as the normal row of a friction row
+    pub scaled_by: Option<usize>,
+    /// Added to the row's diagonal, see ConstraintRow::softness
+    pub softness: f64,
+}
+
+impl LcpRow {
+    pub fn new(a: usize, b: usize, jacobian: Jacobian, bias: f64, lower: f64, upper: f64) -> LcpRow {
+        LcpRow {
+            a,
+            b,
+            jacobian,
+            bias,
+            lower,
+            upper,
+            scaled_by: None,
+            softness: 0.0,
+        }
+    }
+
+    /// row between bodies a and b
+    pub fn from_row(a: usize, b: usize, row: &ConstraintRow) -> LcpRow {
+        LcpRow {
+            softness: row.softness,
+            ..LcpRow::new(a, b, row.jacobian, row.bias, row.lower, row.upper)
+        }
+    }
+
+    /// The two axes of point between bodies a and b
+    pub fn from_point(a: usize, b: usize, point: &PointConstraint) -> [LcpRow; 2] {
+        let axis = |n: Vec2, bias: f64| {
+            let jacobian = Jacobian::along(&n, &point.ra, &point.rb);
+            LcpRow::new(a, b, jacobian, bias, f64::NEG_INFINITY, f64::INFINITY)
+        };
+        [axis(Vec2::new(1.0, 0.0), point.bias.x), axis(Vec2::new(0.0, 1.0), point.bias.y)]
+    }
+
+    /// Linear and angular Jacobian of the row for body, if the row involves it
+    fn side(&self, body: usize) -> Option<(Vec2, f64)> {
+        if body == self.a {
+            Some((self.jacobian.linear_a, self.jacobian.angular_a))
+        } else if body == self.b {
+            Some((self.jacobian.linear_b, self.jacobian.angular_b))
+        } else {
+            None
+        }
+    }
+}
+
+/// Solve rows together, applying the impulses to bodies and returning the impulse of each row.
+/// Rows that can't move either body get no impulse.
+pub fn solve(bodies: &mut [SolverBody], rows: &[LcpRow], config: &IterativeConfig) -> Vec<f64> {
+    let mut impulses = vec![0.0; rows.len()];
+    for island in islands(bodies, rows) {
+        let lambda = solve_island(bodies, rows, &island, config);
+        for (&i, &impulse) in island.iter().zip(&lambda) {
+            impulses[i] = impulse;
+            let row = &rows[i];
+            let (a, b) = pair_mut(bodies, row.a, row.b);
+            row.jacobian.apply(a, b, impulse);
+        }
+    }
+    impulses
+}
+
+fn solve_island(bodies: &[SolverBody], rows: &[LcpRow], island: &[usize], config: &IterativeConfig) -> Vec<f64> {
+    let n = island.len();
+    let mut a = Matrix::new(n, n);
+    let mut b = Vector::new(n);
+    let (mut lower, mut upper) = (Vector::new(n), Vector::new(n));
+    let mut coupling = vec![None; n];
+
+    for (i, &ri) in island.iter().enumerate() {
+        let row = &rows[ri];
+        let (ba, bb) = (&bodies[row.a], &bodies[row.b]);
+        b[i] = -(row.jacobian.velocity(ba, bb) + row.bias);
+        lower[i] = row.lower;
+        upper[i] = row.upper;
+        coupling[i] = row.scaled_by.and_then(|s| island.iter().position(|&r| r == s));
+
+        for (j, &rj) in island.iter().enumerate() {
+            let other = &rows[rj];
+            let mut k = if i == j { row.softness } else { 0.0 };
+            for body in [row.a, row.b] {
+                if let (Some((lin_i, ang_i)), Some((lin_j, ang_j))) = (row.side(body), other.side(body)) {
+                    let s = &bodies[body];
+                    k += s.inv_mass * Vec2::dot(&lin_i, &lin_j) + s.inv_inertia * ang_i * ang_j;
+                }
+            }
+            a.set(i, j, k);
+        }
+    }
+    let x = a.projected_gauss_seidel_coupled(&b, &lower, &upper, &coupling, config).into_x();
+    (0..n).map(|i| x[i]).collect()
+}
+
+/// Rows grouped by the island of moving bodies they connect, in row order. Static bodies don't
+/// join islands together, and rows that move neither body are left out.
+fn islands(bodies: &[SolverBody], rows: &[LcpRow]) -> Vec<Vec<usize>> {
+    let moves = |i: usize| bodies[i].inv_mass > 0.0 || bodies[i].inv_inertia > 0.0;
+    let mut parent: Vec<usize> = (0..bodies.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut active = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        if row.jacobian.inverse_effective_mass(&bodies[row.a], &bodies[row.b]) <= 0.0 {
+            continue;
+        }
+        if moves(row.a) && moves(row.b) {
+            let (ra, rb) = (root(&mut parent, row.a), root(&mut parent, row.b));
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+        active.push(i);
+    }
+
+    let mut islands: Vec<Vec<usize>> = Vec::new();
+    let mut island_of = vec![usize::MAX; bodies.len()];
+    for i in active {
+        let row = &rows[i];
+        let body = if moves(row.a) { row.a } else { row.b };
+        let r = root(&mut parent, body);
+        if island_of[r] == usize::MAX {
+            island_of[r] = islands.len();
+            islands.push(Vec::new());
+        }
+        islands[island_of[r]].push(i);
+    }
+    islands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn along(n: Vec2) -> Jacobian {
+        Jacobian::along(&n, &Vec2::new(0.0, 0.0), &Vec2::new(0.0, 0.0))
+    }
+
+    #[test]
+    fn test_chain_solved_at_once() {
+        // Three bodies in a line held together, the first pulled left
+        let mut bodies = vec![
+            SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(-3.0, 0.0), 0.0),
+            SolverBody::new(1.0, 1.0),
+            SolverBody::new(1.0, 1.0),
+        ];
+        let x = Vec2::new(1.0, 0.0);
+        let rows = [
+            LcpRow::new(0, 1, along(x), 0.0, f64::NEG_INFINITY, f64::INFINITY),
+            LcpRow::new(1, 2, along(x), 0.0, f64::NEG_INFINITY, f64::INFINITY),
+        ];
+        solve(&mut bodies, &rows, &IterativeConfig::new());
+        for body in &bodies {
+            assert!((body.velocity - Vec2::new(-1.0, 0.0)).len() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_friction_scaled_by_normal() {
+        let mut bodies = vec![
+            SolverBody::new(0.0, 0.0),
+            SolverBody::new(1.0, 0.0).with_velocity(Vec2::new(5.0, -1.0), 0.0),
+        ];
+        let normal = LcpRow::new(0, 1, along(Vec2::new(0.0, 1.0)), 0.0, 0.0, f64::INFINITY);
+        let friction = LcpRow {
+            scaled_by: Some(0),
+            ..LcpRow::new(0, 1, along(Vec2::new(1.0, 0.0)), 0.0, -0.5, 0.5)
+        };
+        let impulses = solve(&mut bodies, &[normal, friction], &IterativeConfig::new());
+        assert!((impulses[0] - 1.0).abs() < 1e-12);
+        assert!((impulses[1] + 0.5).abs() < 1e-12);
+        assert!((bodies[1].velocity - Vec2::new(4.5, 0.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn test_islands() {
+        // Bodies 1 and 3 both rest on static body 0 but don't touch each other
+        let bodies = vec![
+            SolverBody::new(0.0, 0.0),
+            SolverBody::new(1.0, 1.0),
+            SolverBody::new(0.0, 0.0),
+            SolverBody::new(1.0, 1.0),
+        ];
+        let y = along(Vec2::new(0.0, 1.0));
+        let rows = [
+            LcpRow::new(0, 1, y, 0.0, 0.0, f64::INFINITY),
+            LcpRow::new(0, 3, y, 0.0, 0.0, f64::INFINITY),
+            LcpRow::new(1, 3, y, 0.0, 0.0, f64::INFINITY),
+            LcpRow::new(0, 2, y, 0.0, 0.0, f64::INFINITY),
+        ];
+        assert_eq!(islands(&bodies, &rows[..2]), vec![vec![0], vec![1]]);
+        // Touching joins them, and rows between static bodies are dropped
+        assert_eq!(islands(&bodies, &rows), vec![vec![0, 1, 2]]);
+    }
+}
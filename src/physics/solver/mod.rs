@@ -0,0 +1,667 @@
+//! Sequential impulse constraint solver.
+//!
+//! Constraints are built from rows with a Jacobian `J = [linear_a, angular_a, linear_b, angular_b]`.
+//! Each row is solved for an impulse `lambda` such that `J·v + bias = 0`, with the accumulated
+//! impulse clamped to `[lower, upper]`. The functions here can solve a single constraint in
+//! isolation, which is useful to check the Jacobian of a custom constraint.
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{Mat2, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{SerializeError, Value};
+
+use super::body::RigidBody;
+use super::contact::{POSITION_CORRECTION, POSITION_ITERATIONS, SLOP, VELOCITY_ITERATIONS};
+
+pub mod contact;
+pub mod lcp;
+
+pub use contact::{ContactConstraint, ContactImpulse};
+pub use lcp::LcpRow;
+
+/// Accuracy of the World solver. More iterations make stacks and joint chains stiffer at the
+/// cost of speed.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverConfig {
+    /// Passes over all contacts and joints to solve velocities each step
+    pub velocity_iterations: usize,
+    /// Passes pushing overlapping bodies apart each step, see PositionCorrection
+    pub position_iterations: usize,
+    /// Fraction of the remaining overlap removed by each position pass, or each step for
+    /// corrections that don't use position passes
+    pub baumgarte: f64,
+    /// Overlap allowed without correction, which keeps resting contacts from jittering
+    pub slop: f64,
+    /// Start each contact from the impulse it ended the previous step with
+    pub warm_starting: bool,
+    /// Apply friction at contacts
+    pub friction: bool,
+    pub backend: SolverBackend,
+    pub position_correction: PositionCorrection,
+    /// Parts each step is split into, with contacts found and solved again for each. More
+    /// substeps keep tall stacks and fast spinning bodies stable. 0 counts as 1.
+    pub substeps: usize,
+    pub integrator: Integrator,
+    /// How far joints give per unit of force under the XPBD integrator, in m/N along anchors
+    /// and rad/(N m) for angles. 0 keeps them rigid.
+    pub compliance: f64,
+}
+
+/// How contacts and joints are solved each step
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolverBackend {
+    /// Sequential impulses, velocity_iterations passes over the constraints one at a time
+    SequentialImpulse,
+    /// All constraints of an island assembled into one system and solved until the impulses
+    /// settle, see solver::lcp. Slower, but more accurate for machinery. Ignores warm_starting
+    /// and velocity_iterations.
+    Lcp,
+}
+
+/// How overlapping contacts are pushed apart
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionCorrection {
+    /// Add a separating velocity to each overlapping contact. Cheap, but the push is kept as
+    /// velocity, so deep overlaps make bodies jump apart and resting stacks jitter.
+    Baumgarte,
+    /// After solving velocities, move and turn the bodies apart along the contact normals over
+    /// position_iterations passes, estimating the overlap left after each. Velocities are
+    /// untouched.
+    NonlinearGaussSeidel,
+    /// Solve the separating velocity on its own and only use it to move the bodies, so
+    /// overlaps are removed without adding energy. Takes velocity_iterations passes.
+    SplitImpulse,
+}
+
+/// How bodies are moved on by their velocities and forces each step
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Integrator {
+    /// Velocity first, then position from the new velocity. Cheap and stable for games.
+    SemiImplicitEuler,
+    /// Position from the velocity and acceleration, then velocity from the average of the
+    /// accelerations before and after, evaluating forces twice. Exact under constant forces and
+    /// keeps the energy of springs and orbits from drifting.
+    VelocityVerlet,
+    /// Extended position based dynamics: bodies move to predicted positions, then contacts and
+    /// joints are solved there as position constraints over position_iterations passes, each
+    /// accumulating a Lagrange multiplier. Joints give by SolverConfig::compliance and contacts
+    /// stay rigid. Velocities are taken from how far the bodies moved, and a velocity pass adds
+    /// friction, restitution, motors and mouse joint damping. Stays stiff without Baumgarte
+    /// bias, best with a few substeps. Ignores position_correction and warm_starting.
+    Xpbd,
+    /// Classic fourth order Runge-Kutta, sampling forces at three intermediate states as well
+    /// as the start, including World::set_force_callback. Far more accurate for orbits and
+    /// mechanisms in free flight, at four times the cost of forces. Contacts and joints are still
+    /// solved once per step after the move, so accuracy drops back to first order while bodies
+    /// touch or are jointed, and energy added by position correction isn't accounted for. Use
+    /// substeps rather than RK4 for stiff stacks and machinery.
+    Rk4,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig::new()
+    }
+}
+
+#[wasm_bindgen]
+impl SolverConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SolverConfig {
+        SolverConfig {
+            velocity_iterations: VELOCITY_ITERATIONS,
+            position_iterations: POSITION_ITERATIONS,
+            baumgarte: POSITION_CORRECTION,
+            slop: SLOP,
+            warm_starting: true,
+            friction: true,
+            backend: SolverBackend::SequentialImpulse,
+            position_correction: PositionCorrection::NonlinearGaussSeidel,
+            substeps: 1,
+            integrator: Integrator::SemiImplicitEuler,
+            compliance: 0.0,
+        }
+    }
+}
+
+impl SolverConfig {
+    pub(crate) fn to_value(self) -> Value {
+        let mut v = Value::object();
+        v.insert("velocity_iterations", (self.velocity_iterations as f64).into());
+        v.insert("position_iterations", (self.position_iterations as f64).into());
+        v.insert("baumgarte", self.baumgarte.into());
+        v.insert("slop", self.slop.into());
+        v.insert("warm_starting", self.warm_starting.into());
+        v.insert("friction", self.friction.into());
+        v.insert("backend", self.backend.name().into());
+        v.insert("position_correction", self.position_correction.name().into());
+        v.insert("substeps", (self.substeps as f64).into());
+        v.insert("integrator", self.integrator.name().into());
+        v.insert("compliance", self.compliance.into());
+        v
+    }
+
+    pub(crate) fn from_value(v: &Value) -> Result<SolverConfig, SerializeError> {
+        Ok(SolverConfig {
+            velocity_iterations: v.field_f64("velocity_iterations")? as usize,
+            position_iterations: v.field_f64("position_iterations")? as usize,
+            baumgarte: v.field_f64("baumgarte")?,
+            slop: v.field_f64("slop")?,
+            warm_starting: v.field_bool("warm_starting")?,
+            friction: v.field_bool("friction")?,
+            backend: SolverBackend::from_name(v.field_str("backend")?)?,
+            position_correction: PositionCorrection::from_name(v.field_str("position_correction")?)?,
+            substeps: v.field_f64("substeps")? as usize,
+            integrator: Integrator::from_name(v.field_str("integrator")?)?,
+            compliance: v.field_f64("compliance")?,
+        })
+    }
+}
+
+impl SolverBackend {
+    fn name(self) -> &'static str {
+        match self {
+            SolverBackend::SequentialImpulse => "sequential_impulse",
+            SolverBackend::Lcp => "lcp",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<SolverBackend, SerializeError> {
+        match name {
+            "sequential_impulse" => Ok(SolverBackend::SequentialImpulse),
+            "lcp" => Ok(SolverBackend::Lcp),
+            other => Err(SerializeError::InvalidValue(format!("unknown solver backend '{}'", other))),
+        }
+    }
+}
+
+impl PositionCorrection {
+    fn name(self) -> &'static str {
+        match self {
+            PositionCorrection::Baumgarte => "baumgarte",
+            PositionCorrection::NonlinearGaussSeidel => "nonlinear_gauss_seidel",
+            PositionCorrection::SplitImpulse => "split_impulse",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<PositionCorrection, SerializeError> {
+        match name {
+            "baumgarte" => Ok(PositionCorrection::Baumgarte),
+            "nonlinear_gauss_seidel" => Ok(PositionCorrection::NonlinearGaussSeidel),
+            "split_impulse" => Ok(PositionCorrection::SplitImpulse),
+            other => Err(SerializeError::InvalidValue(format!("unknown position correction '{}'", other))),
+        }
+    }
+}
+
+impl Integrator {
+    fn name(self) -> &'static str {
+        match self {
+            Integrator::SemiImplicitEuler => "semi_implicit_euler",
+            Integrator::VelocityVerlet => "velocity_verlet",
+            Integrator::Xpbd => "xpbd",
+            Integrator::Rk4 => "rk4",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Integrator, SerializeError> {
+        match name {
+            "semi_implicit_euler" => Ok(Integrator::SemiImplicitEuler),
+            "velocity_verlet" => Ok(Integrator::VelocityVerlet),
+            "xpbd" => Ok(Integrator::Xpbd),
+            "rk4" => Ok(Integrator::Rk4),
+            other => Err(SerializeError::InvalidValue(format!("unknown integrator '{}'", other))),
+        }
+    }
+}
+
+impl Binary for SolverBackend {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<SolverBackend, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(SolverBackend::SequentialImpulse),
+            1 => Ok(SolverBackend::Lcp),
+            tag => Err(r.invalid_tag("solver backend", tag)),
+        }
+    }
+}
+
+impl Binary for PositionCorrection {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<PositionCorrection, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(PositionCorrection::Baumgarte),
+            1 => Ok(PositionCorrection::NonlinearGaussSeidel),
+            2 => Ok(PositionCorrection::SplitImpulse),
+            tag => Err(r.invalid_tag("position correction", tag)),
+        }
+    }
+}
+
+impl Binary for Integrator {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&(*self as u8));
+    }
+
+    fn decode(r: &mut Reader) -> Result<Integrator, SerializeError> {
+        match r.get::<u8>()? {
+            0 => Ok(Integrator::SemiImplicitEuler),
+            1 => Ok(Integrator::VelocityVerlet),
+            2 => Ok(Integrator::Xpbd),
+            3 => Ok(Integrator::Rk4),
+            tag => Err(r.invalid_tag("integrator", tag)),
+        }
+    }
+}
+
+impl Binary for SolverConfig {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.velocity_iterations);
+        w.put(&self.position_iterations);
+        w.put(&self.baumgarte);
+        w.put(&self.slop);
+        w.put(&self.warm_starting);
+        w.put(&self.friction);
+        w.put(&self.backend);
+        w.put(&self.position_correction);
+        w.put(&self.substeps);
+        w.put(&self.integrator);
+        w.put(&self.compliance);
+    }
+
+    fn decode(r: &mut Reader) -> Result<SolverConfig, SerializeError> {
+        Ok(SolverConfig {
+            velocity_iterations: r.get()?,
+            position_iterations: r.get()?,
+            baumgarte: r.get()?,
+            slop: r.get()?,
+            warm_starting: r.get()?,
+            friction: r.get()?,
+            backend: r.get()?,
+            position_correction: r.get()?,
+            substeps: r.get()?,
+            integrator: r.get()?,
+            compliance: r.get()?,
+        })
+    }
+}
+
+/// Velocity and inverse mass properties of a body as seen by the solver
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct SolverBody {
+    pub inv_mass: f64,
+    pub inv_inertia: f64,
+    pub velocity: Vec2,
+    pub angular_velocity: f64,
+}
+
+impl SolverBody {
+    /// A body at rest. Zero mass or inertia makes the body immovable along those axes.
+    pub fn new(mass: f64, inertia: f64) -> SolverBody {
+        SolverBody {
+            inv_mass: if mass > 0.0 { 1.0 / mass } else { 0.0 },
+            inv_inertia: if inertia > 0.0 { 1.0 / inertia } else { 0.0 },
+            velocity: Vec2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+        }
+    }
+
+    pub fn from_body(body: &RigidBody) -> SolverBody {
+        SolverBody {
+            inv_mass: body.inv_mass(),
+            inv_inertia: body.inv_inertia(),
+            velocity: body.velocity(),
+            angular_velocity: body.angular_velocity(),
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec2, angular_velocity: f64) -> SolverBody {
+        self.velocity = velocity;
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    /// Velocity of the point at offset r from the center of mass
+    pub fn velocity_at(&self, r: &Vec2) -> Vec2 {
+        self.velocity + Vec2::cross_scalar(self.angular_velocity, r)
+    }
+
+    /// Apply an impulse at offset r from the center of mass
+    pub fn apply_impulse(&mut self, impulse: &Vec2, r: &Vec2) {
+        self.velocity = self.velocity + impulse * self.inv_mass;
+        self.angular_velocity += Vec2::cross(r, impulse) * self.inv_inertia;
+    }
+}
+
+/// One row of a constraint Jacobian
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Jacobian {
+    pub linear_a: Vec2,
+    pub angular_a: f64,
+    pub linear_b: Vec2,
+    pub angular_b: f64,
+}
+
+impl Jacobian {
+    /// Relative velocity of two points along direction n, with lever arms ra and rb
+    pub fn along(n: &Vec2, ra: &Vec2, rb: &Vec2) -> Jacobian {
+        Jacobian {
+            linear_a: -n,
+            angular_a: -Vec2::cross(ra, n),
+            linear_b: *n,
+            angular_b: Vec2::cross(rb, n),
+        }
+    }
+
+    /// Relative angular velocity of b with respect to a
+    pub fn angular() -> Jacobian {
+        Jacobian {
+            linear_a: Vec2::new(0.0, 0.0),
+            angular_a: -1.0,
+            linear_b: Vec2::new(0.0, 0.0),
+            angular_b: 1.0,
+        }
+    }
+
+    /// J·v
+    pub fn velocity(&self, a: &SolverBody, b: &SolverBody) -> f64 {
+        Vec2::dot(&self.linear_a, &a.velocity)
+            + self.angular_a * a.angular_velocity
+            + Vec2::dot(&self.linear_b, &b.velocity)
+            + self.angular_b * b.angular_velocity
+    }
+
+    /// J M^-1 J^T
+    pub fn inverse_effective_mass(&self, a: &SolverBody, b: &SolverBody) -> f64 {
+        a.inv_mass * self.linear_a.len_sq()
+            + a.inv_inertia * self.angular_a * self.angular_a
+            + b.inv_mass * self.linear_b.len_sq()
+            + b.inv_inertia * self.angular_b * self.angular_b
+    }
+
+    /// J M^-1 other^T, how much an impulse along other changes the velocity along this row
+    pub fn coupling(&self, other: &Jacobian, a: &SolverBody, b: &SolverBody) -> f64 {
+        a.inv_mass * Vec2::dot(&self.linear_a, &other.linear_a)
+            + a.inv_inertia * self.angular_a * other.angular_a
+            + b.inv_mass * Vec2::dot(&self.linear_b, &other.linear_b)
+            + b.inv_inertia * self.angular_b * other.angular_b
+    }
+
+    /// v += M^-1 J^T lambda
+    pub fn apply(&self, a: &mut SolverBody, b: &mut SolverBody, lambda: f64) {
+        a.velocity = a.velocity + self.linear_a * (a.inv_mass * lambda);
+        a.angular_velocity += a.inv_inertia * self.angular_a * lambda;
+        b.velocity = b.velocity + self.linear_b * (b.inv_mass * lambda);
+        b.angular_velocity += b.inv_inertia * self.angular_b * lambda;
+    }
+}
+
+/// A constraint row with its bias and impulse bounds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstraintRow {
+    pub jacobian: Jacobian,
+    /// Velocity the row drives J·v towards the negative of
+    pub bias: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// Total impulse applied so far
+    pub impulse: f64,
+    /// Extra relative velocity allowed per unit of impulse, 0 for a rigid row
+    pub softness: f64,
+    effective_mass: f64,
+}
+
+impl ConstraintRow {
+    pub fn new(jacobian: Jacobian, a: &SolverBody, b: &SolverBody, bias: f64, lower: f64, upper: f64) -> ConstraintRow {
+        let k = jacobian.inverse_effective_mass(a, b);
+        ConstraintRow {
+            jacobian,
+            bias,
+            lower,
+            upper,
+            impulse: 0.0,
+            softness: 0.0,
+            effective_mass: if k > 0.0 { 1.0 / k } else { 0.0 },
+        }
+    }
+
+    /// An equality row that acts like a damped spring with frequency in Hz and damping ratio, where mass
+    /// is the mass being pulled. error is the current position error along the row.
+    #[allow(clippy::too_many_arguments)]
+    pub fn spring(
+        jacobian: Jacobian,
+        a: &SolverBody,
+        b: &SolverBody,
+        error: f64,
+        mass: f64,
+        frequency: f64,
+        damping_ratio: f64,
+        dt: f64,
+    ) -> ConstraintRow {
+        let omega = 2.0 * std::f64::consts::PI * frequency;
+        let damping = 2.0 * mass * damping_ratio * omega;
+        let stiffness = mass * omega * omega;
+        let scale = dt * (damping + dt * stiffness);
+        let mut row = ConstraintRow::equality(jacobian, a, b, 0.0);
+        if scale > 0.0 {
+            row.softness = 1.0 / scale;
+            row.bias = error * stiffness / (damping + dt * stiffness);
+            let k = jacobian.inverse_effective_mass(a, b);
+            row.effective_mass = if k > 0.0 { 1.0 / (k + row.softness) } else { 0.0 };
+        }
+        row
+    }
+
+    /// Make a rigid row give by softness extra relative velocity per unit of impulse
+    pub fn with_softness(mut self, softness: f64) -> ConstraintRow {
+        if self.effective_mass > 0.0 {
+            self.effective_mass = 1.0 / (1.0 / self.effective_mass + softness);
+        }
+        self.softness = softness;
+        self
+    }
+
+    /// An equality row with unbounded impulse
+    pub fn equality(jacobian: Jacobian, a: &SolverBody, b: &SolverBody, bias: f64) -> ConstraintRow {
+        ConstraintRow::new(jacobian, a, b, bias, f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    pub fn effective_mass(&self) -> f64 {
+        self.effective_mass
+    }
+
+    /// Remaining J·v + bias
+    pub fn velocity_error(&self, a: &SolverBody, b: &SolverBody) -> f64 {
+        self.jacobian.velocity(a, b) + self.bias
+    }
+
+    /// Apply the impulse that best satisfies the row, returning the change in accumulated impulse
+    pub fn solve(&mut self, a: &mut SolverBody, b: &mut SolverBody) -> f64 {
+        let lambda = -self.effective_mass * (self.velocity_error(a, b) + self.softness * self.impulse);
+        let old = self.impulse;
+        self.impulse = (old + lambda).max(self.lower).min(self.upper);
+        let delta = self.impulse - old;
+        self.jacobian.apply(a, b, delta);
+        delta
+    }
+
+    /// Re-apply the accumulated impulse, e.g. from the previous step
+    pub fn warm_start(&self, a: &mut SolverBody, b: &mut SolverBody) {
+        self.jacobian.apply(a, b, self.impulse);
+    }
+}
+
+/// Keeps an anchor on each body together, solving both axes at once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointConstraint {
+    /// Anchor relative to the center of body a
+    pub ra: Vec2,
+    /// Anchor relative to the center of body b
+    pub rb: Vec2,
+    /// Velocity the relative anchor velocity is driven towards the negative of
+    pub bias: Vec2,
+    /// Total impulse applied to b so far, a receives the negative
+    pub impulse: Vec2,
+    /// Extra relative anchor velocity allowed per unit of impulse, 0 for a rigid point
+    pub softness: f64,
+    effective_mass: Mat2,
+}
+
+impl PointConstraint {
+    pub fn new(a: &SolverBody, b: &SolverBody, ra: Vec2, rb: Vec2, bias: Vec2) -> PointConstraint {
+        let m = a.inv_mass + b.inv_mass;
+        let (ia, ib) = (a.inv_inertia, b.inv_inertia);
+        let k = Mat2::new(
+            m + ia * ra.y * ra.y + ib * rb.y * rb.y,
+            -ia * ra.x * ra.y - ib * rb.x * rb.y,
+            -ia * ra.x * ra.y - ib * rb.x * rb.y,
+            m + ia * ra.x * ra.x + ib * rb.x * rb.x,
+        );
+        PointConstraint {
+            ra,
+            rb,
+            bias,
+            impulse: Vec2::new(0.0, 0.0),
+            softness: 0.0,
+            effective_mass: k.inverse(),
+        }
+    }
+
+    /// Make the point give by softness extra relative anchor velocity per unit of impulse
+    pub fn with_softness(mut self, softness: f64) -> PointConstraint {
+        let mut k = self.effective_mass.inverse();
+        k.m00 += softness - self.softness;
+        k.m11 += softness - self.softness;
+        self.effective_mass = k.inverse();
+        self.softness = softness;
+        self
+    }
+
+    /// Remaining relative anchor velocity plus bias
+    pub fn velocity_error(&self, a: &SolverBody, b: &SolverBody) -> Vec2 {
+        b.velocity_at(&self.rb) - a.velocity_at(&self.ra) + self.bias
+    }
+
+    /// Apply the impulse that removes the relative anchor velocity
+    pub fn solve(&mut self, a: &mut SolverBody, b: &mut SolverBody) {
+        let lambda = -(self.effective_mass * (self.velocity_error(a, b) + self.impulse * self.softness));
+        self.impulse = self.impulse + lambda;
+        a.apply_impulse(&-lambda, &self.ra);
+        b.apply_impulse(&lambda, &self.rb);
+    }
+}
+
+/// Solve a single constraint row in isolation, returning the accumulated impulse
+pub fn solve_constraint(a: &mut SolverBody, b: &mut SolverBody, row: &mut ConstraintRow, iterations: usize) -> f64 {
+    for _ in 0..iterations {
+        row.solve(a, b);
+    }
+    row.impulse
+}
+
+/// Solve a single contact in isolation, returning the accumulated impulses
+pub fn solve_contact(
+    a: &mut SolverBody,
+    b: &mut SolverBody,
+    contact: &mut ContactConstraint,
+    iterations: usize,
+) -> ContactImpulse {
+    for _ in 0..iterations {
+        contact.solve(a, b);
+    }
+    contact.impulse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equality_row_removes_relative_velocity() {
+        let mut a = SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(1.0, 0.0), 0.0);
+        let mut b = SolverBody::new(3.0, 1.0);
+        let j = Jacobian::along(&Vec2::new(1.0, 0.0), &Vec2::new(0.0, 0.0), &Vec2::new(0.0, 0.0));
+        let mut row = ConstraintRow::equality(j, &a, &b, 0.0);
+
+        let impulse = solve_constraint(&mut a, &mut b, &mut row, 1);
+        assert_eq!(impulse, 0.75);
+        assert_eq!(a.velocity, Vec2::new(0.25, 0.0));
+        assert_eq!(b.velocity, Vec2::new(0.25, 0.0));
+        assert_eq!(row.velocity_error(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn test_spring_row_is_soft() {
+        let mut a = SolverBody::new(0.0, 0.0);
+        let mut b = SolverBody::new(1.0, 1.0);
+        let j = Jacobian::along(&Vec2::new(1.0, 0.0), &Vec2::new(0.0, 0.0), &Vec2::new(0.0, 0.0));
+        // b is 1 past where the spring pulls it
+        let mut row = ConstraintRow::spring(j, &a, &b, 1.0, 1.0, 2.0, 1.0, 1.0 / 60.0);
+        assert!(row.softness > 0.0);
+
+        let impulse = solve_constraint(&mut a, &mut b, &mut row, 20);
+        assert!(impulse < 0.0);
+        // Pulled back, but slower than a rigid row would
+        assert!(b.velocity.x < 0.0 && -b.velocity.x < row.bias);
+        assert!((row.velocity_error(&a, &b) + row.softness * impulse).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_row_clamps_impulse() {
+        let mut a = SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(-1.0, 0.0), 0.0);
+        let mut b = SolverBody::new(1.0, 1.0);
+        let j = Jacobian::along(&Vec2::new(1.0, 0.0), &Vec2::new(0.0, 0.0), &Vec2::new(0.0, 0.0));
+        // Separating bodies: a non-negative row must not pull them together
+        let mut row = ConstraintRow::new(j, &a, &b, 0.0, 0.0, f64::INFINITY);
+
+        assert_eq!(solve_constraint(&mut a, &mut b, &mut row, 4), 0.0);
+        assert_eq!(a.velocity, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_angular_row() {
+        let mut a = SolverBody::new(1.0, 2.0).with_velocity(Vec2::new(0.0, 0.0), 1.0);
+        let mut b = SolverBody::new(1.0, 2.0);
+        let mut row = ConstraintRow::equality(Jacobian::angular(), &a, &b, 0.0);
+
+        solve_constraint(&mut a, &mut b, &mut row, 1);
+        assert_eq!(a.angular_velocity, 0.5);
+        assert_eq!(b.angular_velocity, 0.5);
+    }
+
+    #[test]
+    fn test_lever_arm_spins_body() {
+        let mut a = SolverBody::new(0.0, 0.0);
+        let mut b = SolverBody::new(1.0, 1.0).with_velocity(Vec2::new(0.0, -1.0), 0.0);
+        let j = Jacobian::along(&Vec2::new(0.0, 1.0), &Vec2::new(0.0, 0.0), &Vec2::new(1.0, 0.0));
+        let mut row = ConstraintRow::equality(j, &a, &b, 0.0);
+
+        solve_constraint(&mut a, &mut b, &mut row, 1);
+        assert!(b.angular_velocity > 0.0);
+        assert!(row.velocity_error(&a, &b).abs() < 1e-12);
+        assert_eq!(a, SolverBody::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_point_constraint() {
+        let mut a = SolverBody::new(0.0, 0.0);
+        let mut b = SolverBody::new(1.0, 0.5).with_velocity(Vec2::new(1.0, -2.0), 3.0);
+        let mut point = PointConstraint::new(&a, &b, Vec2::new(0.0, 0.0), Vec2::new(-1.0, 0.5), Vec2::new(0.0, 0.0));
+
+        // Both axes are coupled through the lever arm, so one solve removes the whole error
+        point.solve(&mut a, &mut b);
+        assert!(point.velocity_error(&a, &b).len() < 1e-12);
+        assert_eq!(a, SolverBody::new(0.0, 0.0));
+    }
+}
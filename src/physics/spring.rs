@@ -0,0 +1,185 @@
+use wasm_bindgen::prelude::*;
+
+use super::body::RigidBody;
+use super::world::BodyHandle;
+use crate::math::Vec2;
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, SerializeError, Value};
+
+/// A damped spring connecting an anchor on each of two bodies.
+/// Springs act through forces each step rather than as a hard constraint.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2,
+    local_anchor_b: Vec2,
+    pub stiffness: f64,
+    pub damping: f64,
+    pub rest_length: f64,
+}
+
+#[wasm_bindgen]
+impl Spring {
+    /// Saved spring with its bodies given as positions in the saved body list
+    pub(crate) fn to_value(self, body_a: usize, body_b: usize) -> Value {
+        let mut v = Value::object();
+        v.insert("body_a", (body_a as f64).into());
+        v.insert("body_b", (body_b as f64).into());
+        v.insert("local_anchor_a", serialize::vec2_to_value(&self.local_anchor_a));
+        v.insert("local_anchor_b", serialize::vec2_to_value(&self.local_anchor_b));
+        v.insert("stiffness", self.stiffness.into());
+        v.insert("damping", self.damping.into());
+        v.insert("rest_length", self.rest_length.into());
+        v
+    }
+
+    /// Read a spring, looking up its bodies in the handles of the loaded bodies
+    pub(crate) fn from_value(v: &Value, bodies: &[BodyHandle]) -> Result<Spring, SerializeError> {
+        let body = |key| {
+            let index = v.field_f64(key)? as usize;
+            bodies
+                .get(index)
+                .copied()
+                .ok_or_else(|| SerializeError::InvalidValue(format!("no body {} for spring", index)))
+        };
+        Ok(Spring::new(
+            body("body_a")?,
+            body("body_b")?,
+            serialize::vec2_from_value(v.field("local_anchor_a")?)?,
+            serialize::vec2_from_value(v.field("local_anchor_b")?)?,
+            v.field_f64("stiffness")?,
+            v.field_f64("damping")?,
+            v.field_f64("rest_length")?,
+        ))
+    }
+
+    #[wasm_bindgen(constructor)]
+    /// Anchors are given in each body's local coordinates
+    pub fn new(
+        body_a: BodyHandle,
+        body_b: BodyHandle,
+        local_anchor_a: Vec2,
+        local_anchor_b: Vec2,
+        stiffness: f64,
+        damping: f64,
+        rest_length: f64,
+    ) -> Spring {
+        Spring {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            stiffness,
+            damping,
+            rest_length,
+        }
+    }
+
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    pub fn local_anchor_a(&self) -> Vec2 {
+        self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> Vec2 {
+        self.local_anchor_b
+    }
+}
+
+impl Spring {
+    /// Force acting on body a and the lever arms from each body's center to its anchor.
+    /// Body b receives the opposite force.
+    pub fn force(&self, a: &RigidBody, b: &RigidBody) -> (Vec2, Vec2, Vec2) {
+        let ra = self.local_anchor_a.rotate(a.rotation());
+        let rb = self.local_anchor_b.rotate(b.rotation());
+        let d = (b.position() + rb) - (a.position() + ra);
+        let len = d.len();
+        if len <= f64::EPSILON {
+            return (Vec2::new(0.0, 0.0), ra, rb);
+        }
+        let n = d / len;
+
+        let va = a.velocity() + Vec2::cross_scalar(a.angular_velocity(), &ra);
+        let vb = b.velocity() + Vec2::cross_scalar(b.angular_velocity(), &rb);
+        let speed = Vec2::dot(&(vb - va), &n);
+
+        let magnitude = self.stiffness * (len - self.rest_length) + self.damping * speed;
+        (n * magnitude, ra, rb)
+    }
+}
+
+impl Binary for Spring {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.body_a);
+        w.put(&self.body_b);
+        w.put(&self.local_anchor_a);
+        w.put(&self.local_anchor_b);
+        w.put(&self.stiffness);
+        w.put(&self.damping);
+        w.put(&self.rest_length);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Spring, SerializeError> {
+        Ok(Spring {
+            body_a: r.get()?,
+            body_b: r.get()?,
+            local_anchor_a: r.get()?,
+            local_anchor_b: r.get()?,
+            stiffness: r.get()?,
+            damping: r.get()?,
+            rest_length: r.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spring_force_stretched() {
+        let a = RigidBody::new(1.0);
+        let mut b = RigidBody::new(1.0);
+        b.set_position(Vec2::new(3.0, 0.0));
+        let spring = Spring::new(
+            BodyHandle::new(0, 0),
+            BodyHandle::new(1, 0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            2.0,
+            0.0,
+            1.0,
+        );
+
+        let (f, _, _) = spring.force(&a, &b);
+        assert_eq!(f, Vec2::new(4.0, 0.0));
+    }
+
+    #[test]
+    fn test_spring_damping() {
+        let a = RigidBody::new(1.0);
+        let mut b = RigidBody::new(1.0);
+        b.set_position(Vec2::new(1.0, 0.0));
+        b.set_velocity(Vec2::new(-2.0, 0.0));
+        let spring = Spring::new(
+            BodyHandle::new(0, 0),
+            BodyHandle::new(1, 0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            1.0,
+            0.5,
+            1.0,
+        );
+
+        let (f, _, _) = spring.force(&a, &b);
+        assert_eq!(f, Vec2::new(-1.0, 0.0));
+    }
+}
@@ -0,0 +1,261 @@
+//! Counters and timings of the last step, for finding out where a frame's time goes.
+//!
+//! Timings come from performance.now() in the browser and from std::time::Instant elsewhere.
+//! A single step is noisy, so a World can also keep the stats of its last steps in a
+//! StatsHistory, for graphing and for catching the odd slow step.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+/// What the last World::step did and how long each phase took, in milliseconds.
+/// Counts and timings add up over the substeps a step is split into by scheduled events.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct WorldStats {
+    /// Integration passes, one more for each scheduled event falling inside the step
+    pub substeps: usize,
+    /// Pairs of bodies whose broadphase boxes overlap
+    pub broadphase_pairs: usize,
+    /// Pairs run through the narrowphase, the broadphase pairs minus those of two static bodies
+    pub narrowphase_tests: usize,
+    /// Contacts found in the last substep
+    pub contacts: usize,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    /// Applying forces and moving bodies
+    pub integrate_ms: f64,
+    /// Updating body boxes and finding overlapping pairs
+    pub broadphase_ms: f64,
+    /// Colliding the broadphase pairs
+    pub narrowphase_ms: f64,
+    /// Solving contacts and joints
+    pub solver_ms: f64,
+    pub particles_ms: f64,
+    /// The whole step, including bookkeeping outside the phases above
+    pub step_ms: f64,
+}
+
+/// One number of WorldStats, picked out for StatsHistory
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsMetric {
+    Substeps,
+    BroadphasePairs,
+    NarrowphaseTests,
+    Contacts,
+    VelocityIterations,
+    PositionIterations,
+    IntegrateMs,
+    BroadphaseMs,
+    NarrowphaseMs,
+    SolverMs,
+    ParticlesMs,
+    StepMs,
+}
+
+#[wasm_bindgen]
+impl WorldStats {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WorldStats {
+        WorldStats::default()
+    }
+
+    pub fn metric(&self, metric: StatsMetric) -> f64 {
+        match metric {
+            StatsMetric::Substeps => self.substeps as f64,
+            StatsMetric::BroadphasePairs => self.broadphase_pairs as f64,
+            StatsMetric::NarrowphaseTests => self.narrowphase_tests as f64,
+            StatsMetric::Contacts => self.contacts as f64,
+            StatsMetric::VelocityIterations => self.velocity_iterations as f64,
+            StatsMetric::PositionIterations => self.position_iterations as f64,
+            StatsMetric::IntegrateMs => self.integrate_ms,
+            StatsMetric::BroadphaseMs => self.broadphase_ms,
+            StatsMetric::NarrowphaseMs => self.narrowphase_ms,
+            StatsMetric::SolverMs => self.solver_ms,
+            StatsMetric::ParticlesMs => self.particles_ms,
+            StatsMetric::StepMs => self.step_ms,
+        }
+    }
+}
+
+/// Stats of the last capacity steps, oldest first, see World::set_stats_history_capacity
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct StatsHistory {
+    capacity: usize,
+    steps: VecDeque<WorldStats>,
+}
+
+#[wasm_bindgen]
+impl StatsHistory {
+    /// A history of the last capacity steps, 0 to keep none
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> StatsHistory {
+        StatsHistory {
+            capacity,
+            steps: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change how many steps are kept, dropping the oldest ones that no longer fit
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.steps.len() > capacity {
+            self.steps.pop_front();
+        }
+    }
+
+    /// Steps kept so far
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    /// Add the stats of a step, dropping the oldest once full
+    pub fn push(&mut self, stats: WorldStats) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.steps.len() == self.capacity {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(stats);
+    }
+
+    /// Stats of a kept step, 0 being the oldest
+    pub fn get(&self, index: usize) -> Option<WorldStats> {
+        self.steps.get(index).copied()
+    }
+
+    /// A metric of every kept step, oldest first
+    pub fn values(&self, metric: StatsMetric) -> Vec<f64> {
+        self.steps.iter().map(|s| s.metric(metric)).collect()
+    }
+
+    /// Average of a metric over each step and the window - 1 steps before it, oldest first.
+    /// The first steps average over as many steps as there are.
+    pub fn moving_average(&self, metric: StatsMetric, window: usize) -> Vec<f64> {
+        let values = self.values(metric);
+        let window = window.max(1);
+        let mut sum = 0.0;
+        (0..values.len())
+            .map(|i| {
+                sum += values[i];
+                if i >= window {
+                    sum -= values[i - window];
+                }
+                sum / (i + 1).min(window) as f64
+            })
+            .collect()
+    }
+
+    /// Average of a metric over the kept steps, 0 when there are none
+    pub fn average(&self, metric: StatsMetric) -> f64 {
+        if self.steps.is_empty() {
+            return 0.0;
+        }
+        self.steps.iter().map(|s| s.metric(metric)).sum::<f64>() / self.steps.len() as f64
+    }
+
+    /// Largest value of a metric over the kept steps, 0 when there are none
+    pub fn max(&self, metric: StatsMetric) -> f64 {
+        self.steps.iter().map(|s| s.metric(metric)).fold(0.0, f64::max)
+    }
+}
+
+impl StatsHistory {
+    /// Kept steps, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &WorldStats> {
+        self.steps.iter()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// Milliseconds since an arbitrary fixed point
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_ms() -> f64 {
+    performance_now()
+}
+
+/// Milliseconds since an arbitrary fixed point
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Adds the milliseconds between being made and stopped to a timing
+pub(crate) struct Timer(f64);
+
+impl Timer {
+    pub fn start() -> Timer {
+        Timer(now_ms())
+    }
+
+    pub fn stop(self, total: &mut f64) {
+        *total += now_ms() - self.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer() {
+        let mut total = 1.0;
+        let timer = Timer::start();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        timer.stop(&mut total);
+        assert!(total >= 3.0);
+    }
+
+    #[test]
+    fn test_history() {
+        let step = |contacts: usize, step_ms: f64| WorldStats {
+            contacts,
+            step_ms,
+            ..WorldStats::new()
+        };
+        let mut history = StatsHistory::new(3);
+        for (i, ms) in [1.0, 2.0, 6.0, 4.0].iter().enumerate() {
+            history.push(step(i, *ms));
+        }
+
+        // The oldest step was dropped to make room
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.values(StatsMetric::Contacts), vec![1.0, 2.0, 3.0]);
+        assert_eq!(history.values(StatsMetric::StepMs), vec![2.0, 6.0, 4.0]);
+        assert_eq!(history.moving_average(StatsMetric::StepMs, 2), vec![2.0, 4.0, 5.0]);
+        assert_eq!(history.average(StatsMetric::StepMs), 4.0);
+        assert_eq!(history.max(StatsMetric::StepMs), 6.0);
+
+        history.set_capacity(1);
+        assert_eq!(history.get(0), Some(step(3, 4.0)));
+        history.set_capacity(0);
+        history.push(step(5, 1.0));
+        assert!(history.is_empty());
+        assert_eq!(history.average(StatsMetric::StepMs), 0.0);
+    }
+}
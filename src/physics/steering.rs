@@ -0,0 +1,217 @@
+//! Flow fields for steering many agents toward a shared goal.
+//!
+//! A flow field stores the shortest walking distance to the goal for each cell of a grid.
+//! Cells closer to static geometry than the agent clearance are blocked, so agents following
+//! the field keep away from walls and the contact solver only has to handle agents bumping
+//! into each other. Building the field is a single Dijkstra search from the goal, after which
+//! any number of agents can sample it cheaply.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{Aabb, DistanceField};
+use crate::math::Vec2;
+
+/// Walking distance to a goal over a grid, with steering directions toward it
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowField {
+    region: Aabb,
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    goal: Vec2,
+    /// Distance to the goal for each cell, row major, infinite where unreachable
+    costs: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl FlowField {
+    pub fn goal(&self) -> Vec2 {
+        self.goal
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Walking distances as a flat row major array
+    pub fn costs(&self) -> Vec<f64> {
+        self.costs.clone()
+    }
+
+    /// Walking distance to the goal from the cell containing point
+    pub fn cost(&self, point: &Vec2) -> f64 {
+        self.costs[self.cell(point)]
+    }
+
+    /// Unit direction to walk from point toward the neighbouring cell closest to the goal.
+    /// Agents pushed into blocked cells are led back out. Zero if the goal can't be reached.
+    pub fn direction(&self, point: &Vec2) -> Vec2 {
+        let cell = self.cell(point);
+        if self.costs[cell] == 0.0 {
+            return toward(point, &self.goal);
+        }
+        let next = self
+            .neighbours(cell)
+            .into_iter()
+            .min_by(|a, b| self.costs[a.0].total_cmp(&self.costs[b.0]));
+        match next {
+            Some((n, _)) if self.costs[n] < self.costs[cell] => toward(point, &self.center(n)),
+            _ => Vec2::new(0.0, 0.0),
+        }
+    }
+
+    /// Steering velocity at point, slowing down over the last cell before the goal
+    pub fn velocity(&self, point: &Vec2, speed: f64) -> Vec2 {
+        let remaining = (self.goal - point).len() / self.cell_size;
+        self.direction(point) * (speed * remaining.min(1.0))
+    }
+}
+
+impl FlowField {
+    /// Flow toward goal over the grid of sdf, avoiding cells within clearance of geometry
+    pub fn new(sdf: &DistanceField, goal: Vec2, clearance: f64) -> FlowField {
+        let mut field = FlowField {
+            region: sdf.region(),
+            cell_size: sdf.cell_size(),
+            columns: sdf.columns(),
+            rows: sdf.rows(),
+            goal,
+            costs: vec![f64::INFINITY; sdf.as_slice().len()],
+        };
+        let walkable: Vec<bool> = sdf.as_slice().iter().map(|d| *d >= clearance).collect();
+
+        let start = field.cell(&goal);
+        field.costs[start] = 0.0;
+        let mut open = BinaryHeap::new();
+        open.push(Open { cost: 0.0, cell: start });
+        while let Some(Open { cost, cell }) = open.pop() {
+            if cost > field.costs[cell] {
+                continue;
+            }
+            for (n, step) in field.neighbours(cell) {
+                if !walkable[n] || !field.can_step(cell, n, &walkable) {
+                    continue;
+                }
+                let next = cost + step;
+                if next < field.costs[n] {
+                    field.costs[n] = next;
+                    open.push(Open { cost: next, cell: n });
+                }
+            }
+        }
+        field
+    }
+
+    fn cell(&self, point: &Vec2) -> usize {
+        let g = (point - self.region.min) / self.cell_size;
+        let column = (g.x.max(0.0) as usize).min(self.columns - 1);
+        let row = (g.y.max(0.0) as usize).min(self.rows - 1);
+        row * self.columns + column
+    }
+
+    fn center(&self, cell: usize) -> Vec2 {
+        let (column, row) = (cell % self.columns, cell / self.columns);
+        self.region.min + Vec2::new(column as f64 + 0.5, row as f64 + 0.5) * self.cell_size
+    }
+
+    /// Cells around cell with the distance to each
+    fn neighbours(&self, cell: usize) -> Vec<(usize, f64)> {
+        let (column, row) = ((cell % self.columns) as isize, (cell / self.columns) as isize);
+        let mut out = Vec::with_capacity(8);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (c, r) = (column + dx, row + dy);
+                if (dx, dy) == (0, 0) || c < 0 || r < 0 || c >= self.columns as isize || r >= self.rows as isize {
+                    continue;
+                }
+                let step = if dx != 0 && dy != 0 { std::f64::consts::SQRT_2 } else { 1.0 };
+                out.push((r as usize * self.columns + c as usize, step * self.cell_size));
+            }
+        }
+        out
+    }
+
+    /// Diagonal steps may not cut past a blocked corner
+    fn can_step(&self, from: usize, to: usize, walkable: &[bool]) -> bool {
+        let (fc, fr) = (from % self.columns, from / self.columns);
+        let (tc, tr) = (to % self.columns, to / self.columns);
+        fc == tc || fr == tr || (walkable[fr * self.columns + tc] && walkable[tr * self.columns + fc])
+    }
+}
+
+/// Unit vector from a to b, zero if they coincide
+fn toward(a: &Vec2, b: &Vec2) -> Vec2 {
+    let d = b - a;
+    let len = d.len();
+    if len > 0.0 {
+        d / len
+    } else {
+        d
+    }
+}
+
+/// Dijkstra queue entry, ordered so the lowest cost pops first
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Open {
+    cost: f64,
+    cell: usize,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Open) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Open) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 6x4 unit cells with a wall at x in [2, 3] up to y = 3
+    fn walled() -> DistanceField {
+        let region = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(6.0, 4.0));
+        DistanceField::from_fn(region, 1.0, |p| if p.x > 2.0 && p.x < 3.0 && p.y < 3.0 { -1.0 } else { 1.0 })
+    }
+
+    #[test]
+    fn test_flow_around_wall() {
+        let field = FlowField::new(&walled(), Vec2::new(5.5, 0.5), 0.5);
+
+        assert_eq!(field.cost(&Vec2::new(5.5, 0.5)), 0.0);
+        assert!(field.cost(&Vec2::new(2.5, 1.5)).is_infinite());
+        // The way from the left side leads over the top of the wall
+        assert!(field.direction(&Vec2::new(0.5, 0.5)).y > 0.0);
+        assert!(field.direction(&Vec2::new(1.5, 3.5)).x > 0.0);
+        assert!(field.cost(&Vec2::new(0.5, 0.5)) > 6.0);
+    }
+
+    #[test]
+    fn test_flow_velocity() {
+        let field = FlowField::new(&walled(), Vec2::new(5.5, 0.5), 0.5);
+
+        assert_eq!(field.velocity(&Vec2::new(5.5, 2.5), 2.0), Vec2::new(0.0, -2.0));
+        assert_eq!(field.velocity(&Vec2::new(5.5, 0.75), 2.0), Vec2::new(0.0, -0.5));
+        // Inside the wall the flow leads out to the nearest reachable cell
+        assert!(field.velocity(&Vec2::new(2.5, 1.5), 2.0).x > 0.0);
+
+        // Agents too wide to fit anywhere
+        let unreachable = FlowField::new(&walled(), Vec2::new(5.5, 0.5), 2.0);
+        assert_eq!(unreachable.velocity(&Vec2::new(0.5, 0.5), 2.0), Vec2::new(0.0, 0.0));
+    }
+}
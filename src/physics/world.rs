@@ -0,0 +1,3921 @@
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use super::batch;
+use super::bodies::BodySet;
+use super::body::RigidBody;
+use super::bounds::{BoundsPolicy, WorldBounds};
+use super::constraints;
+use super::contact::{self, Contact, ContactEvent, ContactManager, HitFilter};
+use super::debug_draw::{self, CommandBuffer, DebugColor, DebugDraw};
+use super::features::WorldFeatures;
+use super::forces::{Attractor, Buoyancy, Explosion, ForceCallback, ForceGenerator, Wind};
+use super::hooks::{ContactHooks, ContactSettings};
+use super::joint::{AngleJoint, Joint, JointEvent, MouseJoint, PrismaticJoint, RevoluteJoint};
+use super::material::Material;
+use super::particles::{Emitter, ParticleSystem};
+use super::prediction::SavedStates;
+use super::query::{QueryOptions, QueryPage};
+use super::schedule::{Schedule, ScheduledForce, ScheduledImpulse};
+use super::settings::WorldSettings;
+use super::snapshot::WorldState;
+use super::solver::{Integrator, SolverConfig};
+use super::stats::{StatsHistory, Timer, WorldStats};
+use super::spring::Spring;
+use super::steering::FlowField;
+use crate::collision::broadphase::{Broadphase, BroadphaseConfig};
+use crate::collision::cast::{self, CastHit};
+use crate::collision::narrowphase::{self, NarrowphaseConfig};
+use crate::geometry::{Aabb, DistanceField, Polygon, Ray, RayHit, Segment, Shape, Shapes};
+use crate::math::{Rng, Transform2D, Vec2};
+use crate::serialize::binary::{Binary, Reader, Writer};
+use crate::serialize::{self, json, SerializeError, Value};
+
+/// Identifies a body owned by a World.
+/// Handles of removed bodies stay invalid even when their slot is reused.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle {
+    index: usize,
+    generation: u32,
+}
+
+#[wasm_bindgen]
+impl BodyHandle {
+    /// Slot of the body in the world
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of times the slot was reused before this body was added
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl BodyHandle {
+    pub(crate) fn new(index: usize, generation: u32) -> BodyHandle {
+        BodyHandle { index, generation }
+    }
+}
+
+impl Binary for BodyHandle {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.index);
+        w.put(&self.generation);
+    }
+
+    fn decode(r: &mut Reader) -> Result<BodyHandle, SerializeError> {
+        Ok(BodyHandle::new(r.get()?, r.get()?))
+    }
+}
+
+/// Sides of the polygon a circle is turned into before it explodes
+const EXPLOSION_CIRCLE_SEGMENTS: usize = 16;
+/// Largest angle in radians a fragment may fly off away from straight outward
+const EXPLOSION_SPREAD: f64 = 0.3;
+/// Largest fraction a fragment's speed may differ from the average
+const EXPLOSION_VARIATION: f64 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldError {
+    /// The body was removed or never belonged to this world
+    InvalidHandle(BodyHandle),
+    /// The body's shape can't be broken into fragments
+    CannotFracture(BodyHandle),
+    /// The body descriptor starting at this offset of a batch is malformed
+    InvalidDescriptor(usize),
+    /// Bodies need a mass that is zero, for static bodies, or positive
+    InvalidMass(f64),
+    /// Scheduled events need a finite simulation time
+    InvalidTime(f64),
+    /// Border walls need a finite, positive thickness
+    InvalidThickness(f64),
+    /// Explosions need a finite energy that isn't negative
+    InvalidEnergy(f64),
+    /// Mirror axes need a finite, nonzero direction
+    InvalidAxis(Vec2),
+    /// A joint needs two different bodies
+    SelfJoint(BodyHandle),
+    /// There is no spring at this index
+    InvalidSpring(usize),
+    /// There is no force generator at this index
+    InvalidForceGenerator(usize),
+    /// There is no emitter at this index
+    InvalidEmitter(usize),
+    /// A state buffer needs exactly this many values
+    InvalidBufferLength { expected: usize, actual: usize },
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::InvalidHandle(h) => write!(f, "No body for handle {}v{}", h.index, h.generation),
+            WorldError::CannotFracture(h) => write!(f, "Body {}v{} has a shape that can't fracture", h.index, h.generation),
+            WorldError::InvalidDescriptor(offset) => write!(f, "Invalid body descriptor at offset {}", offset),
+            WorldError::InvalidMass(mass) => write!(f, "Invalid body mass {}, expected zero or positive", mass),
+            WorldError::InvalidTime(time) => write!(f, "Invalid event time {}, expected a finite value", time),
+            WorldError::InvalidThickness(t) => write!(f, "Invalid border thickness {}, expected a positive value", t),
+            WorldError::InvalidEnergy(e) => write!(f, "Invalid explosion energy {}, expected zero or positive", e),
+            WorldError::InvalidAxis(a) => write!(f, "Invalid mirror axis ({}, {}), expected a nonzero direction", a.x, a.y),
+            WorldError::SelfJoint(h) => write!(f, "Joint between body {}v{} and itself", h.index, h.generation),
+            WorldError::InvalidSpring(i) => write!(f, "No spring at index {}", i),
+            WorldError::InvalidForceGenerator(i) => write!(f, "No force generator at index {}", i),
+            WorldError::InvalidEmitter(i) => write!(f, "No emitter at index {}", i),
+            WorldError::InvalidBufferLength { expected, actual } => {
+                write!(f, "Buffer has {} values, expected {}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct World {
+    objects: BodySet,
+    settings: WorldSettings,
+    time: f64,
+    tick: u64,
+    schedule: Schedule,
+    springs: Vec<Spring>,
+    force_generators: Vec<Box<dyn ForceGenerator>>,
+    force_callback: Option<ForceCallback>,
+    joints: Vec<Joint>,
+    particles: ParticleSystem,
+    saved_states: SavedStates,
+    broadphase: Box<dyn Broadphase>,
+    broadphase_config: BroadphaseConfig,
+    /// Broadphase proxy of each body
+    proxies: Vec<usize>,
+    contacts: ContactManager,
+    contact_events: Vec<ContactEvent>,
+    hit_filter: HitFilter,
+    hooks: ContactHooks,
+    joint_events: Vec<JointEvent>,
+    bounds: Option<WorldBounds>,
+    /// Bodies that left the bounds during the last step
+    bounds_events: Vec<BodyHandle>,
+    narrowphase: NarrowphaseConfig,
+    solver: SolverConfig,
+    features: WorldFeatures,
+    stats: WorldStats,
+    stats_history: StatsHistory,
+    /// Source of gameplay randomness, such as explosion fragments
+    rng: Rng,
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new()
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> World {
+        World::with_settings(WorldSettings::new())
+    }
+
+    pub fn with_settings(settings: WorldSettings) -> World {
+        World {
+            objects: BodySet::new(),
+            settings,
+            time: 0.0,
+            tick: 0,
+            schedule: Schedule::new(),
+            springs: Vec::new(),
+            force_generators: Vec::new(),
+            force_callback: None,
+            joints: Vec::new(),
+            particles: ParticleSystem::new(),
+            saved_states: SavedStates::new(settings.keyframe_capacity),
+            broadphase: BroadphaseConfig::new().build(),
+            broadphase_config: BroadphaseConfig::new(),
+            proxies: Vec::new(),
+            contacts: ContactManager::new(),
+            contact_events: Vec::new(),
+            hit_filter: HitFilter::new(),
+            hooks: ContactHooks::new(),
+            joint_events: Vec::new(),
+            bounds: None,
+            bounds_events: Vec::new(),
+            narrowphase: NarrowphaseConfig::new(),
+            solver: SolverConfig::new(),
+            features: WorldFeatures::new(),
+            stats: WorldStats::new(),
+            stats_history: StatsHistory::new(0),
+            rng: Rng::default(),
+        }
+    }
+
+    pub fn settings(&self) -> WorldSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: WorldSettings) {
+        self.saved_states.set_capacity(settings.keyframe_capacity);
+        self.settings = settings;
+    }
+
+    /// Reseed the generator used for random variation in explosions
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// GJK/EPA accuracy used when colliding bodies and particles
+    pub fn narrowphase_config(&self) -> NarrowphaseConfig {
+        self.narrowphase
+    }
+
+    pub fn set_narrowphase_config(&mut self, config: NarrowphaseConfig) {
+        self.narrowphase = config;
+    }
+
+    /// Iterations and overlap correction used when solving contacts and joints
+    pub fn solver_config(&self) -> SolverConfig {
+        self.solver
+    }
+
+    pub fn set_solver_config(&mut self, config: SolverConfig) {
+        self.solver = config;
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.solver.integrator
+    }
+
+    /// Change how bodies are moved each step, keeping the rest of the solver config
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.solver.integrator = integrator;
+    }
+
+    /// Parts of the simulation switched on, for comparing their effect while debugging
+    pub fn features(&self) -> WorldFeatures {
+        self.features
+    }
+
+    pub fn set_features(&mut self, features: WorldFeatures) {
+        self.features = features;
+    }
+
+    /// Region dynamic bodies are kept in, if any
+    pub fn bounds(&self) -> Option<WorldBounds> {
+        self.bounds
+    }
+
+    /// Apply the policy of bounds to each dynamic body whose position leaves it during a step
+    pub fn set_bounds(&mut self, bounds: WorldBounds) {
+        self.bounds = Some(bounds);
+    }
+
+    /// Let bodies go anywhere
+    pub fn clear_bounds(&mut self) {
+        self.bounds = None;
+    }
+
+    /// Counters and timings of the last step
+    pub fn stats(&self) -> WorldStats {
+        self.stats
+    }
+
+    /// Keep the stats of the last capacity steps, 0 to keep none. Off by default.
+    pub fn set_stats_history_capacity(&mut self, capacity: usize) {
+        self.stats_history.set_capacity(capacity);
+    }
+
+    /// Stats of the last steps, see set_stats_history_capacity
+    #[wasm_bindgen(js_name = stats_history)]
+    pub fn wasm_stats_history(&self) -> StatsHistory {
+        self.stats_history.clone()
+    }
+
+    /// Which broadphase finds the pairs of bodies to collide
+    pub fn broadphase_config(&self) -> BroadphaseConfig {
+        self.broadphase_config
+    }
+
+    /// Switch to another broadphase, moving every body into it
+    pub fn set_broadphase_config(&mut self, config: BroadphaseConfig) {
+        self.broadphase_config = config;
+        self.broadphase = config.build();
+        self.rebuild_broadphase();
+    }
+
+    pub fn gravity(&self) -> Vec2 {
+        self.settings.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vec2) {
+        self.settings.gravity = gravity;
+    }
+
+    /// Total simulated time
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Number of steps taken
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Add a body to the world
+    pub fn create_body(&mut self, body: RigidBody) -> BodyHandle {
+        let index = self.objects.len();
+        self.proxies.push(self.broadphase.insert(narrowphase::bounds(body.shape(), &body.transform()), index));
+        self.objects.insert(body)
+    }
+
+    /// Add every body described by a flat descriptor buffer, returning their handles in order.
+    /// See the batch module for the layout. No bodies are added if any descriptor is invalid.
+    #[wasm_bindgen(js_name = add_bodies_batch)]
+    pub fn wasm_add_bodies_batch(&mut self, buffer: &[f64]) -> Result<Vec<BodyHandle>, JsError> {
+        self.add_bodies_batch(buffer).map_err(JsError::from)
+    }
+
+    /// Enclose region with static walls of the given thickness and material, returning their handles.
+    /// The walls lie just outside region, and the top wall is left out if open_top is set.
+    /// Throws if thickness isn't positive.
+    #[wasm_bindgen(js_name = add_border)]
+    pub fn wasm_add_border(
+        &mut self,
+        region: Aabb,
+        thickness: f64,
+        material: Material,
+        open_top: bool,
+    ) -> Result<Vec<BodyHandle>, JsError> {
+        self.add_border(region, thickness, material, open_top).map_err(JsError::from)
+    }
+
+    /// Break a body into fragment_count pieces flying outward with a total kinetic energy of energy.
+    /// Returns the fragment handles. Only circle and polygon bodies can explode.
+    #[wasm_bindgen(js_name = explode_body)]
+    pub fn wasm_explode_body(
+        &mut self,
+        handle: BodyHandle,
+        energy: f64,
+        fragment_count: usize,
+    ) -> Result<Vec<BodyHandle>, JsError> {
+        self.explode_body(handle, energy, fragment_count)
+            .map_err(JsError::from)
+    }
+
+    /// Add a mirror image of a body across the line through point along axis, returning its handle
+    #[wasm_bindgen(js_name = mirror_body)]
+    pub fn wasm_mirror_body(&mut self, handle: BodyHandle, point: &Vec2, axis: &Vec2) -> Result<BodyHandle, JsError> {
+        self.mirror_body(handle, point, axis).map_err(JsError::from)
+    }
+
+    /// Remove a body. Springs and scheduled events using it are skipped from then on.
+    #[wasm_bindgen(js_name = remove_body)]
+    pub fn wasm_remove_body(&mut self, handle: BodyHandle) -> Result<(), JsError> {
+        self.remove_body(handle).map(|_| ()).map_err(JsError::from)
+    }
+
+    /// Copy of a body
+    #[wasm_bindgen(js_name = get_body)]
+    pub fn wasm_get_body(&self, handle: BodyHandle) -> Result<RigidBody, JsError> {
+        self.get_body(handle).cloned().map_err(JsError::from)
+    }
+
+    pub fn contains_body(&self, handle: BodyHandle) -> bool {
+        self.objects.contains(handle)
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// The k bodies closest to point, nearest first, measured to their shapes
+    #[wasm_bindgen(js_name = k_nearest)]
+    pub fn wasm_k_nearest(&self, point: Vec2, k: usize) -> Vec<BodyHandle> {
+        self.k_nearest(&point, k, |_, _| true)
+            .into_iter()
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+
+    /// Slot indices of the bodies overlapping the rectangle from min to max. See World::query_aabb.
+    #[wasm_bindgen(js_name = query_aabb)]
+    pub fn wasm_query_aabb(&self, min: Vec2, max: Vec2) -> Vec<u32> {
+        self.query_aabb(min, max).into_iter().map(|h| h.index() as u32).collect()
+    }
+
+    /// Slot indices of one page of the bodies overlapping the rectangle from min to max.
+    /// The next page starts at options.cursor plus the number returned, if it was options.limit.
+    #[wasm_bindgen(js_name = query_aabb_page)]
+    pub fn wasm_query_aabb_page(&self, min: Vec2, max: Vec2, options: &QueryOptions) -> Vec<u32> {
+        let page = self.query_aabb_with(min, max, options);
+        page.results.into_iter().map(|h| h.index() as u32).collect()
+    }
+
+    /// Slot indices of the bodies hit by ray within max_distance, nearest first. See World::raycast_all.
+    #[wasm_bindgen(js_name = raycast_all)]
+    pub fn wasm_raycast_all(&self, ray: &Ray, max_distance: f64) -> Vec<u32> {
+        self.raycast_all(ray, max_distance, |_, _| true)
+            .into_iter()
+            .map(|(h, _)| h.index() as u32)
+            .collect()
+    }
+
+    /// Debug drawing as a flat command list. See World::debug_draw and CommandBuffer.
+    #[wasm_bindgen(js_name = debug_draw)]
+    pub fn wasm_debug_draw(&self) -> Vec<f64> {
+        let mut buffer = CommandBuffer::new();
+        self.debug_draw(&mut buffer);
+        buffer.into_commands()
+    }
+
+    /// Refresh body bounds in the broadphase.
+    /// Needed before queries when bodies were moved directly rather than by stepping.
+    pub fn update_broadphase(&mut self) {
+        for (body, proxy) in self.objects.as_slice().iter().zip(&self.proxies) {
+            self.broadphase.update(*proxy, narrowphase::bounds(body.shape(), &body.transform()));
+        }
+    }
+
+    /// Signed distance to static bodies sampled every resolution units over region.
+    /// Negative inside geometry and infinite when the world has no static bodies.
+    pub fn rasterize_sdf(&self, region: Aabb, resolution: f64) -> DistanceField {
+        let statics: Vec<&RigidBody> = self.objects.as_slice().iter().filter(|b| b.is_static()).collect();
+        DistanceField::from_fn(region, resolution, |p| {
+            statics
+                .iter()
+                .map(|b| narrowphase::signed_distance(b.shape(), &b.transform(), p))
+                .fold(f64::INFINITY, f64::min)
+        })
+    }
+
+    /// Flow toward goal over region for agents of the given radius, avoiding static bodies
+    pub fn flow_field(&self, region: Aabb, resolution: f64, goal: Vec2, clearance: f64) -> FlowField {
+        FlowField::new(&self.rasterize_sdf(region, resolution), goal, clearance)
+    }
+
+    /// Set the velocity of a body to follow a flow field at speed
+    #[wasm_bindgen(js_name = steer_body)]
+    pub fn wasm_steer_body(&mut self, handle: BodyHandle, field: &FlowField, speed: f64) -> Result<(), JsError> {
+        self.steer_body(handle, field, speed).map_err(JsError::from)
+    }
+
+    /// Add a spring between two bodies, returning its index
+    pub fn add_spring(&mut self, spring: Spring) -> usize {
+        self.springs.push(spring);
+        self.springs.len() - 1
+    }
+
+    /// Remove the spring at index, throwing if there is none
+    #[wasm_bindgen(js_name = remove_spring)]
+    pub fn wasm_remove_spring(&mut self, index: usize) -> Result<Spring, JsError> {
+        self.remove_spring(index).map_err(JsError::from)
+    }
+
+    /// Pull bodies towards or push them away from a point, returning the generator index
+    #[wasm_bindgen(js_name = add_attractor)]
+    pub fn wasm_add_attractor(&mut self, attractor: Attractor) -> usize {
+        self.add_force_generator(attractor)
+    }
+
+    /// Push bodies near a point away once, returning the generator index
+    #[wasm_bindgen(js_name = add_explosion)]
+    pub fn wasm_add_explosion(&mut self, explosion: Explosion) -> usize {
+        self.add_force_generator(explosion)
+    }
+
+    /// Drag bodies along with moving air, returning the generator index
+    #[wasm_bindgen(js_name = add_wind)]
+    pub fn wasm_add_wind(&mut self, wind: Wind) -> usize {
+        self.add_force_generator(wind)
+    }
+
+    /// Float bodies in a fluid, returning the generator index
+    #[wasm_bindgen(js_name = add_buoyancy)]
+    pub fn wasm_add_buoyancy(&mut self, buoyancy: Buoyancy) -> usize {
+        self.add_force_generator(buoyancy)
+    }
+
+    pub fn force_generator_count(&self) -> usize {
+        self.force_generators.len()
+    }
+
+    /// Remove the force generator at index, throwing if there is none
+    #[wasm_bindgen(js_name = remove_force_generator)]
+    pub fn wasm_remove_force_generator(&mut self, index: usize) -> Result<(), JsError> {
+        self.remove_force_generator(index).map_err(JsError::from)
+    }
+
+    /// Add a particle emitter, returning its index
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.particles.add_emitter(emitter)
+    }
+
+    /// Remove the emitter at index, throwing if there is none
+    #[wasm_bindgen(js_name = remove_emitter)]
+    pub fn wasm_remove_emitter(&mut self, index: usize) -> Result<Emitter, JsError> {
+        self.remove_emitter(index).map_err(JsError::from)
+    }
+
+    pub fn emitter(&self, index: usize) -> Option<Emitter> {
+        self.particles.emitters().get(index).copied()
+    }
+
+    /// Change the settings of a running emitter
+    pub fn set_emitter(&mut self, index: usize, emitter: Emitter) {
+        if let Some(e) = self.particles.emitter_mut(index) {
+            let accumulator = e.accumulator;
+            *e = emitter;
+            e.accumulator = accumulator;
+        }
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.particles().len()
+    }
+
+    /// Particle positions as a flat [x0, y0, x1, y1, ...] array for rendering
+    pub fn particle_positions(&self) -> Vec<f64> {
+        self.particles
+            .particles()
+            .iter()
+            .flat_map(|p| vec![p.position.x, p.position.y])
+            .collect()
+    }
+
+    /// Handles in the order bodies appear in the state buffers.
+    /// The order only changes when bodies are added or removed.
+    pub fn body_handles(&self) -> Vec<BodyHandle> {
+        self.objects.handles().to_vec()
+    }
+
+    /// Body positions as a flat [x0, y0, x1, y1, ...] array
+    pub fn positions_buffer(&self) -> Vec<f64> {
+        self.objects
+            .as_slice()
+            .iter()
+            .flat_map(|body| {
+                let p = body.position();
+                vec![p.x, p.y]
+            })
+            .collect()
+    }
+
+    /// Body rotations in radians
+    pub fn rotations_buffer(&self) -> Vec<f64> {
+        self.objects.as_slice().iter().map(RigidBody::rotation).collect()
+    }
+
+    /// Body velocities as a flat [x0, y0, x1, y1, ...] array
+    pub fn velocities_buffer(&self) -> Vec<f64> {
+        self.objects
+            .as_slice()
+            .iter()
+            .flat_map(|body| {
+                let v = body.velocity();
+                vec![v.x, v.y]
+            })
+            .collect()
+    }
+
+    /// Write body positions into a buffer of 2 * body_count values, throwing if its length differs.
+    /// Reusing one buffer every frame avoids allocating a new array.
+    #[wasm_bindgen(js_name = fill_positions)]
+    pub fn wasm_fill_positions(&self, out: &mut [f64]) -> Result<(), JsError> {
+        self.fill_positions(out).map_err(JsError::from)
+    }
+
+    /// Write body rotations into a buffer of body_count values, throwing if its length differs
+    #[wasm_bindgen(js_name = fill_rotations)]
+    pub fn wasm_fill_rotations(&self, out: &mut [f64]) -> Result<(), JsError> {
+        self.fill_rotations(out).map_err(JsError::from)
+    }
+
+    /// Write body velocities into a buffer of 2 * body_count values, throwing if its length differs
+    #[wasm_bindgen(js_name = fill_velocities)]
+    pub fn wasm_fill_velocities(&self, out: &mut [f64]) -> Result<(), JsError> {
+        self.fill_velocities(out).map_err(JsError::from)
+    }
+
+    /// Queue an impulse to be applied to a body once the simulation reaches at_time, throwing
+    /// if at_time isn't finite
+    #[wasm_bindgen(js_name = schedule_impulse)]
+    pub fn wasm_schedule_impulse(&mut self, handle: BodyHandle, at_time: f64, impulse: Vec2) -> Result<(), JsError> {
+        self.schedule_impulse(handle, at_time, impulse).map_err(JsError::from)
+    }
+
+    /// Queue a force to act on a body from start for duration seconds of simulation time,
+    /// throwing if start isn't finite or duration is NaN
+    #[wasm_bindgen(js_name = schedule_force)]
+    pub fn wasm_schedule_force(&mut self, handle: BodyHandle, start: f64, duration: f64, force: Vec2) -> Result<(), JsError> {
+        self.schedule_force(handle, start, duration, force).map_err(JsError::from)
+    }
+
+    /// Advance the simulation by dt.
+    /// The step is split at scheduled event times so they are applied exactly when they are due.
+    pub fn step(&mut self, dt: f64) {
+        if !dt.is_finite() || dt < 0.0 {
+            log::warn!("Ignoring step with invalid dt {}", dt);
+            return;
+        }
+
+        let step_timer = Timer::start();
+        self.stats = WorldStats::new();
+        let previous = self.contacts.contacts().to_vec();
+        self.joint_events.clear();
+        self.bounds_events.clear();
+        let inside = self.bodies_in_bounds();
+        let end = self.time + dt;
+        let mut t = self.time;
+        loop {
+            for s in self.schedule.take_due_impulses(t) {
+                match self.body_mut(s.body) {
+                    Some(body) => body.apply_impulse(&s.impulse),
+                    None => log::debug!("Dropping scheduled impulse for missing body {:?}", s.body),
+                }
+            }
+
+            let next = self.schedule.next_boundary(t, end);
+            let substeps = self.solver.substeps.max(1);
+            let h = (next - t) / substeps as f64;
+            for i in 0..substeps {
+                self.integrate(t + h * i as f64, h);
+            }
+            t = next;
+            if t >= end {
+                break;
+            }
+        }
+        self.enforce_bounds(&inside);
+
+        let timer = Timer::start();
+        self.particles
+            .step(dt, &self.settings.gravity, self.objects.as_slice(), &self.narrowphase);
+        timer.stop(&mut self.stats.particles_ms);
+        self.schedule.prune_forces(end);
+        for body in self.objects.as_mut_slice() {
+            body.clear_forces();
+        }
+        self.update_broadphase();
+        self.contact_events = contact::contact_events(&previous, self.contacts.contacts());
+        self.hit_filter.filter(&mut self.contact_events, end);
+        self.time = end;
+        self.tick += 1;
+        step_timer.stop(&mut self.stats.step_ms);
+        self.stats_history.push(self.stats);
+    }
+
+    /// Bodies that left the bounds during the last step. Removed ones are no longer in the world.
+    #[wasm_bindgen(js_name = left_bounds)]
+    pub fn wasm_left_bounds(&self) -> Vec<BodyHandle> {
+        self.bounds_events.clone()
+    }
+
+    /// Contacts that began or ended during the last step
+    #[wasm_bindgen(js_name = contact_events)]
+    pub fn wasm_contact_events(&self) -> Vec<ContactEvent> {
+        self.contact_events.clone()
+    }
+
+    /// Only report one begin contact event between a body and each other body every cooldown
+    /// seconds, so a projectile piercing through a target hits it once. 0 turns this off.
+    #[wasm_bindgen(js_name = set_hit_cooldown)]
+    pub fn wasm_set_hit_cooldown(&mut self, handle: BodyHandle, cooldown: f64) -> Result<(), JsError> {
+        self.set_hit_cooldown(handle, cooldown).map_err(JsError::from)
+    }
+
+    /// Hit cooldown of a body, 0 if it has none
+    pub fn hit_cooldown(&self, handle: BodyHandle) -> f64 {
+        self.hit_filter.cooldown(handle)
+    }
+
+    /// Let two bodies touch, or pass through each other with false
+    #[wasm_bindgen(js_name = set_pair_enabled)]
+    pub fn wasm_set_pair_enabled(&mut self, a: BodyHandle, b: BodyHandle, enabled: bool) -> Result<(), JsError> {
+        self.set_pair_enabled(a, b, enabled).map_err(JsError::from)
+    }
+
+    /// Solve contacts between two bodies with this friction and restitution instead of mixing
+    /// their materials
+    #[wasm_bindgen(js_name = set_pair_material)]
+    pub fn wasm_set_pair_material(
+        &mut self,
+        a: BodyHandle,
+        b: BodyHandle,
+        friction: f64,
+        restitution: f64,
+    ) -> Result<(), JsError> {
+        self.set_pair_material(a, b, friction, restitution)
+            .map_err(JsError::from)
+    }
+
+    /// Go back to mixing the materials of two bodies
+    pub fn clear_pair_material(&mut self, a: BodyHandle, b: BodyHandle) {
+        self.hooks.set_pair_material(a, b, None);
+    }
+
+    /// Total normal impulse between two bodies in the last step, e.g. for the volume of an impact
+    pub fn pair_impulse(&self, a: BodyHandle, b: BodyHandle) -> f64 {
+        self.contacts
+            .contacts()
+            .iter()
+            .filter(|c| (c.body_a, c.body_b) == (a, b) || (c.body_a, c.body_b) == (b, a))
+            .map(|c| c.impulse.normal)
+            .sum()
+    }
+
+    #[wasm_bindgen(js_name = set_user_data)]
+    pub fn wasm_set_user_data(&mut self, handle: BodyHandle, user_data: u64) -> Result<(), JsError> {
+        self.set_user_data(handle, user_data).map_err(JsError::from)
+    }
+
+    /// User data of a body, None if the body doesn't exist
+    pub fn user_data(&self, handle: BodyHandle) -> Option<u64> {
+        self.objects.get(handle).map(RigidBody::user_data)
+    }
+
+    /// Add a revolute joint, returning its index, throwing if both ends are the same body
+    #[wasm_bindgen(js_name = add_revolute_joint)]
+    pub fn wasm_add_revolute_joint(&mut self, joint: RevoluteJoint) -> Result<usize, JsError> {
+        self.add_revolute_joint(joint).map_err(JsError::from)
+    }
+
+    /// The revolute joint at index, None if there is no joint or it is of another kind
+    pub fn revolute_joint(&self, index: usize) -> Option<RevoluteJoint> {
+        match self.joints.get(index) {
+            Some(Joint::Revolute(j)) => Some(*j),
+            _ => None,
+        }
+    }
+
+    /// Change the settings of a revolute joint, keeping its bodies, anchors and limit and stall tracking
+    pub fn set_revolute_joint(&mut self, index: usize, joint: RevoluteJoint) {
+        if let Some(Joint::Revolute(j)) = self.joints.get_mut(index) {
+            j.set_params(&joint);
+        }
+    }
+
+    /// Add a joint holding the relative rotation of two bodies, returning its index, throwing if both ends are the same body
+    #[wasm_bindgen(js_name = add_angle_joint)]
+    pub fn wasm_add_angle_joint(&mut self, joint: AngleJoint) -> Result<usize, JsError> {
+        self.add_angle_joint(joint).map_err(JsError::from)
+    }
+
+    /// The angle joint at index, None if there is no joint or it is of another kind
+    pub fn angle_joint(&self, index: usize) -> Option<AngleJoint> {
+        match self.joints.get(index) {
+            Some(Joint::Angle(j)) => Some(*j),
+            _ => None,
+        }
+    }
+
+    /// Add a joint letting a body slide along an axis of another, returning its index, throwing if both ends are the same body
+    #[wasm_bindgen(js_name = add_prismatic_joint)]
+    pub fn wasm_add_prismatic_joint(&mut self, joint: PrismaticJoint) -> Result<usize, JsError> {
+        self.add_prismatic_joint(joint).map_err(JsError::from)
+    }
+
+    /// The prismatic joint at index, None if there is no joint or it is of another kind
+    pub fn prismatic_joint(&self, index: usize) -> Option<PrismaticJoint> {
+        match self.joints.get(index) {
+            Some(Joint::Prismatic(j)) => Some(*j),
+            _ => None,
+        }
+    }
+
+    /// Change the settings of a prismatic joint, keeping its bodies, anchors, axis and limit and stall tracking
+    pub fn set_prismatic_joint(&mut self, index: usize, joint: PrismaticJoint) {
+        if let Some(Joint::Prismatic(j)) = self.joints.get_mut(index) {
+            j.set_params(&joint);
+        }
+    }
+
+    /// Add a joint dragging a body towards a target point, returning its index, throwing if both ends are the same body
+    #[wasm_bindgen(js_name = add_mouse_joint)]
+    pub fn wasm_add_mouse_joint(&mut self, joint: MouseJoint) -> Result<usize, JsError> {
+        self.add_mouse_joint(joint).map_err(JsError::from)
+    }
+
+    /// The mouse joint at index, None if there is no joint or it is of another kind
+    pub fn mouse_joint(&self, index: usize) -> Option<MouseJoint> {
+        match self.joints.get(index) {
+            Some(Joint::Mouse(j)) => Some(*j),
+            _ => None,
+        }
+    }
+
+    /// Change the target and settings of a mouse joint, keeping its bodies and anchor
+    pub fn set_mouse_joint(&mut self, index: usize, joint: MouseJoint) {
+        if let Some(Joint::Mouse(j)) = self.joints.get_mut(index) {
+            j.set_params(&joint);
+        }
+    }
+
+    /// Move the target of a mouse joint, e.g. to follow the pointer
+    pub fn set_mouse_target(&mut self, index: usize, x: f64, y: f64) {
+        if let Some(Joint::Mouse(j)) = self.joints.get_mut(index) {
+            j.set_target(x, y);
+        }
+    }
+
+    /// Force the joint at index applied to its body b over the last step, given 1 / dt of that step.
+    /// None if there is no joint. Body a felt the opposite.
+    pub fn joint_reaction_force(&self, index: usize, inv_dt: f64) -> Option<Vec2> {
+        self.joints.get(index).map(|j| j.reaction_force(inv_dt))
+    }
+
+    /// Torque of the motor, limit or angle rows of the joint at index over the last step, given 1 / dt
+    pub fn joint_reaction_torque(&self, index: usize, inv_dt: f64) -> Option<f64> {
+        self.joints.get(index).map(|j| j.reaction_torque(inv_dt))
+    }
+
+    /// User data of the joint at index, None if there is no joint
+    pub fn joint_user_data(&self, index: usize) -> Option<u64> {
+        self.joints.get(index).map(Joint::user_data)
+    }
+
+    pub fn set_joint_user_data(&mut self, index: usize, user_data: u64) {
+        if let Some(joint) = self.joints.get_mut(index) {
+            joint.set_user_data(user_data);
+        }
+    }
+
+    /// World position of the anchor on body a of the joint at index, None if the joint or body is gone
+    pub fn joint_anchor_a(&self, index: usize) -> Option<Vec2> {
+        let joint = self.joints.get(index)?;
+        Some(joint.anchor_a(self.objects.get(joint.body_a())?))
+    }
+
+    /// World position of the anchor on body b of the joint at index, None if the joint or body is gone
+    pub fn joint_anchor_b(&self, index: usize) -> Option<Vec2> {
+        let joint = self.joints.get(index)?;
+        Some(joint.anchor_b(self.objects.get(joint.body_b())?))
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// Joints that reached or left a limit or whose motor stalled or recovered during the last step
+    #[wasm_bindgen(js_name = joint_events)]
+    pub fn wasm_joint_events(&self) -> Vec<JointEvent> {
+        self.joint_events.clone()
+    }
+
+    /// Save bodies, springs, joints, hit cooldowns, emitters and settings as a level or save file.
+    /// Live particles, contacts and scheduled events are not saved.
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// Load a world written by to_json of this or an older version of the crate.
+    /// Body handles are renumbered in the order the bodies were saved.
+    #[wasm_bindgen(js_name = from_json)]
+    pub fn wasm_from_json(s: &str) -> Result<World, JsError> {
+        World::from_json(s).map_err(JsError::from)
+    }
+
+    /// Full simulation state in a compact binary form, for rollback and replays
+    pub fn save_state(&self) -> Vec<u8> {
+        self.snapshot().to_bytes()
+    }
+
+    /// Restore a state written by save_state. The world is unchanged if bytes are invalid.
+    #[wasm_bindgen(js_name = load_state)]
+    pub fn wasm_load_state(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        self.load_state(bytes).map_err(JsError::from)
+    }
+
+    /// Save the current state as the keyframe for the current tick
+    pub fn save_keyframe(&mut self) -> u64 {
+        let state = self.snapshot();
+        self.saved_states.push(state);
+        self.tick
+    }
+
+    /// Restore the keyframe saved at tick, discarding newer keyframes.
+    /// Returns false if no keyframe exists for tick.
+    pub fn rewind_to(&mut self, tick: u64) -> bool {
+        let state = match self.saved_states.get(tick) {
+            Some(state) => state.clone(),
+            None => return false,
+        };
+        self.saved_states.truncate_after(tick);
+        self.restore(&state);
+        true
+    }
+
+    /// Number of keyframes kept for rewinding
+    pub fn keyframe_capacity(&self) -> usize {
+        self.settings.keyframe_capacity
+    }
+
+    pub fn set_keyframe_capacity(&mut self, capacity: usize) {
+        self.settings.keyframe_capacity = capacity;
+        self.saved_states.set_capacity(capacity);
+    }
+}
+
+impl World {
+    /// Enclose region with static walls of the given thickness and material, returning their handles.
+    /// The walls lie just outside region, and the top wall is left out if open_top is set.
+    pub fn add_border(
+        &mut self,
+        region: Aabb,
+        thickness: f64,
+        material: Material,
+        open_top: bool,
+    ) -> Result<Vec<BodyHandle>, WorldError> {
+        if !(thickness > 0.0 && thickness.is_finite()) {
+            return Err(WorldError::InvalidThickness(thickness));
+        }
+        let (min, max, t) = (region.min, region.max, thickness);
+        let mut walls = vec![
+            // Floor and sides reach past the corners so the enclosure has no gaps
+            Aabb::new(Vec2::new(min.x - t, min.y - t), Vec2::new(max.x + t, min.y)),
+            Aabb::new(Vec2::new(min.x - t, min.y), Vec2::new(min.x, max.y + t)),
+            Aabb::new(Vec2::new(max.x, min.y), Vec2::new(max.x + t, max.y + t)),
+        ];
+        if !open_top {
+            walls.push(Aabb::new(Vec2::new(min.x, max.y), Vec2::new(max.x, max.y + t)));
+        }
+
+        Ok(walls
+            .into_iter()
+            .map(|wall| {
+                let (hw, hh) = (wall.width() / 2.0, wall.height() / 2.0);
+                let mut body = RigidBody::with_polygon(0.0, &[-hw, -hh, hw, -hh, hw, hh, -hw, hh]);
+                body.set_position((wall.min + wall.max) / 2.0);
+                body.set_material(material);
+                self.create_body(body)
+            })
+            .collect())
+    }
+
+    /// Remove the spring at index
+    pub fn remove_spring(&mut self, index: usize) -> Result<Spring, WorldError> {
+        if index >= self.springs.len() {
+            return Err(WorldError::InvalidSpring(index));
+        }
+        Ok(self.springs.remove(index))
+    }
+
+    /// Remove the force generator at index. Finished generators are removed by themselves,
+    /// moving the ones after them down.
+    pub fn remove_force_generator(&mut self, index: usize) -> Result<(), WorldError> {
+        if index >= self.force_generators.len() {
+            return Err(WorldError::InvalidForceGenerator(index));
+        }
+        self.force_generators.remove(index);
+        Ok(())
+    }
+
+    /// Remove the emitter at index. Particles it already spawned live on.
+    pub fn remove_emitter(&mut self, index: usize) -> Result<Emitter, WorldError> {
+        if index >= self.particles.emitters().len() {
+            return Err(WorldError::InvalidEmitter(index));
+        }
+        Ok(self.particles.remove_emitter(index))
+    }
+
+    /// Write body positions into a buffer of 2 * body_count values.
+    /// Reusing one buffer every frame avoids allocating a new array.
+    pub fn fill_positions(&self, out: &mut [f64]) -> Result<(), WorldError> {
+        self.check_buffer(out, 2)?;
+        for (chunk, body) in out.chunks_exact_mut(2).zip(self.objects.as_slice()) {
+            let p = body.position();
+            chunk.copy_from_slice(&[p.x, p.y]);
+        }
+        Ok(())
+    }
+
+    /// Write body rotations into a buffer of body_count values
+    pub fn fill_rotations(&self, out: &mut [f64]) -> Result<(), WorldError> {
+        self.check_buffer(out, 1)?;
+        for (r, body) in out.iter_mut().zip(self.objects.as_slice()) {
+            *r = body.rotation();
+        }
+        Ok(())
+    }
+
+    /// Write body velocities into a buffer of 2 * body_count values
+    pub fn fill_velocities(&self, out: &mut [f64]) -> Result<(), WorldError> {
+        self.check_buffer(out, 2)?;
+        for (chunk, body) in out.chunks_exact_mut(2).zip(self.objects.as_slice()) {
+            let v = body.velocity();
+            chunk.copy_from_slice(&[v.x, v.y]);
+        }
+        Ok(())
+    }
+
+    fn check_buffer(&self, out: &[f64], values_per_body: usize) -> Result<(), WorldError> {
+        let expected = values_per_body * self.objects.len();
+        if out.len() != expected {
+            return Err(WorldError::InvalidBufferLength { expected, actual: out.len() });
+        }
+        Ok(())
+    }
+
+    /// Switch to a broadphase of your own, moving every body into it.
+    /// broadphase_config no longer applies until it is set again.
+    pub fn set_broadphase(&mut self, broadphase: Box<dyn Broadphase>) {
+        self.broadphase = broadphase;
+        self.rebuild_broadphase();
+    }
+
+    pub fn broadphase(&self) -> &dyn Broadphase {
+        &*self.broadphase
+    }
+
+    /// Send body outlines and transforms, bounding boxes, contact points and normals, joints and
+    /// springs to draw
+    pub fn debug_draw<D: DebugDraw + ?Sized>(&self, draw: &mut D) {
+        for body in self.objects.as_slice() {
+            let transform = body.transform();
+            let color = if body.is_static() { DebugColor::StaticBody } else { DebugColor::DynamicBody };
+            debug_draw::draw_shape(draw, body.shape(), &transform, color);
+            draw.draw_transform(&transform);
+            let aabb = narrowphase::bounds(body.shape(), &transform);
+            let corners = [aabb.min, Vec2::new(aabb.max.x, aabb.min.y), aabb.max, Vec2::new(aabb.min.x, aabb.max.y)];
+            draw.draw_polygon(&corners, DebugColor::Aabb);
+        }
+        for c in self.contacts.contacts() {
+            draw.draw_point(c.point, DebugColor::Contact);
+            draw.draw_segment(c.point, c.point + c.normal * debug_draw::NORMAL_LENGTH, DebugColor::Contact);
+        }
+        // Each body's center is joined to its anchor
+        let mut link = |a: &RigidBody, b: &RigidBody, anchor_a: Vec2, anchor_b: Vec2, color: DebugColor| {
+            draw.draw_segment(a.position(), anchor_a, color);
+            draw.draw_segment(anchor_a, anchor_b, color);
+            draw.draw_segment(anchor_b, b.position(), color);
+        };
+        for joint in &self.joints {
+            if let (Some(a), Some(b)) = (self.objects.get(joint.body_a()), self.objects.get(joint.body_b())) {
+                link(a, b, joint.anchor_a(a), joint.anchor_b(b), DebugColor::Joint);
+            }
+        }
+        for spring in &self.springs {
+            if let (Some(a), Some(b)) = (self.objects.get(spring.body_a()), self.objects.get(spring.body_b())) {
+                let anchor_a = a.transform().apply(&spring.local_anchor_a());
+                let anchor_b = b.transform().apply(&spring.local_anchor_b());
+                link(a, b, anchor_a, anchor_b, DebugColor::Spring);
+            }
+        }
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        self.objects.get(handle)
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        self.objects.get_mut(handle)
+    }
+
+    /// Queue an impulse to be applied to a body once the simulation reaches at_time
+    pub fn schedule_impulse(&mut self, handle: BodyHandle, at_time: f64, impulse: Vec2) -> Result<(), WorldError> {
+        self.schedule.add_impulse(ScheduledImpulse {
+            body: handle,
+            time: at_time,
+            impulse,
+        })
+    }
+
+    /// Queue a force to act on a body from start for duration seconds of simulation time
+    pub fn schedule_force(&mut self, handle: BodyHandle, start: f64, duration: f64, force: Vec2) -> Result<(), WorldError> {
+        self.schedule.add_force(ScheduledForce {
+            body: handle,
+            start,
+            end: start + duration,
+            force,
+        })
+    }
+
+    pub fn get_body(&self, handle: BodyHandle) -> Result<&RigidBody, WorldError> {
+        self.objects.get(handle).ok_or(WorldError::InvalidHandle(handle))
+    }
+
+    pub fn get_body_mut(&mut self, handle: BodyHandle) -> Result<&mut RigidBody, WorldError> {
+        self.objects.get_mut(handle).ok_or(WorldError::InvalidHandle(handle))
+    }
+
+    /// Add every body described by a flat descriptor buffer, returning their handles in order.
+    /// No bodies are added if any descriptor is invalid.
+    pub fn add_bodies_batch(&mut self, buffer: &[f64]) -> Result<Vec<BodyHandle>, WorldError> {
+        let bodies = batch::parse_bodies(buffer).map_err(WorldError::InvalidDescriptor)?;
+        Ok(bodies.into_iter().map(|body| self.create_body(body)).collect())
+    }
+
+    /// Set the velocity of a body to follow a flow field at speed
+    pub fn steer_body(&mut self, handle: BodyHandle, field: &FlowField, speed: f64) -> Result<(), WorldError> {
+        let body = self.get_body_mut(handle)?;
+        body.set_velocity(field.velocity(&body.position(), speed));
+        Ok(())
+    }
+
+    /// Break a body into fragment_count pieces flying outward with a total kinetic energy of energy.
+    /// Fragments keep the motion of the body they came from, with speed and direction varied by
+    /// the world's seeded generator. Returns the fragment handles. Only circle and polygon bodies
+    /// can explode, and fragments of static bodies stay in place.
+    pub fn explode_body(
+        &mut self,
+        handle: BodyHandle,
+        energy: f64,
+        fragment_count: usize,
+    ) -> Result<Vec<BodyHandle>, WorldError> {
+        if !(energy >= 0.0 && energy.is_finite()) {
+            return Err(WorldError::InvalidEnergy(energy));
+        }
+        let body = self.get_body(handle)?;
+        let outline = match body.shape() {
+            Shapes::Polygon(p) => p.clone(),
+            Shapes::Circle(c) => Polygon::new(&c.enclosing_points(EXPLOSION_CIRCLE_SEGMENTS)),
+            _ => return Err(WorldError::CannotFracture(handle)),
+        };
+
+        // Sites are spread over the shape, in body coordinates
+        let bounds = outline.aabb();
+        let radius = (bounds.max - bounds.min).len() / 2.0;
+        let middle = (bounds.min + bounds.max) / 2.0;
+        let mut sites = Vec::with_capacity(fragment_count);
+        while sites.len() < fragment_count.max(1) {
+            let site = self.rng.in_disk(&middle, radius);
+            if outline.contains(&site) {
+                sites.push(site);
+            }
+        }
+        let pieces = outline.fracture(&sites);
+
+        let body = self.remove_body(handle)?;
+        let transform = body.transform();
+        let total_area = outline.area().abs();
+        let speed = if body.mass() > 0.0 { (2.0 * energy / body.mass()).sqrt() } else { 0.0 };
+        let mut fragments = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            let center = transform.apply(&piece.center());
+            let vertices: Vec<Vec2> = piece.vertices().iter().map(|v| transform.apply(v) - center).collect();
+            let mut fragment = RigidBody::with_polygon(
+                body.mass() * piece.area().abs() / total_area,
+                &vertices.iter().flat_map(|v| vec![v.x, v.y]).collect::<Vec<f64>>(),
+            );
+            fragment.set_position(center);
+
+            if !body.is_static() {
+                let r = center - body.position();
+                let spin = Vec2::cross_scalar(body.angular_velocity(), &r);
+                let direction = if r.len() > 0.0 { r / r.len() } else { Vec2::new(0.0, 1.0) };
+                let angle = self.rng.range(-EXPLOSION_SPREAD, EXPLOSION_SPREAD);
+                let variation = self.rng.range(1.0 - EXPLOSION_VARIATION, 1.0 + EXPLOSION_VARIATION);
+                fragment.set_velocity(body.velocity() + spin + direction.rotate(angle) * (speed * variation));
+                fragment.set_angular_velocity(body.angular_velocity());
+            }
+            fragments.push(self.create_body(fragment));
+        }
+        Ok(fragments)
+    }
+
+    /// Add a mirror image of a body across the line through point along axis, returning its handle.
+    /// The copy moves as the mirror image of the original, so its rotation and spin are reversed.
+    pub fn mirror_body(&mut self, handle: BodyHandle, point: &Vec2, axis: &Vec2) -> Result<BodyHandle, WorldError> {
+        if !(axis.len_sq() > 0.0 && axis.len_sq().is_finite()) {
+            return Err(WorldError::InvalidAxis(*axis));
+        }
+        let mut body = self.get_body(handle)?.clone();
+        // Mirroring a rotation by r equals rotating by -r after mirroring, so the shape is
+        // mirrored in local space across the same axis
+        body.set_shape(body.shape().mirrored(axis));
+        body.set_position(point + (body.position() - point).mirror(axis));
+        body.set_rotation(-body.rotation());
+        body.set_velocity(body.velocity().mirror(axis));
+        body.set_angular_velocity(-body.angular_velocity());
+        Ok(self.create_body(body))
+    }
+
+    /// Only report one begin contact event between a body and each other body every cooldown
+    /// seconds. End events are still reported. A cooldown of 0 turns filtering off.
+    pub fn set_hit_cooldown(&mut self, handle: BodyHandle, cooldown: f64) -> Result<(), WorldError> {
+        self.get_body(handle)?;
+        self.hit_filter.set_cooldown(handle, cooldown);
+        Ok(())
+    }
+
+    /// Let two bodies touch, or pass through each other with false
+    pub fn set_pair_enabled(&mut self, a: BodyHandle, b: BodyHandle, enabled: bool) -> Result<(), WorldError> {
+        self.get_body(a)?;
+        self.get_body(b)?;
+        self.hooks.set_pair_enabled(a, b, enabled);
+        Ok(())
+    }
+
+    /// Solve contacts between two bodies with this friction and restitution instead of mixing
+    /// their materials
+    pub fn set_pair_material(&mut self, a: BodyHandle, b: BodyHandle, friction: f64, restitution: f64) -> Result<(), WorldError> {
+        self.get_body(a)?;
+        self.get_body(b)?;
+        self.hooks.set_pair_material(a, b, Some((friction, restitution)));
+        Ok(())
+    }
+
+    /// Only let bodies touch when filter returns true for them, checked each step
+    pub fn set_contact_filter<F: Fn(&RigidBody, &RigidBody) -> bool + 'static>(&mut self, filter: F) {
+        self.hooks.set_filter(Some(Rc::new(filter)));
+    }
+
+    /// Call pre_solve with each contact before it is solved, letting it change or disable the contact
+    pub fn set_pre_solve<F: Fn(&Contact, &mut ContactSettings) + 'static>(&mut self, pre_solve: F) {
+        self.hooks.set_pre_solve(Some(Rc::new(pre_solve)));
+    }
+
+    /// Call post_solve with each contact after it is solved, with the impulse that was applied
+    pub fn set_post_solve<F: Fn(&Contact) + 'static>(&mut self, post_solve: F) {
+        self.hooks.set_post_solve(Some(Rc::new(post_solve)));
+    }
+
+    /// Remove the contact filter and solve hooks. Pair flags are kept.
+    pub fn clear_contact_hooks(&mut self) {
+        self.hooks.set_filter(None);
+        self.hooks.set_pre_solve(None);
+        self.hooks.set_post_solve(None);
+    }
+
+    /// Store a value on a body to map it back to a game entity, e.g. from contact events
+    pub fn set_user_data(&mut self, handle: BodyHandle, user_data: u64) -> Result<(), WorldError> {
+        self.objects.get_mut(handle).ok_or(WorldError::InvalidHandle(handle))?.set_user_data(user_data);
+        Ok(())
+    }
+
+    /// Remove a body and return it.
+    /// Contacts it had end in the next step, and particles stuck to it are released.
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Result<RigidBody, WorldError> {
+        let (index, body) = self.objects.remove(handle).ok_or(WorldError::InvalidHandle(handle))?;
+        self.broadphase.remove(self.proxies.swap_remove(index));
+        if let Some(&moved) = self.proxies.get(index) {
+            self.broadphase.set_data(moved, index);
+        }
+        self.particles.body_removed(index, self.objects.len());
+        self.hit_filter.body_removed(handle);
+        self.hooks.body_removed(handle);
+        Ok(body)
+    }
+
+    /// Bodies with their handles
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.objects.iter()
+    }
+
+    /// Bodies with their handles, for changing many at once.
+    /// Call update_broadphase before querying bodies moved this way.
+    pub fn bodies_mut(&mut self) -> impl Iterator<Item = (BodyHandle, &mut RigidBody)> {
+        self.objects.iter_mut()
+    }
+
+    /// Handles of the bodies filter accepts, in slot order
+    pub fn query<F: FnMut(&RigidBody) -> bool>(&self, mut filter: F) -> Vec<BodyHandle> {
+        let mut handles: Vec<BodyHandle> =
+            self.objects.iter().filter(|(_, body)| filter(body)).map(|(handle, _)| handle).collect();
+        handles.sort_unstable();
+        handles
+    }
+
+    /// Load a world written by to_json of this or an older version of the crate.
+    /// Body handles are renumbered in the order the bodies were saved.
+    pub fn from_json(s: &str) -> Result<World, SerializeError> {
+        World::from_value(json::parse(s)?)
+    }
+
+    /// Load a parsed save file of this or an older version of the crate
+    pub(crate) fn from_value(doc: Value) -> Result<World, SerializeError> {
+        let doc = serialize::upgrade(doc)?;
+        let mut world = World::with_settings(WorldSettings::from_value(doc.field("settings")?)?);
+        world.time = doc.field_f64("time")?;
+        let tick = doc.field_f64("tick")?;
+        if tick < 0.0 || tick.fract() != 0.0 {
+            return Err(SerializeError::InvalidValue(format!("tick {} is not a step count", tick)));
+        }
+        world.tick = tick as u64;
+        world.solver = SolverConfig::from_value(doc.field("solver")?)?;
+        world.features = WorldFeatures::from_value(doc.field("features")?)?;
+        world.bounds = match doc.field("bounds")? {
+            Value::Null => None,
+            bounds => Some(WorldBounds::from_value(bounds)?),
+        };
+
+        let mut handles = Vec::new();
+        for body in doc.field_array("bodies")? {
+            handles.push(world.create_body(RigidBody::from_value(body)?));
+        }
+        for spring in doc.field_array("springs")? {
+            world.add_spring(Spring::from_value(spring, &handles)?);
+        }
+        // Joints were added after the first version of the format
+        if doc.get("joints").is_some() {
+            for joint in doc.field_array("joints")? {
+                // from_value already rejects a joint between a body and itself
+                world.joints.push(Joint::from_value(joint, &handles)?);
+            }
+        }
+        if doc.get("hit_cooldowns").is_some() {
+            for entry in doc.field_array("hit_cooldowns")? {
+                let handle = handles
+                    .get(entry.field_f64("body")? as usize)
+                    .copied()
+                    .ok_or_else(|| SerializeError::InvalidValue("no body for hit cooldown".to_string()))?;
+                world.hit_filter.set_cooldown(handle, entry.field_f64("cooldown")?);
+            }
+        }
+        for emitter in doc.field_array("emitters")? {
+            world.add_emitter(Emitter::from_value(emitter)?);
+        }
+        Ok(world)
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        let mut settings = self.settings.to_value();
+        settings.remove("version");
+        let springs = self
+            .springs
+            .iter()
+            .filter_map(|spring| {
+                let a = self.objects.index_of(spring.body_a())?;
+                let b = self.objects.index_of(spring.body_b())?;
+                Some(spring.to_value(a, b))
+            })
+            .collect();
+        let joints = self
+            .joints
+            .iter()
+            .filter_map(|joint| {
+                let a = self.objects.index_of(joint.body_a())?;
+                let b = self.objects.index_of(joint.body_b())?;
+                Some(joint.to_value(a, b))
+            })
+            .collect();
+        let hit_cooldowns = self
+            .hit_filter
+            .cooldowns()
+            .filter_map(|(handle, cooldown)| {
+                let mut entry = Value::object();
+                entry.insert("body", (self.objects.index_of(handle)? as f64).into());
+                entry.insert("cooldown", cooldown.into());
+                Some(entry)
+            })
+            .collect();
+
+        let mut v = Value::object();
+        v.insert("version", Value::Number(serialize::FORMAT_VERSION as f64));
+        v.insert("settings", settings);
+        v.insert("time", self.time.into());
+        v.insert("tick", (self.tick as f64).into());
+        v.insert("solver", self.solver.to_value());
+        v.insert("features", self.features.to_value());
+        v.insert("bounds", self.bounds.map_or(Value::Null, WorldBounds::to_value));
+        v.insert("bodies", Value::Array(self.objects.as_slice().iter().map(RigidBody::to_value).collect()));
+        v.insert("springs", Value::Array(springs));
+        v.insert("joints", Value::Array(joints));
+        v.insert("hit_cooldowns", Value::Array(hit_cooldowns));
+        v.insert("emitters", Value::Array(self.particles.emitters().iter().map(|e| e.to_value()).collect()));
+        v
+    }
+
+    /// Restore a state written by save_state. The world is unchanged if bytes are invalid.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), SerializeError> {
+        let state = WorldState::from_bytes(bytes)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    /// Capture the simulation state, with the force generators, pair flags, solver config,
+    /// features and bounds. Left out are the closures set from Rust, the broadphase and
+    /// narrowphase settings, stats and saved keyframes, which restore keeps as they are.
+    pub fn snapshot(&self) -> WorldState {
+        WorldState {
+            objects: self.objects.clone(),
+            settings: self.settings,
+            time: self.time,
+            tick: self.tick,
+            schedule: self.schedule.clone(),
+            springs: self.springs.clone(),
+            joints: self.joints.clone(),
+            particles: self.particles.clone(),
+            contacts: self.contacts.contacts().to_vec(),
+            hit_filter: self.hit_filter.clone(),
+            rng: self.rng.clone(),
+            pairs: self.hooks.pair_flags().clone(),
+            solver: self.solver,
+            features: self.features,
+            bounds: self.bounds,
+            force_generators: Some(self.force_generators.clone()),
+        }
+    }
+
+    /// Replace the simulation state with a previously captured one
+    pub fn restore(&mut self, state: &WorldState) {
+        self.objects = state.objects.clone();
+        self.set_settings(state.settings);
+        self.time = state.time;
+        self.tick = state.tick;
+        self.schedule = state.schedule.clone();
+        self.springs = state.springs.clone();
+        self.joints = state.joints.clone();
+        self.particles = state.particles.clone();
+        self.contacts.set_contacts(state.contacts.clone());
+        self.hit_filter = state.hit_filter.clone();
+        self.rng = state.rng.clone();
+        self.hooks.set_pair_flags(state.pairs.clone());
+        self.solver = state.solver;
+        self.features = state.features;
+        self.bounds = state.bounds;
+        if let Some(generators) = &state.force_generators {
+            self.force_generators = generators.clone();
+        }
+        self.contact_events.clear();
+        self.joint_events.clear();
+        self.bounds_events.clear();
+        self.rebuild_broadphase();
+    }
+
+    pub fn saved_states(&self) -> &SavedStates {
+        &self.saved_states
+    }
+
+    /// Step forward until to_tick, saving a keyframe before each step.
+    /// inputs is called with the tick about to be simulated so it can reapply player commands.
+    pub fn resimulate<F: FnMut(&mut World, u64)>(&mut self, to_tick: u64, dt: f64, mut inputs: F) {
+        while self.tick < to_tick {
+            self.save_keyframe();
+            let tick = self.tick;
+            inputs(self, tick);
+            self.step(dt);
+        }
+    }
+
+    pub fn springs(&self) -> &[Spring] {
+        &self.springs
+    }
+
+    pub fn springs_mut(&mut self) -> &mut [Spring] {
+        &mut self.springs
+    }
+
+    /// Apply a generator's force to every dynamic body each step, returning its index
+    pub fn add_force_generator<G: ForceGenerator + 'static>(&mut self, generator: G) -> usize {
+        self.force_generators.push(Box::new(generator));
+        self.force_generators.len() - 1
+    }
+
+    pub fn force_generators(&self) -> &[Box<dyn ForceGenerator>] {
+        &self.force_generators
+    }
+
+    /// Call force each time forces are gathered, with the time and all bodies, to add forces
+    /// depending on several bodies at once. RK4 calls it at intermediate states too.
+    pub fn set_force_callback<F: Fn(f64, &[RigidBody], &mut [(Vec2, f64)]) + 'static>(&mut self, force: F) {
+        self.force_callback = Some(ForceCallback(Rc::new(force)));
+    }
+
+    pub fn clear_force_callback(&mut self) {
+        self.force_callback = None;
+    }
+
+    /// Add a joint between two different bodies, returning its index
+    pub fn add_joint(&mut self, joint: Joint) -> Result<usize, WorldError> {
+        if joint.body_a() == joint.body_b() {
+            return Err(WorldError::SelfJoint(joint.body_a()));
+        }
+        self.joints.push(joint);
+        Ok(self.joints.len() - 1)
+    }
+
+    /// Add a revolute joint, returning its index
+    pub fn add_revolute_joint(&mut self, joint: RevoluteJoint) -> Result<usize, WorldError> {
+        self.add_joint(joint.into())
+    }
+
+    /// Add a joint holding the relative rotation of two bodies, returning its index
+    pub fn add_angle_joint(&mut self, joint: AngleJoint) -> Result<usize, WorldError> {
+        self.add_joint(joint.into())
+    }
+
+    /// Add a joint letting a body slide along an axis of another, returning its index
+    pub fn add_prismatic_joint(&mut self, joint: PrismaticJoint) -> Result<usize, WorldError> {
+        self.add_joint(joint.into())
+    }
+
+    /// Add a joint dragging a body towards a target point, returning its index
+    pub fn add_mouse_joint(&mut self, joint: MouseJoint) -> Result<usize, WorldError> {
+        self.add_joint(joint.into())
+    }
+
+    /// Remove the joint at index. Later joints move down by one.
+    pub fn remove_joint(&mut self, index: usize) -> Joint {
+        self.joints.remove(index)
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    pub fn joints_mut(&mut self) -> &mut [Joint] {
+        &mut self.joints
+    }
+
+    /// Joints that reached or left a limit or whose motor stalled or recovered during the last step
+    pub fn joint_events(&self) -> &[JointEvent] {
+        &self.joint_events
+    }
+
+    /// Bodies that left the bounds during the last step. Removed ones are no longer in the world.
+    pub fn left_bounds(&self) -> &[BodyHandle] {
+        &self.bounds_events
+    }
+
+    /// The k bodies closest to point with their distances, nearest first.
+    /// Distances are measured to the body shapes and are 0 for bodies containing point.
+    /// Bodies for which filter returns false are skipped.
+    pub fn k_nearest<F: FnMut(BodyHandle, &RigidBody) -> bool>(
+        &self,
+        point: &Vec2,
+        k: usize,
+        mut filter: F,
+    ) -> Vec<(BodyHandle, f64)> {
+        self.broadphase
+            .nearest(point, k, &mut |index| {
+                let body = &self.objects.as_slice()[index];
+                if !filter(self.objects.handle(index), body) {
+                    return None;
+                }
+                Some(narrowphase::point_distance(body.shape(), &body.transform(), point))
+            })
+            .into_iter()
+            .map(|(index, distance)| (self.objects.handle(index), distance))
+            .collect()
+    }
+
+    /// Bodies whose shapes overlap the rectangle with corners min and max, in slot order
+    pub fn query_aabb(&self, min: Vec2, max: Vec2) -> Vec<BodyHandle> {
+        let (lo, hi) = (Vec2::new(min.x.min(max.x), min.y.min(max.y)), Vec2::new(min.x.max(max.x), min.y.max(max.y)));
+        // A box with no width or height, like a click without a drag, is a segment or a point
+        let rect = if lo.x < hi.x && lo.y < hi.y {
+            Shapes::Polygon(Polygon::new(&[lo, Vec2::new(hi.x, lo.y), hi, Vec2::new(lo.x, hi.y)]))
+        } else {
+            Shapes::Segment(Segment::new(lo, hi))
+        };
+        let identity = Transform2D::identity();
+        let mut handles: Vec<BodyHandle> = self
+            .broadphase
+            .query(&Aabb::new(lo, hi))
+            .into_iter()
+            .filter(|&index| {
+                let body = &self.objects.as_slice()[index];
+                narrowphase::overlaps(body.shape(), &body.transform(), &rect, &identity)
+            })
+            .map(|index| self.objects.handle(index))
+            .collect();
+        handles.sort_unstable();
+        handles
+    }
+
+    /// Like query_aabb, but sorted and paged by options
+    pub fn query_aabb_with(&self, min: Vec2, max: Vec2, options: &QueryOptions) -> QueryPage<BodyHandle> {
+        options.page(self.query_aabb(min, max), |&handle| {
+            let body = self.objects.get(handle).unwrap();
+            narrowphase::point_distance(body.shape(), &body.transform(), &options.origin)
+        })
+    }
+
+    /// First body touched by shape moving from transform by translation, with where it was hit.
+    /// Bodies for which filter returns false are skipped, like the body the shape belongs to.
+    pub fn shape_cast<F: FnMut(BodyHandle, &RigidBody) -> bool>(
+        &self,
+        shape: &Shapes,
+        transform: &Transform2D,
+        translation: &Vec2,
+        mut filter: F,
+    ) -> Option<(BodyHandle, CastHit)> {
+        let end = Transform2D::new(transform.position + translation, transform.rotation);
+        let swept = narrowphase::bounds(shape, transform).union(&narrowphase::bounds(shape, &end));
+        let mut first: Option<(BodyHandle, CastHit)> = None;
+        for index in self.broadphase.query(&swept) {
+            let (handle, body) = (self.objects.handle(index), &self.objects.as_slice()[index]);
+            if !filter(handle, body) {
+                continue;
+            }
+            if let Some(hit) = cast::shape_cast(shape, transform, translation, 0.0, body.shape(), &body.transform()) {
+                if first.is_none_or(|(_, f)| hit.fraction < f.fraction) {
+                    first = Some((handle, hit));
+                }
+            }
+        }
+        first
+    }
+
+    /// Closest body hit by ray within max_distance, with where it was hit.
+    /// Bodies for which filter returns false are skipped, like friendly bodies for a laser sight.
+    pub fn raycast<F: FnMut(BodyHandle, &RigidBody) -> bool>(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        filter: F,
+    ) -> Option<(BodyHandle, RayHit)> {
+        self.raycast_all(ray, max_distance, filter).into_iter().next()
+    }
+
+    /// Every body hit by ray within max_distance, nearest first, with where each was hit.
+    /// Bodies the ray starts inside are hit at distance 0.
+    pub fn raycast_all<F: FnMut(BodyHandle, &RigidBody) -> bool>(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        mut filter: F,
+    ) -> Vec<(BodyHandle, RayHit)> {
+        let mut hits: Vec<(BodyHandle, RayHit)> = self
+            .broadphase
+            .query(&ray.aabb(max_distance))
+            .into_iter()
+            .filter_map(|index| {
+                let (handle, body) = (self.objects.handle(index), &self.objects.as_slice()[index]);
+                if !filter(handle, body) {
+                    return None;
+                }
+                Some((handle, cast::raycast(ray, max_distance, body.shape(), &body.transform())?))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.1.distance.total_cmp(&b.1.distance).then(a.0.cmp(&b.0)));
+        hits
+    }
+
+    /// Touching child shape pairs found in the last step
+    pub fn contacts(&self) -> &[Contact] {
+        self.contacts.contacts()
+    }
+
+    /// Contacts that began or ended during the last step
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        &self.contact_events
+    }
+
+    /// Stats of the last steps, see set_stats_history_capacity
+    pub fn stats_history(&self) -> &StatsHistory {
+        &self.stats_history
+    }
+
+    pub fn particles(&self) -> &ParticleSystem {
+        &self.particles
+    }
+
+    pub fn particles_mut(&mut self) -> &mut ParticleSystem {
+        &mut self.particles
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    fn rebuild_broadphase(&mut self) {
+        let broadphase = &mut *self.broadphase;
+        broadphase.clear();
+        self.proxies = self
+            .objects
+            .as_slice()
+            .iter()
+            .enumerate()
+            .map(|(index, body)| broadphase.insert(narrowphase::bounds(body.shape(), &body.transform()), index))
+            .collect();
+    }
+
+    /// Dynamic bodies inside the bounds, empty without bounds
+    fn bodies_in_bounds(&self) -> Vec<BodyHandle> {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+        self.query(|body| !body.is_static() && bounds.aabb.contains_point(&body.position()))
+    }
+
+    /// Apply the bounds policy to the bodies of inside that are now out of bounds
+    fn enforce_bounds(&mut self, inside: &[BodyHandle]) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        for &handle in inside {
+            let body = match self.objects.get_mut(handle) {
+                Some(body) if !bounds.aabb.contains_point(&body.position()) => body,
+                _ => continue,
+            };
+            match bounds.policy {
+                BoundsPolicy::Remove => {
+                    self.remove_body(handle).unwrap();
+                }
+                BoundsPolicy::Freeze => body.freeze(),
+                BoundsPolicy::Wrap => body.set_position(bounds.wrap(&body.position())),
+                BoundsPolicy::Report => {}
+            }
+            self.bounds_events.push(handle);
+        }
+    }
+
+    /// Force and torque on each body from scheduled forces, springs, force generators and the
+    /// force callback, h into a substep of dt starting at t. Scheduled forces are taken at t, as
+    /// steps are split where they start and end.
+    fn forces(&self, t: f64, h: f64, dt: f64) -> Vec<(Vec2, f64)> {
+        let mut forces = vec![(Vec2::new(0.0, 0.0), 0.0); self.objects.len()];
+        for f in self.schedule.active_forces(t) {
+            if let Some(i) = self.objects.index_of(f.body) {
+                forces[i].0 = forces[i].0 + f.force;
+            }
+        }
+
+        for spring in &self.springs {
+            let (ia, ib) = match (self.objects.index_of(spring.body_a()), self.objects.index_of(spring.body_b())) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            let bodies = self.objects.as_slice();
+            let (f, ra, rb) = spring.force(&bodies[ia], &bodies[ib]);
+            let fa = &mut forces[ia];
+            fa.0 = fa.0 + f;
+            fa.1 += Vec2::cross(&ra, &f);
+            let fb = &mut forces[ib];
+            fb.0 = fb.0 - f;
+            fb.1 -= Vec2::cross(&rb, &f);
+        }
+
+        for generator in &self.force_generators {
+            for (body, (force, torque)) in self.objects.as_slice().iter().zip(&mut forces) {
+                if !body.is_static() {
+                    let (f, t) = generator.force(body, &self.settings.gravity, dt);
+                    *force = *force + f;
+                    *torque += t;
+                }
+            }
+        }
+        if let Some(ForceCallback(callback)) = &self.force_callback {
+            callback(t + h, self.objects.as_slice(), &mut forces);
+        }
+        forces
+    }
+
+    /// Move the bodies on by dt with fourth order Runge-Kutta, sampling forces at the start, twice
+    /// halfway and at the end
+    fn integrate_rk4(&mut self, t: f64, dt: f64) {
+        let gravity = self.settings.gravity;
+        let zero = (Vec2::new(0.0, 0.0), 0.0, Vec2::new(0.0, 0.0), 0.0);
+        let start: Vec<(Vec2, f64, Vec2, f64)> = self
+            .objects
+            .as_slice()
+            .iter()
+            .map(|b| (b.position(), b.rotation(), b.velocity(), b.angular_velocity()))
+            .collect();
+        // Rates of change of position, rotation, velocity and angular velocity
+        let mut rates = vec![zero; start.len()];
+        let mut sums = vec![zero; start.len()];
+        for &(offset, weight) in &[(0.0, 1.0), (0.5, 2.0), (0.5, 2.0), (1.0, 1.0)] {
+            let h = offset * dt;
+            for ((body, &(x, r, v, w)), &(dx, dr, dv, dw)) in self.objects.as_mut_slice().iter_mut().zip(&start).zip(&rates) {
+                if !body.is_static() {
+                    body.set_position(x + dx * h);
+                    body.set_rotation(r + dr * h);
+                    body.set_velocity(v + dv * h);
+                    body.set_angular_velocity(w + dw * h);
+                }
+            }
+            let forces = self.forces(t, h, dt);
+            for ((body, (force, torque)), (rate, sum)) in
+                self.objects.as_slice().iter().zip(forces).zip(rates.iter_mut().zip(&mut sums))
+            {
+                let (a, alpha) = body.accelerations(&gravity, &force, torque);
+                *rate = (body.velocity(), body.angular_velocity(), a, alpha);
+                *sum = (sum.0 + rate.0 * weight, sum.1 + rate.1 * weight, sum.2 + rate.2 * weight, sum.3 + rate.3 * weight);
+            }
+        }
+        for ((body, &(x, r, v, w)), &(dx, dr, dv, dw)) in self.objects.as_mut_slice().iter_mut().zip(&start).zip(&sums) {
+            if !body.is_static() {
+                body.set_position(x + dx * (dt / 6.0));
+                body.set_rotation(r + dr * (dt / 6.0));
+                body.set_velocity(v + dv * (dt / 6.0));
+                body.set_angular_velocity(w + dw * (dt / 6.0));
+                body.set_acceleration(dv / 6.0);
+            }
+        }
+    }
+
+    /// Integrate all bodies over dt starting at time t
+    fn integrate(&mut self, t: f64, dt: f64) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        let timer = Timer::start();
+        let gravity = self.settings.gravity;
+        match self.solver.integrator {
+            Integrator::VelocityVerlet => {
+                let forces = self.forces(t, 0.0, dt);
+                for (body, (force, torque)) in self.objects.as_mut_slice().iter_mut().zip(forces) {
+                    body.verlet_drift(&gravity, &force, torque, dt);
+                }
+                let forces = self.forces(t, dt, dt);
+                for (body, (force, torque)) in self.objects.as_mut_slice().iter_mut().zip(forces) {
+                    body.verlet_kick(&gravity, &force, torque, dt);
+                }
+            }
+            Integrator::Rk4 => self.integrate_rk4(t, dt),
+            Integrator::Xpbd => {
+                let forces = self.forces(t, 0.0, dt);
+                for (body, (force, torque)) in self.objects.as_mut_slice().iter_mut().zip(forces) {
+                    body.integrate(&gravity, &force, torque, dt);
+                }
+            }
+            // Positions are integrated by the solver once contacts have taken out the velocity
+            // pushing into them
+            Integrator::SemiImplicitEuler => {
+                let forces = self.forces(t, 0.0, dt);
+                for (body, (force, torque)) in self.objects.as_mut_slice().iter_mut().zip(forces) {
+                    body.integrate_velocity(&gravity, &force, torque, dt);
+                }
+            }
+        }
+        self.force_generators.retain_mut(|g| g.advance(dt));
+        timer.stop(&mut self.stats.integrate_ms);
+
+        let timer = Timer::start();
+        self.update_broadphase();
+        let mut pairs = self.broadphase.pairs();
+        if self.hooks.filters_pairs() {
+            let bodies = &self.objects;
+            let body = |i| (bodies.handle(i), &bodies.as_slice()[i]);
+            pairs.retain(|&(i, j)| self.hooks.should_collide(body(i), body(j)));
+        }
+        timer.stop(&mut self.stats.broadphase_ms);
+
+        let timer = Timer::start();
+        let tests = self.contacts.update(&self.objects, &pairs, &self.narrowphase, self.solver.slop);
+        timer.stop(&mut self.stats.narrowphase_ms);
+
+        let timer = Timer::start();
+        let settings: Vec<ContactSettings> = self
+            .contacts
+            .contacts()
+            .iter()
+            .map(|c| self.hooks.settings(c, self.objects.get(c.body_a).unwrap(), self.objects.get(c.body_b).unwrap()))
+            .collect();
+        let contacts = self.contacts.contacts_mut();
+        let config = self.features.apply(&self.solver);
+        let events = if config.integrator == Integrator::SemiImplicitEuler {
+            constraints::solve_and_integrate(&mut self.objects, contacts, &settings, &mut self.joints, &config, dt)
+        } else {
+            constraints::solve_with(&mut self.objects, contacts, &settings, &mut self.joints, &config, dt)
+        };
+        self.joint_events.extend(events);
+        self.hooks.post_solve(self.contacts.contacts());
+        timer.stop(&mut self.stats.solver_ms);
+
+        let stats = &mut self.stats;
+        stats.substeps += 1;
+        stats.broadphase_pairs += pairs.len();
+        stats.narrowphase_tests += tests;
+        stats.contacts = self.contacts.contacts().len();
+        stats.velocity_iterations += config.velocity_iterations;
+        stats.position_iterations += config.position_iterations;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::broadphase::BroadphaseKind;
+    use super::super::fixture::{CollisionFilter, Fixture};
+    use super::super::material::{CombineRule, Material};
+    use crate::geometry::{Circle, Compound, Shapes};
+    use super::super::contact::{ContactEventKind, SLOP};
+    use super::super::joint::JointEventKind;
+    use super::super::particles::ParticleCollision;
+    use super::super::solver::SolverBackend;
+    use super::super::stats::StatsMetric;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.set_gravity(Vec2::new(0.0, 0.0));
+        world
+    }
+
+    #[test]
+    fn test_step() {
+        let mut world = World::new();
+        let handle = world.create_body(RigidBody::new(1.0));
+        world.step(1.0);
+
+        assert_eq!(world.time(), 1.0);
+        assert_eq!(world.body(handle).unwrap().velocity(), Vec2::new(0.0, -9.81));
+    }
+
+    #[test]
+    fn test_schedule_impulse_mid_step() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(2.0));
+        world.schedule_impulse(handle, 0.25, Vec2::new(2.0, 0.0)).unwrap();
+
+        world.step(1.0);
+        let body = world.body(handle).unwrap();
+        assert_eq!(body.velocity(), Vec2::new(1.0, 0.0));
+        assert_eq!(body.position(), Vec2::new(0.75, 0.0));
+        assert!(world.schedule().is_empty());
+    }
+
+    #[test]
+    fn test_schedule_impulse_future_step() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(1.0));
+        world.schedule_impulse(handle, 1.5, Vec2::new(1.0, 0.0)).unwrap();
+
+        world.step(1.0);
+        assert_eq!(world.body(handle).unwrap().velocity(), Vec2::new(0.0, 0.0));
+        world.step(1.0);
+        assert_eq!(world.body(handle).unwrap().position(), Vec2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn test_schedule_rejects_non_finite_time() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(1.0));
+        assert!(matches!(
+            world.schedule_impulse(handle, f64::NAN, Vec2::new(1.0, 0.0)),
+            Err(WorldError::InvalidTime(_))
+        ));
+        world.schedule_impulse(handle, 0.01, Vec2::new(1.0, 0.0)).unwrap();
+
+        world.step(0.02);
+        assert!(world.schedule().is_empty());
+        assert_eq!(world.body(handle).unwrap().velocity().x, 1.0);
+    }
+
+    #[test]
+    fn test_step_ignores_non_finite_dt() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(1.0));
+        world.step(f64::INFINITY);
+        world.step(f64::NAN);
+        assert_eq!(world.time(), 0.0);
+        assert_eq!(world.body(handle).unwrap().position(), Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_spring_pulls_bodies_together() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::new(1.0));
+        let mut body = RigidBody::new(1.0);
+        body.set_position(Vec2::new(2.0, 0.0));
+        let b = world.create_body(body);
+        world.add_spring(Spring::new(a, b, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0), 1.0, 0.0, 1.0));
+
+        world.step(0.1);
+        assert!(world.body(a).unwrap().velocity().x > 0.0);
+        assert!(world.body(b).unwrap().velocity().x < 0.0);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut world = World::new();
+        let handle = world.create_body(RigidBody::new(1.0));
+        let state = world.snapshot();
+        world.step(1.0);
+
+        world.restore(&state);
+        assert_eq!(world.tick(), 0);
+        assert_eq!(world.body(handle).unwrap().velocity(), Vec2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_generators_and_config() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        let mut body = RigidBody::with_circle(1.0, 0.5);
+        body.set_position(Vec2::new(2.0, 0.0));
+        let b = world.create_body(body);
+        world.add_force_generator(Explosion::new(Vec2::new(1.0, -1.0), 5.0, 4.0));
+        world.set_pair_enabled(a, b, false).unwrap();
+        let mut config = world.solver_config();
+        config.substeps = 3;
+        world.set_solver_config(config);
+        let state = world.snapshot();
+        let bytes = world.save_state();
+
+        // The explosion fires once and removes itself, then everything else is changed
+        world.step(1.0 / 60.0);
+        let after_step = world.snapshot();
+        assert_eq!(world.force_generator_count(), 0);
+        world.set_pair_enabled(a, b, true).unwrap();
+        world.set_solver_config(SolverConfig::new());
+        let mut features = WorldFeatures::new();
+        features.enable_friction = false;
+        world.set_features(features);
+        world.set_bounds(WorldBounds::new(Aabb::new(Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)), BoundsPolicy::Remove));
+
+        // Replaying from the snapshot fires the explosion again
+        world.restore(&state);
+        assert_eq!(world.force_generator_count(), 1);
+        assert_eq!(world.solver_config().substeps, 3);
+        assert_eq!(world.features(), WorldFeatures::new());
+        assert_eq!(world.bounds(), None);
+        assert!(!world.hooks.should_collide((a, world.body(a).unwrap()), (b, world.body(b).unwrap())));
+        world.step(1.0 / 60.0);
+        assert_eq!(world.snapshot(), after_step);
+        assert!(world.body(a).unwrap().velocity().len() > 0.0);
+
+        // Bytes can't hold the generators, so loading them keeps the world's own
+        world.add_force_generator(Wind::new(Vec2::new(1.0, 0.0), 0.1));
+        world.set_pair_enabled(a, b, true).unwrap();
+        world.load_state(&bytes).unwrap();
+        assert_eq!(world.snapshot(), state);
+        assert_eq!(world.force_generator_count(), 1);
+        assert!(format!("{:?}", world.force_generators()[0]).starts_with("Wind"));
+    }
+
+    #[test]
+    fn test_rewind_and_resimulate() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(1.0));
+        let push = |world: &mut World, tick: u64| {
+            if tick == 1 {
+                world.body_mut(handle).unwrap().apply_impulse(&Vec2::new(1.0, 0.0));
+            }
+        };
+
+        world.resimulate(3, 1.0, |_, _| {});
+        assert_eq!(world.saved_states().len(), 3);
+        assert_eq!(world.body(handle).unwrap().position(), Vec2::new(0.0, 0.0));
+
+        // A late input for tick 1 arrives: rewind and replay with it
+        assert!(world.rewind_to(1));
+        assert_eq!(world.saved_states().newest_tick(), Some(1));
+        world.resimulate(3, 1.0, push);
+        assert_eq!(world.tick(), 3);
+        assert_eq!(world.body(handle).unwrap().position(), Vec2::new(2.0, 0.0));
+        assert!(!world.rewind_to(10));
+    }
+
+    #[test]
+    fn test_keyframe_capacity() {
+        let mut world = world();
+        world.set_keyframe_capacity(2);
+        world.resimulate(5, 1.0, |_, _| {});
+
+        assert_eq!(world.saved_states().oldest_tick(), Some(3));
+        assert!(!world.rewind_to(1));
+    }
+
+    #[test]
+    fn test_schedule_force() {
+        let mut world = world();
+        let handle = world.create_body(RigidBody::new(1.0));
+        world.schedule_force(handle, 0.5, 1.0, Vec2::new(1.0, 0.0)).unwrap();
+
+        world.step(1.0);
+        assert_eq!(world.body(handle).unwrap().velocity(), Vec2::new(0.5, 0.0));
+        world.step(1.0);
+        assert_eq!(world.body(handle).unwrap().velocity(), Vec2::new(1.0, 0.0));
+        assert!(world.schedule().is_empty());
+    }
+
+    #[test]
+    fn test_emitter_in_world() {
+        let mut world = World::new();
+        let index = world.add_emitter(Emitter::new(Vec2::new(0.0, 0.0), 2.0, 10.0));
+        world.step(1.0);
+        assert_eq!(world.particle_count(), 2);
+        assert_eq!(world.particle_positions().len(), 4);
+
+        let mut emitter = world.emitter(index).unwrap();
+        emitter.enabled = false;
+        world.set_emitter(index, emitter);
+        let state = world.snapshot();
+        world.step(1.0);
+        assert_eq!(world.particle_count(), 2);
+        assert!(world.particle_positions()[1] < 0.0);
+
+        world.restore(&state);
+        assert_eq!(world.particle_positions(), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_k_nearest() {
+        let mut world = world();
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let mut body = RigidBody::new(1.0);
+            body.set_shape(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)));
+            body.set_position(Vec2::new(i as f64 * 2.0, 0.0));
+            handles.push(world.create_body(body));
+        }
+        // A large body whose center is far away but whose surface is close
+        let mut wall = RigidBody::new(0.0);
+        wall.set_shape(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 9.0)));
+        wall.set_position(Vec2::new(10.0, 10.0));
+        let wall = world.create_body(wall);
+
+        let nearest = world.k_nearest(&Vec2::new(10.0, 0.5), 3, |_, _| true);
+        assert_eq!(nearest[0].0, handles[5]);
+        assert_eq!(nearest[1].0, wall);
+        assert!((nearest[1].1 - 0.5).abs() < 1e-12);
+
+        let dynamic = world.k_nearest(&Vec2::new(10.0, 0.5), 2, |_, b| b.mass() > 0.0);
+        assert!(dynamic.iter().all(|(h, _)| *h != wall));
+        assert_eq!(world.wasm_k_nearest(Vec2::new(-3.0, 0.0), 1), vec![handles[0]]);
+    }
+
+    #[test]
+    fn test_k_nearest_follows_bodies() {
+        let mut world = World::new();
+        let a = world.create_body(RigidBody::new(1.0));
+        let mut body = RigidBody::new(0.0);
+        body.set_position(Vec2::new(0.0, -10.0));
+        let b = world.create_body(body);
+
+        // a falls past b
+        for _ in 0..20 {
+            world.step(0.1);
+        }
+        assert_eq!(world.wasm_k_nearest(Vec2::new(0.0, -20.0), 1), vec![a]);
+
+        world.body_mut(a).unwrap().set_position(Vec2::new(0.0, 0.0));
+        world.update_broadphase();
+        assert_eq!(world.wasm_k_nearest(Vec2::new(0.0, -20.0), 1), vec![b]);
+    }
+
+    fn ground(world: &mut World) -> BodyHandle {
+        let mut ground = RigidBody::with_polygon(0.0, &[-10.0, -1.0, 10.0, -1.0, 10.0, 0.0, -10.0, 0.0]);
+        ground.set_position(Vec2::new(0.0, 0.0));
+        world.create_body(ground)
+    }
+
+    /// Highest body h gets once it starts moving up, over steps of 1/60 s
+    fn bounce_height(world: &mut World, h: BodyHandle, steps: usize) -> f64 {
+        let mut highest: f64 = 0.0;
+        let mut bounced = false;
+        for _ in 0..steps {
+            world.step(1.0 / 60.0);
+            let body = world.body(h).unwrap();
+            bounced |= body.velocity().y > 0.0;
+            if bounced {
+                highest = highest.max(body.position().y);
+            }
+        }
+        highest
+    }
+
+    #[test]
+    fn test_material_bounce() {
+        let bounce = |restitution, combine| {
+            let mut world = World::new();
+            ground(&mut world);
+            let mut material = Material::new();
+            material.restitution = restitution;
+            material.restitution_combine = combine;
+            let mut ball = RigidBody::with_material(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)), material);
+            ball.set_position(Vec2::new(0.0, 3.0));
+            let ball = world.create_body(ball);
+            bounce_height(&mut world, ball, 120)
+        };
+
+        // The ground's default material averages the restitution down, unless the ball insists
+        assert!(bounce(0.0, CombineRule::Average) < 0.6);
+        let averaged = bounce(0.9, CombineRule::Average);
+        let kept = bounce(0.9, CombineRule::Max);
+        assert!(averaged > 0.7 && kept > averaged + 1.0);
+    }
+
+    #[test]
+    fn test_fixtures() {
+        // A ball whose mask leaves out the ground's category falls through it
+        let mut world = World::new();
+        ground(&mut world);
+        let mut ghost = RigidBody::with_circle(1.0, 0.5);
+        ghost.set_position(Vec2::new(0.0, 1.0));
+        ghost.set_filter(CollisionFilter {
+            mask: !1,
+            ..CollisionFilter::new()
+        });
+        let ghost = world.create_body(ghost);
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.body(ghost).unwrap().position().y < -2.0);
+        assert!(world.contacts().is_empty());
+
+        // Only the outer fixture touches the ground, so its bounce is used rather than the body's
+        let mut world = World::new();
+        ground(&mut world);
+        let mut bouncy = Material::new();
+        bouncy.restitution = 0.9;
+        bouncy.restitution_combine = CombineRule::Max;
+        let core = Fixture::new(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.1)), Material::new());
+        let shell = Fixture::new(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)), bouncy);
+        let mut ball = RigidBody::with_fixtures(&[core, shell]);
+        ball.set_position(Vec2::new(0.0, 3.0));
+        let ball = world.create_body(ball);
+        assert!(bounce_height(&mut world, ball, 120) > 2.0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let run = |policy| {
+            let mut world = World::new();
+            world.set_bounds(WorldBounds::new(Aabb::new(Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0)), policy));
+            let resting = world.create_body(RigidBody::new(0.0));
+            let mut ball = RigidBody::with_circle(1.0, 0.5);
+            ball.set_position(Vec2::new(3.0, 0.0));
+            let ball = world.create_body(ball);
+            let mut left = false;
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+                if !world.left_bounds().is_empty() {
+                    assert_eq!(world.left_bounds(), &[ball]);
+                    left = true;
+                    break;
+                }
+            }
+            assert!(left && world.contains_body(resting));
+            (world, ball)
+        };
+
+        let (world, ball) = run(BoundsPolicy::Remove);
+        assert!(!world.contains_body(ball));
+        let (mut world, ball) = run(BoundsPolicy::Freeze);
+        let frozen = world.body(ball).unwrap().position();
+        assert!(world.body(ball).unwrap().is_static());
+        world.step(1.0 / 60.0);
+        assert_eq!(world.body(ball).unwrap().position(), frozen);
+        assert!(world.left_bounds().is_empty());
+        let (world, ball) = run(BoundsPolicy::Wrap);
+        assert!(world.body(ball).unwrap().position().y > 4.0);
+        assert!(world.body(ball).unwrap().velocity().y < 0.0);
+
+        // Reported once when it leaves, then left to fall
+        let (mut world, ball) = run(BoundsPolicy::Report);
+        world.step(1.0 / 60.0);
+        assert!(world.left_bounds().is_empty());
+        world.clear_bounds();
+        assert!(world.body(ball).unwrap().position().y < -5.0);
+    }
+
+    #[test]
+    fn test_force_generators() {
+        let mut space = world();
+        space.add_force_generator(Attractor::new(Vec2::new(0.0, 0.0), 10.0));
+        let mut moon = RigidBody::with_circle(2.0, 0.1);
+        moon.set_position(Vec2::new(1.0, 0.0));
+        // Speed of a circular orbit at radius 1
+        moon.set_velocity(Vec2::new(0.0, 10.0_f64.sqrt()));
+        let moon = space.create_body(moon);
+        for _ in 0..600 {
+            space.step(1.0 / 60.0);
+        }
+        assert!((space.body(moon).unwrap().position().len() - 1.0).abs() < 0.05);
+
+        // A blast only pushes once, and wind carries a body along at its speed
+        let mut world = world();
+        let ball = world.create_body(RigidBody::with_circle(1.0, 0.1));
+        world.add_force_generator(Explosion::new(Vec2::new(-1.0, 0.0), 4.0, 2.0));
+        world.step(1.0 / 60.0);
+        assert_eq!(world.force_generator_count(), 0);
+        assert!((world.body(ball).unwrap().velocity() - Vec2::new(2.0, 0.0)).len() < 1e-9);
+        world.step(1.0 / 60.0);
+        assert!((world.body(ball).unwrap().velocity() - Vec2::new(2.0, 0.0)).len() < 1e-9);
+        world.add_force_generator(Wind::new(Vec2::new(0.0, 3.0), 1.0));
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.body(ball).unwrap().velocity() - Vec2::new(0.0, 3.0)).len() < 1e-3);
+    }
+
+    #[test]
+    fn test_buoyancy() {
+        // A box half as dense as the water settles half under
+        let mut world = World::new();
+        let mut water = Buoyancy::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0), 2.0);
+        water.linear_drag = 6.0;
+        world.add_force_generator(water);
+        let mut material = Material::new();
+        material.density = 1.0;
+        let mut raft = RigidBody::with_material(Shapes::Polygon(Polygon::rect(4.0, 1.0)), material);
+        raft.set_position(Vec2::new(0.0, 2.0));
+        let raft = world.create_body(raft);
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(raft).unwrap();
+        assert!(body.position().y.abs() < 0.02);
+        assert!(body.velocity().len() < 0.01 && body.rotation().abs() < 0.01);
+    }
+
+    #[test]
+    fn test_off_center_shape_spins_about_its_center() {
+        // A square given away from the origin still turns about its own middle
+        let mut world = World::new();
+        world.set_gravity(Vec2::new(0.0, 0.0));
+        let mut square = RigidBody::with_polygon(1.0, &[0.0, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0, 2.0]);
+        square.set_angular_velocity(3.0);
+        let square = world.create_body(square);
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(square).unwrap();
+        assert!((body.transform().apply(&body.shape_center()) - Vec2::new(1.0, 1.0)).len() < 1e-9);
+        assert!(body.velocity().len() < 1e-9);
+    }
+
+    #[test]
+    fn test_conveyor() {
+        // A belt running right carries a box along while staying put itself
+        let mut world = World::new();
+        let mut belt = Material::new();
+        belt.surface_velocity = 2.0;
+        belt.density = 0.0;
+        let conveyor = world.create_body(RigidBody::with_material(Shapes::Polygon(Polygon::rect(20.0, 1.0)), belt));
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        block.set_position(Vec2::new(0.0, 1.0));
+        let block = world.create_body(block);
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        // The contact points keep up with the belt, the box itself lags a little from rocking on them
+        assert!((world.body(block).unwrap().velocity().x - 2.0).abs() < 0.1);
+        assert_eq!(world.body(conveyor).unwrap().position(), Vec2::new(0.0, 0.0));
+
+        // Underneath a body the belt runs left, driving it right along the ground like a tank track
+        let mut world = World::new();
+        ground(&mut world);
+        belt.density = 1.0;
+        let mut treadmill = RigidBody::with_material(Shapes::Polygon(Polygon::rect(1.0, 1.0)), belt);
+        treadmill.set_position(Vec2::new(0.0, 0.5));
+        let treadmill = world.create_body(treadmill);
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.body(treadmill).unwrap().velocity().x > 0.5);
+    }
+
+    #[test]
+    fn test_contact_hooks() {
+        let drop_ball = |world: &mut World| {
+            let mut ball = RigidBody::with_circle(1.0, 0.5);
+            ball.set_position(Vec2::new(0.0, 1.0));
+            ball.set_user_data(7);
+            world.create_body(ball)
+        };
+
+        // Filtered out by a closure or a pair flag, the ball falls through the ground
+        let mut world = World::new();
+        ground(&mut world);
+        let ball = drop_ball(&mut world);
+        world.set_contact_filter(|a, b| a.user_data() != 7 && b.user_data() != 7);
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.body(ball).unwrap().position().y < 0.0);
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let ball = drop_ball(&mut world);
+        world.set_pair_enabled(ball, floor, false).unwrap();
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.body(ball).unwrap().position().y < 0.0);
+
+        // A pre-solve hook turning the ground into a trampoline, with impulses read after solving
+        let mut world = World::new();
+        ground(&mut world);
+        let ball = drop_ball(&mut world);
+        world.set_pre_solve(|_, settings| settings.restitution = 1.0);
+        let hardest = Rc::new(std::cell::Cell::new(0.0_f64));
+        let seen = hardest.clone();
+        world.set_post_solve(move |c| seen.set(seen.get().max(c.impulse.normal)));
+        assert!(bounce_height(&mut world, ball, 60) > 0.8);
+        assert!(hardest.get() > 0.0);
+
+        // Pair materials do the same for wasm, and disabled contacts push nothing apart
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let ball = drop_ball(&mut world);
+        world.set_pair_material(floor, ball, 0.0, 1.0).unwrap();
+        assert!(bounce_height(&mut world, ball, 60) > 0.8);
+        world.clear_pair_material(floor, ball);
+        let landed = (0..120).any(|_| {
+            world.step(1.0 / 60.0);
+            world.pair_impulse(floor, ball) > 0.0
+        });
+        assert!(landed);
+        assert_eq!(world.pair_impulse(floor, ball), world.pair_impulse(ball, floor));
+        world.set_pre_solve(|_, settings| settings.enabled = false);
+        world.step(1.0 / 60.0);
+        assert!(world.contacts().iter().all(|c| c.impulse.normal == 0.0));
+        assert_eq!(world.pair_impulse(floor, ball), 0.0);
+    }
+
+    #[test]
+    fn test_body_rests_on_ground() {
+        let mut world = World::new();
+        ground(&mut world);
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 1.0));
+        let ball = world.create_body(ball);
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(ball).unwrap();
+        assert!((body.position().y - 0.5).abs() < 0.05);
+        assert!(body.velocity().len() < 0.1);
+        assert_eq!(world.contacts().len(), 1);
+    }
+
+    #[test]
+    fn test_box_rests_on_ground() {
+        let mut world = World::new();
+        ground(&mut world);
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        block.set_position(Vec2::new(0.0, 0.5));
+        let block = world.create_body(block);
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+
+        // Held up at both bottom corners, sinking no further than the slop
+        let body = world.body(block).unwrap();
+        assert!((body.position().y - 0.5).abs() < SLOP);
+        assert_eq!(body.rotation(), 0.0);
+        assert_eq!(world.contacts().len(), 2);
+    }
+
+    #[test]
+    fn test_box_spawned_touching_stays_put() {
+        let mut world = World::new();
+        world.create_body(RigidBody::with_polygon(0.0, &[-2.5, -0.5, 2.5, -0.5, 2.5, 0.5, -2.5, 0.5]));
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        // Off center, so the box's bottom edge lies along part of the ground's top edge
+        block.set_position(Vec2::new(1.5, 1.0));
+        let block = world.create_body(block);
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+
+        let body = world.body(block).unwrap();
+        assert!((body.position() - Vec2::new(1.5, 1.0)).len() < SLOP);
+        assert!(body.rotation().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_box_stack_rests() {
+        let mut world = World::new();
+        ground(&mut world);
+        let blocks: Vec<BodyHandle> = (0..20)
+            .map(|i| {
+                let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+                block.set_position(Vec2::new(0.0, 0.5 + i as f64));
+                world.create_body(block)
+            })
+            .collect();
+        for _ in 0..1200 {
+            world.step(1.0 / 60.0);
+        }
+
+        // Each contact keeps about the slop of overlap, a little more under the weight of the
+        // blocks above, and the stack stands straight
+        for (i, &block) in blocks.iter().enumerate() {
+            let body = world.body(block).unwrap();
+            let sunk = 0.5 + i as f64 - body.position().y;
+            assert!(sunk < (i + 1) as f64 * (SLOP + 1.5e-3) + 1e-2, "{} sunk {}", i, sunk);
+            assert!(body.position().x.abs() < 1e-6 && body.rotation().abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_tilted_box_settles_in_place() {
+        let mut world = World::new();
+        ground(&mut world);
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        block.set_position(Vec2::new(0.0, 0.8));
+        block.set_rotation(0.3);
+        let block = world.create_body(block);
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+
+        // Once settled, position correction mustn't walk the box along the ground
+        let settled = world.body(block).unwrap().position();
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(block).unwrap();
+        assert!((body.position().x - settled.x).abs() < 1e-3);
+        assert!(body.angular_velocity().abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_features() {
+        // Distance a box slides after being pushed along the ground
+        let slide = |features| {
+            let mut world = World::new();
+            world.set_features(features);
+            ground(&mut world);
+            let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+            block.set_position(Vec2::new(0.0, 0.5));
+            block.set_velocity(Vec2::new(2.0, 0.0));
+            let block = world.create_body(block);
+            for _ in 0..60 {
+                world.step(1.0 / 60.0);
+            }
+            world.body(block).unwrap().position().x
+        };
+
+        let frictionless = WorldFeatures {
+            enable_friction: false,
+            ..WorldFeatures::new()
+        };
+        assert!(slide(WorldFeatures::new()) < 1.5);
+        assert!((slide(frictionless) - 2.0).abs() < 1e-6);
+        assert!(world().features().enable_warm_start);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut world = World::new();
+        ground(&mut world);
+        for i in 0..3 {
+            let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+            block.set_position(Vec2::new(2.0 * i as f64, 0.45));
+            world.create_body(block);
+        }
+        let mut wall = RigidBody::with_polygon(0.0, &[-0.5, 0.0, 0.5, 0.0, 0.5, 2.0, -0.5, 2.0]);
+        wall.set_position(Vec2::new(8.0, 0.0));
+        world.create_body(wall);
+        assert_eq!(world.stats(), WorldStats::new());
+
+        world.step(1.0 / 60.0);
+        let stats = world.stats();
+        // Each block rests on the ground, and the wall standing on it is never tested
+        assert_eq!((stats.substeps, stats.broadphase_pairs, stats.narrowphase_tests), (1, 4, 3));
+        assert_eq!(stats.contacts, world.contacts().len());
+        assert_eq!(stats.velocity_iterations, world.solver_config().velocity_iterations);
+        assert!(stats.step_ms >= stats.solver_ms + stats.narrowphase_ms && stats.solver_ms > 0.0);
+
+        // A scheduled impulse inside the step splits it in two
+        let block = world.body_handles()[1];
+        let time = world.time() + 0.01;
+        world
+            .schedule_mut()
+            .add_impulse(ScheduledImpulse {
+                body: block,
+                time,
+                impulse: Vec2::new(0.0, 1.0),
+            })
+            .unwrap();
+        world.step(1.0 / 60.0);
+        assert_eq!(world.stats().substeps, 2);
+        assert_eq!(world.stats().broadphase_pairs, 8);
+
+        // Kept for graphing once a history is asked for
+        assert!(world.stats_history().is_empty());
+        world.set_stats_history_capacity(4);
+        for _ in 0..6 {
+            world.step(1.0 / 60.0);
+        }
+        let history = world.wasm_stats_history();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.get(3), Some(world.stats()));
+        assert_eq!(history.values(StatsMetric::Substeps), vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_warm_starting() {
+        // Height of the top of a stack of boxes after it settled
+        let stack_height = |warm_starting| {
+            let mut world = World::new();
+            let floor = ground(&mut world);
+            world.set_solver_config(SolverConfig {
+                warm_starting,
+                velocity_iterations: 4,
+                position_iterations: 1,
+                ..SolverConfig::new()
+            });
+            let mut top = None;
+            for i in 0..8 {
+                let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+                block.set_position(Vec2::new(0.0, 0.5 + i as f64));
+                top = Some(world.create_body(block));
+            }
+            for _ in 0..180 {
+                world.step(1.0 / 60.0);
+            }
+            let top = world.body(top.unwrap()).unwrap().position().y + 0.5;
+            let contacts = world.contacts();
+            let ground_load: f64 = contacts.iter().filter(|c| c.body_a == floor).map(|c| c.impulse.normal).sum();
+            (top, ground_load)
+        };
+
+        // Without warm starting the few iterations can't hold the weight up and the stack sinks
+        let (cold, _) = stack_height(false);
+        let (warm, ground_load) = stack_height(true);
+        assert!(warm > 7.5 && warm > cold + 1.5);
+        // The ground carries the whole stack
+        assert!((ground_load - 8.0 * 9.81 / 60.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_solver_substeps() {
+        // Height of the top of a stack of boxes the solver can barely hold without substeps
+        let stack_height = |substeps| {
+            let mut world = World::new();
+            ground(&mut world);
+            world.set_solver_config(SolverConfig {
+                warm_starting: false,
+                velocity_iterations: 4,
+                position_iterations: 1,
+                substeps,
+                ..SolverConfig::new()
+            });
+            let mut top = None;
+            for i in 0..8 {
+                let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+                block.set_position(Vec2::new(0.0, 0.5 + i as f64));
+                top = Some(world.create_body(block));
+            }
+            for _ in 0..180 {
+                world.step(1.0 / 60.0);
+            }
+            assert_eq!(world.stats().substeps, substeps.max(1));
+            world.body(top.unwrap()).unwrap().position().y + 0.5
+        };
+
+        let single = stack_height(0);
+        let split = stack_height(4);
+        assert!(split > 7.5 && split > single + 1.0, "{} {}", single, split);
+    }
+
+    #[test]
+    fn test_integrators() {
+        // Under gravity alone Verlet lands on the parabola, Euler a step's worth ahead of it
+        let fall = |integrator| {
+            let mut space = World::new();
+            space.set_integrator(integrator);
+            let ball = space.create_body(RigidBody::new(1.0));
+            for _ in 0..60 {
+                space.step(1.0 / 60.0);
+            }
+            space.body(ball).unwrap().position().y
+        };
+        let exact = -9.81 / 2.0;
+        assert!((fall(Integrator::VelocityVerlet) - exact).abs() < 1e-9);
+        assert!((fall(Integrator::SemiImplicitEuler) - exact).abs() > 0.05);
+
+        // A spring oscillator keeps its amplitude with Verlet
+        let mut space = world();
+        space.set_integrator(Integrator::VelocityVerlet);
+        let anchor = space.create_body(RigidBody::new(0.0));
+        let mut weight = RigidBody::new(1.0);
+        weight.set_position(Vec2::new(1.0, 0.0));
+        let weight = space.create_body(weight);
+        space.add_spring(Spring::new(anchor, weight, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0), 40.0, 0.0, 0.0));
+        let mut furthest: f64 = 0.0;
+        for i in 0..600 {
+            space.step(1.0 / 60.0);
+            if i >= 540 {
+                furthest = furthest.max(space.body(weight).unwrap().position().x.abs());
+            }
+        }
+        assert!((furthest - 1.0).abs() < 0.01, "{}", furthest);
+
+        // XPBD rests a box on the ground and still bounces a springy ball
+        let mut world = World::new();
+        ground(&mut world);
+        world.set_integrator(Integrator::Xpbd);
+        assert_eq!(world.solver_config().integrator, Integrator::Xpbd);
+        let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+        block.set_position(Vec2::new(-3.0, 1.0));
+        let block = world.create_body(block);
+        let mut material = Material::new();
+        material.restitution = 0.9;
+        material.restitution_combine = CombineRule::Max;
+        let mut ball = RigidBody::with_material(Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5)), material);
+        ball.set_position(Vec2::new(3.0, 3.0));
+        let ball = world.create_body(ball);
+        let highest_after_bounce = bounce_height(&mut world, ball, 120);
+        let block = world.body(block).unwrap();
+        assert!((block.position().y - 0.5).abs() < 0.02, "{:?}", block.position());
+        assert!(block.velocity().len() < 0.05);
+        assert!(highest_after_bounce > 2.0, "{}", highest_after_bounce);
+    }
+
+    #[test]
+    fn test_rk4_orbit() {
+        // A moon on a circular orbit of radius 1 around a planet pulling with strength 1 through the
+        // force callback, which RK4 brings back to its start after one turn
+        let orbit_error = |integrator| {
+            let mut space = world();
+            space.set_integrator(integrator);
+            let mut moon = RigidBody::new(1.0);
+            moon.set_position(Vec2::new(1.0, 0.0));
+            moon.set_velocity(Vec2::new(0.0, 1.0));
+            let moon = space.create_body(moon);
+            space.set_force_callback(|_, bodies, forces| {
+                for (body, force) in bodies.iter().zip(forces) {
+                    let p = body.position();
+                    force.0 = force.0 - p * (body.mass() / p.len().powi(3));
+                }
+            });
+            let steps = 100;
+            for _ in 0..steps {
+                space.step(2.0 * std::f64::consts::PI / steps as f64);
+            }
+            Vec2::distance(&space.body(moon).unwrap().position(), &Vec2::new(1.0, 0.0))
+        };
+
+        let rk4 = orbit_error(Integrator::Rk4);
+        assert!(rk4 < 1e-5, "{}", rk4);
+        assert!(orbit_error(Integrator::SemiImplicitEuler) > 100.0 * rk4);
+    }
+
+    #[test]
+    fn test_lcp_backend() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        world.set_solver_config(SolverConfig {
+            backend: SolverBackend::Lcp,
+            ..SolverConfig::new()
+        });
+        let mut top = None;
+        for i in 0..8 {
+            let mut block = RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]);
+            block.set_position(Vec2::new(0.0, 0.5 + i as f64));
+            top = Some(world.create_body(block));
+        }
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+        }
+        // Solved as one system the stack stands as well as with warm started sequential impulses
+        let top = world.body(top.unwrap()).unwrap();
+        assert!(top.position().y + 0.5 > 7.5 && top.velocity().len() < 0.1);
+        let contacts = world.contacts();
+        let ground_load: f64 = contacts.iter().filter(|c| c.body_a == floor).map(|c| c.impulse.normal).sum();
+        assert!((ground_load - 8.0 * 9.81 / 60.0).abs() < 0.05, "{}", ground_load);
+    }
+
+    #[test]
+    fn test_body_iteration_and_query() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let balls: Vec<BodyHandle> = (0..4)
+            .map(|i| {
+                let mut ball = RigidBody::with_circle(1.0, 0.25);
+                ball.set_position(Vec2::new(i as f64, 2.0));
+                world.create_body(ball)
+            })
+            .collect();
+        world.remove_body(balls[3]).unwrap();
+
+        assert_eq!(world.query(RigidBody::is_static), vec![floor]);
+        assert_eq!(world.query(|b| b.position().x >= 1.0), vec![balls[1], balls[2]]);
+        // Removing the first ball moves the last one into its place in the dense array
+        world.remove_body(balls[0]).unwrap();
+        assert_eq!(world.query(|b| !b.is_static()), vec![balls[1], balls[2]]);
+
+        // Launch every moving body straight up
+        for (_, body) in world.bodies_mut().filter(|(_, b)| !b.is_static()) {
+            body.set_velocity(Vec2::new(0.0, 5.0));
+        }
+        world.step(1.0 / 60.0);
+        for (handle, body) in world.bodies() {
+            assert_eq!(body.velocity().y > 4.0, handle != floor);
+        }
+    }
+
+    #[test]
+    fn test_user_data() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        world.set_user_data(floor, 7).unwrap();
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 1.0));
+        ball.set_user_data(u64::MAX);
+        let ball = world.create_body(ball);
+        let mut hinge = AngleJoint::new(floor, ball, 0.0);
+        hinge.user_data = 42;
+        let index = world.add_angle_joint(hinge).unwrap();
+
+        // Contact events map back to whatever the game stored on the bodies
+        let mut begin = None;
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            begin = begin.or_else(|| world.contact_events().iter().find(|e| e.kind == ContactEventKind::Begin).copied());
+        }
+        let begin = begin.expect("ball should land");
+        assert_eq!(world.user_data(begin.body_a), Some(7));
+        assert_eq!(world.user_data(begin.body_b), Some(u64::MAX));
+        assert_eq!(world.joint_user_data(index), Some(42));
+        world.set_joint_user_data(index, 43);
+
+        let loaded = World::from_json(&world.to_json()).unwrap();
+        let handles = loaded.body_handles();
+        assert_eq!(loaded.user_data(handles[1]), Some(u64::MAX));
+        assert_eq!(loaded.joint_user_data(0), Some(43));
+        let mut restored = World::new();
+        restored.load_state(&world.save_state()).unwrap();
+        assert_eq!(restored.user_data(ball), Some(u64::MAX));
+        assert_eq!(restored.joint_user_data(0), Some(43));
+
+        world.remove_body(ball).unwrap();
+        assert_eq!(world.user_data(ball), None);
+        assert_eq!(world.set_user_data(ball, 1), Err(WorldError::InvalidHandle(ball)));
+    }
+
+    #[test]
+    fn test_contact_events_identify_child() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+
+        // Two feet, only the right one low enough to land
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::new(Vec2::new(-1.0, 0.0), 0.0), Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.25)));
+        compound.add_child(Transform2D::new(Vec2::new(1.0, -0.5), 0.0), Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.25)));
+        let mut body = RigidBody::with_shape(1.0, Shapes::Compound(compound));
+        body.set_position(Vec2::new(0.0, 1.0));
+        let body = world.create_body(body);
+
+        let mut begin = None;
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            if let Some(e) = world.contact_events().iter().find(|e| e.kind == ContactEventKind::Begin) {
+                begin = Some(*e);
+                break;
+            }
+        }
+        let begin = begin.expect("landing should begin a contact");
+        assert_eq!((begin.body_a, begin.body_b), (floor, body));
+        assert_eq!((begin.child_a, begin.child_b), (0, 1));
+
+        world.body_mut(body).unwrap().set_position(Vec2::new(0.0, 5.0));
+        world.update_broadphase();
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contact_events().len(), 1);
+        assert_eq!(world.contact_events()[0].kind, ContactEventKind::End);
+    }
+
+    #[test]
+    fn test_hit_cooldown() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let ball = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        world.set_hit_cooldown(ball, 0.5).unwrap();
+        let hit = |world: &mut World, y: f64| {
+            let body = world.body_mut(ball).unwrap();
+            body.set_position(Vec2::new(0.0, y));
+            body.set_velocity(Vec2::new(0.0, 0.0));
+            world.update_broadphase();
+            world.step(0.1);
+            world.contact_events().iter().map(|e| e.kind).collect::<Vec<_>>()
+        };
+
+        assert_eq!(hit(&mut world, 0.45), vec![ContactEventKind::Begin]);
+        assert_eq!(hit(&mut world, 5.0), vec![ContactEventKind::End]);
+        // Touching again within the cooldown isn't reported
+        assert_eq!(hit(&mut world, 0.45), vec![]);
+        assert_eq!(hit(&mut world, 5.0), vec![ContactEventKind::End]);
+        assert_eq!(hit(&mut world, 5.0), vec![]);
+        assert_eq!(hit(&mut world, 0.45), vec![ContactEventKind::Begin]);
+
+        assert_eq!(world.hit_cooldown(ball), 0.5);
+        assert_eq!(world.hit_cooldown(floor), 0.0);
+        world.remove_body(ball).unwrap();
+        assert_eq!(world.hit_cooldown(ball), 0.0);
+        assert_eq!(world.set_hit_cooldown(ball, 1.0), Err(WorldError::InvalidHandle(ball)));
+    }
+
+    #[test]
+    fn test_add_bodies_batch() {
+        let mut world = World::new();
+        let mut buffer = vec![3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 5.0, -5.0, 0.0, 5.0, 0.0, 0.0];
+        for i in 0..10 {
+            buffer.extend_from_slice(&[0.0, 1.0, i as f64 - 4.5, 0.35, 0.0, 0.0, 0.0, 1.0, 0.4]);
+        }
+        let handles = world.add_bodies_batch(&buffer).unwrap();
+        assert_eq!(handles.len(), 11);
+        assert_eq!(world.body(handles[3]).unwrap().position(), Vec2::new(-2.5, 0.35));
+        // The new bodies collide right away
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contacts().len(), 10);
+
+        buffer.truncate(buffer.len() - 1);
+        assert_eq!(world.add_bodies_batch(&buffer), Err(WorldError::InvalidDescriptor(13 + 9 * 9)));
+        assert_eq!(world.body_count(), 11);
+    }
+
+    #[test]
+    fn test_broadphase_kinds() {
+        let run = |kind| {
+            let mut world = World::new();
+            let mut config = BroadphaseConfig::new();
+            config.kind = kind;
+            config.cell_size = 0.5;
+            world.set_broadphase_config(config);
+            ground(&mut world);
+            for i in 0..20 {
+                let mut ball = RigidBody::with_circle(1.0, 0.2);
+                ball.set_position(Vec2::new((i % 5) as f64 * 0.5, 0.5 + (i / 5) as f64 * 0.5));
+                world.create_body(ball);
+            }
+            for _ in 0..60 {
+                world.step(1.0 / 60.0);
+            }
+            world.snapshot()
+        };
+
+        // The broadphase only changes how pairs are found, not which
+        let bvh = run(BroadphaseKind::Bvh);
+        assert_eq!(run(BroadphaseKind::SpatialHash), bvh);
+        assert_eq!(run(BroadphaseKind::SweepAndPrune), bvh);
+
+        let mut world = World::new();
+        ground(&mut world);
+        let mut config = BroadphaseConfig::new();
+        config.kind = BroadphaseKind::SpatialHash;
+        world.set_broadphase_config(config);
+        let ball = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        assert_eq!(world.broadphase_config().kind, BroadphaseKind::SpatialHash);
+        assert_eq!(world.k_nearest(&Vec2::new(0.0, 3.0), 1, |_, _| true)[0].0, ball);
+    }
+
+    /// Checks every pair of boxes, standing in for a user's broadphase
+    #[derive(Clone, Debug, Default)]
+    struct BruteForce {
+        boxes: Vec<Option<(Aabb, usize)>>,
+    }
+
+    impl Broadphase for BruteForce {
+        fn len(&self) -> usize {
+            self.boxes.iter().flatten().count()
+        }
+
+        fn clear(&mut self) {
+            self.boxes.clear();
+        }
+
+        fn insert(&mut self, aabb: Aabb, data: usize) -> usize {
+            self.boxes.push(Some((aabb, data)));
+            self.boxes.len() - 1
+        }
+
+        fn remove(&mut self, proxy: usize) {
+            self.boxes[proxy] = None;
+        }
+
+        fn update(&mut self, proxy: usize, aabb: Aabb) -> bool {
+            if let Some(entry) = &mut self.boxes[proxy] {
+                entry.0 = aabb;
+            }
+            true
+        }
+
+        fn data(&self, proxy: usize) -> usize {
+            self.boxes[proxy].unwrap().1
+        }
+
+        fn set_data(&mut self, proxy: usize, data: usize) {
+            if let Some(entry) = &mut self.boxes[proxy] {
+                entry.1 = data;
+            }
+        }
+
+        fn query(&self, aabb: &Aabb) -> Vec<usize> {
+            self.boxes.iter().flatten().filter(|(b, _)| b.overlaps(aabb)).map(|(_, d)| *d).collect()
+        }
+
+        fn pairs(&self) -> Vec<(usize, usize)> {
+            let boxes: Vec<&(Aabb, usize)> = self.boxes.iter().flatten().collect();
+            let mut out = Vec::new();
+            for (i, (a, da)) in boxes.iter().enumerate() {
+                for (b, db) in &boxes[i + 1..] {
+                    if a.overlaps(b) {
+                        out.push(((*da).min(*db), (*da).max(*db)));
+                    }
+                }
+            }
+            out.sort_unstable();
+            out
+        }
+
+        fn nearest(&self, _point: &Vec2, k: usize, distance: &mut dyn FnMut(usize) -> Option<f64>) -> Vec<(usize, f64)> {
+            let mut out: Vec<(usize, f64)> =
+                self.boxes.iter().flatten().filter_map(|(_, d)| Some((*d, distance(*d)?))).collect();
+            out.sort_by(|a, b| a.1.total_cmp(&b.1));
+            out.truncate(k);
+            out
+        }
+    }
+
+    #[test]
+    fn test_custom_broadphase() {
+        let run = |custom| {
+            let mut world = World::new();
+            if custom {
+                world.set_broadphase(Box::new(BruteForce::default()));
+            }
+            ground(&mut world);
+            let mut balls = Vec::new();
+            for i in 0..10 {
+                let mut ball = RigidBody::with_circle(1.0, 0.2);
+                ball.set_position(Vec2::new((i % 5) as f64 * 0.5, 0.5 + (i / 5) as f64 * 0.5));
+                balls.push(world.create_body(ball));
+            }
+            world.remove_body(balls[3]).unwrap();
+            for _ in 0..60 {
+                world.step(1.0 / 60.0);
+            }
+            world
+        };
+
+        let world = run(true);
+        assert_eq!(world.broadphase().len(), 10);
+        assert_eq!(world.snapshot(), run(false).snapshot());
+        // Clones carry their own copy of the custom broadphase
+        let mut copy = world.clone();
+        copy.step(1.0 / 60.0);
+        assert_eq!(world.broadphase().len(), copy.broadphase().len());
+        assert_eq!(copy.query_aabb(Vec2::new(-0.1, -0.1), Vec2::new(0.1, 3.0)).len(), 3);
+    }
+
+    #[test]
+    fn test_query_aabb() {
+        let mut world = World::new();
+        let mut handles = Vec::new();
+        for i in 0..5 {
+            let mut ball = RigidBody::with_circle(1.0, 0.5);
+            ball.set_position(Vec2::new(i as f64 * 2.0, 0.0));
+            handles.push(world.create_body(ball));
+        }
+        let mut diamond = RigidBody::with_polygon(1.0, &[0.0, -1.0, 1.0, 0.0, 0.0, 1.0, -1.0, 0.0]);
+        diamond.set_position(Vec2::new(0.0, 5.0));
+        let diamond = world.create_body(diamond);
+
+        assert_eq!(world.query_aabb(Vec2::new(1.8, -0.1), Vec2::new(4.2, 0.1)), vec![handles[1], handles[2]]);
+        // Corners may be given in any order
+        assert_eq!(world.query_aabb(Vec2::new(4.2, 0.1), Vec2::new(1.8, -0.1)), vec![handles[1], handles[2]]);
+        // Shapes are tested, not just their bounds
+        assert!(world.query_aabb(Vec2::new(0.7, 5.7), Vec2::new(2.0, 7.0)).is_empty());
+        assert_eq!(world.query_aabb(Vec2::new(0.4, 5.4), Vec2::new(2.0, 7.0)), vec![diamond]);
+        // A box with no size is a line or a point
+        assert_eq!(world.query_aabb(Vec2::new(0.0, 4.5), Vec2::new(0.0, 4.5)), vec![diamond]);
+        assert!(world.query_aabb(Vec2::new(0.9, 5.9), Vec2::new(0.9, 5.9)).is_empty());
+        assert_eq!(world.query_aabb(Vec2::new(2.0, -3.0), Vec2::new(2.0, 3.0)), vec![handles[1]]);
+        assert_eq!(world.query_aabb(Vec2::new(-1.0, 0.0), Vec2::new(3.0, 0.0)), vec![handles[0], handles[1]]);
+
+        // Nearest the right end first, two at a time
+        let mut options = QueryOptions::nearest(Vec2::new(9.0, 0.0), 2);
+        let page = world.query_aabb_with(Vec2::new(-1.0, -1.0), Vec2::new(9.0, 1.0), &options);
+        assert_eq!(page.results, vec![handles[4], handles[3]]);
+        options.cursor = page.next.unwrap();
+        let page = world.query_aabb_with(Vec2::new(-1.0, -1.0), Vec2::new(9.0, 1.0), &options);
+        assert_eq!(page.results, vec![handles[2], handles[1]]);
+        options.cursor = page.next.unwrap();
+        let page = world.query_aabb_with(Vec2::new(-1.0, -1.0), Vec2::new(9.0, 1.0), &options);
+        assert_eq!((page.results, page.next), (vec![handles[0]], None));
+
+        world.remove_body(handles[1]).unwrap();
+        assert_eq!(world.wasm_query_aabb(Vec2::new(-1.0, -1.0), Vec2::new(9.0, 1.0)), vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        let mut world = World::new();
+        let mut walls = Vec::new();
+        for x in [3.0, 6.0] {
+            let mut wall = RigidBody::with_polygon(0.0, &[-0.5, -2.0, 0.5, -2.0, 0.5, 2.0, -0.5, 2.0]);
+            wall.set_position(Vec2::new(x, 0.0));
+            walls.push(world.create_body(wall));
+        }
+        let ball = Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.5));
+        let start = Transform2D::identity();
+
+        let (handle, hit) = world.shape_cast(&ball, &start, &Vec2::new(10.0, 0.0), |_, _| true).unwrap();
+        assert_eq!(handle, walls[0]);
+        assert!((hit.fraction - 0.2).abs() < 1e-4);
+        assert!((hit.normal - Vec2::new(-1.0, 0.0)).len() < 1e-6);
+        // Filtered bodies are passed through
+        let (handle, hit) = world.shape_cast(&ball, &start, &Vec2::new(10.0, 0.0), |h, _| h != walls[0]).unwrap();
+        assert_eq!(handle, walls[1]);
+        assert!((hit.fraction - 0.5).abs() < 1e-4);
+        assert!(world.shape_cast(&ball, &start, &Vec2::new(0.0, 10.0), |_, _| true).is_none());
+    }
+
+    /// Counts primitives by what they show
+    #[derive(Default)]
+    struct Tally {
+        segments: Vec<DebugColor>,
+        polygons: Vec<DebugColor>,
+        circles: usize,
+        transforms: usize,
+        points: usize,
+    }
+
+    impl DebugDraw for Tally {
+        fn draw_segment(&mut self, _: Vec2, _: Vec2, color: DebugColor) {
+            self.segments.push(color);
+        }
+
+        fn draw_polygon(&mut self, _: &[Vec2], color: DebugColor) {
+            self.polygons.push(color);
+        }
+
+        fn draw_circle(&mut self, _: Vec2, _: f64, _: DebugColor) {
+            self.circles += 1;
+        }
+
+        fn draw_transform(&mut self, _: &Transform2D) {
+            self.transforms += 1;
+        }
+
+        fn draw_point(&mut self, _: Vec2, _: DebugColor) {
+            self.points += 1;
+        }
+    }
+
+    #[test]
+    fn test_debug_draw() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 0.49));
+        let ball = world.create_body(ball);
+        world.add_revolute_joint(RevoluteJoint::new(floor, ball, Vec2::new(0.0, 1.0), Vec2::new(0.0, 0.5))).unwrap();
+        world.step(1.0 / 60.0);
+
+        let mut tally = Tally::default();
+        world.debug_draw(&mut tally);
+        assert_eq!(tally.polygons, vec![DebugColor::StaticBody, DebugColor::Aabb, DebugColor::Aabb]);
+        assert_eq!((tally.circles, tally.transforms), (1, 2));
+        let contacts = world.contacts().len();
+        assert!(contacts > 0);
+        assert_eq!(tally.points, contacts);
+        let count = |color| tally.segments.iter().filter(|&&c| c == color).count();
+        assert_eq!((count(DebugColor::Contact), count(DebugColor::Joint)), (contacts, 3));
+
+        let commands = world.wasm_debug_draw();
+        assert_eq!(commands[0], debug_draw::DRAW_POLYGON);
+        assert_eq!(commands[1], DebugColor::StaticBody as u8 as f64);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut world = World::new();
+        let mut walls = Vec::new();
+        for x in [3.0, 6.0, 9.0] {
+            let mut wall = RigidBody::with_polygon(0.0, &[-0.5, -2.0, 0.5, -2.0, 0.5, 2.0, -0.5, 2.0]);
+            wall.set_position(Vec2::new(x, 0.0));
+            walls.push(world.create_body(wall));
+        }
+        let ray = Ray::new(Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0));
+
+        let (handle, hit) = world.raycast(&ray, 20.0, |_, _| true).unwrap();
+        assert_eq!(handle, walls[0]);
+        assert!((hit.distance - 2.5).abs() < 1e-4);
+        assert!((hit.point - Vec2::new(2.5, 1.0)).len() < 1e-4);
+        assert!((hit.normal - Vec2::new(-1.0, 0.0)).len() < 1e-6);
+
+        // Friendly bodies are ignored, and all hits come nearest first
+        let friendly = walls[1];
+        let hits = world.raycast_all(&ray, 20.0, |h, _| h != friendly);
+        let handles: Vec<BodyHandle> = hits.iter().map(|(h, _)| *h).collect();
+        assert_eq!(handles, vec![walls[0], walls[2]]);
+        assert!((hits[1].1.distance - 8.5).abs() < 1e-4);
+        assert_eq!(world.raycast_all(&ray, 6.0, |_, _| true).len(), 2);
+        assert_eq!(world.wasm_raycast_all(&ray, 20.0), vec![0, 1, 2]);
+        assert!(world.raycast(&Ray::new(Vec2::new(0.0, 3.0), Vec2::new(1.0, 0.0)), 20.0, |_, _| true).is_none());
+
+        // Starting inside a body hits it straight away
+        let inside = Ray::new(Vec2::new(3.0, 0.0), Vec2::new(1.0, 0.0));
+        let (handle, hit) = world.raycast(&inside, 20.0, |_, _| true).unwrap();
+        assert_eq!((handle, hit.distance), (walls[0], 0.0));
+    }
+
+    #[test]
+    fn test_remove_body() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::new(1.0));
+        let mut body = RigidBody::new(2.0);
+        body.set_position(Vec2::new(5.0, 0.0));
+        let b = world.create_body(body);
+
+        assert_eq!(world.remove_body(a).unwrap().mass(), 1.0);
+        assert_eq!(world.body_count(), 1);
+        assert_eq!(world.remove_body(a), Err(WorldError::InvalidHandle(a)));
+        assert_eq!(world.get_body(b).unwrap().mass(), 2.0);
+        // The moved body is still found by the broadphase
+        assert_eq!(world.wasm_k_nearest(Vec2::new(0.0, 0.0), 1), vec![b]);
+
+        // The freed slot is reused, but the old handle stays stale
+        let c = world.create_body(RigidBody::new(3.0));
+        assert_eq!(c.index(), a.index());
+        assert!(world.get_body(a).is_err());
+        assert!(world.get_body_mut(a).is_err());
+        assert_eq!(world.get_body(c).unwrap().mass(), 3.0);
+    }
+
+    #[test]
+    fn test_removed_body_ends_contacts() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 0.45));
+        let ball = world.create_body(ball);
+        world.schedule_impulse(floor, 1.0, Vec2::new(1.0, 0.0)).unwrap();
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contacts().len(), 1);
+
+        world.remove_body(floor).unwrap();
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contact_events().len(), 1);
+        assert_eq!(world.contact_events()[0].kind, ContactEventKind::End);
+        assert_eq!(world.contact_events()[0].body_a, floor);
+        assert!(world.body(ball).unwrap().velocity().y < 0.0);
+    }
+
+    #[test]
+    fn test_state_buffers() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::new(1.0));
+        let mut body = RigidBody::new(1.0);
+        body.set_position(Vec2::new(1.0, 2.0));
+        body.set_rotation(0.5);
+        body.set_velocity(Vec2::new(3.0, 4.0));
+        let b = world.create_body(body);
+        world.remove_body(a).unwrap();
+
+        assert_eq!(world.body_handles(), vec![b]);
+        assert_eq!(world.positions_buffer(), vec![1.0, 2.0]);
+        assert_eq!(world.rotations_buffer(), vec![0.5]);
+        assert_eq!(world.velocities_buffer(), vec![3.0, 4.0]);
+
+        let mut out = [0.0; 2];
+        world.fill_velocities(&mut out).unwrap();
+        assert_eq!(out, [3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_fill_wrong_length() {
+        let mut world = world();
+        world.create_body(RigidBody::new(1.0));
+        let mut out = [7.0; 3];
+        assert_eq!(
+            world.fill_positions(&mut out),
+            Err(WorldError::InvalidBufferLength { expected: 2, actual: 3 })
+        );
+        assert_eq!(out, [7.0; 3]);
+        assert_eq!(
+            world.fill_rotations(&mut [0.0; 2]),
+            Err(WorldError::InvalidBufferLength { expected: 1, actual: 2 })
+        );
+        assert_eq!(
+            world.fill_velocities(&mut []),
+            Err(WorldError::InvalidBufferLength { expected: 2, actual: 0 })
+        );
+    }
+
+    #[test]
+    fn test_remove_missing_index() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::new(1.0));
+        let b = world.create_body(RigidBody::new(1.0));
+        assert_eq!(world.remove_spring(0).err(), Some(WorldError::InvalidSpring(0)));
+        assert_eq!(world.remove_force_generator(1), Err(WorldError::InvalidForceGenerator(1)));
+        assert_eq!(world.remove_emitter(0).err(), Some(WorldError::InvalidEmitter(0)));
+
+        world.add_spring(Spring::new(a, b, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0), 1.0, 0.0, 1.0));
+        assert!(world.remove_spring(0).is_ok());
+        assert_eq!(world.remove_spring(0).err(), Some(WorldError::InvalidSpring(0)));
+    }
+
+    #[test]
+    fn test_self_joint_rejected() {
+        let mut world = world();
+        let a = world.create_body(RigidBody::new(1.0));
+        let joint = RevoluteJoint::new(a, a, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0));
+        assert_eq!(world.add_revolute_joint(joint), Err(WorldError::SelfJoint(a)));
+        assert!(world.joints().is_empty());
+    }
+
+    #[test]
+    fn test_rasterize_sdf() {
+        let mut world = world();
+        ground(&mut world);
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 2.0));
+        world.create_body(ball);
+
+        let field = world.rasterize_sdf(Aabb::new(Vec2::new(-1.0, -1.0), Vec2::new(1.0, 3.0)), 0.5);
+        assert_eq!((field.columns(), field.rows()), (4, 8));
+        // Only the ground counts, the dynamic ball is ignored
+        assert_eq!(field.value(0, 0), -0.25);
+        assert_eq!(field.value(0, 5), 1.75);
+        assert!(World::new().rasterize_sdf(field.region(), 1.0).values().iter().all(|v| v.is_infinite()));
+    }
+
+    #[test]
+    fn test_agents_follow_flow_field() {
+        let mut world = world();
+        // A wall between the agents and the goal with a gap at the top
+        let mut wall = RigidBody::with_polygon(0.0, &[-0.5, -4.0, 0.5, -4.0, 0.5, 2.0, -0.5, 2.0]);
+        wall.set_position(Vec2::new(0.0, 0.0));
+        world.create_body(wall);
+        let region = Aabb::new(Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0));
+        let goal = Vec2::new(3.0, -3.0);
+        let field = world.flow_field(region, 0.25, goal, 0.3);
+
+        let agents: Vec<BodyHandle> = (0..3)
+            .map(|i| {
+                let mut agent = RigidBody::with_circle(1.0, 0.2);
+                agent.set_position(Vec2::new(-3.0, -3.0 + i as f64 * 0.5));
+                world.create_body(agent)
+            })
+            .collect();
+        for _ in 0..600 {
+            for agent in &agents {
+                world.steer_body(*agent, &field, 2.0).unwrap();
+            }
+            world.step(1.0 / 60.0);
+        }
+        for agent in &agents {
+            assert!((world.body(*agent).unwrap().position() - goal).len() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let edge = world.create_body(RigidBody::with_segment(Vec2::new(-1.0, 2.0), Vec2::new(1.0, 2.0), true));
+        let mut compound = Compound::new();
+        compound.add_child(Transform2D::new(Vec2::new(0.5, 0.0), 0.3), Shapes::Circle(Circle::new(Vec2::new(0.0, 0.0), 0.25)));
+        let mut body = RigidBody::with_shape(2.0, Shapes::Compound(compound));
+        body.set_position(Vec2::new(0.1, 3.7));
+        body.set_velocity(Vec2::new(1.0 / 3.0, 0.0));
+        let body = world.create_body(body);
+        world.remove_body(floor).unwrap();
+        world.add_spring(Spring::new(body, body, Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 2.0, 0.1, 1.0));
+        let mut hinge = RevoluteJoint::new(edge, body, Vec2::new(0.0, 2.0), Vec2::new(0.0, 1.7));
+        hinge.enable_limit = true;
+        hinge.lower_angle = -0.25;
+        world.add_revolute_joint(hinge).unwrap();
+        world.add_emitter(Emitter::new(Vec2::new(0.0, 5.0), 10.0, 1.0));
+        world.set_hit_cooldown(body, 0.5).unwrap();
+        world.set_solver_config(SolverConfig {
+            substeps: 4,
+            integrator: Integrator::VelocityVerlet,
+            ..SolverConfig::new()
+        });
+        world.set_features(WorldFeatures {
+            enable_friction: false,
+            ..WorldFeatures::new()
+        });
+        world.set_bounds(WorldBounds::new(Aabb::new(Vec2::new(-50.0, -50.0), Vec2::new(50.0, 50.0)), BoundsPolicy::Wrap));
+        world.step(0.1);
+
+        let loaded = World::from_json(&world.to_json()).unwrap();
+        assert_eq!(loaded.solver_config(), world.solver_config());
+        assert_eq!(loaded.features(), world.features());
+        assert_eq!(loaded.bounds(), world.bounds());
+        assert_eq!(loaded.body_count(), 2);
+        assert_eq!(loaded.tick(), 1);
+        for ((_, a), (_, b)) in world.bodies().zip(loaded.bodies()) {
+            assert_eq!(a, b);
+        }
+        assert_eq!(loaded.springs()[0].body_a(), loaded.body_handles()[0]);
+        assert_eq!(loaded.revolute_joint(0).unwrap().lower_angle, -0.25);
+        assert_eq!(loaded.joints()[0].body_b(), loaded.body_handles()[0]);
+        assert_eq!(loaded.hit_cooldown(loaded.body_handles()[0]), 0.5);
+        assert_eq!(loaded.emitter(0), world.emitter(0).map(|mut e| {
+            e.accumulator = 0.0;
+            e
+        }));
+        assert_eq!(loaded.to_json(), world.to_json());
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert!(World::from_json("{").is_err());
+        assert_eq!(
+            World::from_json(r#"{"settings":{"gravity":[0,-1]},"time":0,"tick":0,"bodies":[],"springs":[{"body_a":0}],"emitters":[]}"#).err(),
+            Some(SerializeError::InvalidValue("no body 0 for spring".to_string()))
+        );
+
+        let mut world = World::new();
+        let a = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        let b = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        world.add_revolute_joint(RevoluteJoint::new(a, b, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0))).unwrap();
+        let json = world.to_json();
+        assert!(json.contains(r#""body_b":1"#));
+        assert_eq!(
+            World::from_json(&json.replace(r#""body_b":1"#, r#""body_b":0"#)).err(),
+            Some(SerializeError::InvalidValue("joint between body 0 and itself".to_string()))
+        );
+        assert_eq!(
+            World::from_json(&json.replace(r#""body_b":1"#, r#""body_b":2"#)).err(),
+            Some(SerializeError::InvalidValue("no body 2 for joint".to_string()))
+        );
+        assert_eq!(
+            World::from_json(&json.replace(r#""body_b":1"#, r#""body_b":-1"#)).err(),
+            Some(SerializeError::InvalidValue("no body -1 for joint".to_string()))
+        );
+
+        assert!(json.contains(r#""tick":0"#));
+        assert_eq!(
+            World::from_json(&json.replace(r#""tick":0"#, r#""tick":-1"#)).err(),
+            Some(SerializeError::InvalidValue("tick -1 is not a step count".to_string()))
+        );
+        assert_eq!(
+            World::from_json(&json.replace(r#""tick":0"#, r#""tick":2.5"#)).err(),
+            Some(SerializeError::InvalidValue("tick 2.5 is not a step count".to_string()))
+        );
+        assert!(World::from_json(&json.replace(r#""tick":0"#, r#""tick":1e400"#)).is_err());
+    }
+
+    #[test]
+    fn test_binary_state_rollback() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let removed = world.create_body(RigidBody::new(1.0));
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 2.0));
+        let ball = world.create_body(ball);
+        world.remove_body(removed).unwrap();
+        let mut emitter = Emitter::new(Vec2::new(0.0, 3.0), 30.0, 2.0);
+        emitter.spread = 0.5;
+        emitter.collision = ParticleCollision::Stick;
+        world.add_emitter(emitter);
+        world.schedule_force(ball, 0.5, 0.5, Vec2::new(1.0, 0.0)).unwrap();
+        let mut motor = RevoluteJoint::new(floor, ball, Vec2::new(0.0, 3.0), Vec2::new(0.0, 1.0));
+        motor.enable_motor = true;
+        motor.motor_speed = 1.0;
+        motor.max_motor_torque = 0.5;
+        world.add_revolute_joint(motor).unwrap();
+        world.set_hit_cooldown(ball, 0.25).unwrap();
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+
+        let bytes = world.save_state();
+        let saved = world.snapshot();
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+
+        let mut other = World::new();
+        other.load_state(&bytes).unwrap();
+        assert_eq!(other.snapshot(), saved);
+        for _ in 0..60 {
+            other.step(1.0 / 60.0);
+        }
+        assert_eq!(other.snapshot(), world.snapshot());
+        assert!(!other.contains_body(removed));
+    }
+
+    #[test]
+    fn test_binary_state_errors() {
+        let mut world = World::new();
+        world.create_body(RigidBody::new(1.0));
+        let bytes = world.save_state();
+        let before = world.snapshot();
+
+        assert!(world.load_state(&bytes[..bytes.len() - 1]).is_err());
+        assert!(world.load_state(b"nope").is_err());
+        let mut future = bytes.clone();
+        future[4] = 13;
+        assert_eq!(world.load_state(&future), Err(SerializeError::UnsupportedVersion(13)));
+        assert_eq!(world.snapshot(), before);
+    }
+
+    #[test]
+    fn test_binary_state_corrupted() {
+        let mut world = World::new();
+        let floor = ground(&mut world);
+        let removed = world.create_body(RigidBody::new(1.0));
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 0.6));
+        let ball = world.create_body(ball);
+        world.remove_body(removed).unwrap();
+        world.create_body(RigidBody::with_circle(1.0, 0.25));
+        world.add_revolute_joint(RevoluteJoint::new(floor, ball, Vec2::new(0.0, 3.0), Vec2::new(0.0, 1.0))).unwrap();
+        world.step(1.0 / 60.0);
+        let bytes = world.save_state();
+
+        // Every byte changed to a few values either fails to load, leaving the world as it was,
+        // or loads a world that can still be stepped and emptied
+        for i in 0..bytes.len() {
+            for &value in &[0, 1, 2, 0xff] {
+                let mut corrupted = bytes.clone();
+                corrupted[i] = value;
+                let mut loaded = world.clone();
+                match loaded.load_state(&corrupted) {
+                    Err(_) => assert_eq!(loaded.save_state(), bytes, "byte {} set to {}", i, value),
+                    Ok(()) => {
+                        loaded.step(1.0 / 60.0);
+                        for handle in loaded.body_handles() {
+                            loaded.remove_body(handle).unwrap();
+                        }
+                        loaded.step(1.0 / 60.0);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A rod of length 1 hanging from a static pivot at the origin, starting horizontal
+    fn pendulum(world: &mut World) -> (BodyHandle, BodyHandle) {
+        let pivot = world.create_body(RigidBody::new(0.0));
+        let mut rod = RigidBody::with_circle(1.0, 0.2);
+        rod.set_position(Vec2::new(1.0, 0.0));
+        let rod = world.create_body(rod);
+        (pivot, rod)
+    }
+
+    #[test]
+    fn test_joint_limit_events() {
+        let mut world = World::new();
+        let (pivot, rod) = pendulum(&mut world);
+        let mut joint = RevoluteJoint::new(pivot, rod, Vec2::new(0.0, 0.0), Vec2::new(-1.0, 0.0));
+        joint.enable_limit = true;
+        joint.lower_angle = -0.5;
+        joint.upper_angle = 0.5;
+        let index = world.add_revolute_joint(joint).unwrap();
+
+        let mut reached = 0;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            reached += world.joint_events().iter().filter(|e| e.kind == JointEventKind::LimitReached).count();
+            assert!(world.joint_events().iter().all(|e| e.joint == index));
+        }
+        let body = world.body(rod).unwrap();
+        assert_eq!(reached, 1);
+        assert!(world.revolute_joint(index).unwrap().at_limit());
+        assert!((body.rotation() + 0.5).abs() < 0.05);
+        assert!((body.position().len() - 1.0).abs() < 0.05);
+
+        world.set_gravity(Vec2::new(0.0, 9.81));
+        let mut left = false;
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+            left |= world.joint_events().iter().any(|e| e.kind == JointEventKind::LimitLeft);
+        }
+        assert!(left);
+        assert!(!world.revolute_joint(index).unwrap().at_limit());
+    }
+
+    #[test]
+    fn test_joint_reaction() {
+        // A motor below its speed pushes with all its torque
+        let mut world = world();
+        let (pivot, wheel) = pendulum(&mut world);
+        let mut motor = RevoluteJoint::new(pivot, wheel, Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0));
+        motor.enable_motor = true;
+        motor.motor_speed = 100.0;
+        motor.max_motor_torque = 0.5;
+        let index = world.add_revolute_joint(motor).unwrap();
+        world.step(1.0 / 60.0);
+        let joint = world.joints()[index];
+        assert!((joint.reaction_torque(60.0) - 0.5).abs() < 1e-9);
+        assert!(joint.reaction_force(60.0).len() < 1e-9);
+        assert_eq!(world.joint_anchor_a(index), Some(Vec2::new(1.0, 0.0)));
+
+        // A ball hanging still below a pivot is held up by its own weight
+        let mut world = World::new();
+        let pivot = world.create_body(RigidBody::new(0.0));
+        let mut ball = RigidBody::with_circle(2.0, 0.2);
+        ball.set_position(Vec2::new(0.0, -1.0));
+        let ball = world.create_body(ball);
+        let hang = world.add_revolute_joint(RevoluteJoint::new(pivot, ball, Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0))).unwrap();
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let force = world.joint_reaction_force(hang, 60.0).unwrap();
+        assert!((force - Vec2::new(0.0, 2.0 * 9.81)).len() < 0.01, "{:?}", force);
+        assert_eq!(world.joint_reaction_torque(hang, 60.0), Some(0.0));
+        let (anchor_a, anchor_b) = (world.joint_anchor_a(hang).unwrap(), world.joint_anchor_b(hang).unwrap());
+        assert_eq!(anchor_a, Vec2::new(0.0, 0.0));
+        // Sags a little as position error is only corrected by a fraction each step
+        assert!(anchor_b.len() < 0.02, "{:?}", anchor_b);
+        assert_eq!(world.joint_reaction_force(hang + 1, 60.0), None);
+    }
+
+    #[test]
+    fn test_xpbd_joint_compliance() {
+        // Gap between the anchors and force on a ball hanging still below a pivot
+        let hang = |compliance: f64| {
+            let mut world = World::new();
+            world.set_solver_config(SolverConfig {
+                integrator: Integrator::Xpbd,
+                compliance,
+                ..SolverConfig::new()
+            });
+            let pivot = world.create_body(RigidBody::new(0.0));
+            let mut ball = RigidBody::with_circle(2.0, 0.2);
+            ball.set_position(Vec2::new(0.0, -1.0));
+            let ball = world.create_body(ball);
+            let hang = world.add_revolute_joint(RevoluteJoint::new(pivot, ball, Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0))).unwrap();
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+            }
+            (world.joint_anchor_b(hang).unwrap().len(), world.joint_reaction_force(hang, 60.0).unwrap())
+        };
+
+        // Rigid joints hold their anchors together where Baumgarte lets them sag
+        let (gap, force) = hang(0.0);
+        assert!(gap < 1e-9, "{}", gap);
+        assert!((force - Vec2::new(0.0, 2.0 * 9.81)).len() < 0.01, "{:?}", force);
+
+        // Compliant ones stretch by the compliance times the weight they carry
+        let (gap, force) = hang(1e-3);
+        assert!((gap - 1e-3 * 2.0 * 9.81).abs() < 1e-4, "{}", gap);
+        assert!((force - Vec2::new(0.0, 2.0 * 9.81)).len() < 0.01, "{:?}", force);
+    }
+
+    #[test]
+    fn test_angle_joint() {
+        // Largest tilt and lowest point of a platform hanging from one end by a rope-like spring
+        let hang = |level: bool| {
+            let mut world = World::new();
+            let ceiling = world.create_body(RigidBody::new(0.0));
+            let mut platform = RigidBody::with_polygon(1.0, &[-1.0, -0.1, 1.0, -0.1, 1.0, 0.1, -1.0, 0.1]);
+            platform.set_position(Vec2::new(1.0, 0.0));
+            let platform = world.create_body(platform);
+            world.add_spring(Spring::new(ceiling, platform, Vec2::new(0.0, 0.0), Vec2::new(-1.0, 0.0), 20.0, 1.0, 1.0));
+            if level {
+                world.add_angle_joint(AngleJoint::new(ceiling, platform, 0.0)).unwrap();
+            }
+
+            let (mut tilt, mut lowest): (f64, f64) = (0.0, 0.0);
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+                let body = world.body(platform).unwrap();
+                tilt = tilt.max(body.rotation().abs());
+                lowest = lowest.min(body.position().y);
+            }
+            (tilt, lowest, world)
+        };
+
+        let (free_tilt, _, _) = hang(false);
+        let (tilt, lowest, world) = hang(true);
+        assert!(free_tilt > 1.0 && tilt < 0.2);
+        // Still free to move
+        assert!(lowest < -1.0);
+
+        let loaded = World::from_json(&world.to_json()).unwrap();
+        let handles = loaded.body_handles();
+        assert_eq!(loaded.angle_joint(0), Some(AngleJoint::new(handles[0], handles[1], 0.0)));
+        assert!(loaded.revolute_joint(0).is_none());
+    }
+
+    #[test]
+    fn test_motor_stall_events() {
+        let mut world = world();
+        let (pivot, wheel) = pendulum(&mut world);
+        let mut joint = RevoluteJoint::new(pivot, wheel, Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0));
+        joint.enable_motor = true;
+        joint.motor_speed = 2.0;
+        joint.max_motor_torque = 10.0;
+        joint.enable_limit = true;
+        joint.lower_angle = -0.5;
+        joint.upper_angle = 0.5;
+        joint.stall_steps = 5;
+        let index = world.add_revolute_joint(joint).unwrap();
+
+        // The motor spins freely until it drives the wheel into the upper limit
+        let mut stalled_at = None;
+        for i in 0..60 {
+            world.step(1.0 / 60.0);
+            if world.joint_events().iter().any(|e| e.kind == JointEventKind::MotorStalled) {
+                stalled_at = Some(i);
+                break;
+            }
+        }
+        assert!(world.revolute_joint(index).unwrap().is_stalled());
+        let rotation = world.body(wheel).unwrap().rotation();
+        assert!(rotation > 0.45, "stalled at rotation {}", rotation);
+        assert!(stalled_at.unwrap() > 10);
+
+        // Reversing keeps the stall state until the motor catches up
+        joint.motor_speed = -2.0;
+        world.set_revolute_joint(index, joint);
+        assert!(world.revolute_joint(index).unwrap().is_stalled());
+        let mut events = Vec::new();
+        for _ in 0..5 {
+            world.step(1.0 / 60.0);
+            events.extend(world.joint_events().iter().map(|e| e.kind));
+        }
+        assert_eq!(events, vec![JointEventKind::MotorRecovered, JointEventKind::LimitLeft]);
+        assert!(!world.revolute_joint(index).unwrap().is_stalled());
+    }
+
+    #[test]
+    fn test_prismatic_motor() {
+        // A cart driven up a vertical rail until it reaches the top
+        let mut world = world();
+        let mut rail = RigidBody::new(0.0);
+        rail.set_position(Vec2::new(0.0, -3.0));
+        let rail = world.create_body(rail);
+        let mut cart = RigidBody::with_polygon(1.0, &[-0.5, -0.25, 0.5, -0.25, 0.5, 0.25, -0.5, 0.25]);
+        cart.set_angular_velocity(2.0);
+        let cart = world.create_body(cart);
+        let mut joint = PrismaticJoint::new(rail, cart, Vec2::new(0.0, 3.0), Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0));
+        joint.enable_motor = true;
+        joint.motor_speed = 1.0;
+        joint.max_motor_force = 20.0;
+        joint.enable_limit = true;
+        joint.lower_translation = -1.0;
+        joint.upper_translation = 1.0;
+        joint.stall_steps = 5;
+        let index = world.add_prismatic_joint(joint).unwrap();
+        assert_eq!(world.prismatic_joint(index).unwrap().local_axis(), Vec2::new(0.0, 1.0));
+
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(cart).unwrap();
+        assert!((body.velocity() - Vec2::new(0.0, 1.0)).len() < 0.01, "{:?}", body.velocity());
+        assert!(body.position().x.abs() < 0.01 && body.rotation().abs() < 0.05);
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            events.extend(world.joint_events().iter().map(|e| e.kind));
+        }
+        assert_eq!(events, vec![JointEventKind::LimitReached, JointEventKind::MotorStalled]);
+        assert!((world.body(cart).unwrap().position().y - 1.0).abs() < 0.02);
+
+        // Too weak to hold the cart up against gravity, it slides down to the lower limit
+        joint.max_motor_force = 5.0;
+        world.set_prismatic_joint(index, joint);
+        world.set_gravity(Vec2::new(0.0, -9.81));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.body(cart).unwrap();
+        assert!((body.position().y + 1.0).abs() < 0.05, "{:?}", body.position());
+
+        let loaded = World::from_json(&world.to_json()).unwrap();
+        let saved = loaded.prismatic_joint(0).unwrap();
+        assert_eq!((saved.max_motor_force, saved.upper_translation), (5.0, 1.0));
+        let mut restored = World::new();
+        restored.load_state(&world.save_state()).unwrap();
+        assert_eq!(restored.prismatic_joint(0), world.prismatic_joint(0));
+    }
+
+    #[test]
+    fn test_mouse_joint_drags_body() {
+        let drag = |backend: SolverBackend, max_force: f64| {
+            let mut world = World::new();
+            world.set_solver_config(SolverConfig { backend, ..SolverConfig::new() });
+            let mut anchor = RigidBody::new(0.0);
+            anchor.set_position(Vec2::new(0.0, -10.0));
+            let anchor = world.create_body(anchor);
+            let body = world.create_body(RigidBody::with_polygon(1.0, &[-0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, 0.5]));
+            let mut joint = MouseJoint::new(anchor, body, Vec2::new(0.0, 0.0), Vec2::new(3.0, 2.0));
+            joint.max_force = max_force;
+            let index = world.add_mouse_joint(joint).unwrap();
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+            }
+            let first = world.body(body).unwrap().position();
+            world.set_mouse_target(index, -2.0, 1.0);
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+            }
+            let second = world.body(body).unwrap().position();
+            (first, second, world)
+        };
+
+        for backend in [SolverBackend::SequentialImpulse, SolverBackend::Lcp] {
+            let (first, second, world) = drag(backend, 1000.0);
+            // Hangs just below the target under gravity
+            assert!(Vec2::distance(&first, &Vec2::new(3.0, 2.0)) < 0.05, "{:?}", first);
+            assert!(Vec2::distance(&second, &Vec2::new(-2.0, 1.0)) < 0.05, "{:?}", second);
+            assert_eq!(world.mouse_joint(0).unwrap().target(), Vec2::new(-2.0, 1.0));
+        }
+
+        // Too weak to hold the body against gravity
+        let (first, _, world) = drag(SolverBackend::SequentialImpulse, 5.0);
+        assert!(first.y < -5.0);
+
+        let loaded = World::from_json(&world.to_json()).unwrap();
+        assert_eq!(loaded.mouse_joint(0).unwrap().target(), Vec2::new(-2.0, 1.0));
+        let mut restored = World::new();
+        restored.load_state(&world.save_state()).unwrap();
+        assert_eq!(restored.mouse_joint(0), world.mouse_joint(0));
+    }
+
+    #[test]
+    fn test_border_keeps_bodies_inside() {
+        let mut world = World::new();
+        let region = Aabb::new(Vec2::new(-2.0, 0.0), Vec2::new(2.0, 4.0));
+        let mut bouncy = Material::new();
+        bouncy.restitution = 0.9;
+        let walls = world.add_border(region, 0.5, bouncy, false).unwrap();
+        assert_eq!(walls.len(), 4);
+        assert!(walls.iter().all(|&w| world.body(w).unwrap().material() == bouncy));
+        assert_eq!(world.add_border(region, 0.5, Material::new(), true).unwrap().len(), 3);
+        assert_eq!(world.add_border(region, 0.0, bouncy, false), Err(WorldError::InvalidThickness(0.0)));
+        assert!(world.add_border(region, f64::NAN, bouncy, false).is_err());
+
+        let mut ball = RigidBody::with_circle(1.0, 0.25);
+        ball.set_position(Vec2::new(0.0, 2.0));
+        ball.set_velocity(Vec2::new(4.0, 3.0));
+        let ball = world.create_body(ball);
+        for _ in 0..240 {
+            world.step(1.0 / 60.0);
+            let p = world.body(ball).unwrap().position();
+            assert!(p.x > -2.0 && p.x < 2.0 && p.y > 0.0 && p.y < 4.0, "escaped to {:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_explode_body() {
+        let explode = |seed| {
+            let mut world = world();
+            world.set_seed(seed);
+            let mut crate_body = RigidBody::with_polygon(4.0, &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0]);
+            crate_body.set_position(Vec2::new(3.0, 2.0));
+            let handle = world.create_body(crate_body);
+            let fragments = world.explode_body(handle, 50.0, 6).unwrap();
+            assert!(!world.contains_body(handle));
+            (world, fragments)
+        };
+        let (world, fragments) = explode(7);
+
+        assert_eq!(fragments.len(), 6);
+        let bodies: Vec<&RigidBody> = fragments.iter().map(|f| world.body(*f).unwrap()).collect();
+        let mass: f64 = bodies.iter().map(|b| b.mass()).sum();
+        assert!((mass - 4.0).abs() < 1e-9);
+        let energy: f64 = bodies.iter().map(|b| 0.5 * b.mass() * b.velocity().len_sq()).sum();
+        assert!(energy > 50.0 * 0.5 && energy < 50.0 * 1.6, "energy {}", energy);
+        for b in &bodies {
+            let outward = b.position() - Vec2::new(3.0, 2.0);
+            assert!(Vec2::dot(&outward, &b.velocity()) > 0.0);
+        }
+
+        // The same seed gives the same fragments
+        let (again, _) = explode(7);
+        assert_eq!(again.snapshot(), world.snapshot());
+        let (other, _) = explode(8);
+        assert_ne!(other.snapshot(), world.snapshot());
+    }
+
+    #[test]
+    fn test_explode_errors() {
+        let mut world = world();
+        let edge = world.create_body(RigidBody::with_segment(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), false));
+        assert_eq!(world.explode_body(edge, 1.0, 3), Err(WorldError::CannotFracture(edge)));
+        assert_eq!(world.explode_body(edge, -1.0, 3), Err(WorldError::InvalidEnergy(-1.0)));
+        assert!(world.explode_body(edge, f64::NAN, 3).is_err());
+        assert!(world.contains_body(edge));
+
+        let ball = world.create_body(RigidBody::with_circle(1.0, 0.5));
+        assert_eq!(world.explode_body(ball, 1.0, 4).unwrap().len(), 4);
+        assert_eq!(world.explode_body(ball, 1.0, 4), Err(WorldError::InvalidHandle(ball)));
+    }
+
+    #[test]
+    fn test_mirror_body() {
+        let mut world = world();
+        let mut wedge = RigidBody::with_polygon(1.0, &[0.0, 0.0, 2.0, 0.0, 0.0, 1.0]);
+        wedge.set_position(Vec2::new(3.0, 1.0));
+        wedge.set_rotation(0.4);
+        wedge.set_velocity(Vec2::new(1.0, 2.0));
+        wedge.set_angular_velocity(0.5);
+        let wedge = world.create_body(wedge);
+
+        // Mirror across the vertical line x = 1
+        let copy = world.mirror_body(wedge, &Vec2::new(1.0, 5.0), &Vec2::new(0.0, 1.0)).unwrap();
+        let (a, b) = (world.body(wedge).unwrap(), world.body(copy).unwrap());
+        assert_eq!(b.position(), Vec2::new(-1.0, 1.0));
+        assert_eq!(b.velocity(), Vec2::new(-1.0, 2.0));
+        assert_eq!(b.angular_velocity(), -0.5);
+        assert!((b.inertia() - a.inertia()).abs() < 1e-12);
+        let (ta, tb) = (a.transform(), b.transform());
+        let Shapes::Polygon(pa) = a.shape() else { panic!() };
+        let Shapes::Polygon(pb) = b.shape() else { panic!() };
+        assert!(pb.area() > 0.0);
+        for (va, vb) in pa.vertices().iter().zip(pb.vertices().iter().rev()) {
+            let (wa, wb) = (ta.apply(va), tb.apply(vb));
+            assert!((Vec2::new(2.0 - wa.x, wa.y) - wb).len() < 1e-12);
+        }
+        let missing = BodyHandle::new(9, 0);
+        assert_eq!(
+            world.mirror_body(missing, &Vec2::new(0.0, 0.0), &Vec2::new(1.0, 0.0)),
+            Err(WorldError::InvalidHandle(missing))
+        );
+        let zero = Vec2::new(0.0, 0.0);
+        assert_eq!(world.mirror_body(wedge, &zero, &zero), Err(WorldError::InvalidAxis(zero)));
+    }
+}
@@ -0,0 +1,215 @@
+//! Compact little endian encoding for simulation state.
+//!
+//! Values are written back to back with no field names, so a reader must decode them in the
+//! same order they were written. Floats keep their exact bits, which makes a decoded state
+//! resimulate identically to the original.
+
+use super::SerializeError;
+use crate::math::{Transform2D, Vec2};
+
+/// A value that can be written to and read back from bytes
+pub(crate) trait Binary: Sized {
+    fn encode(&self, w: &mut Writer);
+    fn decode(r: &mut Reader) -> Result<Self, SerializeError>;
+}
+
+#[derive(Default)]
+pub(crate) struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn put<T: Binary>(&mut self, value: &T) {
+        value.encode(self);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, pos: 0 }
+    }
+
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8], SerializeError> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let out = &self.bytes[self.pos..end];
+                self.pos = end;
+                Ok(out)
+            }
+            None => Err(SerializeError::InvalidValue("unexpected end of data".to_string())),
+        }
+    }
+
+    pub fn get<T: Binary>(&mut self) -> Result<T, SerializeError> {
+        T::decode(self)
+    }
+
+    /// Error unless every byte was read
+    pub fn finish(self) -> Result<(), SerializeError> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(SerializeError::InvalidValue("trailing bytes".to_string()))
+        }
+    }
+
+    /// Error for an enum tag with no matching variant
+    pub fn invalid_tag(&self, what: &str, tag: u8) -> SerializeError {
+        SerializeError::InvalidValue(format!("unknown {} tag {}", what, tag))
+    }
+}
+
+macro_rules! impl_binary_int {
+    ($($t:ty),*) => {$(
+        impl Binary for $t {
+            fn encode(&self, w: &mut Writer) {
+                w.bytes(&self.to_le_bytes());
+            }
+
+            fn decode(r: &mut Reader) -> Result<$t, SerializeError> {
+                let mut buf = [0; std::mem::size_of::<$t>()];
+                buf.copy_from_slice(r.take(std::mem::size_of::<$t>())?);
+                Ok(<$t>::from_le_bytes(buf))
+            }
+        }
+    )*};
+}
+
+impl_binary_int!(u8, u32, u64, f64);
+
+impl Binary for usize {
+    fn encode(&self, w: &mut Writer) {
+        (*self as u64).encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<usize, SerializeError> {
+        Ok(u64::decode(r)? as usize)
+    }
+}
+
+impl Binary for bool {
+    fn encode(&self, w: &mut Writer) {
+        (*self as u8).encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<bool, SerializeError> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(r.invalid_tag("bool", tag)),
+        }
+    }
+}
+
+impl Binary for Vec2 {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.x);
+        w.put(&self.y);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Vec2, SerializeError> {
+        Ok(Vec2::new(r.get()?, r.get()?))
+    }
+}
+
+impl Binary for Transform2D {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.position);
+        w.put(&self.rotation);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Transform2D, SerializeError> {
+        Ok(Transform2D::new(r.get()?, r.get()?))
+    }
+}
+
+impl<T: Binary> Binary for Option<T> {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Some(v) => {
+                w.put(&true);
+                w.put(v);
+            }
+            None => w.put(&false),
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Option<T>, SerializeError> {
+        Ok(if r.get::<bool>()? { Some(r.get()?) } else { None })
+    }
+}
+
+impl<A: Binary, B: Binary> Binary for (A, B) {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.0);
+        w.put(&self.1);
+    }
+
+    fn decode(r: &mut Reader) -> Result<(A, B), SerializeError> {
+        Ok((r.get()?, r.get()?))
+    }
+}
+
+impl<T: Binary> Binary for Vec<T> {
+    fn encode(&self, w: &mut Writer) {
+        w.put(&self.len());
+        for v in self {
+            w.put(v);
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Vec<T>, SerializeError> {
+        let len: usize = r.get()?;
+        // Every value takes at least a byte, which bounds the allocation for corrupt lengths
+        if len > r.bytes.len() - r.pos {
+            return Err(SerializeError::InvalidValue("length exceeds data".to_string()));
+        }
+        (0..len).map(|_| r.get()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = (vec![Some(Vec2::new(0.1, -2.0)), None], (7usize, u64::MAX));
+        let mut w = Writer::new();
+        w.put(&value);
+        let bytes = w.finish();
+
+        let mut r = Reader::new(&bytes);
+        assert_eq!(r.get::<(Vec<Option<Vec2>>, (usize, u64))>().unwrap(), value);
+        assert!(r.finish().is_ok());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut w = Writer::new();
+        w.put(&vec![1.0f64, 2.0]);
+        let bytes = w.finish();
+
+        let mut r = Reader::new(&bytes[..12]);
+        assert!(r.get::<Vec<f64>>().is_err());
+        assert!(Reader::new(&[2]).get::<bool>().is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Versioned save files.
+//!
+//! Every document carries a `version` field. Documents written by older crate versions are
+//! passed through `upgrade`, which applies each migration in turn so new fields get defaults.
+
+use std::fmt;
+
+use crate::math::{Transform2D, Vec2};
+use crate::physics::{SolverConfig, WorldFeatures};
+
+pub mod binary;
+pub mod json;
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+pub use json::Value;
+
+/// Version written by this crate
+pub const FORMAT_VERSION: u32 = 3;
+
+/// MIGRATIONS[i] upgrades a version i document to version i + 1
+const MIGRATIONS: &[fn(&mut Value)] = &[v0_to_v1, v1_to_v2, v2_to_v3];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SerializeError {
+    Parse(String),
+    MissingField(String),
+    InvalidValue(String),
+    /// Document was written by a newer crate version
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializeError::Parse(msg) => write!(f, "Invalid JSON: {}", msg),
+            SerializeError::MissingField(name) => write!(f, "Missing field '{}'", name),
+            SerializeError::InvalidValue(msg) => write!(f, "Invalid value: {}", msg),
+            SerializeError::UnsupportedVersion(v) => {
+                write!(f, "Version {} is newer than supported version {}", v, FORMAT_VERSION)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+/// Version of a document. Documents written before versioning existed are version 0.
+pub fn version(doc: &Value) -> u32 {
+    doc.get("version").and_then(Value::as_f64).unwrap_or(0.0) as u32
+}
+
+/// Bring a settings or scene document up to FORMAT_VERSION
+pub fn upgrade(mut doc: Value) -> Result<Value, SerializeError> {
+    let from = version(&doc);
+    if from > FORMAT_VERSION {
+        return Err(SerializeError::UnsupportedVersion(from));
+    }
+    for migrate in &MIGRATIONS[from as usize..] {
+        migrate(&mut doc);
+    }
+    doc.insert("version", Value::Number(FORMAT_VERSION as f64));
+    Ok(doc)
+}
+
+/// The settings object of a document: nested for scenes, the document itself for settings
+fn settings_mut(doc: &mut Value) -> &mut Value {
+    if doc.get("settings").is_some() {
+        doc.get_mut("settings").unwrap()
+    } else {
+        doc
+    }
+}
+
+/// Unversioned settings only stored gravity
+fn v0_to_v1(doc: &mut Value) {
+    let settings = settings_mut(doc);
+    if settings.get("keyframe_capacity").is_none() {
+        settings.insert("keyframe_capacity", Value::Number(64.0));
+    }
+}
+
+/// Scenes didn't store the solver config, features or bounds, so they loaded with defaults
+fn v1_to_v2(doc: &mut Value) {
+    if doc.get("bodies").is_none() {
+        return;
+    }
+    if doc.get("solver").is_none() {
+        doc.insert("solver", SolverConfig::new().to_value());
+    }
+    if doc.get("features").is_none() {
+        doc.insert("features", WorldFeatures::new().to_value());
+    }
+    if doc.get("bounds").is_none() {
+        doc.insert("bounds", Value::Null);
+    }
+}
+
+/// The solver config had no XPBD compliance, so joints were rigid
+fn v2_to_v3(doc: &mut Value) {
+    if let Some(solver) = doc.get_mut("solver") {
+        if solver.get("compliance").is_none() {
+            solver.insert("compliance", Value::Number(0.0));
+        }
+    }
+}
+
+pub(crate) fn vec2_to_value(v: &Vec2) -> Value {
+    Value::Array(vec![Value::Number(v.x), Value::Number(v.y)])
+}
+
+pub(crate) fn vec2_from_value(v: &Value) -> Result<Vec2, SerializeError> {
+    match v.as_array() {
+        Some([Value::Number(x), Value::Number(y)]) => Ok(Vec2::new(*x, *y)),
+        _ => Err(SerializeError::InvalidValue(format!("expected [x, y], found {}", v))),
+    }
+}
+
+/// User data is written as a decimal string since JSON numbers can't hold every u64
+pub(crate) fn user_data_to_value(data: u64) -> Value {
+    Value::String(data.to_string())
+}
+
+/// User data of an object, 0 if it has none
+pub(crate) fn user_data_from_value(v: &Value) -> Result<u64, SerializeError> {
+    match v.get("user_data") {
+        None => Ok(0),
+        Some(data) => data
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| SerializeError::InvalidValue(format!("expected user data string, found {}", data))),
+    }
+}
+
+pub(crate) fn transform_to_value(t: &Transform2D) -> Value {
+    let mut v = Value::object();
+    v.insert("position", vec2_to_value(&t.position));
+    v.insert("rotation", Value::Number(t.rotation));
+    v
+}
+
+pub(crate) fn transform_from_value(v: &Value) -> Result<Transform2D, SerializeError> {
+    Ok(Transform2D::new(
+        vec2_from_value(v.field("position")?)?,
+        v.field_f64("rotation")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_unversioned() {
+        let doc = json::parse(r#"{"gravity":[0,-10]}"#).unwrap();
+        let doc = upgrade(doc).unwrap();
+
+        assert_eq!(version(&doc), FORMAT_VERSION);
+        assert_eq!(doc.get("keyframe_capacity"), Some(&Value::Number(64.0)));
+    }
+
+    #[test]
+    fn test_upgrade_nested_settings() {
+        let doc = json::parse(r#"{"settings":{"gravity":[0,-10]}}"#).unwrap();
+        let doc = upgrade(doc).unwrap();
+
+        assert!(doc.get("settings").unwrap().get("keyframe_capacity").is_some());
+    }
+
+    #[test]
+    fn test_upgrade_v1_scene() {
+        let doc = json::parse(r#"{"version":1,"settings":{"gravity":[0,-10],"keyframe_capacity":8},"bodies":[]}"#).unwrap();
+        let doc = upgrade(doc).unwrap();
+
+        assert_eq!(SolverConfig::from_value(doc.get("solver").unwrap()), Ok(SolverConfig::new()));
+        assert_eq!(WorldFeatures::from_value(doc.get("features").unwrap()), Ok(WorldFeatures::new()));
+        assert_eq!(doc.get("bounds"), Some(&Value::Null));
+        assert_eq!(doc.get("settings").unwrap().get("keyframe_capacity"), Some(&Value::Number(8.0)));
+    }
+
+    #[test]
+    fn test_upgrade_v2_solver() {
+        let mut solver = SolverConfig::new().to_value();
+        solver.remove("compliance");
+        let mut doc = json::parse(r#"{"version":2,"settings":{"gravity":[0,-10],"keyframe_capacity":8},"bodies":[]}"#).unwrap();
+        doc.insert("solver", solver);
+        let doc = upgrade(doc).unwrap();
+
+        assert_eq!(SolverConfig::from_value(doc.get("solver").unwrap()), Ok(SolverConfig::new()));
+    }
+
+    #[test]
+    fn test_upgrade_future_version() {
+        let doc = json::parse(r#"{"version":1000}"#).unwrap();
+        assert_eq!(upgrade(doc), Err(SerializeError::UnsupportedVersion(1000)));
+    }
+}
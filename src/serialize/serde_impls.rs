@@ -0,0 +1,188 @@
+//! Serde support, with the `serde` feature.
+//!
+//! World, RigidBody and Shapes serialize as the same documents `World::to_json` writes, so a
+//! World saved in any serde format is versioned and goes through `upgrade` when loaded.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::Value;
+use crate::geometry::Shapes;
+use crate::physics::{RigidBody, World};
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => serializer.serialize_f64(*n),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Number(n as f64))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        Ok(Value::Number(n as f64))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::Number(n))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut v = Value::object();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            v.insert(&key, value);
+        }
+        Ok(v)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl Serialize for World {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for World {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<World, D::Error> {
+        World::from_value(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for RigidBody {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RigidBody {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RigidBody, D::Error> {
+        RigidBody::from_value(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Shapes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shapes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Shapes, D::Error> {
+        Shapes::from_value(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Circle, Polygon};
+    use crate::math::Vec2;
+    use crate::physics::RevoluteJoint;
+    use crate::serialize::json;
+
+    #[test]
+    fn test_serde_world() {
+        let mut world = World::new();
+        let ground = world.create_body(RigidBody::with_polygon(0.0, &[-5.0, -0.5, 5.0, -0.5, 5.0, 0.5, -5.0, 0.5]));
+        let mut ball = RigidBody::with_circle(1.0, 0.5);
+        ball.set_position(Vec2::new(0.0, 3.0));
+        ball.set_user_data(u64::MAX);
+        let ball = world.create_body(ball);
+        world.add_revolute_joint(RevoluteJoint::new(ground, ball, Vec2::new(0.0, 3.0), Vec2::new(0.0, 0.0))).unwrap();
+        world.step(1.0 / 60.0);
+
+        // Same document as to_json, and back to the same world
+        let s = serde_json::to_string(&world).unwrap();
+        assert_eq!(json::parse(&s), json::parse(&world.to_json()));
+        let loaded: World = serde_json::from_str(&s).unwrap();
+        assert_eq!(loaded.to_json(), world.to_json());
+
+        // Older documents are upgraded as from_json does
+        let old = r#"{"settings":{"gravity":[0,-10]},"time":0,"tick":0,"bodies":[],"springs":[],"emitters":[]}"#;
+        let loaded: World = serde_json::from_str(old).unwrap();
+        assert_eq!(loaded.gravity(), Vec2::new(0.0, -10.0));
+        assert!(serde_json::from_str::<World>(r#"{"version":1000}"#).is_err());
+    }
+
+    #[test]
+    fn test_serde_body_and_shape() {
+        let mut body = RigidBody::with_shape(2.0, Shapes::Polygon(Polygon::rect(2.0, 1.0)));
+        body.set_velocity(Vec2::new(1.0, -2.0));
+        let loaded: RigidBody = serde_json::from_str(&serde_json::to_string(&body).unwrap()).unwrap();
+        assert_eq!(loaded.velocity(), body.velocity());
+        assert_eq!(loaded.shape(), body.shape());
+
+        let shape: Shapes = serde_json::from_str(r#"{"type":"circle","center":[1,2],"radius":0.5}"#).unwrap();
+        assert_eq!(shape, Shapes::Circle(Circle::new(Vec2::new(1.0, 2.0), 0.5)));
+        assert!(serde_json::from_str::<Shapes>(r#"{"type":"blob"}"#).is_err());
+    }
+}